@@ -0,0 +1,769 @@
+//! Progress reporting and cancellation for long-running analyses.
+//!
+//! Computing the width or a largest antichain, counting linear extensions, deciding isomorphism, and building
+//! the Dedekind–MacNeille completion can take a long time on large inputs. Each of these has a variant taking
+//! an [AnalysisContext], which carries an optional progress callback, an optional cancellation token, and an
+//! optional deadline. The algorithm counts its iterations and, every [AnalysisContext::with_check_every] of
+//! them, reports the count to the callback and checks the token and the deadline, returning [Cancelled] if
+//! either has fired. The plain variants run with an empty context, which never cancels.
+//!
+//! With the `rayon` feature, the width and the count of linear extensions run parts of their work on
+//! several threads. The iterations of the threads are added up, so the counts are the same either way, and
+//! the callback is still only called from the thread running the analysis.
+//!
+//! ```
+//! use fin_pos::analysis::{AnalysisContext, CancelReason};
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//! use std::sync::atomic::AtomicBool;
+//!
+//! let p = PosetM::new_antichain(4);
+//! assert_eq!(p.count_linear_extensions(), 24);
+//!
+//! let token = AtomicBool::new(true);
+//! let mut ctx = AnalysisContext::new().with_cancel(&token).with_check_every(1);
+//! let err = p.count_linear_extensions_with(&mut ctx).unwrap_err();
+//! assert_eq!(err.reason, CancelReason::Token);
+//! ```
+
+use crate::bitset::{BitMatrix, BitRow};
+use crate::parallel::map_range;
+use crate::posetm::PosetM;
+use crate::relation::{components, strict_matrix};
+use crate::Poset;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Why an analysis stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelReason {
+    /// The cancellation token was set.
+    Token,
+    /// The deadline passed.
+    Deadline,
+}
+
+/// The error returned by an analysis that was cancelled before it finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled {
+    pub reason: CancelReason,
+    /// The number of iterations performed before stopping.
+    pub iterations: u64,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            CancelReason::Token => "cancelled",
+            CancelReason::Deadline => "deadline exceeded",
+        };
+        write!(
+            f,
+            "analysis {} after {} iterations",
+            reason, self.iterations
+        )
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Hooks observed by a long-running analysis. A context is built with [AnalysisContext::new] and the
+/// `with_*` methods, and keeps counting iterations across the analyses it is passed to.
+pub struct AnalysisContext<'a> {
+    progress: Option<Box<dyn FnMut(u64) + 'a>>,
+    cancel: Option<&'a AtomicBool>,
+    deadline: Option<Instant>,
+    check_every: u64,
+    iterations: u64,
+}
+
+impl Default for AnalysisContext<'_> {
+    fn default() -> Self {
+        AnalysisContext {
+            progress: None,
+            cancel: None,
+            deadline: None,
+            check_every: 1024,
+            iterations: 0,
+        }
+    }
+}
+
+impl<'a> AnalysisContext<'a> {
+    /// A context without hooks, checking every 1024 iterations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls f with the number of iterations so far at every check. The counts passed are increasing.
+    pub fn with_progress<F: FnMut(u64) + 'a>(mut self, f: F) -> Self {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    /// Stops the analysis once token is set, e.g., from another thread.
+    pub fn with_cancel(mut self, token: &'a AtomicBool) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Stops the analysis once deadline has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Checks the hooks every k iterations (at least 1). Smaller values respond faster but cost more.
+    pub fn with_check_every(mut self, k: u64) -> Self {
+        self.check_every = k.max(1);
+        self
+    }
+
+    /// The number of iterations performed so far.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Counts one iteration, checking the hooks if it is due.
+    #[inline]
+    pub(crate) fn tick(&mut self) -> Result<(), Cancelled> {
+        self.iterations += 1;
+        if self.iterations.is_multiple_of(self.check_every) {
+            self.check()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts the iterations performed by the workers of a parallel section, checking the hooks if a check
+    /// fell due among them.
+    pub(crate) fn absorb(&mut self, iterations: u64) -> Result<(), Cancelled> {
+        let before = self.iterations / self.check_every;
+        self.iterations += iterations;
+        if self.iterations / self.check_every > before {
+            self.check()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The hooks that can be handed to other threads, i.e., all but the progress callback.
+    pub(crate) fn hooks(&self) -> Hooks<'a> {
+        Hooks {
+            cancel: self.cancel,
+            deadline: self.deadline,
+            check_every: self.check_every,
+        }
+    }
+
+    #[cold]
+    fn check(&mut self) -> Result<(), Cancelled> {
+        if let Some(f) = self.progress.as_mut() {
+            f(self.iterations);
+        }
+        let reason = if self.cancel.is_some_and(|t| t.load(Ordering::Relaxed)) {
+            CancelReason::Token
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            CancelReason::Deadline
+        } else {
+            return Ok(());
+        };
+        Err(Cancelled {
+            reason,
+            iterations: self.iterations,
+        })
+    }
+}
+
+/// The cancellation token, the deadline, and the frequency of checks of a context, to be checked by the
+/// workers of a parallel section.
+#[derive(Clone, Copy)]
+pub(crate) struct Hooks<'a> {
+    cancel: Option<&'a AtomicBool>,
+    deadline: Option<Instant>,
+    check_every: u64,
+}
+
+impl<'a> Hooks<'a> {
+    /// A fresh context with these hooks and no progress callback, for a worker.
+    pub(crate) fn context(self) -> AnalysisContext<'a> {
+        AnalysisContext {
+            progress: None,
+            cancel: self.cancel,
+            deadline: self.deadline,
+            check_every: self.check_every,
+            iterations: 0,
+        }
+    }
+}
+
+/// Unwraps the result of an analysis run with an empty context, which cannot be cancelled.
+pub(crate) fn uncancelled<T>(r: Result<T, Cancelled>) -> T {
+    r.expect("an analysis without a token or deadline is never cancelled")
+}
+
+/// Computes the width of p, i.e., the size of a largest antichain.
+pub fn width<P: Poset + ?Sized>(p: &P, ctx: &mut AnalysisContext) -> Result<usize, Cancelled> {
+    strict_width(&strict_matrix(p), ctx)
+}
+
+/// The width, as $n$ minus a maximum matching of the comparability bipartite graph (Dilworth). The width
+/// is the sum of those of the connected components, whose matchings are found in parallel with the `rayon`
+/// feature.
+pub(crate) fn strict_width(
+    lt: &[Vec<bool>],
+    ctx: &mut AnalysisContext,
+) -> Result<usize, Cancelled> {
+    let all: Vec<usize> = (0..lt.len()).collect();
+    let parts = components(&all, |x, y| lt[x][y] || lt[y][x]);
+    if parts.len() <= 1 {
+        let matching = Matching::maximum(lt, ctx)?;
+        return Ok(lt.len() - matching.size());
+    }
+    let hooks = ctx.hooks();
+    let widths = map_range(parts.len(), |i| {
+        let part = &parts[i];
+        let sub: Vec<Vec<bool>> = part
+            .iter()
+            .map(|&x| part.iter().map(|&y| lt[x][y]).collect())
+            .collect();
+        let mut worker = hooks.context();
+        let width = Matching::maximum(&sub, &mut worker).map(|m| part.len() - m.size());
+        (width, worker.iterations)
+    });
+    ctx.absorb(widths.iter().map(|(_, i)| i).sum())?;
+    widths
+        .into_iter()
+        .map(|(width, _)| {
+            width.map_err(|e| Cancelled {
+                reason: e.reason,
+                iterations: ctx.iterations,
+            })
+        })
+        .sum()
+}
+
+/// A partition of the elements into [width] chains, each in increasing order, read off a maximum matching of
+/// the comparability bipartite graph: the element matched to x follows it in its chain.
+pub(crate) fn chain_partition(lt: &[Vec<bool>]) -> Vec<Vec<usize>> {
+    let matching = uncancelled(Matching::maximum(lt, &mut AnalysisContext::new()));
+    (0..lt.len())
+        .filter(|&x| matching.right[x].is_none())
+        .map(|x| {
+            let mut chain = vec![x];
+            while let Some(y) = matching.left[*chain.last().unwrap()] {
+                chain.push(y);
+            }
+            chain
+        })
+        .collect()
+}
+
+/// Returns a largest antichain of p. It is read off a maximum matching of the comparability bipartite
+/// graph by König's theorem: the elements whose left copy, but not whose right copy, is reachable from an
+/// unmatched left copy along alternating paths.
+pub fn max_antichain<P: Poset + ?Sized>(
+    p: &P,
+    ctx: &mut AnalysisContext,
+) -> Result<HashSet<usize>, Cancelled> {
+    let lt = strict_matrix(p);
+    let matching = Matching::maximum(&lt, ctx)?;
+    let (left, right) = matching.alternating_reach(&lt);
+    Ok((0..lt.len()).filter(|&x| left[x] && !right[x]).collect())
+}
+
+/// A matching of the bipartite graph with a left and a right copy of every element, and an edge from the
+/// left copy of i to the right copy of j whenever $i < j$.
+struct Matching {
+    left: Vec<Option<usize>>,
+    right: Vec<Option<usize>>,
+}
+
+impl Matching {
+    /// A maximum matching by the Hopcroft–Karp algorithm, in $O(n^{2.5})$ steps. Every element visited by
+    /// a breadth first or a depth first search is an iteration.
+    fn maximum(lt: &[Vec<bool>], ctx: &mut AnalysisContext) -> Result<Matching, Cancelled> {
+        let n = lt.len();
+        let mut m = Matching {
+            left: vec![None; n],
+            right: vec![None; n],
+        };
+        loop {
+            let Some(mut dist) = m.layers(lt, ctx)? else {
+                return Ok(m);
+            };
+            for i in 0..n {
+                if m.left[i].is_none() {
+                    m.augment(lt, i, &mut dist, ctx)?;
+                }
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.left.iter().flatten().count()
+    }
+
+    /// The distance of every left copy from the unmatched left copies along alternating paths, or None if
+    /// no augmenting path exists.
+    fn layers(
+        &self,
+        lt: &[Vec<bool>],
+        ctx: &mut AnalysisContext,
+    ) -> Result<Option<Vec<Option<usize>>>, Cancelled> {
+        let n = lt.len();
+        let mut dist: Vec<Option<usize>> =
+            self.left.iter().map(|j| j.is_none().then_some(0)).collect();
+        let mut queue: VecDeque<usize> = (0..n).filter(|&i| dist[i].is_some()).collect();
+        let mut found = false;
+        while let Some(i) = queue.pop_front() {
+            ctx.tick()?;
+            for j in (0..n).filter(|&j| lt[i][j]) {
+                match self.right[j] {
+                    None => found = true,
+                    Some(k) if dist[k].is_none() => {
+                        dist[k] = Some(dist[i].unwrap() + 1);
+                        queue.push_back(k);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(found.then_some(dist))
+    }
+
+    /// Looks for an augmenting path from the left copy of i along the layers, and flips it if found.
+    fn augment(
+        &mut self,
+        lt: &[Vec<bool>],
+        i: usize,
+        dist: &mut [Option<usize>],
+        ctx: &mut AnalysisContext,
+    ) -> Result<bool, Cancelled> {
+        ctx.tick()?;
+        for j in 0..lt.len() {
+            if !lt[i][j] {
+                continue;
+            }
+            let free = match self.right[j] {
+                None => true,
+                Some(k) => dist[k] == dist[i].map(|d| d + 1) && self.augment(lt, k, dist, ctx)?,
+            };
+            if free {
+                self.left[i] = Some(j);
+                self.right[j] = Some(i);
+                return Ok(true);
+            }
+        }
+        dist[i] = None;
+        Ok(false)
+    }
+
+    /// The left and the right copies reachable from the unmatched left copies, going from left to right
+    /// along any edge and from right to left along the matching.
+    fn alternating_reach(&self, lt: &[Vec<bool>]) -> (Vec<bool>, Vec<bool>) {
+        let n = lt.len();
+        let mut left: Vec<bool> = self.left.iter().map(Option::is_none).collect();
+        let mut right = vec![false; n];
+        let mut stack: Vec<usize> = (0..n).filter(|&i| left[i]).collect();
+        while let Some(i) = stack.pop() {
+            for j in 0..n {
+                if !lt[i][j] || right[j] {
+                    continue;
+                }
+                right[j] = true;
+                if let Some(k) = self.right[j].filter(|&k| !left[k]) {
+                    left[k] = true;
+                    stack.push(k);
+                }
+            }
+        }
+        (left, right)
+    }
+}
+
+/// Counts the linear extensions of p by dynamic programming over its down-sets: the number of ways to
+/// reach a down-set is the sum over the ways to reach the down-sets it covers. Every cover is an
+/// iteration, so the running time is proportional to the number of down-sets, which can be exponential.
+/// Down-sets of posets with at most 64 elements are stored as single words, and those of larger posets as
+/// [BitRow]s. With the `rayon` feature, the down-sets of each size are extended in parallel, in batches of
+/// a few checks' worth, and the covers found are then counted as iterations. Panics if the count does not
+/// fit in a `u128`.
+pub fn count_linear_extensions<P: Poset + ?Sized>(
+    p: &P,
+    ctx: &mut AnalysisContext,
+) -> Result<u128, Cancelled> {
+    let b = BitMatrix::new(p);
+    let n = b.n();
+    let below: Vec<BitRow> = (0..n)
+        .map(|x| {
+            let mut r = b.down_row(x).clone();
+            r.unset(x);
+            r
+        })
+        .collect();
+    if n <= 64 {
+        let below = below
+            .iter()
+            .map(|r| (0..n).filter(|&y| r.get(y)).fold(0, |m, y| m | 1 << y))
+            .collect();
+        count_by_words(below, ctx)
+    } else {
+        count_by_rows(below, ctx)
+    }
+}
+
+/// The down-set dynamic programming, bit y of below\[x\] being set if $y < x$.
+fn count_by_words(below: Vec<u64>, ctx: &mut AnalysisContext) -> Result<u128, Cancelled> {
+    let n = below.len();
+    count_by_layers(n, 0, ctx, |&d| {
+        (0..n)
+            .filter(|&x| d >> x & 1 == 0 && below[x] & !d == 0)
+            .map(|x| d | 1 << x)
+            .collect()
+    })
+}
+
+/// The down-set dynamic programming for any number of elements.
+fn count_by_rows(below: Vec<BitRow>, ctx: &mut AnalysisContext) -> Result<u128, Cancelled> {
+    let n = below.len();
+    count_by_layers(n, BitRow::new(n), ctx, |d| {
+        (0..n)
+            .filter(|&x| !d.get(x) && below[x].is_subset(d))
+            .map(|x| {
+                let mut e = d.clone();
+                e.set(x);
+                e
+            })
+            .collect()
+    })
+}
+
+/// Sums the ways to reach the down-sets of each size from the empty one, given the down-sets covering
+/// each one. Adding each cover is an iteration.
+fn count_by_layers<D, F>(
+    n: usize,
+    empty: D,
+    ctx: &mut AnalysisContext,
+    covers: F,
+) -> Result<u128, Cancelled>
+where
+    D: Eq + std::hash::Hash + Send + Sync,
+    F: Fn(&D) -> Vec<D> + Sync + Send,
+{
+    let batch = usize::try_from(ctx.check_every.saturating_mul(4)).unwrap_or(usize::MAX);
+    let mut layer: Vec<(D, u128)> = vec![(empty, 1)];
+    for _ in 0..n {
+        let mut next: HashMap<D, u128> = HashMap::new();
+        for chunk in layer.chunks(batch) {
+            let found = map_range(chunk.len(), |i| covers(&chunk[i].0));
+            for ((_, count), found) in chunk.iter().zip(found) {
+                for e in found {
+                    ctx.tick()?;
+                    let total = next.entry(e).or_insert(0);
+                    *total = total
+                        .checked_add(*count)
+                        .expect("the number of linear extensions does not fit in a u128");
+                }
+            }
+        }
+        layer = next.into_iter().collect();
+    }
+    Ok(layer
+        .into_iter()
+        .try_fold(0u128, |total, (_, count)| total.checked_add(count))
+        .expect("the number of linear extensions does not fit in a u128"))
+}
+
+/// Returns true if p and q are isomorphic, by comparing their canonical forms (see
+/// [CanonicalPoset::new_with](crate::canonical::CanonicalPoset::new_with)).
+pub fn is_isomorphic<P: Poset + ?Sized, Q: Poset + ?Sized>(
+    p: &P,
+    q: &Q,
+    ctx: &mut AnalysisContext,
+) -> Result<bool, Cancelled> {
+    use crate::canonical::CanonicalPoset;
+
+    if p.elements().count() != q.elements().count() {
+        return Ok(false);
+    }
+    Ok(CanonicalPoset::new_with(p, ctx)? == CanonicalPoset::new_with(q, ctx)?)
+}
+
+/// Computes the Dedekind–MacNeille completion of p, the smallest lattice into which p embeds. Its
+/// elements are the cuts of p, i.e., the intersections of principal down-sets $\{y \mid y \le x\}$
+/// (the empty intersection being the whole poset), ordered by inclusion. The cuts are numbered by
+/// increasing size, ties broken by comparing their sorted members, and $x$ corresponds to the cut
+/// $\{y \mid y \le x\}$. Every intersection formed is an iteration.
+pub fn dedekind_macneille<P: Poset + ?Sized>(
+    p: &P,
+    ctx: &mut AnalysisContext,
+) -> Result<PosetM, Cancelled> {
+    let b = BitMatrix::new(p);
+    let n = b.n();
+    let mut cuts = vec![BitRow::full(n)];
+    let mut seen: HashSet<BitRow> = cuts.iter().cloned().collect();
+    for x in 0..n {
+        // The family stays closed under intersection when intersecting each member with the new generator.
+        for i in 0..cuts.len() {
+            ctx.tick()?;
+            let mut c = cuts[i].clone();
+            c.and_assign(b.down_row(x));
+            if seen.insert(c.clone()) {
+                cuts.push(c);
+            }
+        }
+    }
+    let mut keyed: Vec<(usize, Vec<usize>, BitRow)> = cuts
+        .into_iter()
+        .map(|c| (c.count_ones(), c.iter_ones().collect(), c))
+        .collect();
+    keyed.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    let m = keyed
+        .iter()
+        .map(|(_, _, c)| keyed.iter().map(|(_, _, d)| c.is_subset(d)).collect())
+        .collect();
+    Ok(PosetM::new(&m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus, random_poset};
+    use crate::refinement::is_linear_extension_of;
+
+    use std::cell::RefCell;
+    use std::sync::atomic::AtomicU64;
+    use std::time::Duration;
+
+    fn permutations(n: usize) -> Vec<Vec<usize>> {
+        if n == 0 {
+            return vec![vec![]];
+        }
+        let mut out = vec![];
+        for perm in permutations(n - 1) {
+            for pos in 0..n {
+                let mut q = perm.clone();
+                q.insert(pos, n - 1);
+                out.push(q);
+            }
+        }
+        out
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in a u128")]
+    fn test_count_linear_extensions_overflow() {
+        // Two chains of 70 elements side by side have C(140, 70) > 2^128 linear extensions.
+        let chain = PosetM::new_chain(70);
+        chain.coproduct(&chain).count_linear_extensions();
+    }
+
+    #[test]
+    fn test_count_linear_extensions() {
+        assert_eq!(PosetM::new_chain(5).count_linear_extensions(), 1);
+        assert_eq!(PosetM::new_antichain(5).count_linear_extensions(), 120);
+        assert_eq!(boolean_lattice(2).count_linear_extensions(), 2);
+        assert_eq!(PosetM::new_antichain(0).count_linear_extensions(), 1);
+        for p in corpus().into_iter().filter(|p| p.md.n <= 6) {
+            let n = p.md.n;
+            let brute = permutations(n)
+                .into_iter()
+                .filter(|perm| {
+                    let m = (0..n)
+                        .map(|x| (0..n).map(|y| perm[x] <= perm[y]).collect())
+                        .collect();
+                    is_linear_extension_of(&PosetM::new(&m), &p).unwrap()
+                })
+                .count();
+            assert_eq!(p.count_linear_extensions(), brute as u128);
+        }
+    }
+
+    #[test]
+    fn test_count_beyond_a_word() {
+        // A chain of 70 elements next to a single element: it can go in any of 71 places.
+        let mut p = PosetM::new_chain(70);
+        p.m.iter_mut().for_each(|row| row.push(false));
+        p.m.push((0..71).map(|y| y == 70).collect());
+        p.md.n = 71;
+        assert_eq!(p.count_linear_extensions(), 71);
+        for p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let b = BitMatrix::new(&p);
+            let below: Vec<BitRow> = (0..b.n())
+                .map(|x| {
+                    let mut r = b.down_row(x).clone();
+                    r.unset(x);
+                    r
+                })
+                .collect();
+            let mut ctx = AnalysisContext::new();
+            assert_eq!(
+                count_by_rows(below, &mut ctx),
+                Ok(p.count_linear_extensions())
+            );
+        }
+    }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(PosetM::new_chain(4).width(), 1);
+        assert_eq!(PosetM::new_antichain(4).width(), 4);
+        assert_eq!(PosetM::new_corolla(3).width(), 3);
+        assert_eq!(boolean_lattice(3).width(), 3);
+        assert_eq!(PosetM::new_antichain(0).width(), 0);
+        assert_eq!(
+            PosetM::new_corolla(3).max_antichain(),
+            HashSet::from([0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_width_over_components() {
+        let ps: Vec<PosetM> = corpus().into_iter().filter(|p| p.md.n <= 12).collect();
+        for (p, q) in ps.iter().zip(ps.iter().skip(1)) {
+            let sum = p.coproduct(q);
+            let mut ctx = AnalysisContext::new().with_check_every(1);
+            assert_eq!(sum.width_with(&mut ctx), Ok(p.width() + q.width()));
+            // Every element is visited at least once by the searches of its component.
+            assert!(ctx.iterations() >= sum.md.n as u64);
+        }
+        let token = AtomicBool::new(true);
+        let mut ctx = AnalysisContext::new().with_cancel(&token).with_check_every(1);
+        let err = PosetM::new_antichain(5).width_with(&mut ctx).unwrap_err();
+        assert_eq!(err.reason, CancelReason::Token);
+        assert_eq!(err.iterations, ctx.iterations());
+    }
+
+    #[test]
+    fn test_max_antichain_witness() {
+        for p in corpus() {
+            let a = p.max_antichain();
+            assert_eq!(a.len(), p.width());
+            assert!(a.iter().all(|&x| a.iter().all(|&y| !p.lt(x, y))));
+        }
+    }
+
+    #[test]
+    fn test_dedekind_macneille() {
+        // A lattice is its own completion.
+        for k in 1..4 {
+            let l = boolean_lattice(k);
+            assert!(l.dedekind_macneille().is_isomorphic(&l));
+        }
+        // An antichain gains a top and a bottom.
+        let mut diamond = PosetM::new_antichain(3);
+        diamond.adjoin_bot();
+        diamond.adjoin_top();
+        assert!(PosetM::new_antichain(3)
+            .dedekind_macneille()
+            .is_isomorphic(&diamond));
+        // Every completion is a lattice: any two cuts have a least upper bound.
+        for p in corpus() {
+            let l = p.dedekind_macneille();
+            assert!(l.md.n >= p.md.n.max(1));
+            for x in 0..l.md.n {
+                for y in 0..l.md.n {
+                    let ub: Vec<usize> = (0..l.md.n)
+                        .filter(|&z| l.leq(x, z) && l.leq(y, z))
+                        .collect();
+                    assert!(ub.iter().any(|&s| ub.iter().all(|&z| l.leq(s, z))));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_isomorphic_with() {
+        let mut ctx = AnalysisContext::new();
+        let p = random_poset(6, 30, 3);
+        assert!(is_isomorphic(&p, &p.op().op(), &mut ctx).unwrap());
+        assert!(!is_isomorphic(&p, &PosetM::new_chain(5), &mut ctx).unwrap());
+        assert!(ctx.iterations() > 0);
+    }
+
+    #[test]
+    fn test_cancel_from_another_thread() {
+        let token = AtomicBool::new(false);
+        let reached = AtomicU64::new(0);
+        let check_every = 64;
+        let p = PosetM::new_antichain(60);
+        let result = std::thread::scope(|s| {
+            let worker = s.spawn(|| {
+                let mut ctx = AnalysisContext::new()
+                    .with_cancel(&token)
+                    .with_check_every(check_every)
+                    .with_progress(|i| reached.store(i, Ordering::Relaxed));
+                p.count_linear_extensions_with(&mut ctx)
+            });
+            while reached.load(Ordering::Relaxed) < 10 * check_every {
+                std::thread::yield_now();
+            }
+            let set_at = reached.load(Ordering::Relaxed);
+            token.store(true, Ordering::Relaxed);
+            (worker.join().unwrap(), set_at)
+        });
+        let (err, set_at) = (result.0.unwrap_err(), result.1);
+        assert_eq!(err.reason, CancelReason::Token);
+        assert!(err.iterations.is_multiple_of(check_every));
+        // The worker stops at the first check after it observes the token.
+        assert!(err.iterations > set_at);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let p = PosetM::new_antichain(60);
+        let mut ctx =
+            AnalysisContext::new().with_deadline(Instant::now() + Duration::from_millis(20));
+        let err = p.count_linear_extensions_with(&mut ctx).unwrap_err();
+        assert_eq!(err.reason, CancelReason::Deadline);
+        // The canonical search settles an antichain in a few steps, so check at every one.
+        let mut ctx = AnalysisContext::new()
+            .with_check_every(1)
+            .with_deadline(Instant::now());
+        assert!(PosetM::new_antichain(9)
+            .is_isomorphic_with(&PosetM::new_antichain(9), &mut ctx)
+            .is_err());
+    }
+
+    #[test]
+    fn test_progress_is_monotone() {
+        let seen = RefCell::new(vec![]);
+        let p = random_poset(8, 20, 5);
+        {
+            let mut ctx = AnalysisContext::new()
+                .with_check_every(3)
+                .with_progress(|i| seen.borrow_mut().push(i));
+            p.count_linear_extensions_with(&mut ctx).unwrap();
+            p.width_with(&mut ctx).unwrap();
+            p.dedekind_macneille_with(&mut ctx).unwrap();
+        }
+        let seen = seen.into_inner();
+        assert!(!seen.is_empty());
+        assert!(seen.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_unhooked_context_matches_plain() {
+        for p in corpus() {
+            let mut ctx = AnalysisContext::new().with_check_every(1);
+            assert_eq!(p.width_with(&mut ctx), Ok(p.width()));
+            assert_eq!(
+                p.count_linear_extensions_with(&mut ctx),
+                Ok(p.count_linear_extensions())
+            );
+            assert_eq!(
+                p.dedekind_macneille_with(&mut ctx).unwrap(),
+                p.dedekind_macneille()
+            );
+            assert_eq!(
+                p.is_isomorphic_with(&p.op(), &mut ctx),
+                Ok(p.is_isomorphic(&p.op()))
+            );
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! Antichains of maximum total weight, and the enumeration and counting of all antichains.
+//!
+//! Given a weight $w(x) \ge 0$ for every element, the heaviest antichain is found with a maximum flow, as in
+//! Fulkerson's proof of Dilworth's theorem: every element $x$ is split into a left copy, fed from a source
+//! with capacity $w(x)$, and a right copy, draining into a sink with capacity $w(x)$, and the left copy of
+//! $x$ is joined to the right copy of $y$ with unbounded capacity whenever $x < y$. The heaviest antichain
+//! then weighs the total weight minus the maximum flow, and consists of the elements whose left copy, but
+//! not whose right copy, is on the source side of a minimum cut. With unit weights this is the width.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_corolla(3);
+//! assert_eq!(p.heaviest_antichain(&[1, 1, 1, 1]), (3, vec![0, 1, 2]));
+//! assert_eq!(p.heaviest_antichain(&[1, 1, 1, 5]), (5, vec![3]));
+//! ```
+//!
+//! The antichains of a poset correspond one to one to its down-sets (an antichain generates the down-set
+//! of the elements below it, and a down-set has its maximal elements as antichain), so
+//! [count_antichains] also counts the order ideals. There can be exponentially many: every subset of an
+//! antichain is one.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_corolla(3);
+//! assert_eq!(p.count_antichains(), 9);
+//! assert_eq!(p.antichains().count(), 9);
+//! ```
+
+use crate::bitset::{BitMatrix, BitRow};
+use crate::relation::strict_matrix;
+use crate::{AnElement, Elements, Poset};
+
+use std::collections::{HashMap, VecDeque};
+
+/// Returns the weight of a heaviest antichain of p together with its elements, sorted. Entry x of weights
+/// is the weight of element x. Among the heaviest antichains, the one returned is determined by the
+/// minimum cut found, and is the same for equal inputs.
+pub fn heaviest_antichain<P: Poset + ?Sized>(p: &P, weights: &[u64]) -> (u64, Vec<AnElement>) {
+    heaviest_strict(&strict_matrix(p), weights)
+}
+
+pub(crate) fn heaviest_strict(lt: &[Vec<bool>], weights: &[u64]) -> (u64, Vec<AnElement>) {
+    let n = lt.len();
+    assert_eq!(weights.len(), n, "one weight per element is needed");
+    let (source, sink) = (2 * n, 2 * n + 1);
+    let mut net = Network::new(2 * n + 2);
+    for x in 0..n {
+        net.add_edge(source, x, weights[x]);
+        net.add_edge(n + x, sink, weights[x]);
+        for y in (0..n).filter(|&y| lt[x][y]) {
+            net.add_edge(x, n + y, u64::MAX);
+        }
+    }
+    let flow = net.max_flow(source, sink);
+    let reach = net.reachable(source);
+    let antichain = (0..n).filter(|&x| reach[x] && !reach[n + x]).collect();
+    (weights.iter().sum::<u64>() - flow, antichain)
+}
+
+/// An iterator over all the antichains of a poset, the empty one included, in lexicographic order of their
+/// sorted elements.
+pub struct Antichains {
+    comparable: Vec<Vec<bool>>,
+    antichain: Vec<AnElement>,
+    /// The next candidate to try for the element after each prefix of antichain.
+    cursors: Vec<AnElement>,
+    started: bool,
+}
+
+impl Antichains {
+    pub fn new<P: Poset + ?Sized>(p: &P) -> Antichains {
+        let lt = strict_matrix(p);
+        let n = lt.len();
+        Antichains {
+            comparable: (0..n)
+                .map(|x| (0..n).map(|y| lt[x][y] || lt[y][x]).collect())
+                .collect(),
+            antichain: vec![],
+            cursors: vec![],
+            started: false,
+        }
+    }
+}
+
+impl Iterator for Antichains {
+    type Item = Elements;
+
+    fn next(&mut self) -> Option<Elements> {
+        if !self.started {
+            self.started = true;
+            self.cursors.push(0);
+            return Some(Elements::new());
+        }
+        let n = self.comparable.len();
+        while let Some(cursor) = self.cursors.last_mut() {
+            let fits = |y: &AnElement| self.antichain.iter().all(|&x| !self.comparable[x][*y]);
+            match (*cursor..n).find(fits) {
+                Some(y) => {
+                    *cursor = y + 1;
+                    self.antichain.push(y);
+                    self.cursors.push(y + 1);
+                    return Some(self.antichain.iter().copied().collect());
+                }
+                None => {
+                    self.cursors.pop();
+                    self.antichain.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Counts the antichains of p, the empty one included. The count branches on an element x of the
+/// remaining elements S, comparable to as many others in S as possible: the antichains of S either avoid
+/// x, or contain x and avoid everything comparable to it. The counts are memoized by S, and a remaining
+/// antichain of k elements is counted as $2^k$ at once. Panics if the count does not fit in a `u128`.
+pub fn count_antichains<P: Poset + ?Sized>(p: &P) -> u128 {
+    let b = BitMatrix::new(p);
+    let n = b.n();
+    let comparable: Vec<BitRow> = (0..n)
+        .map(|x| {
+            let mut r = b.up_row(x).clone();
+            r.or_assign(b.down_row(x));
+            r
+        })
+        .collect();
+    count_in(&BitRow::full(n), &comparable, &mut HashMap::new())
+}
+
+fn count_in(s: &BitRow, comparable: &[BitRow], memo: &mut HashMap<BitRow, u128>) -> u128 {
+    if let Some(&c) = memo.get(s) {
+        return c;
+    }
+    let degree = |x: usize| {
+        let mut r = comparable[x].clone();
+        r.and_assign(s);
+        r.count_ones()
+    };
+    let count = match s.iter_ones().max_by_key(|&x| degree(x)) {
+        None => 1,
+        Some(x) if degree(x) == 1 => 1u128
+            .checked_shl(s.count_ones() as u32)
+            .expect("the number of antichains does not fit in a u128"),
+        Some(x) => {
+            let mut without = s.clone();
+            without.unset(x);
+            let mut apart = s.clone();
+            let mut keep = comparable[x].clone();
+            keep.not_assign();
+            apart.and_assign(&keep);
+            count_in(&without, comparable, memo)
+                .checked_add(count_in(&apart, comparable, memo))
+                .expect("the number of antichains does not fit in a u128")
+        }
+    };
+    memo.insert(s.clone(), count);
+    count
+}
+
+/// A flow network for Dinic's algorithm. Edge 2i is an edge of the network and edge 2i+1 its reverse.
+struct Network {
+    head: Vec<Vec<usize>>,
+    to: Vec<usize>,
+    cap: Vec<u64>,
+}
+
+impl Network {
+    fn new(nodes: usize) -> Network {
+        Network {
+            head: vec![vec![]; nodes],
+            to: vec![],
+            cap: vec![],
+        }
+    }
+
+    fn add_edge(&mut self, u: usize, v: usize, c: u64) {
+        self.head[u].push(self.to.len());
+        self.to.push(v);
+        self.cap.push(c);
+        self.head[v].push(self.to.len());
+        self.to.push(u);
+        self.cap.push(0);
+    }
+
+    /// The distance of every node from s along edges with residual capacity, or None if unreachable.
+    fn distances(&self, s: usize) -> Vec<Option<usize>> {
+        let mut dist = vec![None; self.head.len()];
+        dist[s] = Some(0);
+        let mut queue = VecDeque::from([s]);
+        while let Some(u) = queue.pop_front() {
+            for &e in &self.head[u] {
+                let v = self.to[e];
+                if self.cap[e] > 0 && dist[v].is_none() {
+                    dist[v] = Some(dist[u].unwrap() + 1);
+                    queue.push_back(v);
+                }
+            }
+        }
+        dist
+    }
+
+    fn reachable(&self, s: usize) -> Vec<bool> {
+        self.distances(s).into_iter().map(|d| d.is_some()).collect()
+    }
+
+    fn max_flow(&mut self, s: usize, t: usize) -> u64 {
+        let mut flow = 0;
+        loop {
+            let dist = self.distances(s);
+            if dist[t].is_none() {
+                return flow;
+            }
+            let mut next = vec![0; self.head.len()];
+            loop {
+                let pushed = self.push(s, t, u64::MAX, &dist, &mut next);
+                if pushed == 0 {
+                    break;
+                }
+                flow += pushed;
+            }
+        }
+    }
+
+    /// Pushes at most limit units from u to t along the level graph, advancing past saturated edges.
+    fn push(
+        &mut self,
+        u: usize,
+        t: usize,
+        limit: u64,
+        dist: &[Option<usize>],
+        next: &mut [usize],
+    ) -> u64 {
+        if u == t {
+            return limit;
+        }
+        while next[u] < self.head[u].len() {
+            let e = self.head[u][next[u]];
+            let v = self.to[e];
+            if self.cap[e] > 0 && dist[v] == dist[u].map(|d| d + 1) {
+                let pushed = self.push(v, t, limit.min(self.cap[e]), dist, next);
+                if pushed > 0 {
+                    self.cap[e] -= pushed;
+                    self.cap[e ^ 1] += pushed;
+                    return pushed;
+                }
+            }
+            next[u] += 1;
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetm::PosetM;
+    use crate::rng::SplitMix64;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_against_brute_force() {
+        let mut rng = SplitMix64::new(11);
+        for p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let n = p.md.n;
+            for unit in [true, false] {
+                let weights: Vec<u64> = (0..n)
+                    .map(|_| if unit { 1 } else { rng.next_u64() % 6 })
+                    .collect();
+                let best = (0..1usize << n)
+                    .map(|mask| (0..n).filter(|i| mask >> i & 1 == 1).collect::<Vec<_>>())
+                    .filter(|s| s.iter().all(|&x| s.iter().all(|&y| !p.lt(x, y))))
+                    .map(|s| s.iter().map(|&x| weights[x]).sum::<u64>())
+                    .max()
+                    .unwrap();
+                let (w, a) = p.heaviest_antichain(&weights);
+                assert_eq!(w, best);
+                assert_eq!(a.iter().map(|&x| weights[x]).sum::<u64>(), w);
+                assert!(a.iter().all(|&x| a.iter().all(|&y| !p.lt(x, y))));
+                if unit {
+                    assert_eq!(w as usize, p.width());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_antichains() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 12) {
+            let n = p.md.n;
+            let all: Vec<Elements> = p.antichains().collect();
+            let sorted: Vec<Vec<AnElement>> = all
+                .iter()
+                .map(|a| {
+                    let mut v: Vec<_> = a.iter().copied().collect();
+                    v.sort_unstable();
+                    v
+                })
+                .collect();
+            assert!(sorted.is_sorted());
+            assert_eq!(sorted.iter().collect::<HashSet<_>>().len(), all.len());
+            assert!(all
+                .iter()
+                .all(|a| a.iter().all(|&x| a.iter().all(|&y| !p.lt(x, y)))));
+            let brute = (0..1usize << n)
+                .filter(|mask| {
+                    (0..n).all(|x| (0..n).all(|y| mask >> x & mask >> y & 1 == 0 || !p.lt(x, y)))
+                })
+                .count();
+            assert_eq!(all.len(), brute);
+            assert_eq!(p.count_antichains(), brute as u128);
+        }
+    }
+
+    #[test]
+    fn test_count_antichains_large() {
+        // A product of chains 5 x 8 has binomial(13, 5) down-sets.
+        let p = PosetM::new_chain(5).product(&PosetM::new_chain(8));
+        assert_eq!(p.count_antichains(), 1287);
+        assert_eq!(PosetM::new_antichain(100).count_antichains(), 1 << 100);
+        assert_eq!(PosetM::new_boolean(4).count_antichains(), 168);
+    }
+}
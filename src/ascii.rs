@@ -0,0 +1,127 @@
+//! Hasse diagrams drawn as text, for looking at small posets while debugging.
+//!
+//! The elements are written as their indices, on one row per level (the minimal elements at the bottom),
+//! at the horizontal positions computed by [layout](crate::layout). Between two rows, each cover joining
+//! them is drawn as `|`, `/`, or `\` halfway between its ends, and an `X` marks where two of them cross.
+//! Covers skipping a level cannot be drawn this way, and are listed below the diagram instead. This is the
+//! [Display](std::fmt::Display) form of [PosetM](crate::posetm::PosetM), [PosetG](crate::posetg::PosetG),
+//! and [PosetH](crate::poseth::PosetH).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let mut p = PosetM::new_antichain(2);
+//! p.adjoin_bot();
+//! p.adjoin_top();
+//! assert_eq!(p.to_string(), "  3\n / \\\n0   1\n \\ /\n  2\n");
+//! ```
+
+use crate::layout::{Layout, LayoutOptions};
+use crate::Poset;
+
+/// Draws the Hasse diagram of p, as described in the [module level documentation](self).
+pub fn hasse_diagram<P: Poset + ?Sized>(p: &P) -> String {
+    let Layout { coords, edges, .. } = p.hasse_layout(&LayoutOptions::default());
+    let n = coords.len();
+    if n == 0 {
+        return String::new();
+    }
+    let width = (n - 1).to_string().len();
+    let left = coords.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+    // Positions are multiples of a half, so doubling them gives whole steps, each as wide as a label and a
+    // space.
+    let column: Vec<usize> = coords
+        .iter()
+        .map(|c| ((c.0 - left) * 2.0).round() as usize * (width + 1))
+        .collect();
+    let level: Vec<usize> = coords.iter().map(|c| c.1.round() as usize).collect();
+    let center = |x: usize| column[x] + (x.to_string().len() - 1) / 2;
+    let top = level.iter().max().copied().unwrap_or(0);
+
+    let mut rows: Vec<String> = vec![];
+    for l in (0..=top).rev() {
+        let mut row: Vec<char> = vec![];
+        for x in (0..n).filter(|&x| level[x] == l) {
+            let label = x.to_string();
+            row.resize(row.len().max(column[x] + label.len()), ' ');
+            for (k, c) in label.chars().enumerate() {
+                row[column[x] + k] = c;
+            }
+        }
+        rows.push(row.into_iter().collect());
+        if l == 0 {
+            break;
+        }
+        let mut row: Vec<char> = vec![];
+        for &(x, y) in edges
+            .iter()
+            .filter(|&&(x, y)| level[x] + 1 == l && level[y] == l)
+        {
+            let (a, b) = (center(x), center(y));
+            let mid = (a + b) / 2;
+            let c = match a.cmp(&b) {
+                std::cmp::Ordering::Less => '/',
+                std::cmp::Ordering::Equal => '|',
+                std::cmp::Ordering::Greater => '\\',
+            };
+            row.resize(row.len().max(mid + 1), ' ');
+            row[mid] = match row[mid] {
+                ' ' => c,
+                d if d == c => c,
+                _ => 'X',
+            };
+        }
+        rows.push(row.into_iter().collect());
+    }
+
+    let long: Vec<String> = edges
+        .iter()
+        .filter(|&&(x, y)| level[y] > level[x] + 1)
+        .map(|(x, y)| format!("{} < {}", x, y))
+        .collect();
+    if !long.is_empty() {
+        rows.push(format!("also {}", long.join(", ")));
+    }
+    rows.iter().map(|r| format!("{}\n", r.trim_end())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_chain_and_antichain() {
+        assert_eq!(PosetG::new_chain(3).to_string(), "2\n|\n1\n|\n0\n");
+        assert_eq!(PosetH::new_antichain(3).to_string().lines().count(), 1);
+        assert_eq!(PosetM::new_chain(0).to_string(), "");
+    }
+
+    #[test]
+    fn test_long_covers_and_wide_labels() {
+        // 3 is minimal and covered by 2, which sits two levels up on the chain 0 < 1 < 2.
+        let m = vec![
+            vec![true, true, true, false],
+            vec![false, true, true, false],
+            vec![false, false, true, false],
+            vec![false, false, true, true],
+        ];
+        assert_eq!(
+            PosetM::new(&m).to_string(),
+            "  2\n  |\n  1\n /\n0   3\nalso 3 < 2\n"
+        );
+        assert!(PosetG::new_chain(11)
+            .to_string()
+            .starts_with("10\n|\n9\n|\n8\n"));
+        let mut p = PosetG::new_antichain(3);
+        p.adjoin_bot();
+        p.adjoin_top();
+        assert_eq!(
+            p.to_string(),
+            "    4\n  / | \\\n0   1   2\n  \\ | /\n    3\n"
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Bit-parallel storage of the order relation.
+//!
+//! A [BitMatrix] stores, for every element $x$, its up-set $\{y \mid x\le y\}$ and its down-set
+//! $\{y \mid y \le x\}$ as [BitRow]s, i.e., as bit vectors packed into 64-bit words. Set operations on rows
+//! then process 64 elements per machine instruction, which is what inner loops such as growing antichains
+//! need.
+//!
+//! ```
+//! use fin_pos::bitset::BitMatrix;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let b = BitMatrix::from(&PosetM::new_corolla(3));
+//! assert_eq!(b.incomparable_neighbors(0).collect::<Vec<_>>(), vec![1, 2]);
+//! assert_eq!(b.grow_antichain_from(0, &[3, 1, 2]).len(), 3);
+//! ```
+
+use crate::posetm::PosetM;
+use crate::{AnElement, Poset};
+
+use std::collections::HashSet;
+
+/// A set of elements of $\{0, ..., n-1\}$ stored as a bit vector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitRow {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitRow {
+    /// The empty set, as a subset of $\{0, ..., len-1\}$.
+    pub fn new(len: usize) -> BitRow {
+        BitRow {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// The full set $\{0, ..., len-1\}$.
+    pub fn full(len: usize) -> BitRow {
+        let mut r = BitRow {
+            len,
+            words: vec![!0; len.div_ceil(64)],
+        };
+        r.mask();
+        r
+    }
+
+    /// The size of the ambient set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the ambient set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Enlarges the ambient set to $\{0, ..., len-1\}$, leaving the new elements out of the set. Panics if
+    /// len is smaller than the current length.
+    pub fn grow(&mut self, len: usize) {
+        assert!(len >= self.len, "a row can only grow");
+        self.len = len;
+        self.words.resize(len.div_ceil(64), 0);
+    }
+
+    /// Returns true if i is in the set.
+    pub fn get(&self, i: usize) -> bool {
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    /// Adds i to the set.
+    pub fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Removes i from the set.
+    pub fn unset(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    /// The number of elements in the set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns true if the set has no elements.
+    pub fn none(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Intersects the set with another one of the same length.
+    pub fn and_assign(&mut self, other: &BitRow) {
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a &= b);
+    }
+
+    /// Unites the set with another one of the same length.
+    pub fn or_assign(&mut self, other: &BitRow) {
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    /// Replaces the set by its symmetric difference with another one of the same length.
+    pub fn xor_assign(&mut self, other: &BitRow) {
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a ^= b);
+    }
+
+    /// Replaces the set by its complement.
+    pub fn not_assign(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = !*w);
+        self.mask();
+    }
+
+    /// Returns true if every element of the set is in other.
+    pub fn is_subset(&self, other: &BitRow) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(a, b)| a & !b == 0)
+    }
+
+    /// Iterates over the elements of the set in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(k, &w)| word_ones(k, w))
+    }
+
+    /// Iterates over the elements of the set in increasing order, consuming the set.
+    pub fn into_ones(self) -> impl Iterator<Item = usize> {
+        self.words
+            .into_iter()
+            .enumerate()
+            .flat_map(|(k, w)| word_ones(k, w))
+    }
+
+    /// Clears the unused bits of the last word.
+    fn mask(&mut self) {
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+    }
+}
+
+/// The positions of the set bits of the k-th word w.
+fn word_ones(k: usize, mut w: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if w == 0 {
+            return None;
+        }
+        let i = w.trailing_zeros() as usize;
+        w &= w - 1;
+        Some(64 * k + i)
+    })
+}
+
+/// The order relation of a poset as rows (up-sets) and columns (down-sets) of bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    n: usize,
+    up: Vec<BitRow>,
+    down: Vec<BitRow>,
+}
+
+impl BitMatrix {
+    /// Builds the bit matrix of p, whose elements must be $0, ..., n-1$.
+    pub fn new<P: Poset + ?Sized>(p: &P) -> BitMatrix {
+        let n = p.elements().count();
+        BitMatrix::from_fn(n, |x, y| p.leq(x, y))
+    }
+
+    pub(crate) fn from_fn<F: Fn(usize, usize) -> bool>(n: usize, leq: F) -> BitMatrix {
+        let mut up = vec![BitRow::new(n); n];
+        let mut down = vec![BitRow::new(n); n];
+        for (x, row) in up.iter_mut().enumerate() {
+            for (y, col) in down.iter_mut().enumerate() {
+                if leq(x, y) {
+                    row.set(y);
+                    col.set(x);
+                }
+            }
+        }
+        BitMatrix { n, up, down }
+    }
+
+    /// The number of elements.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns true if $x \le y$, by a lookup in the up-set of x.
+    pub fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.up[x].get(y)
+    }
+
+    /// The up-set $\{y \mid x \le y\}$.
+    pub fn up_row(&self, x: AnElement) -> &BitRow {
+        &self.up[x]
+    }
+
+    /// The down-set $\{y \mid y \le x\}$.
+    pub fn down_row(&self, x: AnElement) -> &BitRow {
+        &self.down[x]
+    }
+
+    /// Appends a new element n, related only to itself, and returns it.
+    pub(crate) fn push(&mut self) -> AnElement {
+        let n = self.n;
+        self.n += 1;
+        for row in self.up.iter_mut().chain(self.down.iter_mut()) {
+            row.grow(n + 1);
+        }
+        let mut own = BitRow::new(n + 1);
+        own.set(n);
+        self.up.push(own.clone());
+        self.down.push(own);
+        n
+    }
+
+    /// Adds $x \le y$ and its transitive consequences, which must keep the relation antisymmetric: every
+    /// $a \le x$ not yet below y gains the up-set of y, and every $b \ge y$ not yet above x gains the
+    /// down-set of x. Only the rows that change are touched.
+    pub(crate) fn insert(&mut self, x: AnElement, y: AnElement) {
+        let (above, below) = (self.up[y].clone(), self.down[x].clone());
+        for a in below.iter_ones() {
+            if !self.up[a].get(y) {
+                self.up[a].or_assign(&above);
+            }
+        }
+        for b in above.iter_ones() {
+            if !self.down[b].get(x) {
+                self.down[b].or_assign(&below);
+            }
+        }
+    }
+
+    /// The matrix of the opposite order.
+    pub(crate) fn transpose(&self) -> BitMatrix {
+        BitMatrix {
+            n: self.n,
+            up: self.down.clone(),
+            down: self.up.clone(),
+        }
+    }
+
+    /// The elements incomparable to x, computed as the complement of the union of its up-set and down-set.
+    pub fn incomparable_row(&self, x: AnElement) -> BitRow {
+        let mut r = self.up[x].clone();
+        r.or_assign(&self.down[x]);
+        r.not_assign();
+        r
+    }
+
+    /// Iterates over the elements incomparable to x, in increasing order.
+    pub fn incomparable_neighbors(&self, x: AnElement) -> impl Iterator<Item = AnElement> {
+        self.incomparable_row(x).into_ones()
+    }
+
+    /// Greedily grows an antichain from seed: the elements of order are considered in turn, and each one
+    /// that is incomparable to everything chosen so far is added. The elements still eligible are kept as
+    /// the intersection of the incomparability rows of the chosen elements, so each step costs $O(n/64)$.
+    /// The result is a maximal antichain if order lists all the elements.
+    pub fn grow_antichain_from(&self, seed: AnElement, order: &[AnElement]) -> HashSet<usize> {
+        let mut chosen: HashSet<usize> = [seed].into_iter().collect();
+        let mut eligible = self.incomparable_row(seed);
+        for &y in order {
+            if eligible.get(y) {
+                chosen.insert(y);
+                eligible.and_assign(&self.incomparable_row(y));
+            }
+        }
+        chosen
+    }
+}
+
+impl From<&PosetM> for BitMatrix {
+    fn from(p: &PosetM) -> BitMatrix {
+        BitMatrix::from_fn(p.md.n, |x, y| p.m[x][y])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus, random_poset};
+
+    #[test]
+    fn test_bit_row() {
+        let mut r = BitRow::new(130);
+        for i in [0, 63, 64, 129] {
+            r.set(i);
+        }
+        assert_eq!(r.iter_ones().collect::<Vec<_>>(), vec![0, 63, 64, 129]);
+        assert_eq!(r.count_ones(), 4);
+        r.unset(63);
+        assert!(!r.get(63));
+        r.not_assign();
+        assert_eq!(r.count_ones(), 127);
+        assert!(r.is_subset(&BitRow::full(130)));
+        assert_eq!(BitRow::full(130).count_ones(), 130);
+        assert!(BitRow::new(5).none());
+        r.grow(200);
+        assert_eq!((r.len(), r.count_ones()), (200, 127));
+        r.set(199);
+        assert!(r.get(199) && !r.get(130));
+    }
+
+    #[test]
+    fn test_incomparable_rows() {
+        for p in corpus()
+            .into_iter()
+            .chain((0..4).map(|s| random_poset(100, 3, s)))
+        {
+            let b = BitMatrix::from(&p);
+            assert_eq!(b, BitMatrix::new(&p));
+            for x in p.elements() {
+                let expected: Vec<usize> = p.elements().filter(|&y| p.incomparable(x, y)).collect();
+                assert_eq!(b.incomparable_neighbors(x).collect::<Vec<_>>(), expected);
+                assert_eq!(
+                    b.incomparable_row(x).iter_ones().collect::<Vec<_>>(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_antichains() {
+        for p in corpus()
+            .into_iter()
+            .chain((0..4).map(|s| random_poset(80, 4, s)))
+        {
+            let b = BitMatrix::from(&p);
+            let order: Vec<usize> = (0..p.md.n).rev().collect();
+            for seed in p.elements() {
+                let a = b.grow_antichain_from(seed, &order);
+                assert!(a.contains(&seed));
+                for &x in a.iter() {
+                    for &y in a.iter() {
+                        assert!(x == y || !p.leq(x, y));
+                    }
+                }
+                for z in p.elements() {
+                    assert!(a.iter().any(|&x| p.leq(x, z) || p.leq(z, x)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_b4_width() {
+        let b4 = boolean_lattice(4);
+        let b = BitMatrix::from(&b4);
+        let mut order: Vec<usize> = (0..16).collect();
+        order.sort_by_key(|&i: &usize| (i.count_ones() as i32 - 2).abs());
+        let best = (0..16)
+            .map(|seed| b.grow_antichain_from(seed, &order).len())
+            .max();
+        assert_eq!(best, Some(6));
+    }
+}
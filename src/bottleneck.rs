@@ -0,0 +1,211 @@
+//! Structurally critical elements: those whose removal lowers the height or disconnects the poset.
+//!
+//! - An element is *height-critical* if it lies on every chain of maximum length, so that removing it
+//!   reduces the height. Writing $d(x)$ for the number of elements of a longest chain with top $x$ and
+//!   $c(x)$ for the number of elements of a longest chain with bottom $x$, the element $x$ lies on some
+//!   longest chain exactly when $d(x) + c(x) - 1$ is the height. It lies on every longest chain exactly
+//!   when, in addition, it is the only such element with its value of $d$.
+//! - An element is a *cut element* if removing it increases the number of connected components of the
+//!   poset, i.e., if it is an articulation point of the comparability graph.
+//! - An element is a *Hasse cut element* if it is an articulation point of the Hasse diagram. Removing
+//!   it disconnects the drawn diagram, though the remaining elements may still be connected through
+//!   relations that were not covers (e.g., the interior elements of a chain).
+
+use crate::relation::{colevels, cover_matrix, levels, strict_matrix};
+use crate::{AnElement, Poset};
+
+use std::collections::{HashMap, HashSet};
+
+/// The ways in which an element can be critical (see the [module level documentation](self)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BottleneckKind {
+    /// The element lies on every chain of maximum length.
+    HeightCritical,
+    /// The element is an articulation point of the comparability graph.
+    Cut,
+    /// The element is an articulation point of the Hasse diagram.
+    HasseCut,
+}
+
+/// Returns the elements lying on every chain of maximum length.
+pub fn height_critical_elements<P: Poset + ?Sized>(p: &P) -> HashSet<usize> {
+    height_critical(&strict_matrix(p))
+}
+
+/// Returns the elements whose removal increases the number of connected components.
+pub fn cut_elements<P: Poset + ?Sized>(p: &P) -> HashSet<usize> {
+    let lt = strict_matrix(p);
+    articulation_points(&symmetrize(&lt))
+}
+
+/// Returns the articulation points of the Hasse diagram.
+pub fn hasse_cut_elements<P: Poset + ?Sized>(p: &P) -> HashSet<usize> {
+    let lt = strict_matrix(p);
+    articulation_points(&symmetrize(&cover_matrix(&lt)))
+}
+
+/// Lists every critical element together with each of the ways it is critical, sorted by element.
+pub fn bottleneck_report<P: Poset + ?Sized>(p: &P) -> Vec<(AnElement, BottleneckKind)> {
+    let lt = strict_matrix(p);
+    let mut report: Vec<(AnElement, BottleneckKind)> = vec![];
+    for (kind, s) in [
+        (BottleneckKind::HeightCritical, height_critical(&lt)),
+        (BottleneckKind::Cut, articulation_points(&symmetrize(&lt))),
+        (
+            BottleneckKind::HasseCut,
+            articulation_points(&symmetrize(&cover_matrix(&lt))),
+        ),
+    ] {
+        report.extend(s.into_iter().map(|x| (x, kind)));
+    }
+    report.sort();
+    report
+}
+
+fn height_critical(lt: &[Vec<bool>]) -> HashSet<usize> {
+    let below = levels(lt);
+    let above = colevels(lt);
+    let height = below.iter().max().map_or(0, |l| l + 1);
+    let mut on_longest: HashMap<usize, Vec<usize>> = HashMap::new();
+    for x in 0..lt.len() {
+        if below[x] + above[x] + 1 == height {
+            on_longest.entry(below[x]).or_default().push(x);
+        }
+    }
+    on_longest
+        .into_values()
+        .filter(|xs| xs.len() == 1)
+        .map(|xs| xs[0])
+        .collect()
+}
+
+fn symmetrize(r: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let n = r.len();
+    (0..n)
+        .map(|i| (0..n).map(|j| r[i][j] || r[j][i]).collect())
+        .collect()
+}
+
+/// The articulation points of an undirected graph without loops, by Tarjan's depth-first search (with an
+/// explicit stack, so that long paths do not overflow the call stack).
+fn articulation_points(adj: &[Vec<bool>]) -> HashSet<usize> {
+    let n = adj.len();
+    let unseen = usize::MAX;
+    let mut disc = vec![unseen; n];
+    let mut low = vec![0; n];
+    let mut time = 0;
+    let mut cut = HashSet::new();
+    for root in 0..n {
+        if disc[root] != unseen {
+            continue;
+        }
+        disc[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        // Entries are (vertex, parent, next neighbour to examine).
+        let mut stack = vec![(root, unseen, 0)];
+        while let Some(&(v, parent, next)) = stack.last() {
+            if next < n {
+                stack.last_mut().unwrap().2 += 1;
+                let w = next;
+                if !adj[v][w] || w == parent {
+                    continue;
+                }
+                if disc[w] == unseen {
+                    disc[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    if v == root {
+                        root_children += 1;
+                    }
+                    stack.push((w, v, 0));
+                } else {
+                    low[v] = low[v].min(disc[w]);
+                }
+            } else {
+                stack.pop();
+                if let Some(&(u, _, _)) = stack.last() {
+                    low[u] = low[u].min(low[v]);
+                    if u != root && low[v] >= disc[u] {
+                        cut.insert(u);
+                    }
+                }
+            }
+        }
+        if root_children > 1 {
+            cut.insert(root);
+        }
+    }
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    fn set(v: &[usize]) -> HashSet<usize> {
+        v.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_chain() {
+        let p = PosetG::new_chain(5);
+        assert_eq!(p.height_critical_elements(), set(&[0, 1, 2, 3, 4]));
+        assert_eq!(p.cut_elements(), set(&[]));
+        assert_eq!(hasse_cut_elements(&p), set(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_diamond() {
+        let m = vec![
+            vec![true, true, true, true],
+            vec![false, true, false, true],
+            vec![false, false, true, true],
+            vec![false, false, false, true],
+        ];
+        let p = PosetM::new(&m);
+        assert_eq!(p.height_critical_elements(), set(&[0, 3]));
+        assert_eq!(p.cut_elements(), set(&[]));
+        assert_eq!(
+            p.bottleneck_report(),
+            vec![
+                (0, BottleneckKind::HeightCritical),
+                (3, BottleneckKind::HeightCritical)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_corolla() {
+        let p = PosetM::new_corolla(3);
+        assert_eq!(p.cut_elements(), set(&[3]));
+        assert_eq!(hasse_cut_elements(&p), set(&[3]));
+        assert_eq!(p.height_critical_elements(), set(&[3]));
+        assert_eq!(
+            p.bottleneck_report(),
+            vec![
+                (3, BottleneckKind::HeightCritical),
+                (3, BottleneckKind::Cut),
+                (3, BottleneckKind::HasseCut)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Two vees 0 < 1, 0 < 2 and 3 < 4, 3 < 5: both bottoms are cut elements.
+        let mut m = vec![vec![false; 6]; 6];
+        for (x, y) in [(0, 1), (0, 2), (3, 4), (3, 5)] {
+            m[x][y] = true;
+        }
+        for (x, row) in m.iter_mut().enumerate() {
+            row[x] = true;
+        }
+        let p = PosetM::new(&m);
+        assert_eq!(p.cut_elements(), set(&[0, 3]));
+        assert_eq!(p.height_critical_elements(), set(&[]));
+    }
+}
@@ -0,0 +1,382 @@
+//! Canonical forms of posets: keys that coincide precisely for isomorphic posets.
+//!
+//! Relabeling the elements of a poset along a linear extension makes its strict order relation upper
+//! triangular. The canonical labeling is found by individualization and refinement: the elements are
+//! colored by their levels, and the colors are refined until elements of the same color have equally many
+//! elements of each color below and above them. While some color is shared, the search branches on which
+//! of its elements comes first and refines again. Every branch ends in a labeling along a linear
+//! extension, and the canonical one is the branch whose upper triangle, read row by row, is
+//! lexicographically largest. Isomorphic posets have the same set of such triangles, and hence the same
+//! canonical form.
+//!
+//! Refinement prunes most of the branches, and elements with the same elements below and above them, which
+//! an automorphism swaps, are only branched on once. Posets with many symmetries that no refinement sees,
+//! such as large Boolean lattices, can still take exponential time.
+//!
+//! [struct_hash] stops before the search: it hashes the colors that refinement reaches from the levels and
+//! the numbers of upper and lower covers, with the elements of each color below and above them. It takes
+//! polynomial time and agrees on isomorphic posets, but posets that refinement cannot tell apart collide,
+//! so it serves to sort many posets into buckets whose members are then compared by their canonical forms.
+//!
+//! ```
+//! use fin_pos::canonical::CanonicalPoset;
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let mut vee = PosetG::new_antichain(2);
+//! vee.adjoin_bot();
+//! let mut caret = PosetM::new_antichain(2);
+//! caret.adjoin_top();
+//! assert_eq!(CanonicalPoset::new(&vee), CanonicalPoset::new(&caret.op()));
+//! assert_ne!(CanonicalPoset::new(&vee), CanonicalPoset::new(&caret));
+//!
+//! // The canonical forms of isomorphic posets have the same order.
+//! assert!(vee.canonical_form().same_order(&caret.op().canonical_form()));
+//! assert_eq!(vee.struct_hash(), caret.op().struct_hash());
+//! ```
+
+use crate::analysis::{uncancelled, AnalysisContext, Cancelled};
+use crate::convertors::FromPoset;
+use crate::parallel::map_range;
+use crate::posetm::PosetM;
+use crate::relation::{cover_matrix, levels, strict_matrix};
+use crate::rng::mix;
+use crate::{AnElement, BoolMatrix, Poset};
+
+/// The canonical form of a poset, usable as a key in hash maps and ordered collections.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone)]
+pub struct CanonicalPoset {
+    n: usize,
+    /// The upper triangle of the canonically labeled strict order relation, row by row.
+    code: Vec<bool>,
+}
+
+impl CanonicalPoset {
+    /// Computes the canonical form of p.
+    pub fn new<P: Poset + ?Sized>(p: &P) -> CanonicalPoset {
+        CanonicalPoset::from_strict(&strict_matrix(p))
+    }
+
+    /// Computes the canonical form of p, reporting to ctx at every node of the search (see [analysis]).
+    ///
+    /// [analysis]: crate::analysis
+    pub fn new_with<P: Poset + ?Sized>(
+        p: &P,
+        ctx: &mut AnalysisContext,
+    ) -> Result<CanonicalPoset, Cancelled> {
+        CanonicalPoset::from_strict_with(&strict_matrix(p), ctx)
+    }
+
+    /// Computes the canonical form of the poset whose strict order relation is lt.
+    pub(crate) fn from_strict(lt: &[Vec<bool>]) -> CanonicalPoset {
+        uncancelled(CanonicalPoset::from_strict_with(
+            lt,
+            &mut AnalysisContext::new(),
+        ))
+    }
+
+    fn from_strict_with(
+        lt: &[Vec<bool>],
+        ctx: &mut AnalysisContext,
+    ) -> Result<CanonicalPoset, Cancelled> {
+        Ok(canonical_labeling(lt, ctx)?.0)
+    }
+
+    /// The number of elements.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns true if $i < j$ in the canonically labeled poset. The canonical labeling is a linear
+    /// extension, so this can only hold when $i < j$ as numbers.
+    pub fn lt(&self, i: usize, j: usize) -> bool {
+        i < j && self.code[triangle_index(self.n, i, j)]
+    }
+}
+
+/// The position of the entry $(i, j)$, with $i < j$, in the row-by-row reading of the upper triangle.
+fn triangle_index(n: usize, i: usize, j: usize) -> usize {
+    i * n - i * (i + 1) / 2 + (j - i - 1)
+}
+
+/// Returns the canonically labeled isomorph of p, together with the element of p behind each of its
+/// elements. Pinned handles follow their elements.
+pub fn canonical_form<P, Q>(p: &P) -> (Q, Vec<AnElement>)
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let lt = strict_matrix(p);
+    let (c, back) = uncancelled(canonical_labeling(&lt, &mut AnalysisContext::new()));
+    let n = c.n();
+    let m: BoolMatrix = (0..n)
+        .map(|i| (0..n).map(|j| i == j || c.lt(i, j)).collect())
+        .collect();
+    let mut position = vec![0; n];
+    back.iter().enumerate().for_each(|(i, &x)| position[x] = i);
+    let mut q = PosetM::new(&m);
+    q.md.handles = p.md().handles.remapped(|x| Some(position[x]));
+    (Q::from_poset(&q), back)
+}
+
+/// Returns a hash of p that is the same for isomorphic posets, as described in the [module level
+/// documentation](self). The hash does not depend on the platform or on the representation of p.
+pub fn struct_hash<P: Poset + ?Sized>(p: &P) -> u64 {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+    let level = levels(&lt);
+    let keys: Vec<(usize, usize, usize)> = (0..n)
+        .map(|x| {
+            let lower = (0..n).filter(|&y| covers[y][x]).count();
+            let upper = (0..n).filter(|&y| covers[x][y]).count();
+            (level[x], lower, upper)
+        })
+        .collect();
+    let mut color = ranks(&keys);
+    refine(&lt, &mut color);
+    let mut signatures: Vec<_> = (0..n)
+        .map(|x| {
+            let mut below: Vec<usize> = (0..n).filter(|&y| lt[y][x]).map(|y| color[y]).collect();
+            let mut above: Vec<usize> = (0..n).filter(|&y| lt[x][y]).map(|y| color[y]).collect();
+            below.sort_unstable();
+            above.sort_unstable();
+            (color[x], keys[x], below, above)
+        })
+        .collect();
+    signatures.sort_unstable();
+    let absorb = |h: u64, v: usize| mix(h ^ v as u64);
+    signatures
+        .iter()
+        .fold(absorb(0, n), |h, (c, (l, lower, upper), below, above)| {
+            let h = [*c, *l, *lower, *upper, below.len(), above.len()]
+                .into_iter()
+                .fold(h, absorb);
+            below.iter().chain(above).copied().fold(h, absorb)
+        })
+}
+
+/// Returns the canonical form of the poset whose strict order relation is lt, and the element of that
+/// poset labeled $i$, for every $i$.
+fn canonical_labeling(
+    lt: &[Vec<bool>],
+    ctx: &mut AnalysisContext,
+) -> Result<(CanonicalPoset, Vec<usize>), Cancelled> {
+    colored_labeling(lt, &vec![0; lt.len()], ctx)
+}
+
+/// Like [canonical_labeling], but for the poset with its elements colored: only the labelings in which
+/// the colors of the elements come in increasing order within each level compete, so isomorphisms between
+/// colored posets, which must keep the colors, map elements to elements with the same labels.
+pub(crate) fn colored_labeling(
+    lt: &[Vec<bool>],
+    colors: &[usize],
+    ctx: &mut AnalysisContext,
+) -> Result<(CanonicalPoset, Vec<usize>), Cancelled> {
+    let n = lt.len();
+    let keys: Vec<(usize, usize)> = levels(lt).into_iter().zip(colors.iter().copied()).collect();
+    let mut color = ranks(&keys);
+    refine(lt, &mut color);
+    let mut best = None;
+    search(lt, color, &mut best, ctx)?;
+    let (code, order) = best.unwrap_or_default();
+    Ok((CanonicalPoset { n, code }, order))
+}
+
+/// Colors the elements by the positions at which their keys first appear in the sorted keys, so that a
+/// color shared by k elements is followed by k - 1 unused colors.
+fn ranks<K: Ord>(keys: &[K]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|&x, &y| keys[x].cmp(&keys[y]));
+    let mut color = vec![0; keys.len()];
+    for (i, w) in order.windows(2).enumerate() {
+        color[w[1]] = if keys[w[0]] == keys[w[1]] {
+            color[w[0]]
+        } else {
+            i + 1
+        };
+    }
+    color
+}
+
+/// Splits the colors until elements of the same color have the same colors, with multiplicities, below
+/// and above them. The colors only ever split, keeping their relative order.
+fn refine(lt: &[Vec<bool>], color: &mut Vec<usize>) {
+    let n = lt.len();
+    let count = |color: &[usize]| {
+        let mut colors = color.to_vec();
+        colors.sort_unstable();
+        colors.dedup();
+        colors.len()
+    };
+    loop {
+        let signatures: Vec<(usize, Vec<usize>, Vec<usize>)> = map_range(n, |x| {
+            let mut below: Vec<usize> = (0..n).filter(|&y| lt[y][x]).map(|y| color[y]).collect();
+            let mut above: Vec<usize> = (0..n).filter(|&y| lt[x][y]).map(|y| color[y]).collect();
+            below.sort_unstable();
+            above.sort_unstable();
+            (color[x], below, above)
+        });
+        let refined = ranks(&signatures);
+        let done = count(&refined) == count(color);
+        *color = refined;
+        if done {
+            return;
+        }
+    }
+}
+
+fn search(
+    lt: &[Vec<bool>],
+    color: Vec<usize>,
+    best: &mut Option<(Vec<bool>, Vec<usize>)>,
+    ctx: &mut AnalysisContext,
+) -> Result<(), Cancelled> {
+    ctx.tick()?;
+    let n = lt.len();
+    let mut size = vec![0; n];
+    color.iter().for_each(|&c| size[c] += 1);
+    let Some(target) = (0..n).find(|&c| size[c] > 1) else {
+        let mut order = vec![0; n];
+        color.iter().enumerate().for_each(|(x, &c)| order[c] = x);
+        let code: Vec<bool> = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .map(|(i, j)| lt[order[i]][order[j]])
+            .collect();
+        if best.as_ref().is_none_or(|(b, _)| code > *b) {
+            *best = Some((code, order));
+        }
+        return Ok(());
+    };
+    let twins = |u: usize, v: usize| (0..n).all(|w| lt[w][u] == lt[w][v] && lt[u][w] == lt[v][w]);
+    let mut tried: Vec<usize> = vec![];
+    for v in (0..n).filter(|&v| color[v] == target) {
+        // Swapping twins is an automorphism that keeps the coloring, so one of them suffices.
+        if tried.iter().any(|&u| twins(u, v)) {
+            continue;
+        }
+        tried.push(v);
+        let mut individualized = color.clone();
+        for (x, c) in individualized.iter_mut().enumerate() {
+            if *c == target && x != v {
+                *c = target + 1;
+            }
+        }
+        refine(lt, &mut individualized);
+        search(lt, individualized, best, ctx)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetg::PosetG;
+    use crate::rng::SplitMix64;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_relabeling() {
+        // 0 < 1 < 2 and 3 < 2, relabeled by the permutation (0 2)(1 3).
+        let m = vec![
+            vec![true, true, true, false],
+            vec![false, true, true, false],
+            vec![false, false, true, false],
+            vec![false, false, true, true],
+        ];
+        let perm = [2, 3, 0, 1];
+        let m_perm = (0..4)
+            .map(|i| (0..4).map(|j| m[perm[i]][perm[j]]).collect())
+            .collect();
+        let c = CanonicalPoset::new(&PosetM::new(&m));
+        assert_eq!(c, CanonicalPoset::new(&PosetM::new(&m_perm)));
+        assert_eq!(c.n(), 4);
+        assert_ne!(c, CanonicalPoset::new(&PosetM::new_chain(4)));
+    }
+
+    #[test]
+    fn test_across_representations() {
+        assert_eq!(
+            CanonicalPoset::new(&PosetG::new_chain(3)),
+            CanonicalPoset::new(&PosetM::new_chain(3))
+        );
+        let c = CanonicalPoset::new(&PosetG::new_chain(3));
+        assert!(c.lt(0, 1) && c.lt(1, 2) && c.lt(0, 2) && !c.lt(1, 0));
+        let a = CanonicalPoset::new(&PosetG::new_antichain(3));
+        assert!(!a.lt(0, 1) && !a.lt(0, 2) && !a.lt(1, 2));
+        assert_eq!(CanonicalPoset::new(&PosetG::new_antichain(0)).n(), 0);
+    }
+
+    fn relabeled(p: &PosetM, perm: &[usize]) -> PosetM {
+        let n = perm.len();
+        let m = (0..n)
+            .map(|i| (0..n).map(|j| p.leq(perm[i], perm[j])).collect())
+            .collect();
+        PosetM::new(&m)
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let mut rng = SplitMix64::new(5);
+        for p in corpus().into_iter().chain([boolean_lattice(4)]) {
+            let n = p.md.n;
+            let (q, back): (PosetM, _) = canonical_form(&p);
+            let c = CanonicalPoset::new(&p);
+            assert_eq!(CanonicalPoset::new(&q), c);
+            for i in 0..n {
+                for j in 0..n {
+                    assert_eq!(q.leq(i, j), p.leq(back[i], back[j]));
+                    assert_eq!(q.lt(i, j), c.lt(i, j));
+                }
+            }
+            let mut perm: Vec<usize> = (0..n).collect();
+            for i in (1..n).rev() {
+                perm.swap(i, rng.below(i + 1));
+            }
+            let r = relabeled(&p, &perm);
+            assert_eq!(CanonicalPoset::new(&r), c);
+            assert_eq!(struct_hash(&r), struct_hash(&p));
+            assert!(r.canonical_form().same_order(&q));
+        }
+    }
+
+    #[test]
+    fn test_posets_on_four_elements() {
+        // Every reflexive relation on 4 elements, deduplicated up to isomorphism: there are 16 posets.
+        let pairs: Vec<(usize, usize)> = (0..4)
+            .flat_map(|i| (0..4).map(move |j| (i, j)))
+            .filter(|(i, j)| i != j)
+            .collect();
+        let posets: Vec<PosetM> = (0..1u32 << pairs.len())
+            .map(|mask| {
+                let mut m = vec![vec![false; 4]; 4];
+                (0..4).for_each(|i| m[i][i] = true);
+                for (k, &(i, j)) in pairs.iter().enumerate() {
+                    m[i][j] = mask >> k & 1 == 1;
+                }
+                PosetM::new(&m)
+            })
+            .filter(|p| p.is_valid_poset().is_ok())
+            .collect();
+        let classes: HashSet<CanonicalPoset> = posets.iter().map(CanonicalPoset::new).collect();
+        assert_eq!(classes.len(), 16);
+        // Refinement tells these apart, so the hashes separate the classes as well.
+        let hashes: HashSet<u64> = posets.iter().map(struct_hash).collect();
+        assert_eq!(hashes.len(), 16);
+    }
+
+    #[test]
+    fn test_symmetric_posets_are_fast() {
+        // Without refinement and twins, each of these would visit a factorial number of labelings.
+        let c = CanonicalPoset::new(&PosetM::new_antichain(30));
+        assert!((0..30).all(|j| !c.lt(0, j)));
+        let (j, _) = PosetM::new_chain(4)
+            .coproduct(&PosetM::new_antichain(4))
+            .ideal_lattice();
+        assert!(j.is_isomorphic(&j.canonical_form()));
+        assert!(CanonicalPoset::new(&PosetM::new_corolla(20)).lt(0, 20));
+    }
+}
@@ -0,0 +1,149 @@
+//! Censuses of small induced subposets ("motifs").
+//!
+//! For a fixed $k$, every $k$-element subset of a poset induces a subposet, and the census counts how many
+//! subsets induce each isomorphism type, keyed by [CanonicalPoset]. The exact census visits all
+//! $\binom{n}{k}$ subsets and canonicalizes each one, so it is meant for small $k$ (typically 3 or 4). For
+//! larger posets, [motif_census_sampled] estimates the census from uniformly random subsets.
+//!
+//! ```
+//! use fin_pos::canonical::CanonicalPoset;
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::Poset;
+//!
+//! let census = PosetG::new_chain(5).motif_census(3);
+//! assert_eq!(census.len(), 1);
+//! assert_eq!(census[&CanonicalPoset::new(&PosetG::new_chain(3))], 10);
+//! ```
+
+use crate::canonical::CanonicalPoset;
+use crate::relation::strict_matrix;
+use crate::rng::SplitMix64;
+use crate::Poset;
+
+use std::collections::HashMap;
+
+/// Counts, for every isomorphism type of $k$-element poset, the $k$-element subsets of p inducing it.
+pub fn motif_census<P: Poset + ?Sized>(p: &P, k: usize) -> HashMap<CanonicalPoset, u64> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let mut census = HashMap::new();
+    if k > n {
+        return census;
+    }
+    let mut subset: Vec<usize> = (0..k).collect();
+    loop {
+        *census.entry(induced(&lt, &subset)).or_insert(0) += 1;
+        // Advance to the next k-subset in lexicographic order.
+        match (0..k).rev().find(|&i| subset[i] < n - k + i) {
+            Some(i) => {
+                subset[i] += 1;
+                for j in i + 1..k {
+                    subset[j] = subset[j - 1] + 1;
+                }
+            }
+            None => return census,
+        }
+    }
+}
+
+/// Estimates the census of p from the given number of uniformly random $k$-element subsets. The estimates
+/// are scaled to $\binom{n}{k}$ subsets, so they are directly comparable to [motif_census]. The same seed
+/// always gives the same estimate.
+pub fn motif_census_sampled<P: Poset + ?Sized>(
+    p: &P,
+    k: usize,
+    samples: usize,
+    seed: u64,
+) -> HashMap<CanonicalPoset, f64> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let mut counts: HashMap<CanonicalPoset, u64> = HashMap::new();
+    if k > n || samples == 0 {
+        return HashMap::new();
+    }
+    let mut rng = SplitMix64::new(seed);
+    let mut pool: Vec<usize> = (0..n).collect();
+    for _ in 0..samples {
+        // A partial Fisher-Yates shuffle puts a uniform k-subset at the front of the pool.
+        for i in 0..k {
+            let j = i + rng.below(n - i);
+            pool.swap(i, j);
+        }
+        let mut subset = pool[..k].to_vec();
+        subset.sort_unstable();
+        *counts.entry(induced(&lt, &subset)).or_insert(0) += 1;
+    }
+    let scale = binomial(n, k) / samples as f64;
+    counts
+        .into_iter()
+        .map(|(c, m)| (c, m as f64 * scale))
+        .collect()
+}
+
+fn induced(lt: &[Vec<bool>], subset: &[usize]) -> CanonicalPoset {
+    let sub: Vec<Vec<bool>> = subset
+        .iter()
+        .map(|&i| subset.iter().map(|&j| lt[i][j]).collect())
+        .collect();
+    CanonicalPoset::from_strict(&sub)
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::boolean_lattice;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    fn key(m: Vec<Vec<bool>>) -> CanonicalPoset {
+        CanonicalPoset::new(&PosetM::new(&m))
+    }
+
+    #[test]
+    fn test_chain_and_antichain() {
+        let census = PosetM::new_chain(7).motif_census(3);
+        assert_eq!(census.len(), 1);
+        assert_eq!(census[&CanonicalPoset::new(&PosetM::new_chain(3))], 35);
+
+        let census = PosetG::new_antichain(6).motif_census(4);
+        assert_eq!(census.len(), 1);
+        assert_eq!(census[&CanonicalPoset::new(&PosetG::new_antichain(4))], 15);
+
+        assert!(PosetG::new_chain(2).motif_census(3).is_empty());
+    }
+
+    #[test]
+    fn test_b3_census() {
+        let census = boolean_lattice(3).motif_census(3);
+        let (t, f) = (true, false);
+        let vee = key(vec![vec![t, t, t], vec![f, t, f], vec![f, f, t]]);
+        let caret = key(vec![vec![t, f, t], vec![f, t, t], vec![f, f, t]]);
+        let chain_and_point = key(vec![vec![t, t, f], vec![f, t, f], vec![f, f, t]]);
+        let chain = CanonicalPoset::new(&PosetM::new_chain(3));
+        let antichain = CanonicalPoset::new(&PosetM::new_antichain(3));
+
+        assert_eq!(census.len(), 5);
+        assert_eq!(census[&chain], 18);
+        assert_eq!(census[&antichain], 2);
+        assert_eq!(census[&vee], 12);
+        assert_eq!(census[&caret], 12);
+        assert_eq!(census[&chain_and_point], 12);
+    }
+
+    #[test]
+    fn test_sampled_census() {
+        let p = boolean_lattice(4);
+        let exact = p.motif_census(3);
+        let sampled = motif_census_sampled(&p, 3, 20000, 7);
+        let total = binomial(16, 3);
+        for (c, &m) in exact.iter() {
+            let estimate = sampled.get(c).copied().unwrap_or(0.0);
+            assert!((estimate - m as f64).abs() < 0.05 * total);
+        }
+        assert_eq!(sampled, motif_census_sampled(&p, 3, 20000, 7));
+    }
+}
@@ -0,0 +1,322 @@
+//! Chains, i.e., sets of pairwise comparable elements, and the order complex they form.
+//!
+//! Chains are listed from the bottom up. The chains of a poset are the faces of its order complex, a
+//! simplicial complex whose $(k-1)$-dimensional faces are the chains of $k$ elements, so that
+//! [count_chains_by_length] is its face vector (with the empty face in front). Maximal chains, which
+//! cannot be extended, are its facets; they are the paths along the covers from a minimal to a maximal
+//! element.
+//!
+//! Writing $f_{i-1}$ for the number of chains of i elements, so that $(f_{-1}, f_0, ..., f_{d-1})$ is the
+//! *f-vector* of the order complex of dimension $d - 1$, where d is the height, its *h-vector*
+//! $(h_0, ..., h_d)$ is defined by $\sum_i f_{i-1} (t - 1)^{d-i} = \sum_i h_i t^{d-i}$. It is nonnegative for
+//! Cohen–Macaulay complexes, such as the order complexes of distributive lattices. The *zeta polynomial*
+//! $Z(P, k)$ counts the multichains $x_1 \le x_2 \le \cdots \le x_{k-1}$ for $k \ge 2$, and is the
+//! polynomial $\sum_{i \ge 1} f_{i-1} \binom{k-2}{i-1}$ in k, so that $Z(P, 2) = n$. For a poset with a
+//! bottom and a top, $Z(P, -1) = \mu(\hat 0, \hat 1)$.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_corolla(2);
+//! let chains: Vec<Vec<usize>> = p.chains().collect();
+//! assert_eq!(chains, vec![vec![], vec![0], vec![1], vec![2], vec![2, 0], vec![2, 1]]);
+//! assert_eq!(p.maximal_chains(), vec![vec![2, 0], vec![2, 1]]);
+//! assert_eq!(p.count_chains_by_length(), vec![1, 3, 2]);
+//! assert_eq!(p.h_vector(), vec![1, 1, 0]);
+//! // The multichains x <= y: three of equal elements and two of the root below a leaf.
+//! assert_eq!(p.zeta_polynomial(3), 5);
+//! ```
+
+use crate::relation::{strict_matrix, topological_order};
+use crate::{AnElement, Poset};
+
+/// An iterator over all the chains of a poset, the empty chain included, in lexicographic order of their
+/// bottom-up listings.
+pub struct Chains {
+    /// The candidates for the first element, and the elements above each element, in increasing order.
+    all: Vec<AnElement>,
+    above: Vec<Vec<AnElement>>,
+    chain: Vec<AnElement>,
+    /// For each length up to that of chain, the next candidate to try for the following element.
+    cursors: Vec<usize>,
+    started: bool,
+}
+
+impl Chains {
+    pub fn new<P: Poset + ?Sized>(p: &P) -> Chains {
+        let lt = strict_matrix(p);
+        let n = lt.len();
+        Chains {
+            all: (0..n).collect(),
+            above: (0..n)
+                .map(|x| (0..n).filter(|&y| lt[x][y]).collect())
+                .collect(),
+            chain: vec![],
+            cursors: vec![],
+            started: false,
+        }
+    }
+}
+
+impl Iterator for Chains {
+    type Item = Vec<AnElement>;
+
+    fn next(&mut self) -> Option<Vec<AnElement>> {
+        if !self.started {
+            self.started = true;
+            self.cursors.push(0);
+            return Some(vec![]);
+        }
+        if self.cursors.is_empty() {
+            return None;
+        }
+        loop {
+            let depth = self.cursors.len() - 1;
+            let candidates = match self.chain.last() {
+                None => &self.all,
+                Some(&x) => &self.above[x],
+            };
+            if let Some(&y) = candidates.get(self.cursors[depth]) {
+                self.cursors[depth] += 1;
+                self.chain.push(y);
+                self.cursors.push(0);
+                return Some(self.chain.clone());
+            }
+            self.cursors.pop();
+            // Past the last first element, the iteration is over.
+            self.chain.pop()?;
+        }
+    }
+}
+
+/// Returns the maximal chains of p, i.e., the paths along the covers from a minimal to a maximal element,
+/// in lexicographic order.
+pub fn maximal_chains<P: Poset + ?Sized>(p: &P) -> Vec<Vec<AnElement>> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers: Vec<Vec<AnElement>> = (0..n)
+        .map(|x| {
+            (0..n)
+                .filter(|&y| lt[x][y] && !(0..n).any(|z| lt[x][z] && lt[z][y]))
+                .collect()
+        })
+        .collect();
+    let mut chains = vec![];
+    let mut stack: Vec<Vec<AnElement>> = (0..n)
+        .rev()
+        .filter(|&x| (0..n).all(|y| !lt[y][x]))
+        .map(|x| vec![x])
+        .collect();
+    while let Some(chain) = stack.pop() {
+        let last = *chain.last().unwrap();
+        if covers[last].is_empty() {
+            chains.push(chain);
+            continue;
+        }
+        for &y in covers[last].iter().rev() {
+            stack.push([chain.as_slice(), &[y]].concat());
+        }
+    }
+    chains
+}
+
+/// Returns the number of chains of p of every size $k = 0, 1, ..., h$, where $h$ is the height, counted by
+/// dynamic programming along a linear extension in $O(n^2 h)$ steps. Panics if a count does not fit in a
+/// `u64`, as happens from a chain of 68 elements on.
+pub fn count_chains_by_length<P: Poset + ?Sized>(p: &P) -> Vec<u64> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    // ending[x][k] is the number of chains of k + 1 elements with top x.
+    let mut ending: Vec<Vec<u64>> = vec![vec![]; n];
+    for y in topological_order(&lt) {
+        let mut counts = vec![1];
+        for x in (0..n).filter(|&x| lt[x][y]) {
+            if counts.len() < ending[x].len() + 1 {
+                counts.resize(ending[x].len() + 1, 0);
+            }
+            for (k, &c) in ending[x].iter().enumerate() {
+                counts[k + 1] = add(counts[k + 1], c);
+            }
+        }
+        ending[y] = counts;
+    }
+    let mut total = vec![1];
+    for counts in ending {
+        if total.len() < counts.len() + 1 {
+            total.resize(counts.len() + 1, 0);
+        }
+        for (k, c) in counts.into_iter().enumerate() {
+            total[k + 1] = add(total[k + 1], c);
+        }
+    }
+    total
+}
+
+/// Adds two chain counts, panicking on overflow.
+fn add(a: u64, b: u64) -> u64 {
+    a.checked_add(b)
+        .expect("the number of chains does not fit in a u64")
+}
+
+/// Returns the f-vector $(f_{-1}, f_0, ..., f_{d-1})$ of the order complex of p, i.e., the number of
+/// chains of every size from 0 to the height d (see [count_chains_by_length]).
+pub fn f_vector<P: Poset + ?Sized>(p: &P) -> Vec<u64> {
+    count_chains_by_length(p)
+}
+
+/// Returns the h-vector $(h_0, ..., h_d)$ of the order complex of p, where
+/// $h_k = \sum_{i=0}^k (-1)^{k-i} \binom{d-i}{k-i} f_{i-1}$. Panics if an entry does not fit in an `i64`.
+pub fn h_vector<P: Poset + ?Sized>(p: &P) -> Vec<i64> {
+    let f = f_vector(p);
+    let d = f.len() - 1;
+    (0..=d)
+        .map(|k| {
+            let h = (0..=k)
+                .map(|i| {
+                    let sign = if (k - i) % 2 == 0 { 1 } else { -1 };
+                    sign * binomial((d - i) as i128, k - i) * f[i] as i128
+                })
+                .sum::<i128>();
+            i64::try_from(h).expect("the h-vector entry does not fit in an i64")
+        })
+        .collect()
+}
+
+/// Returns the value at k of the zeta polynomial of p, which counts the multichains of $k - 1$ elements
+/// for $k \ge 2$.
+pub fn zeta_polynomial<P: Poset + ?Sized>(p: &P, k: i64) -> i128 {
+    f_vector(p)
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &f)| f as i128 * binomial(k as i128 - 2, i - 1))
+        .sum()
+}
+
+/// The binomial coefficient $\binom{m}{r} = m (m - 1) \cdots (m - r + 1) / r!$, for any integer m.
+fn binomial(m: i128, r: usize) -> i128 {
+    (0..r as i128).fold(1, |b, j| b * (m - j) / (j + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::incidence_algebra::IncidenceAlgebra;
+    use crate::posetm::PosetM;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_against_subsets() {
+        for mut p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let n = p.md.n;
+            let all: Vec<Vec<AnElement>> = p.chains().collect();
+            let distinct: HashSet<Vec<AnElement>> = all.iter().cloned().collect();
+            assert_eq!(distinct.len(), all.len());
+            assert!(all.is_sorted());
+            let brute = (0..1usize << n)
+                .map(|mask| (0..n).filter(|i| mask >> i & 1 == 1).collect::<Vec<_>>())
+                .filter(|s| s.iter().all(|&x| s.iter().all(|&y| !p.incomparable(x, y))))
+                .count();
+            assert_eq!(all.len(), brute);
+            assert!(all.iter().all(|c| c.windows(2).all(|w| p.lt(w[0], w[1]))));
+
+            let counts = p.count_chains_by_length();
+            assert_eq!(counts.len(), p.height() + 1);
+            for (k, &c) in counts.iter().enumerate() {
+                assert_eq!(all.iter().filter(|ch| ch.len() == k).count() as u64, c);
+            }
+
+            let maximal = p.maximal_chains();
+            assert!(maximal.is_sorted());
+            for c in all.iter().filter(|c| !c.is_empty()) {
+                let extends = |m: &Vec<AnElement>| c.iter().all(|x| m.contains(x));
+                let is_maximal = p
+                    .elements()
+                    .all(|z| c.contains(&z) || c.iter().any(|&x| p.incomparable(x, z)));
+                assert_eq!(maximal.contains(c), is_maximal);
+                assert!(maximal.iter().any(extends));
+            }
+        }
+    }
+
+    /// Counts the nondecreasing sequences of k elements.
+    fn multichains(p: &PosetM, k: usize) -> i128 {
+        let mut ending = vec![1i128; p.md.n];
+        for _ in 1..k {
+            ending = p
+                .elements()
+                .map(|y| {
+                    p.elements()
+                        .filter(|&x| p.leq(x, y))
+                        .map(|x| ending[x])
+                        .sum()
+                })
+                .collect();
+        }
+        match k {
+            0 => 1,
+            _ => ending.iter().sum(),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in a u64")]
+    fn test_count_overflow() {
+        // The chain of 70 elements has C(70, 35) > 2^64 chains of 35 elements.
+        PosetM::new_chain(70).count_chains_by_length();
+    }
+
+    #[test]
+    fn test_enumerative_invariants() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 16) {
+            for k in 2..7 {
+                assert_eq!(p.zeta_polynomial(k), multichains(&p, k as usize - 1));
+            }
+            let bounded = p.md.n > 0
+                && p.elements().any(|x| p.elements().all(|y| p.leq(x, y)))
+                && p.elements().any(|x| p.elements().all(|y| p.leq(y, x)));
+            if bounded {
+                let bot = p.elements().find(|&x| p.elements().all(|y| p.leq(x, y)));
+                let top = p.elements().find(|&x| p.elements().all(|y| p.leq(y, x)));
+                let mu = IncidenceAlgebra::new(&p).mobius();
+                assert_eq!(
+                    p.zeta_polynomial(-1),
+                    mu.value(bot.unwrap(), top.unwrap()) as i128
+                );
+            }
+            // The f-vector is recovered from the h-vector by $f_{j-1} = \sum_i \binom{d-i}{j-i} h_i$.
+            let (f, h) = (p.f_vector(), p.h_vector());
+            let d = f.len() - 1;
+            assert_eq!(h.len(), d + 1);
+            for (j, &fj) in f.iter().enumerate() {
+                let back: i128 = (0..=j)
+                    .map(|i| binomial((d - i) as i128, j - i) * h[i] as i128)
+                    .sum();
+                assert_eq!(back, fj as i128);
+            }
+            if p.is_distributive() {
+                assert!(h.iter().all(|&x| x >= 0));
+            }
+        }
+        assert_eq!(PosetM::new_chain(4).h_vector(), vec![1, 0, 0, 0, 0]);
+        assert_eq!(PosetM::new_antichain(3).h_vector(), vec![1, 2]);
+        let b3 = crate::corpus::boolean_lattice(3);
+        assert_eq!(b3.f_vector(), vec![1, 8, 19, 18, 6]);
+        assert_eq!(b3.zeta_polynomial(2), 8);
+        assert_eq!(PosetM::new_chain(3).zeta_polynomial(4), 10);
+    }
+
+    #[test]
+    fn test_empty_poset() {
+        let p = PosetM::new_antichain(0);
+        assert_eq!(
+            p.chains().collect::<Vec<_>>(),
+            vec![Vec::<AnElement>::new()]
+        );
+        assert!(p.maximal_chains().is_empty());
+        assert_eq!(p.count_chains_by_length(), vec![1]);
+    }
+}
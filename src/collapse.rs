@@ -0,0 +1,324 @@
+//! Collapsing classes of interchangeable elements, and analyses of the collapsed poset that account for
+//! the sizes of the classes.
+//!
+//! Two elements are *equivalent* if they have the same elements strictly above them and the same elements
+//! strictly below them, e.g., the leaves of a corolla. Equivalent elements are incomparable, and
+//! equivalence is an equivalence relation whose classes are antichains. Collapsing every class to a single
+//! element gives a poset that is often much smaller, and the original poset is recovered by blowing each
+//! class back up into an antichain of its size, its *multiplicity*. The analyses in this module run on the
+//! collapsed poset and its multiplicities and return the answer for the original poset.
+//!
+//! ```
+//! use fin_pos::collapse::weighted_width;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_corolla(4);
+//! let (q, multiplicities, class_of) = p.collapse_equivalent();
+//! assert!(q.same_order(&PosetM::new_chain(2)));
+//! assert_eq!(multiplicities, vec![1, 4]);
+//! assert_eq!(class_of, vec![1, 1, 1, 1, 0]);
+//! assert_eq!(weighted_width(&q, &multiplicities), 4);
+//! ```
+//!
+//! More generally, [quotient] collapses the blocks of any partition whose induced order has no cycles
+//! through distinct blocks, e.g., intervals, or elements that noisy data cannot tell apart.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//! use std::collections::HashSet;
+//!
+//! let blocks = |v: Vec<Vec<usize>>| -> Vec<HashSet<usize>> {
+//!     v.into_iter().map(|b| b.into_iter().collect()).collect()
+//! };
+//! let p = PosetM::new_chain(4);
+//! let q = p.quotient(&blocks(vec![vec![0], vec![1, 2], vec![3]])).unwrap();
+//! assert!(q.same_order(&PosetM::new_chain(3)));
+//! // Collapsing 0 and 2 would put 1 both above and below their block.
+//! assert!(p.quotient(&blocks(vec![vec![0, 2], vec![1], vec![3]])).is_err());
+//! ```
+
+use crate::antichain::heaviest_antichain;
+use crate::bitset::{BitMatrix, BitRow};
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::posetm::PosetM;
+use crate::relation::{levels, strict_matrix};
+use crate::{AnElement, BoolMatrix, Poset};
+
+use std::collections::{HashMap, HashSet};
+
+/// Collapses the equivalence classes of p. Returns the collapsed poset, the size of every class, and the
+/// class of every element of p. Classes are numbered by level (the number of elements of a longest chain
+/// strictly below them), ties broken by their smallest elements, so that the numbering of the collapsed
+/// poset is a linear extension. Pinned handles are moved to the class of their element.
+pub fn collapse_equivalent<P, Q>(p: &P) -> (Q, Vec<usize>, Vec<usize>)
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let b = BitMatrix::new(p);
+    let n = b.n();
+    let level = levels(&strict_matrix(p));
+    let mut order: Vec<AnElement> = (0..n).collect();
+    order.sort_by_key(|&x| (level[x], x));
+    let strict = |row: &BitRow, x: AnElement| {
+        let mut r = row.clone();
+        r.unset(x);
+        r
+    };
+    let mut classes: HashMap<(BitRow, BitRow), usize> = HashMap::new();
+    let mut class_of = vec![0; n];
+    let mut reps = vec![];
+    for x in order {
+        let key = (strict(b.up_row(x), x), strict(b.down_row(x), x));
+        let next = classes.len();
+        class_of[x] = *classes.entry(key).or_insert(next);
+        if class_of[x] == next {
+            reps.push(x);
+        }
+    }
+    let mut multiplicities = vec![0; reps.len()];
+    for &c in &class_of {
+        multiplicities[c] += 1;
+    }
+    let m = reps
+        .iter()
+        .map(|&x| reps.iter().map(|&y| b.leq(x, y)).collect())
+        .collect();
+    let mut collapsed = PosetM::new(&m);
+    collapsed.md.handles = p.md().handles.remapped(|x| Some(class_of[x]));
+    (Q::from_poset(&collapsed), multiplicities, class_of)
+}
+
+/// Collapses every block of the partition of p to a single element, block i becoming element i, ordered
+/// by the transitive closure of $B \le C$ if $b \le c$ for some $b \in B$ and $c \in C$. Pinned handles
+/// are moved to the block of their element. Fails if the blocks do not partition the elements of p, or
+/// with [PosetError::NotAntisymmetric] naming two blocks if the collapsed relation puts them below each
+/// other, i.e., if the partition is not an order congruence.
+pub fn quotient<P, Q>(p: &P, partition: &[HashSet<AnElement>]) -> Result<Q, PosetError>
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let n = p.md().n;
+    let k = partition.len();
+    let mut block_of = vec![None; n];
+    for (block, elements) in partition.iter().enumerate() {
+        if elements.is_empty() {
+            return Err(PosetError::EmptyBlock { block });
+        }
+        for &x in elements {
+            match block_of.get_mut(x) {
+                None => return Err(PosetError::IndexOutOfRange { index: x, n }),
+                Some(Some(_)) => return Err(PosetError::Overlap { x }),
+                Some(b) => *b = Some(block),
+            }
+        }
+    }
+    if let Some(x) = block_of.iter().position(Option::is_none) {
+        return Err(PosetError::Missing { x });
+    }
+    let block_of: Vec<usize> = block_of.into_iter().flatten().collect();
+    let mut m: BoolMatrix = vec![vec![false; k]; k];
+    for x in p.elements() {
+        for y in p.elements().filter(|&y| p.leq(x, y)) {
+            m[block_of[x]][block_of[y]] = true;
+        }
+    }
+    for via in 0..k {
+        let through = m[via].clone();
+        for row in m.iter_mut().filter(|row| row[via]) {
+            row.iter_mut().zip(&through).for_each(|(r, &t)| *r |= t);
+        }
+    }
+    let mut pairs = (0..k).flat_map(|x| (x + 1..k).map(move |y| (x, y)));
+    if let Some((x, y)) = pairs.find(|&(x, y)| m[x][y] && m[y][x]) {
+        return Err(PosetError::NotAntisymmetric { x, y });
+    }
+    let mut q = PosetM::new(&m);
+    q.md.handles = p.md().handles.remapped(|x| Some(block_of[x]));
+    Ok(Q::from_poset(&q))
+}
+
+/// The width of the poset obtained by blowing up every element $x$ of q into an antichain of
+/// multiplicities\[x\] elements, computed as the weight of a heaviest antichain of q.
+pub fn weighted_width<P: Poset + ?Sized>(q: &P, multiplicities: &[usize]) -> u64 {
+    let weights: Vec<u64> = multiplicities.iter().map(|&m| m as u64).collect();
+    heaviest_antichain(q, &weights).0
+}
+
+/// The height of the blown-up poset (see [weighted_width]). A chain meets each antichain in at most one
+/// element, so this is the number of elements of a longest chain of q avoiding the elements of
+/// multiplicity 0.
+pub fn weighted_height<P: Poset + ?Sized>(q: &P, multiplicities: &[usize]) -> usize {
+    let lt = strict_matrix(q);
+    let keep: Vec<usize> = (0..lt.len()).filter(|&x| multiplicities[x] > 0).collect();
+    let sub: Vec<Vec<bool>> = keep
+        .iter()
+        .map(|&x| keep.iter().map(|&y| lt[x][y]).collect())
+        .collect();
+    levels(&sub).iter().max().map_or(0, |l| l + 1)
+}
+
+/// The number of pairs $x < y$ in the blown-up poset (see [weighted_width]). The elements blown up from a
+/// single element are incomparable, so every pair $a < b$ of q accounts for multiplicities\[a\] times
+/// multiplicities\[b\] pairs.
+pub fn weighted_relation_count<P: Poset + ?Sized>(q: &P, multiplicities: &[usize]) -> u64 {
+    let lt = strict_matrix(q);
+    let n = lt.len();
+    (0..n)
+        .flat_map(|a| (0..n).map(move |b| (a, b)))
+        .filter(|&(a, b)| lt[a][b])
+        .map(|(a, b)| (multiplicities[a] * multiplicities[b]) as u64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetg::PosetG;
+
+    fn relation_count<P: Poset>(p: &P) -> u64 {
+        let lt = strict_matrix(p);
+        lt.iter().flatten().filter(|&&b| b).count() as u64
+    }
+
+    #[test]
+    fn test_corolla() {
+        for n in 1..6 {
+            let p = PosetG::new_corolla(n);
+            let (q, mult, class_of): (PosetG, _, _) = p.collapse_equivalent();
+            assert!(q.same_order(&PosetG::new_chain(2)));
+            assert_eq!(mult, vec![1, n]);
+            assert_eq!(class_of, [vec![1; n], vec![0]].concat());
+        }
+    }
+
+    #[test]
+    fn test_counts_match() {
+        for mut p in corpus() {
+            let (q, mult, class_of): (PosetM, _, _) = p.collapse_equivalent();
+            assert_eq!(mult.iter().sum::<usize>(), p.md.n);
+            for x in 0..p.md.n {
+                for y in 0..p.md.n {
+                    if class_of[x] != class_of[y] {
+                        assert_eq!(p.leq(x, y), q.leq(class_of[x], class_of[y]));
+                    } else {
+                        assert!(x == y || p.incomparable(x, y));
+                    }
+                }
+            }
+            assert_eq!(weighted_width(&q, &mult), p.width() as u64);
+            p.find_height();
+            assert_eq!(Some(weighted_height(&q, &mult)), p.md.height);
+            assert_eq!(weighted_relation_count(&q, &mult), relation_count(&p));
+        }
+    }
+
+    #[test]
+    fn test_nothing_to_collapse() {
+        for p in [
+            boolean_lattice(3),
+            PosetM::new_chain(4),
+            PosetM::new_chain(4).op(),
+        ] {
+            let (q, mult, class_of): (PosetM, _, _) = p.collapse_equivalent();
+            assert!(mult.iter().all(|&m| m == 1));
+            let mut classes = class_of.clone();
+            classes.sort_unstable();
+            assert_eq!(classes, (0..p.md.n).collect::<Vec<_>>());
+            for x in 0..p.md.n {
+                for y in 0..p.md.n {
+                    assert_eq!(q.leq(class_of[x], class_of[y]), p.leq(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_handles_follow_classes() {
+        let mut p = PosetM::new_corolla(3);
+        let leaf = p.pin(2);
+        let root = p.pin(3);
+        let (q, _, _): (PosetM, _, _) = p.collapse_equivalent();
+        assert_eq!(q.resolve(&leaf), Some(1));
+        assert_eq!(q.resolve(&root), Some(0));
+    }
+
+    fn blocks(v: &[&[usize]]) -> Vec<HashSet<usize>> {
+        v.iter().map(|b| b.iter().copied().collect()).collect()
+    }
+
+    #[test]
+    fn test_quotient() {
+        for p in corpus() {
+            let n = p.md.n;
+            // The partition into singletons changes nothing, and the one into classes of equivalent
+            // elements collapses them.
+            let singletons: Vec<HashSet<usize>> =
+                (0..n).map(|x| [x].into_iter().collect()).collect();
+            let q: PosetM = quotient(&p, &singletons).unwrap();
+            assert!(q.same_order(&p));
+            let (_, _, class_of) = p.collapse_equivalent();
+            let classes = class_of.iter().max().map_or(0, |&c| c + 1);
+            let partition: Vec<HashSet<usize>> = (0..classes)
+                .map(|c| (0..n).filter(|&x| class_of[x] == c).collect())
+                .collect();
+            let q: PosetM = quotient(&p, &partition).unwrap();
+            let (collapsed, _, _): (PosetM, _, _) = p.collapse_equivalent();
+            assert!(q.same_order(&collapsed));
+            if n > 0 {
+                let all: Vec<HashSet<usize>> = vec![(0..n).collect()];
+                let q: PosetM = quotient(&p, &all).unwrap();
+                assert_eq!(q.md.n, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quotient_errors() {
+        let mut p = PosetM::new_chain(4);
+        let h = p.pin(2);
+        assert_eq!(
+            quotient::<_, PosetM>(&p, &blocks(&[&[0, 1], &[2]])).unwrap_err(),
+            PosetError::Missing { x: 3 }
+        );
+        assert_eq!(
+            quotient::<_, PosetM>(&p, &blocks(&[&[0, 1], &[1, 2, 3]])).unwrap_err(),
+            PosetError::Overlap { x: 1 }
+        );
+        assert_eq!(
+            quotient::<_, PosetM>(&p, &blocks(&[&[0, 1, 2, 3], &[]])).unwrap_err(),
+            PosetError::EmptyBlock { block: 1 }
+        );
+        assert_eq!(
+            quotient::<_, PosetM>(&p, &blocks(&[&[0, 1, 2, 3, 4]])).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 4, n: 4 }
+        );
+        assert_eq!(
+            quotient::<_, PosetM>(&p, &blocks(&[&[3], &[1], &[0, 2]])).unwrap_err(),
+            PosetError::NotAntisymmetric { x: 1, y: 2 }
+        );
+        // Collapsing an interval of a chain keeps it a chain, and moves the handles along.
+        let q: PosetM = quotient(&p, &blocks(&[&[3], &[1, 2], &[0]])).unwrap();
+        assert!(q.leq(2, 1) && q.leq(1, 0));
+        assert_eq!(q.resolve(&h), Some(1));
+    }
+
+    #[test]
+    fn test_quotient_of_product() {
+        // Collapsing the columns of a product of chains leaves the first factor.
+        let p = PosetM::new_chain(3).product(&PosetM::new_chain(2));
+        let columns: Vec<HashSet<usize>> = (0..3)
+            .map(|i| [2 * i, 2 * i + 1].into_iter().collect())
+            .collect();
+        let q: PosetM = quotient(&p, &columns).unwrap();
+        assert!(q.same_order(&PosetM::new_chain(3)));
+        assert!(boolean_lattice(2)
+            .quotient(&blocks(&[&[0, 3], &[1], &[2]]))
+            .is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Further ways of composing two posets, next to [Poset::product], [Poset::coproduct], and
+//! [Poset::ordinal_sum].
+//!
+//! The *lexicographic* (ordinal) product orders pairs by their first components, and pairs with the same
+//! first component by their second: $(a, b) \le (c, d)$ if $a < c$, or $a = c$ and $b \le d$. It replaces
+//! every element of the first poset by a copy of the second, so the lexicographic product of chains is a
+//! chain, while their product is a grid.
+//!
+//! The *disjoint sum with relations* lies between the coproduct and the ordinal sum: it places some
+//! elements of the first poset below some elements of the second, and closes the order transitively.
+//!
+//! Two orders on the same elements, e.g., from different sources of data, combine by *intersection*, which
+//! keeps the relations both agree on and is always a poset, and by *union*, which keeps the relations of
+//! either and closes them transitively, and fails if the two orders contradict each other.
+//!
+//! Conversely, every poset is the coproduct of its [connected components](connected_components), the
+//! components of its comparability graph, and the ordinal sum of its [maximal ordinal
+//! summands](ordinal_decomposition), the components of its incomparability graph. A poset with more than
+//! one element cannot split both ways, but the components of a summand, and the summands of a component,
+//! may split further, as in [series_parallel](crate::series_parallel).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//! use std::collections::HashSet;
+//!
+//! let two = PosetM::new_chain(2);
+//! assert!(two.lex_product(&two).same_order(&PosetM::new_chain(4)));
+//!
+//! // Two chains 0 < 1 and 2 < 3, tied together by 0 < 3.
+//! let zigzag = two.disjoint_sum_with_relations(&two, &[(0, 1)]).unwrap();
+//! assert!(zigzag.leq(0, 3) && !zigzag.leq(0, 2) && !zigzag.leq(1, 3));
+//!
+//! // The chains 0 < 1 < 2 and 0 < 2 < 1 agree on 0 < 1 and 0 < 2.
+//! let mut other = PosetM::new_antichain(3);
+//! other.add_relation(0, 2).unwrap();
+//! other.add_relation(2, 1).unwrap();
+//! let meet = PosetM::new_chain(3).intersect(&other);
+//! assert!(meet.lt(0, 1) && meet.lt(0, 2) && meet.incomparable(1, 2));
+//! assert!(PosetM::new_chain(3).try_union(&other).is_err());
+//!
+//! // Two chains side by side, below a third.
+//! let vee = two.coproduct(&two).ordinal_sum(&two);
+//! assert_eq!(vee.connected_components(), vec![HashSet::from([0, 1, 2, 3, 4, 5])]);
+//! assert_eq!(
+//!     vee.ordinal_decomposition(),
+//!     vec![HashSet::from([0, 1, 2, 3]), HashSet::from([4]), HashSet::from([5])]
+//! );
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::incremental::IncrementalPoset;
+use crate::posetm::PosetM;
+use crate::relation::{components, strict_matrix};
+use crate::{AnElement, BoolMatrix, Elements, Poset};
+
+/// Returns the lexicographic product of p and q, in which the pair $(a, b)$ is the element $a \cdot m + b$,
+/// where $m$ is the size of q, as in [Poset::product].
+pub fn lex_product<P, Q, R>(p: &P, q: &Q) -> R
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+    R: FromPoset<PosetM>,
+{
+    let m = q.md().n;
+    let n = p.md().n * m;
+    let matrix: BoolMatrix = (0..n)
+        .map(|x| {
+            (0..n)
+                .map(|y| p.lt(x / m, y / m) || (x / m == y / m && q.leq(x % m, y % m)))
+                .collect()
+        })
+        .collect();
+    R::from_poset(&PosetM::new(&matrix))
+}
+
+/// Returns the coproduct of p and q, numbered as in [Poset::coproduct], with $x \le y$ added for every pair
+/// $(x, y)$ of relations, where x is an element of p and y one of q, and then everything these imply by
+/// transitivity. As all the relations go up from p to q, the result is always a poset. The pinned handles
+/// of p carry over. Fails if an element of a pair is out of range.
+pub fn disjoint_sum_with_relations<P, Q, R>(
+    p: &P,
+    q: &Q,
+    relations: &[(AnElement, AnElement)],
+) -> Result<R, PosetError>
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+    R: FromPoset<PosetM>,
+{
+    let (n, m) = (p.md().n, q.md().n);
+    if let Some(&(index, _)) = relations.iter().find(|(x, _)| *x >= n) {
+        return Err(PosetError::IndexOutOfRange { index, n });
+    }
+    if let Some(&(_, index)) = relations.iter().find(|(_, y)| *y >= m) {
+        return Err(PosetError::IndexOutOfRange { index, n: m });
+    }
+    let matrix: BoolMatrix = (0..n + m)
+        .map(|a| {
+            (0..n + m)
+                .map(|b| match (a < n, b < n) {
+                    (true, true) => p.leq(a, b),
+                    (false, false) => q.leq(a - n, b - n),
+                    (true, false) => relations
+                        .iter()
+                        .any(|&(x, y)| p.leq(a, x) && q.leq(y, b - n)),
+                    (false, true) => false,
+                })
+                .collect()
+        })
+        .collect();
+    let mut sum = PosetM::new(&matrix);
+    sum.md.handles = p.md().handles.clone();
+    Ok(R::from_poset(&sum))
+}
+
+/// Panics unless p and q have the same number of elements.
+fn same_elements<P: Poset + ?Sized, Q: Poset + ?Sized>(p: &P, q: &Q) -> usize {
+    let (n, m) = (p.md().n, q.md().n);
+    assert_eq!(n, m, "cannot combine orders on {} and on {} elements", n, m);
+    n
+}
+
+/// Returns the intersection of the orders p and q on the same elements: $x \le y$ if it holds in both. The
+/// pinned handles of p carry over. Panics if p and q differ in size.
+pub fn intersect<P, Q, R>(p: &P, q: &Q) -> R
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+    R: FromPoset<PosetM>,
+{
+    let n = same_elements(p, q);
+    let matrix: BoolMatrix = (0..n)
+        .map(|x| (0..n).map(|y| p.leq(x, y) && q.leq(x, y)).collect())
+        .collect();
+    let mut meet = PosetM::new(&matrix);
+    meet.md.handles = p.md().handles.clone();
+    R::from_poset(&meet)
+}
+
+/// Returns the transitive closure of the union of the orders p and q on the same elements, found by adding
+/// the covers of q to p one at a time (see [IncrementalPoset]). The pinned handles of p carry over. Fails
+/// with [PosetError::NotAntisymmetric] if the union forces $x \le y$ and $y \le x$ for distinct x and y,
+/// reporting a cover $x \lessdot y$ of q, with $y \le x$ implied by the covers added before it. Panics if p
+/// and q differ in size.
+pub fn try_union<P, Q, R>(p: &P, q: &Q) -> Result<R, PosetError>
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+    R: FromPoset<PosetM>,
+{
+    let n = same_elements(p, q);
+    let mut join = IncrementalPoset::from_fn(n, |x, y| p.leq(x, y));
+    for (x, y) in q.cover_relations() {
+        join.add_relation(x, y)?;
+    }
+    let matrix: BoolMatrix = (0..n)
+        .map(|x| (0..n).map(|y| join.leq(x, y)).collect())
+        .collect();
+    let mut join = PosetM::new(&matrix);
+    join.md.handles = p.md().handles.clone();
+    Ok(R::from_poset(&join))
+}
+
+/// Returns the connected components of the comparability graph of p, in order of their smallest elements.
+/// The empty poset has no components.
+pub fn connected_components<P: Poset + ?Sized>(p: &P) -> Vec<Elements> {
+    let lt = strict_matrix(p);
+    let all: Vec<AnElement> = (0..lt.len()).collect();
+    components(&all, |x, y| lt[x][y] || lt[y][x])
+        .into_iter()
+        .map(|part| part.into_iter().collect())
+        .collect()
+}
+
+/// Returns the maximal ordinal summands of p, the connected components of its incomparability graph, from
+/// the bottom up: every element of a summand is below every element of the next. The empty poset has no
+/// summands.
+pub fn ordinal_decomposition<P: Poset + ?Sized>(p: &P) -> Vec<Elements> {
+    let lt = strict_matrix(p);
+    let all: Vec<AnElement> = (0..lt.len()).collect();
+    let mut parts = components(&all, |x, y| x != y && !lt[x][y] && !lt[y][x]);
+    parts.sort_by(|a, b| match lt[a[0]][b[0]] {
+        true => std::cmp::Ordering::Less,
+        false => std::cmp::Ordering::Greater,
+    });
+    parts
+        .into_iter()
+        .map(|part| part.into_iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::poseth::PosetH;
+
+    #[test]
+    fn test_lex_product() {
+        let small: Vec<PosetM> = corpus().into_iter().filter(|p| p.md.n <= 5).collect();
+        for p in &small {
+            for q in &small {
+                let mut lex: PosetM = lex_product(p, q);
+                let prod = p.product(q);
+                assert_eq!(lex.is_valid_poset(), Ok(()));
+                // The lexicographic order extends the componentwise one.
+                for x in lex.elements() {
+                    for y in lex.elements() {
+                        assert!(!prod.leq(x, y) || lex.leq(x, y));
+                    }
+                }
+                if p.md.n > 0 && q.md.n > 0 {
+                    assert_eq!(
+                        lex.height(),
+                        p.longest_chain().len() * q.longest_chain().len()
+                    );
+                }
+                let h: PosetH = lex_product(p, q);
+                assert!(h.same_order(&lex));
+            }
+        }
+        let two = PosetM::new_chain(2);
+        let anti = PosetM::new_antichain(2);
+        assert!(anti.lex_product(&two).same_order(&two.coproduct(&two)));
+        assert!(two.lex_product(&anti).same_order(&anti.ordinal_sum(&anti)));
+    }
+
+    #[test]
+    fn test_disjoint_sum_with_relations() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 5) {
+            let q = PosetM::new_corolla(2);
+            let none: PosetM = disjoint_sum_with_relations(&p, &q, &[]).unwrap();
+            assert!(none.same_order(&p.coproduct(&q)));
+            let pairs: Vec<(usize, usize)> = p
+                .elements()
+                .flat_map(|x| q.elements().map(move |y| (x, y)))
+                .collect();
+            let all: PosetM = disjoint_sum_with_relations(&p, &q, &pairs).unwrap();
+            assert!(all.same_order(&p.ordinal_sum(&q)));
+        }
+        let two = PosetM::new_chain(2);
+        assert_eq!(
+            disjoint_sum_with_relations::<_, _, PosetM>(&two, &two, &[(2, 0)]).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 2, n: 2 }
+        );
+        assert_eq!(
+            disjoint_sum_with_relations::<_, _, PosetM>(&two, &two, &[(0, 5)]).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 5, n: 2 }
+        );
+        let mut p = PosetM::new_chain(2);
+        let h = p.pin(1);
+        let sum = p.disjoint_sum_with_relations(&two, &[(1, 0)]).unwrap();
+        assert_eq!(sum.resolve(&h), Some(1));
+        assert!(sum.same_order(&PosetM::new_chain(4)));
+    }
+
+    #[test]
+    fn test_intersect_and_union() {
+        let small: Vec<PosetM> = corpus().into_iter().filter(|p| p.md.n <= 6).collect();
+        for p in &small {
+            for q in small.iter().filter(|q| q.md.n == p.md.n) {
+                let meet: PosetM = intersect(p, q);
+                assert_eq!(meet.is_valid_poset(), Ok(()));
+                assert!(meet.same_order(&q.intersect(p)));
+                assert_eq!(p.refines(&meet), Ok(true));
+                assert_eq!(q.refines(&meet), Ok(true));
+                match try_union::<_, _, PosetH>(p, q) {
+                    Ok(join) => {
+                        assert_eq!(join.is_valid_poset(), Ok(()));
+                        assert_eq!(join.refines(p), Ok(true));
+                        assert_eq!(join.refines(q), Ok(true));
+                        assert!(join.same_order(&q.try_union(p).unwrap()));
+                    }
+                    Err(PosetError::NotAntisymmetric { x, y }) => {
+                        assert!(q.lt(x, y) && !p.leq(x, y));
+                        assert!(q.try_union(p).is_err());
+                    }
+                    Err(e) => panic!("unexpected error {:?}", e),
+                }
+            }
+        }
+        // A chain and its opposite agree on nothing but contradict each other.
+        let chain = PosetM::new_chain(4);
+        assert!(chain
+            .intersect(&chain.op())
+            .same_order(&PosetM::new_antichain(4)));
+        assert!(chain.try_union(&chain.op()).is_err());
+        let mut p = PosetM::new_antichain(3);
+        let h = p.pin(2);
+        let join = p.try_union(&PosetM::new_chain(3)).unwrap();
+        assert_eq!(join.resolve(&h), Some(2));
+    }
+
+    #[test]
+    fn test_decompositions() {
+        for p in corpus() {
+            let parts = connected_components(&p);
+            let summands = ordinal_decomposition(&p);
+            for split in [&parts, &summands] {
+                let mut all: Vec<AnElement> = split.iter().flatten().copied().collect();
+                all.sort_unstable();
+                assert_eq!(all, p.elements().collect::<Vec<_>>());
+            }
+            for (i, a) in parts.iter().enumerate() {
+                for b in &parts[i + 1..] {
+                    assert!(a.iter().all(|&x| b.iter().all(|&y| p.incomparable(x, y))));
+                }
+                let (sub, _) = p.sub(a);
+                assert_eq!(connected_components(&sub).len(), 1);
+            }
+            for (i, a) in summands.iter().enumerate() {
+                for b in &summands[i + 1..] {
+                    assert!(a.iter().all(|&x| b.iter().all(|&y| p.lt(x, y))));
+                }
+                let (sub, _) = p.sub(a);
+                assert_eq!(ordinal_decomposition(&sub).len(), 1);
+            }
+            assert!(p.md.n <= 1 || parts.len() == 1 || summands.len() == 1);
+        }
+        let chain = PosetM::new_chain(3);
+        assert_eq!(chain.ordinal_decomposition().len(), 3);
+        assert_eq!(chain.connected_components().len(), 1);
+        assert!(PosetM::new_antichain(0).connected_components().is_empty());
+        assert!(PosetM::new_antichain(0).ordinal_decomposition().is_empty());
+    }
+}
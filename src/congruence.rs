@@ -0,0 +1,202 @@
+//! Congruences of lattices.
+//!
+//! A *congruence* of a lattice is an equivalence relation $\theta$ compatible with both operations: if
+//! $x \mathrel\theta y$ then $x \vee z \mathrel\theta y \vee z$ and $x \wedge z \mathrel\theta y \wedge z$
+//! for every z. The congruences, ordered by refinement, form a distributive lattice from the identity
+//! (each element in a class of its own) to the relation identifying everything. The *principal*
+//! congruence $\mathrm{con}(x, y)$ is the smallest one identifying x and y, and every congruence is a join of
+//! principal congruences of covers $x \lessdot y$, the join of two congruences being the transitive
+//! closure of their union.
+//!
+//! Congruences are given as [SetPartition]s of the elements into their classes. A lattice is *simple* if
+//! it has no congruences but the two trivial ones, like the diamond $M_3$.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // In the pentagon 3 < 0 < 1 < 4, 3 < 2 < 4, collapsing 0 and 1 forces nothing else.
+//! let p = PosetM::new_pentagon();
+//! assert_eq!(p.principal_congruence(0, 1).unwrap(), vec![vec![0, 1], vec![2], vec![3], vec![4]]);
+//! let (con, congruences) = p.congruence_lattice().unwrap();
+//! assert_eq!(congruences.len(), 5);
+//! assert!(con.is_distributive());
+//! assert_eq!(PosetM::new_diamond().congruence_lattice().unwrap().1.len(), 2);
+//! ```
+
+use crate::families::SetPartition;
+use crate::lattice::LatticeOps;
+use crate::posetg::PosetG;
+use crate::{AnElement, BiPaGraph, Elt};
+
+use std::collections::HashSet;
+
+/// Returns the smallest congruence identifying the elements of every given pair, each class labeled by
+/// its smallest element.
+fn generate(ops: &LatticeOps, pairs: &[(AnElement, AnElement)]) -> Vec<AnElement> {
+    let n = ops.n();
+    let mut parent: Vec<AnElement> = (0..n).collect();
+    fn find(parent: &mut [AnElement], x: AnElement) -> AnElement {
+        let mut root = x;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[x] = root;
+        root
+    }
+    // Every pair merging two classes is then translated by every element, which suffices: the relation
+    // is the transitive closure of the merged pairs.
+    let mut pending = pairs.to_vec();
+    while let Some((x, y)) = pending.pop() {
+        let (a, b) = (find(&mut parent, x), find(&mut parent, y));
+        if a == b {
+            continue;
+        }
+        parent[a.max(b)] = a.min(b);
+        for z in 0..n {
+            pending.push((ops.join(x, z), ops.join(y, z)));
+            pending.push((ops.meet(x, z), ops.meet(y, z)));
+        }
+    }
+    // The roots are the smallest elements of their classes, as merges keep the smaller root.
+    (0..n).map(|x| find(&mut parent, x)).collect()
+}
+
+/// The classes of a labeling, in order of their smallest elements.
+fn blocks(label: &[AnElement]) -> SetPartition {
+    let mut blocks: Vec<Vec<AnElement>> = vec![vec![]; label.len()];
+    label
+        .iter()
+        .enumerate()
+        .for_each(|(x, &l)| blocks[l].push(x));
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+/// Returns the principal congruence $\mathrm{con}(x, y)$ of the lattice.
+pub fn principal_congruence(ops: &LatticeOps, x: AnElement, y: AnElement) -> SetPartition {
+    blocks(&generate(ops, &[(x, y)]))
+}
+
+/// Returns the lattice of congruences ordered by refinement, together with the congruence behind each of
+/// its elements. The congruences are numbered by decreasing number of classes, and those with equally
+/// many in the lexicographic order of their classes, so the identity is element 0 and the relation
+/// identifying everything is last.
+pub fn congruence_lattice(ops: &LatticeOps) -> (PosetG, Vec<SetPartition>) {
+    let n = ops.n();
+    let principal: Vec<Vec<AnElement>> = (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| x != y && ops.join(x, y) == y)
+        .map(|pair| generate(ops, &[pair]))
+        .collect();
+    let identity: Vec<AnElement> = (0..n).collect();
+    let mut labels = vec![identity.clone()];
+    let mut found: HashSet<Vec<AnElement>> = HashSet::from([identity]);
+    let mut next = 0;
+    while next < labels.len() {
+        for psi in &principal {
+            let pairs: Vec<(AnElement, AnElement)> = (0..n)
+                .flat_map(|x| [(x, labels[next][x]), (x, psi[x])])
+                .collect();
+            let join = generate(ops, &pairs);
+            if found.insert(join.clone()) {
+                labels.push(join);
+            }
+        }
+        next += 1;
+    }
+    let mut congruences: Vec<SetPartition> = labels.iter().map(|l| blocks(l)).collect();
+    congruences.sort_by(|a, b| (b.len(), a).cmp(&(a.len(), b)));
+    let refines = |a: &SetPartition, b: &SetPartition| {
+        a.iter()
+            .all(|block| b.iter().any(|c| block.iter().all(|x| c.contains(x))))
+    };
+    let k = congruences.len();
+    let g: BiPaGraph = (0..k)
+        .map(|i| {
+            let up = (i..k).filter(|&j| refines(&congruences[i], &congruences[j]));
+            (i, up.collect())
+        })
+        .collect();
+    let mut con = PosetG::new(&g);
+    con.md.bot = Some(Elt::A(0));
+    con.md.top = Some(Elt::A(k - 1));
+    con.md.minimals = Some([0].into());
+    con.md.maximals = Some([k - 1].into());
+    (con, congruences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::families::new_partition_lattice;
+    use crate::posetm::PosetM;
+    use crate::Poset;
+
+    fn is_congruence(ops: &LatticeOps, partition: &SetPartition) -> bool {
+        let n = ops.n();
+        let mut class = vec![0; n];
+        for (i, block) in partition.iter().enumerate() {
+            block.iter().for_each(|&x| class[x] = i);
+        }
+        (0..n).all(|x| {
+            (0..n).filter(|&y| class[x] == class[y]).all(|y| {
+                (0..n).all(|z| {
+                    class[ops.join(x, z)] == class[ops.join(y, z)]
+                        && class[ops.meet(x, z)] == class[ops.meet(y, z)]
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn test_against_partitions() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 7) {
+            let Some(ops) = LatticeOps::new(&p) else {
+                assert!(p.congruence_lattice().is_err());
+                continue;
+            };
+            let (con, congruences) = p.congruence_lattice().unwrap();
+            let all = new_partition_lattice::<PosetM>(p.md.n);
+            let mut expected: Vec<SetPartition> = all
+                .labels()
+                .iter()
+                .filter(|partition| is_congruence(&ops, partition))
+                .cloned()
+                .collect();
+            let mut sorted = congruences.clone();
+            expected.sort();
+            sorted.sort();
+            assert_eq!(sorted, expected);
+            assert!(con.is_distributive());
+            assert_eq!(con.is_valid_poset(), Ok(()));
+            for x in 0..p.md.n {
+                for y in 0..p.md.n {
+                    // The principal congruence is the smallest one identifying x and y.
+                    let theta = p.principal_congruence(x, y).unwrap();
+                    let i = congruences.iter().position(|c| *c == theta).unwrap();
+                    for (j, c) in congruences.iter().enumerate() {
+                        let identifies = c.iter().any(|b| b.contains(&x) && b.contains(&y));
+                        assert_eq!(identifies, con.leq(i, j));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_lattices() {
+        // The congruences of a distributive lattice correspond to the sets of its join-irreducibles.
+        let (con, _) = PosetM::new_chain(4).congruence_lattice().unwrap();
+        assert!(con.is_isomorphic(&boolean_lattice(3)));
+        let (con, _) = boolean_lattice(3).congruence_lattice().unwrap();
+        assert!(con.is_isomorphic(&boolean_lattice(3)));
+        // The partition lattice of four elements is simple.
+        let pi4 = new_partition_lattice::<PosetM>(4);
+        assert_eq!(pi4.poset().congruence_lattice().unwrap().1.len(), 2);
+        let (con, congruences) = PosetM::new_chain(0).congruence_lattice().unwrap();
+        assert_eq!((con.md.n, congruences), (1, vec![vec![]]));
+        assert!(PosetM::new_antichain(2).principal_congruence(0, 1).is_err());
+    }
+}
@@ -1,59 +1,660 @@
+//! Conversions between the representations of posets.
+//!
+//! Converting with [FromPoset] keeps the elements (and hence every field of the [MetaData]) unchanged.
+//! The six conversions between [PosetM], [PosetG] and [PosetH] are also available as functions, e.g.,
+//! [matrix_to_hasse].
+//! A pipeline that relies on some [MetaData] fields after a conversion declares them once as [MetaFields]
+//! and calls [Convertible::convert_into]. Fields that are already known are carried over, and each missing
+//! field is computed on whichever side of the conversion makes it cheaper, according to
+//! [Convertible::field_cost].
+//!
+//! ```
+//! use fin_pos::convertors::{Convertible, MetaFields};
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::{Elt, Poset};
+//!
+//! let p = PosetG::new_chain(4);
+//! let m: PosetM = p.convert_into(MetaFields::TOP | MetaFields::HEIGHT).unwrap();
+//! assert_eq!(m.md.top, Some(Elt::A(3)));
+//! assert_eq!(m.md.height, Some(4));
+//! ```
+
+use crate::incremental::IncrementalPoset;
+use crate::lattice::{atoms, coatoms};
+use crate::parallel::map_range;
 use crate::posetg::PosetG;
+use crate::poseth::unique;
 use crate::poseth::PosetH;
 use crate::posetm::PosetM;
-use crate::{BiPaGraph, BoolMatrix, Hasse};
+use crate::poseto::PosetO;
+use crate::posetr::PosetR;
+use crate::relation::{levels, strict_matrix};
+use crate::smallset::SmallSet;
+use crate::{BiPaGraph, BoolMatrix, Elements, Elt, Hasse, MetaData, Poset, SortedGraph};
+
+use std::error::Error;
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+/// Converts a matrix into a graph, keeping the [MetaData]. The same as [PosetG::from_poset].
+pub fn matrix_to_graph(p: &PosetM) -> PosetG {
+    PosetG::from_poset(p)
+}
+
+/// Converts a matrix into a Hasse diagram, keeping the [MetaData]. The same as [PosetH::from_poset].
+pub fn matrix_to_hasse(p: &PosetM) -> PosetH {
+    PosetH::from_poset(p)
+}
+
+/// Converts a Hasse diagram into a matrix, keeping the [MetaData]. The same as [PosetM::from_poset].
+pub fn hasse_to_matrix(p: &PosetH) -> PosetM {
+    PosetM::from_poset(p)
+}
+
+/// Converts a Hasse diagram into a graph, keeping the [MetaData]. The same as [PosetG::from_poset].
+pub fn hasse_to_graph(p: &PosetH) -> PosetG {
+    PosetG::from_poset(p)
+}
+
+/// Converts a graph into a Hasse diagram, keeping the [MetaData]. The same as [PosetH::from_poset].
+pub fn graph_to_hasse(p: &PosetG) -> PosetH {
+    PosetH::from_poset(p)
+}
+
+/// Converts a graph into a matrix, keeping the [MetaData]. The same as [PosetM::from_poset].
+pub fn graph_to_matrix(p: &PosetG) -> PosetM {
+    PosetM::from_poset(p)
+}
+
+/// A set of [MetaData] fields, combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MetaFields(u8);
+
+impl MetaFields {
+    /// No field.
+    pub const NONE: MetaFields = MetaFields(0);
+    /// [MetaData::top].
+    pub const TOP: MetaFields = MetaFields(1);
+    /// [MetaData::bot].
+    pub const BOT: MetaFields = MetaFields(1 << 1);
+    /// [MetaData::minimals].
+    pub const MINIMALS: MetaFields = MetaFields(1 << 2);
+    /// [MetaData::maximals].
+    pub const MAXIMALS: MetaFields = MetaFields(1 << 3);
+    /// [MetaData::height].
+    pub const HEIGHT: MetaFields = MetaFields(1 << 4);
+    /// [MetaData::atoms].
+    pub const ATOMS: MetaFields = MetaFields(1 << 5);
+    /// [MetaData::coatoms].
+    pub const COATOMS: MetaFields = MetaFields(1 << 6);
+    /// Every field above.
+    pub const ALL: MetaFields = MetaFields((1 << 7) - 1);
+
+    /// Returns true if every field of other is in the set.
+    pub fn contains(self, other: MetaFields) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if the set has no field.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the single fields in the set.
+    pub fn iter(self) -> impl Iterator<Item = MetaFields> {
+        (0..7)
+            .map(|i| MetaFields(1 << i))
+            .filter(move |&f| self.contains(f))
+    }
+
+    /// Returns true if the single field is known in md.
+    fn is_known(self, md: &MetaData) -> bool {
+        match self {
+            MetaFields::TOP => md.top.is_some(),
+            MetaFields::BOT => md.bot.is_some(),
+            MetaFields::MINIMALS => md.minimals.is_some(),
+            MetaFields::MAXIMALS => md.maximals.is_some(),
+            MetaFields::HEIGHT => md.height.is_some(),
+            MetaFields::ATOMS => md.atoms.is_some(),
+            MetaFields::COATOMS => md.coatoms.is_some(),
+            _ => unreachable!("not a single field"),
+        }
+    }
+
+    /// Checks that the known value of the single field in md is possible for a poset on md.n elements.
+    fn validate(self, md: &MetaData) -> Result<(), ConversionError> {
+        let n = md.n;
+        let in_range = |e: &Option<Elt>| !matches!(e, Some(Elt::A(x)) if *x >= n);
+        let extremal = |s: &Option<Elements>| {
+            s.as_ref()
+                .is_none_or(|s| s.iter().all(|&x| x < n) && s.is_empty() == (n == 0))
+        };
+        let ok = match self {
+            MetaFields::TOP => in_range(&md.top),
+            MetaFields::BOT => in_range(&md.bot),
+            MetaFields::MINIMALS => extremal(&md.minimals),
+            MetaFields::MAXIMALS => extremal(&md.maximals),
+            MetaFields::HEIGHT => md.height.is_none_or(|h| h <= n && (h == 0) == (n == 0)),
+            MetaFields::ATOMS => md.atoms.iter().flatten().all(|&x| x < n),
+            MetaFields::COATOMS => md.coatoms.iter().flatten().all(|&x| x < n),
+            _ => unreachable!("not a single field"),
+        };
+        match ok {
+            true => Ok(()),
+            false => Err(ConversionError {
+                field: self,
+                reason: "the stored value is inconsistent with the size of the poset",
+            }),
+        }
+    }
+}
+
+impl BitOr for MetaFields {
+    type Output = MetaFields;
+
+    fn bitor(self, rhs: MetaFields) -> MetaFields {
+        MetaFields(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for MetaFields {
+    fn bitor_assign(&mut self, rhs: MetaFields) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The error returned when a required field cannot be provided by a conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    /// The field that could not be provided.
+    pub field: MetaFields,
+    /// Why the field could not be provided.
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.field {
+            MetaFields::TOP => "top",
+            MetaFields::BOT => "bot",
+            MetaFields::MINIMALS => "minimals",
+            MetaFields::MAXIMALS => "maximals",
+            MetaFields::HEIGHT => "height",
+            MetaFields::ATOMS => "atoms",
+            MetaFields::COATOMS => "coatoms",
+            _ => "metadata",
+        };
+        write!(f, "cannot provide {}: {}", name, self.reason)
+    }
+}
+
+impl Error for ConversionError {}
+
+/// How a required field came to be known after a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Provenance {
+    /// The field was already known on the source.
+    Carried,
+    /// The field was computed from the source representation.
+    Source,
+    /// The field was computed from the target representation.
+    Target,
+}
+
+/// Construction of one representation from another, keeping the elements and the [MetaData].
+pub trait FromPoset<S: ?Sized> {
+    /// Builds the poset p in this representation.
+    fn from_poset(p: &S) -> Self;
+}
+
+/// Representations that can be converted into each other while guaranteeing [MetaData] fields.
+pub trait Convertible: Poset + Sized {
+    /// The up-set of every element. The default implementation asks [Poset::leq] for every pair.
+    fn up_sets(&self) -> Vec<Elements> {
+        let n = self.md().n;
+        (0..n)
+            .map(|x| (0..n).filter(|&y| self.leq(x, y)).collect())
+            .collect()
+    }
+
+    /// The estimated number of steps needed to compute the single field in this representation.
+    fn field_cost(&self, field: MetaFields) -> u64;
 
-use std::collections::HashMap;
+    /// Computes the single field in this representation and stores it in md.
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData);
 
-fn matrix_to_graph(p: PosetM) -> PosetG {
-    let n = p.md.n;
-    let g = (0..n)
-        .zip((0..n).map(|i| (0..n).filter(|&j| p.m[i][j]).collect()))
-        .collect::<HashMap<_, _>>();
+    /// Converts into the representation T, guaranteeing that the required fields of the [MetaData] are known.
+    fn convert_into<T: Convertible + FromPoset<Self>>(
+        &self,
+        required: MetaFields,
+    ) -> Result<T, ConversionError> {
+        self.convert_with_report(required).map(|(t, _)| t)
+    }
 
-    PosetG::new(&g)
+    /// Like [Convertible::convert_into], additionally reporting how each required field was obtained.
+    fn convert_with_report<T: Convertible + FromPoset<Self>>(
+        &self,
+        required: MetaFields,
+    ) -> Result<(T, Vec<(MetaFields, Provenance)>), ConversionError> {
+        for field in required.iter() {
+            field.validate(self.md())?;
+        }
+        let mut t = T::from_poset(self);
+        let mut md = t.md().clone();
+        let mut report = vec![];
+        for field in required.iter() {
+            let provenance = if field.is_known(&md) {
+                Provenance::Carried
+            } else if self.field_cost(field) <= t.field_cost(field) {
+                self.compute_field(field, &mut md);
+                Provenance::Source
+            } else {
+                t.compute_field(field, &mut md);
+                Provenance::Target
+            };
+            report.push((field, provenance));
+        }
+        *t.md_mut() = md;
+        Ok((t, report))
+    }
 }
 
-fn matrix_to_hasse(p: PosetM) -> PosetH {
-    todo!();
+/// Computes the single field of p by querying [Poset::leq] for (at most) every pair of elements.
+fn compute_by_leq<P: Poset>(p: &P, field: MetaFields, md: &mut MetaData) {
+    let n = p.md().n;
+    let below_all = |x: usize| (0..n).all(|y| p.leq(x, y));
+    let above_all = |x: usize| (0..n).all(|y| p.leq(y, x));
+    let or_absent = |e: Option<usize>| Some(e.map_or(Elt::NotPresent, Elt::A));
+    match field {
+        MetaFields::TOP => md.top = or_absent((0..n).find(|&x| above_all(x))),
+        MetaFields::BOT => md.bot = or_absent((0..n).find(|&x| below_all(x))),
+        MetaFields::MINIMALS => {
+            md.minimals = Some((0..n).filter(|&x| (0..n).all(|y| !p.lt(y, x))).collect())
+        }
+        MetaFields::MAXIMALS => {
+            md.maximals = Some((0..n).filter(|&x| (0..n).all(|y| !p.lt(x, y))).collect())
+        }
+        MetaFields::HEIGHT => {
+            md.height = Some(levels(&strict_matrix(p)).iter().max().map_or(0, |l| l + 1))
+        }
+        MetaFields::ATOMS => md.atoms = Some(atoms(p)),
+        MetaFields::COATOMS => md.coatoms = Some(coatoms(p)),
+        _ => unreachable!("not a single field"),
+    }
 }
 
-fn hasse_to_matrix(p: PosetH) -> PosetM {
-    todo!();
+impl Convertible for PosetM {
+    /// Every field is found by looking at (at most) every entry of the matrix.
+    fn field_cost(&self, _field: MetaFields) -> u64 {
+        let n = self.md.n as u64;
+        n * n
+    }
+
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        compute_by_leq(self, field, md);
+    }
 }
 
-fn hasse_to_graph(p: PosetH) -> PosetG {
-    todo!();
+impl Convertible for PosetG {
+    fn up_sets(&self) -> Vec<Elements> {
+        (0..self.md.n)
+            .map(|x| self.g.get(&x).unwrap().clone())
+            .collect()
+    }
+
+    /// The bottom and the maximal elements are recognized by the sizes of their up-sets.
+    fn field_cost(&self, field: MetaFields) -> u64 {
+        let n = self.md.n as u64;
+        match field {
+            MetaFields::BOT | MetaFields::TOP | MetaFields::MAXIMALS => n,
+            _ => n * n,
+        }
+    }
+
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        let n = self.md.n;
+        let maximals = || -> Elements {
+            (0..n)
+                .filter(|x| self.g.get(x).unwrap().len() == 1)
+                .collect()
+        };
+        match field {
+            MetaFields::BOT => {
+                md.bot = Some(
+                    (0..n)
+                        .find(|x| self.g.get(x).unwrap().len() == n)
+                        .map_or(Elt::NotPresent, Elt::A),
+                )
+            }
+            MetaFields::TOP => md.top = Some(unique(&maximals())),
+            MetaFields::MAXIMALS => md.maximals = Some(maximals()),
+            _ => compute_by_leq(self, field, md),
+        }
+    }
 }
 
-fn graph_to_hasse(p: PosetG) -> PosetH {
-    let n = p.md.n;
-    let h: Hasse = (0..n)
-        .zip((0..n).map(|i| {
-            p.g.get(&i)
-                .unwrap()
+impl Convertible for PosetO {
+    fn up_sets(&self) -> Vec<Elements> {
+        self.g
+            .values()
+            .map(|s| s.iter().copied().collect())
+            .collect()
+    }
+
+    /// The bottom and the maximal elements are recognized by the sizes of their up-sets.
+    fn field_cost(&self, field: MetaFields) -> u64 {
+        let n = self.md.n as u64;
+        match field {
+            MetaFields::BOT | MetaFields::TOP | MetaFields::MAXIMALS => n,
+            _ => n * n,
+        }
+    }
+
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        let n = self.md.n;
+        let maximals = || -> Elements {
+            self.g
                 .iter()
-                .filter(|j| {
-                    !p.g.get(&i)
-                        .unwrap()
-                        .iter()
-                        .any(|k| p.g.get(k).unwrap().contains(j))
-                })
-                .map(|&x| x)
+                .filter(|(_, s)| s.len() == 1)
+                .map(|(&x, _)| x)
                 .collect()
-        }))
-        .collect();
+        };
+        match field {
+            MetaFields::BOT => {
+                md.bot = Some(
+                    (0..n)
+                        .find(|x| self.g[x].len() == n)
+                        .map_or(Elt::NotPresent, Elt::A),
+                )
+            }
+            MetaFields::TOP => md.top = Some(unique(&maximals())),
+            MetaFields::MAXIMALS => md.maximals = Some(maximals()),
+            _ => compute_by_leq(self, field, md),
+        }
+    }
+}
+
+impl Convertible for PosetH {
+    /// The up-sets are searched for in parallel with the `rayon` feature.
+    fn up_sets(&self) -> Vec<Elements> {
+        map_range(self.md.n, |x| self.up_set(x))
+    }
+
+    /// Every field is found by a single pass over the covers.
+    fn field_cost(&self, _field: MetaFields) -> u64 {
+        (self.md.n + self.h.iter().map(SmallSet::len).sum::<usize>()) as u64
+    }
+
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        match field {
+            MetaFields::TOP => md.top = Some(unique(&self.maximals_from_covers())),
+            MetaFields::BOT => md.bot = Some(unique(&self.minimals_from_covers())),
+            MetaFields::MINIMALS => md.minimals = Some(self.minimals_from_covers()),
+            MetaFields::MAXIMALS => md.maximals = Some(self.maximals_from_covers()),
+            MetaFields::HEIGHT => md.height = Some(self.height_from_covers()),
+            MetaFields::ATOMS => {
+                md.atoms = Some(match unique(&self.minimals_from_covers()) {
+                    Elt::A(bot) => self.h[bot].iter().copied().collect(),
+                    Elt::NotPresent => Elements::new(),
+                })
+            }
+            MetaFields::COATOMS => {
+                md.coatoms = Some(match unique(&self.maximals_from_covers()) {
+                    Elt::A(top) => (0..self.md.n)
+                        .filter(|&x| self.h[x].contains(&top))
+                        .collect(),
+                    Elt::NotPresent => Elements::new(),
+                })
+            }
+            _ => unreachable!("not a single field"),
+        }
+    }
+}
+
+impl Convertible for PosetR {
+    /// The up-sets are read off the chains in parallel with the `rayon` feature.
+    fn up_sets(&self) -> Vec<Elements> {
+        map_range(self.md.n, |x| self.up_set(x))
+    }
+
+    /// Every field is found by a single pass over the covers.
+    fn field_cost(&self, _field: MetaFields) -> u64 {
+        let covers = self.cover_relations().count();
+        (self.md.n + covers) as u64
+    }
+
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        match field {
+            MetaFields::TOP => md.top = Some(unique(&self.maximals_from_covers())),
+            MetaFields::BOT => md.bot = Some(unique(&self.minimals_from_covers())),
+            MetaFields::MINIMALS => md.minimals = Some(self.minimals_from_covers()),
+            MetaFields::MAXIMALS => md.maximals = Some(self.maximals_from_covers()),
+            MetaFields::HEIGHT => md.height = Some(self.height_from_covers()),
+            MetaFields::ATOMS => {
+                md.atoms = Some(match unique(&self.minimals_from_covers()) {
+                    Elt::A(bot) => self.upper_covers(bot),
+                    Elt::NotPresent => Elements::new(),
+                })
+            }
+            MetaFields::COATOMS => {
+                md.coatoms = Some(match unique(&self.maximals_from_covers()) {
+                    Elt::A(top) => (0..self.md.n).filter(|&x| self.covers(x, top)).collect(),
+                    Elt::NotPresent => Elements::new(),
+                })
+            }
+            _ => unreachable!("not a single field"),
+        }
+    }
+}
+
+/// The upper covers of each element: the minimal elements of its up-set with the element itself removed.
+fn covers_from_up_sets(up: &[Elements]) -> Hasse {
+    (0..up.len())
+        .map(|x| {
+            let mut covers = up[x].clone();
+            covers.remove(&x);
+            for &z in up[x].iter().filter(|&&z| z != x) {
+                for y in up[z].iter().filter(|&&y| y != z) {
+                    covers.remove(y);
+                }
+            }
+            SmallSet::from(&covers)
+        })
+        .collect()
+}
+
+impl Convertible for IncrementalPoset {
+    fn up_sets(&self) -> Vec<Elements> {
+        self.elements().map(|x| self.up_set(x)).collect()
+    }
+
+    /// The extremal elements are recognized by the sizes of their rows of bits.
+    fn field_cost(&self, field: MetaFields) -> u64 {
+        let n = self.md.n as u64;
+        match field {
+            MetaFields::BOT | MetaFields::TOP | MetaFields::MINIMALS | MetaFields::MAXIMALS => n,
+            _ => n * n,
+        }
+    }
 
-    PosetH::new(&h)
+    fn compute_field(&self, field: MetaFields, md: &mut MetaData) {
+        let n = self.md.n;
+        let bits = self.bits();
+        let find = |f: &dyn Fn(usize) -> bool| {
+            Some((0..n).find(|&x| f(x)).map_or(Elt::NotPresent, Elt::A))
+        };
+        match field {
+            MetaFields::BOT => md.bot = find(&|x| bits.up_row(x).count_ones() == n),
+            MetaFields::TOP => md.top = find(&|x| bits.down_row(x).count_ones() == n),
+            MetaFields::MINIMALS => {
+                md.minimals = Some(
+                    (0..n)
+                        .filter(|&x| bits.down_row(x).count_ones() == 1)
+                        .collect(),
+                )
+            }
+            MetaFields::MAXIMALS => {
+                md.maximals = Some(
+                    (0..n)
+                        .filter(|&x| bits.up_row(x).count_ones() == 1)
+                        .collect(),
+                )
+            }
+            _ => compute_by_leq(self, field, md),
+        }
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for IncrementalPoset {
+    fn from_poset(p: &S) -> IncrementalPoset {
+        let up = p.up_sets();
+        let mut q = IncrementalPoset::from_fn(p.md().n, |x, y| up[x].contains(&y));
+        q.md = p.md().clone();
+        q
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for PosetR {
+    fn from_poset(p: &S) -> PosetR {
+        let mut q = PosetR::from_covers(covers_from_up_sets(&p.up_sets()));
+        q.md = p.md().clone();
+        q
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for PosetG {
+    fn from_poset(p: &S) -> PosetG {
+        let g: BiPaGraph = p.up_sets().into_iter().enumerate().collect();
+        let mut q = PosetG::new(&g);
+        q.md = p.md().clone();
+        q
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for PosetM {
+    fn from_poset(p: &S) -> PosetM {
+        let n = p.md().n;
+        let m: BoolMatrix = p
+            .up_sets()
+            .iter()
+            .map(|up| (0..n).map(|y| up.contains(&y)).collect())
+            .collect();
+        let mut q = PosetM::new(&m);
+        q.md = p.md().clone();
+        q
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for PosetO {
+    fn from_poset(p: &S) -> PosetO {
+        let g: SortedGraph = p
+            .up_sets()
+            .into_iter()
+            .map(|up| up.into_iter().collect())
+            .enumerate()
+            .collect();
+        let mut q = PosetO::new(&g);
+        q.md = p.md().clone();
+        q
+    }
+}
+
+impl<S: Convertible> FromPoset<S> for PosetH {
+    fn from_poset(p: &S) -> PosetH {
+        let mut q = PosetH::from_covers(covers_from_up_sets(&p.up_sets()));
+        q.md = p.md().clone();
+        q
+    }
 }
 
-fn graph_to_matrix(p: PosetG) -> PosetM {
-    let n = p.md.n;
-    let mut m: BoolMatrix = Vec::with_capacity(n);
-    for i in 0..n {
-        let row = (0..n).map(|j| p.g.get(&i).unwrap().contains(&j)).collect();
-        m.push(row);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+
+    fn chain_h(n: usize) -> PosetH {
+        PosetH::from_covers((0..n).map(|i| (i + 1..n.min(i + 2)).collect()).collect())
+    }
+
+    #[test]
+    fn test_height_computed_on_hasse_side() {
+        let n = 300;
+        let p = chain_h(n);
+        let (m, report): (PosetM, _) = p.convert_with_report(MetaFields::HEIGHT).unwrap();
+        assert_eq!(report, vec![(MetaFields::HEIGHT, Provenance::Source)]);
+        assert_eq!(m.md.height, Some(n));
+        assert!(m.leq(0, n - 1) && !m.leq(n - 1, 0));
+
+        let (_, report): (PosetM, _) = m.convert_with_report(MetaFields::HEIGHT).unwrap();
+        assert_eq!(report, vec![(MetaFields::HEIGHT, Provenance::Carried)]);
     }
 
-    PosetM::new(&m)
+    #[test]
+    fn test_all_fields_populated() {
+        let p = chain_h(50);
+        let mut m: PosetM = p.convert_into(MetaFields::ALL).unwrap();
+        let md = m.md.clone();
+        assert_eq!(md.top, Some(Elt::A(49)));
+        assert_eq!(md.bot, Some(Elt::A(0)));
+        assert_eq!(md.minimals, Some([0].into_iter().collect()));
+        assert_eq!(md.maximals, Some([49].into_iter().collect()));
+        assert_eq!(md.height, Some(50));
+        m.find_height();
+        assert_eq!(m.md, md);
+    }
+
+    #[test]
+    fn test_round_trips_agree_with_find() {
+        for p in corpus() {
+            let g: PosetG = p.convert_into(MetaFields::ALL).unwrap();
+            let h: PosetH = g.convert_into(MetaFields::NONE).unwrap();
+            let mut back: PosetM = h.convert_into(MetaFields::NONE).unwrap();
+            assert_eq!(back.m, p.m);
+            let md = back.md.clone();
+            back.find_top();
+            back.find_bot();
+            back.find_minimals();
+            back.find_maximals();
+            back.find_height();
+            assert_eq!(back.md, md);
+        }
+    }
+
+    #[test]
+    fn test_pairwise_conversions_keep_metadata() {
+        for p in corpus() {
+            let mut p = PosetM::new(&p.m);
+            p.find_top();
+            p.find_minimals();
+            p.find_height();
+            let handle = (p.md.n > 0).then(|| p.pin(p.md.n - 1));
+            let g = matrix_to_graph(&p);
+            let h = matrix_to_hasse(&p);
+            for q in [hasse_to_matrix(&h), graph_to_matrix(&g)] {
+                assert_eq!(q.m, p.m);
+                assert_eq!(q.md, p.md);
+            }
+            for q in [&graph_to_hasse(&g), &h] {
+                assert!(q.same_order(&p));
+                assert_eq!(q.md, p.md);
+            }
+            let back = hasse_to_graph(&h);
+            assert_eq!(back.g, g.g);
+            assert_eq!(back.md, p.md);
+            if let Some(handle) = handle {
+                assert_eq!(back.resolve(&handle), Some(p.md.n - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_metadata() {
+        let mut p = chain_h(3);
+        p.md.height = Some(7);
+        let err = p.convert_into::<PosetM>(MetaFields::HEIGHT).unwrap_err();
+        assert_eq!(err.field, MetaFields::HEIGHT);
+        assert!(err.to_string().starts_with("cannot provide height"));
+        assert!(p.convert_into::<PosetM>(MetaFields::TOP).is_ok());
+    }
 }
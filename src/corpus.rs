@@ -0,0 +1,96 @@
+//! A corpus of small posets used throughout the test suite: the standard families together with
+//! pseudo-random posets of various densities.
+
+use crate::convertors::FromPoset;
+use crate::dynamic::DynPoset;
+use crate::posetg::PosetG;
+use crate::poseth::PosetH;
+use crate::posetm::PosetM;
+use crate::poseto::PosetO;
+use crate::posetr::PosetR;
+use crate::rng::SplitMix64;
+use crate::Poset;
+
+/// The boolean lattice of subsets of a k-element set, element i being the subset with bit mask i.
+pub(crate) fn boolean_lattice(k: u32) -> PosetM {
+    let n = 1usize << k;
+    let m = (0..n)
+        .map(|i| (0..n).map(|j| i & j == i).collect())
+        .collect();
+    PosetM::new(&m)
+}
+
+/// A pseudo-random poset on n elements: the transitive closure of a random relation compatible with the
+/// natural order of the indices, each pair $i < j$ being related with the given probability (in percent).
+pub(crate) fn random_poset(n: usize, percent: u64, seed: u64) -> PosetM {
+    let mut rng = SplitMix64::new(seed);
+    let mut m: Vec<Vec<bool>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| i == j || (i < j && rng.next_u64() % 100 < percent))
+                .collect()
+        })
+        .collect();
+    for k in 0..n {
+        let row_k = m[k].clone();
+        for row in m.iter_mut().filter(|row| row[k]) {
+            row.iter_mut().zip(&row_k).for_each(|(a, &b)| *a |= b);
+        }
+    }
+    PosetM::new(&m)
+}
+
+/// The corpus: small chains, antichains, corollas, boolean lattices, and random posets.
+pub(crate) fn corpus() -> Vec<PosetM> {
+    let mut c = vec![];
+    for n in 0..4 {
+        c.push(PosetM::new_chain(n));
+        c.push(PosetM::new_antichain(n));
+        c.push(PosetM::new_corolla(n));
+    }
+    for k in 1..4 {
+        c.push(boolean_lattice(k));
+    }
+    for seed in 0..12 {
+        c.push(random_poset(
+            3 + seed as usize % 6,
+            15 + 10 * (seed % 5),
+            seed,
+        ));
+    }
+    c
+}
+
+/// Computes every field of the [MetaData](crate::MetaData) of p.
+pub(crate) fn find_all<P: Poset>(p: &mut P) {
+    p.find_top();
+    p.find_bot();
+    p.find_minimals();
+    p.find_maximals();
+    p.find_height();
+    p.find_atoms();
+    p.find_coatoms();
+}
+
+/// Runs check on the posets ps in every representation in turn, all of them in the same one. The meta
+/// data of the posets is computed first, so that the conversions carry it over.
+pub(crate) fn for_each_representation(ps: &[&PosetM], mut check: impl FnMut(&mut [DynPoset])) {
+    let ms: Vec<PosetM> = ps
+        .iter()
+        .map(|p| {
+            let mut m = PosetM::new(&p.m);
+            find_all(&mut m);
+            m
+        })
+        .collect();
+    let representations: [fn(&PosetM) -> DynPoset; 5] = [
+        |m| DynPoset::Matrix(PosetM::from_poset(m)),
+        |m| DynPoset::Graph(PosetG::from_poset(m)),
+        |m| DynPoset::Hasse(PosetH::from_poset(m)),
+        |m| DynPoset::Ordered(PosetO::from_poset(m)),
+        |m| DynPoset::Indexed(PosetR::from_poset(m)),
+    ];
+    for convert in representations {
+        check(&mut ms.iter().map(convert).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,201 @@
+//! A line-based text format for posets with named elements.
+//!
+//! Each line of a cover list is either a single name, declaring an element, or a chain `a < b < c` of
+//! names, declaring that each name covers the one before it. Names are non-empty and contain neither
+//! whitespace nor the characters `<` and `#`. Everything after a `#` is a comment, and blank lines are
+//! ignored. The pairs need not be covers: any pairs generating the order will do (see
+//! [PosetBuilder](crate::labeled::PosetBuilder)), and the elements are numbered in order of first
+//! appearance.
+//!
+//! [to_cover_list] writes the covers, one per line, sorted by the indices of the elements, and declares
+//! elements on lines of their own when needed to keep their order of first appearance. Reading the output
+//! back therefore gives the same poset with the same labels at the same indices.
+//!
+//! ```
+//! use fin_pos::cover_list::{from_cover_list, to_cover_list};
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let text = "# a diamond\nbot < left < top\nbot < right < top\nisolated\n";
+//! let p = from_cover_list::<PosetM>(text).unwrap();
+//! assert_eq!(p.labels(), &["bot", "left", "top", "right", "isolated"]);
+//! assert_eq!(p.leq_labels(&"bot".to_string(), &"top".to_string()), Some(true));
+//! assert_eq!(
+//!     to_cover_list(&p).unwrap(),
+//!     "bot < left\ntop\nbot < right\nleft < top\nright < top\nisolated\n"
+//! );
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::labeled::{LabeledPoset, PosetBuilder};
+use crate::posetm::PosetM;
+use crate::Poset;
+
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::hash::Hash;
+
+/// The ways in which reading or writing a cover list can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoverListError {
+    /// The line, counted from 1, is neither a name nor a chain of names separated by `<`.
+    Syntax { line: usize },
+    /// The cover of lower by upper lies on a cycle of the declared relations.
+    Cycle { lower: String, upper: String },
+    /// The label cannot be written as a name.
+    BadName { name: String },
+}
+
+impl Display for CoverListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverListError::Syntax { line } => write!(f, "line {} is not a chain of names", line),
+            CoverListError::Cycle { lower, upper } => {
+                write!(f, "the cover {} < {} lies on a cycle", lower, upper)
+            }
+            CoverListError::BadName { name } => write!(f, "{:?} is not a valid name", name),
+        }
+    }
+}
+
+impl Error for CoverListError {}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && !s.contains(|c: char| c.is_whitespace() || c == '<' || c == '#')
+}
+
+/// Reads a cover list into a poset in the representation P, labeled by the names.
+pub fn from_cover_list<P>(text: &str) -> Result<LabeledPoset<String, P>, CoverListError>
+where
+    P: Poset + FromPoset<PosetM>,
+{
+    let mut builder = PosetBuilder::new();
+    for (k, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let names: Vec<&str> = line.split('<').map(str::trim).collect();
+        if !names.iter().all(|s| is_name(s)) {
+            return Err(CoverListError::Syntax { line: k + 1 });
+        }
+        builder = builder.element(names[0].to_string());
+        for pair in names.windows(2) {
+            builder = builder.cover(pair[0].to_string(), pair[1].to_string());
+        }
+    }
+    builder.build().map_err(|e| match e {
+        PosetError::Cycle { x, y } => CoverListError::Cycle {
+            lower: builder.labels()[x].clone(),
+            upper: builder.labels()[y].clone(),
+        },
+        e => unreachable!("a builder only reports cycles, not {}", e),
+    })
+}
+
+/// Writes the poset as a cover list, naming the elements by their labels. Fails if a label, as displayed,
+/// is not a valid name.
+pub fn to_cover_list<L: Eq + Hash + Clone + Display, P: Poset>(
+    p: &LabeledPoset<L, P>,
+) -> Result<String, CoverListError> {
+    let names: Vec<String> = p.labels().iter().map(|l| l.to_string()).collect();
+    if let Some(name) = names.iter().find(|s| !is_name(s)) {
+        return Err(CoverListError::BadName { name: name.clone() });
+    }
+    Ok(write_cover_list(p.poset(), &names))
+}
+
+/// Writes the covers of p, declaring the elements not yet mentioned on lines of their own whenever a later
+/// one is about to be, so that the elements appear in the order of their indices.
+pub(crate) fn write_cover_list<P: Poset + ?Sized>(p: &P, names: &[String]) -> String {
+    let n = names.len();
+    let mut out = String::new();
+    let mut mentioned = vec![false; n];
+    // The smallest element that may not have been mentioned yet.
+    let mut next = 0;
+    for (x, y) in p.layout_edges() {
+        // The element first mentioned by the cover being written, which must be declared on its own if
+        // another element has to be declared before y.
+        let mut pending = None;
+        for z in [x, y] {
+            if mentioned[z] {
+                continue;
+            }
+            while next < z {
+                if !mentioned[next] {
+                    if let Some(w) = pending.take() {
+                        writeln!(out, "{}", names[w]).unwrap();
+                    }
+                    mentioned[next] = true;
+                    writeln!(out, "{}", names[next]).unwrap();
+                }
+                next += 1;
+            }
+            mentioned[z] = true;
+            pending = Some(z);
+        }
+        writeln!(out, "{} < {}", names[x], names[y]).unwrap();
+    }
+    for x in (next..n).filter(|&x| !mentioned[x]) {
+        writeln!(out, "{}", names[x]).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+
+    #[test]
+    fn test_round_trip() {
+        for p in corpus() {
+            let text = p.to_cover_list();
+            let q = from_cover_list::<PosetG>(&text).unwrap();
+            let expected: Vec<String> = (0..p.md.n).map(|x| x.to_string()).collect();
+            assert_eq!(q.labels(), expected.as_slice());
+            assert!(q.poset().same_order(&p));
+            assert_eq!(to_cover_list(&q).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_declarations_keep_order() {
+        let p = from_cover_list::<PosetH>("x\nz < x\ny\n").unwrap();
+        assert_eq!(p.labels(), &["x", "z", "y"]);
+        let relabeled = LabeledPoset::new(PosetM::from_poset(p.poset()), vec!["a", "b", "c"]);
+        let mut skip = PosetM::new_antichain(3);
+        skip.add_relation(0, 2).unwrap();
+        assert_eq!(skip.to_cover_list(), "0\n1\n0 < 2\n");
+        // The first element is only above the second, so it is declared before the cover to keep its index.
+        assert_eq!(to_cover_list(&relabeled).unwrap(), "a\nb < a\nc\n");
+        let q = from_cover_list::<PosetM>(&to_cover_list(&p).unwrap()).unwrap();
+        assert_eq!(q.labels(), p.labels());
+        assert!(q.poset().same_order(p.poset()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            from_cover_list::<PosetM>("a < b\n\n a b < c").unwrap_err(),
+            CoverListError::Syntax { line: 3 }
+        );
+        assert_eq!(
+            from_cover_list::<PosetM>("a <").unwrap_err(),
+            CoverListError::Syntax { line: 1 }
+        );
+        let cycle = from_cover_list::<PosetM>("a < b # first\nb < c\nc < a\n").unwrap_err();
+        assert!(matches!(cycle, CoverListError::Cycle { .. }));
+        let bad = LabeledPoset::new(PosetM::new_chain(2), vec!["ok", "not ok"]);
+        assert_eq!(
+            to_cover_list(&bad).unwrap_err().to_string(),
+            "\"not ok\" is not a valid name"
+        );
+        let empty = from_cover_list::<PosetM>("  # nothing\n").unwrap();
+        assert_eq!(empty.poset().md.n, 0);
+        assert_eq!(to_cover_list(&empty).unwrap(), "");
+    }
+}
@@ -0,0 +1,191 @@
+//! The Dushnik–Miller dimension of a poset.
+//!
+//! A *realizer* of a poset is a set of linear extensions whose intersection is the order: $x \le y$ if, and
+//! only if, x comes before y in all of them. The *dimension* is the smallest size of a realizer. Chains
+//! have dimension 1 (and the empty poset dimension 0), a poset has dimension at most 2 if, and only if,
+//! its incomparability graph is a comparability graph, and the crown [Poset::new_crown] of $k \ge 2$
+//! minimal elements has dimension k. Deciding whether the dimension is at most 3 is NP-complete.
+//!
+//! A family of linear extensions is a realizer as soon as it *reverses* every *critical pair*, a pair
+//! $(x, y)$ of incomparable elements such that everything below x is below y and everything above y is
+//! above x, i.e., one of them puts y before x. A set of pairs can be reversed by a single linear extension
+//! if, and only if, adding the reversed pairs to the order creates no cycle. [find_realizer] distributes the
+//! critical pairs among k linear extensions by backtracking, keeping the transitive closure of the order
+//! each extension must contain, and is exact but exponential in the worst case: it is meant for posets of
+//! a few dozen elements.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! assert_eq!(PosetM::new_crown(3).dimension(), 3);
+//! assert!(!PosetM::new_boolean(3).dimension_at_most(2));
+//! let realizer = PosetM::new_antichain(2).realizer();
+//! assert_eq!(realizer, vec![vec![1, 0], vec![0, 1]]);
+//! ```
+
+use crate::bitset::BitRow;
+use crate::relation::strict_matrix;
+use crate::{AnElement, Poset};
+
+/// Returns the critical pairs $(x, y)$ of p, in lexicographic order.
+pub fn critical_pairs<P: Poset + ?Sized>(p: &P) -> Vec<(AnElement, AnElement)> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let incomparable = |x: AnElement, y: AnElement| x != y && !lt[x][y] && !lt[y][x];
+    (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| {
+            incomparable(x, y)
+                && (0..n).all(|z| !lt[z][x] || lt[z][y])
+                && (0..n).all(|z| !lt[y][z] || lt[x][z])
+        })
+        .collect()
+}
+
+/// Returns a realizer of p of at most k linear extensions, or None if the dimension of p exceeds k.
+pub fn find_realizer<P: Poset + ?Sized>(p: &P, k: usize) -> Option<Vec<Vec<AnElement>>> {
+    let n = p.md().n;
+    if n == 0 {
+        return Some(vec![]);
+    }
+    if k == 0 {
+        return None;
+    }
+    // The order as rows of the elements above each element, reflexively.
+    let order: Vec<BitRow> = (0..n)
+        .map(|x| {
+            let mut row = BitRow::new(n);
+            (0..n).filter(|&y| p.leq(x, y)).for_each(|y| row.set(y));
+            row
+        })
+        .collect();
+    let mut extensions = vec![];
+    if !assign(&critical_pairs(p), &order, k, &mut extensions) {
+        return None;
+    }
+    if extensions.is_empty() {
+        // Only chains have no critical pairs, and the order itself is then linear.
+        extensions.push(order);
+    }
+    Some(
+        extensions
+            .into_iter()
+            .map(|rows| {
+                // A transitive relation is extended linearly by the sizes of the down-sets.
+                let mut below = vec![0; n];
+                rows.iter()
+                    .for_each(|row| row.iter_ones().for_each(|y| below[y] += 1));
+                let mut extension: Vec<AnElement> = (0..n).collect();
+                extension.sort_by_key(|&x| below[x]);
+                extension
+            })
+            .collect(),
+    )
+}
+
+/// Assigns each pair $(x, y)$ to one of at most k extensions of the order, whose relations are kept
+/// transitively closed, adding $y \le x$ to it. Extensions are opened as needed, each from a copy of the
+/// order, so that no two assignments differ only by the numbering of the extensions.
+fn assign(
+    pairs: &[(AnElement, AnElement)],
+    order: &[BitRow],
+    k: usize,
+    extensions: &mut Vec<Vec<BitRow>>,
+) -> bool {
+    let Some((&(x, y), rest)) = pairs.split_first() else {
+        return true;
+    };
+    if extensions.iter().any(|e| e[y].get(x)) {
+        return assign(rest, order, k, extensions);
+    }
+    for j in 0..extensions.len() {
+        if extensions[j][x].get(y) {
+            continue;
+        }
+        let saved = extensions[j].clone();
+        reverse(&mut extensions[j], x, y);
+        if assign(rest, order, k, extensions) {
+            return true;
+        }
+        extensions[j] = saved;
+    }
+    if extensions.len() < k {
+        let mut extension = order.to_vec();
+        reverse(&mut extension, x, y);
+        extensions.push(extension);
+        if assign(rest, order, k, extensions) {
+            return true;
+        }
+        extensions.pop();
+    }
+    false
+}
+
+/// Adds $y \le x$ to the transitively closed relation, given that $x \nleq y$.
+fn reverse(rows: &mut [BitRow], x: AnElement, y: AnElement) {
+    let above_x = rows[x].clone();
+    for row in rows.iter_mut().filter(|row| row.get(y)) {
+        row.or_assign(&above_x);
+    }
+}
+
+/// Returns a realizer of p of the smallest size, which is the dimension.
+pub fn realizer<P: Poset + ?Sized>(p: &P) -> Vec<Vec<AnElement>> {
+    (0..)
+        .find_map(|k| find_realizer(p, k))
+        .expect("the critical pairs can be reversed one extension each")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    fn check_realizer<P: Poset>(p: &P, realizer: &[Vec<AnElement>]) {
+        let n = p.md().n;
+        let positions: Vec<Vec<usize>> = realizer
+            .iter()
+            .map(|l| {
+                let mut position = vec![0; n];
+                l.iter().enumerate().for_each(|(i, &x)| position[x] = i);
+                position
+            })
+            .collect();
+        for x in 0..n {
+            for y in 0..n {
+                let before = positions.iter().all(|position| position[x] <= position[y]);
+                assert_eq!(before, p.leq(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_realizers() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 12) {
+            let realizer = p.realizer();
+            check_realizer(&p, &realizer);
+            let d = realizer.len();
+            assert_eq!(p.dimension(), d);
+            assert!(p.dimension_at_most(d));
+            assert!(d == 0 || !p.dimension_at_most(d - 1));
+            assert!(d <= p.width().max(1));
+        }
+    }
+
+    #[test]
+    fn test_standard_examples() {
+        assert_eq!(PosetM::new_chain(0).dimension(), 0);
+        assert_eq!(PosetM::new_chain(4).dimension(), 1);
+        assert_eq!(PosetM::new_antichain(5).dimension(), 2);
+        for k in 2..5 {
+            assert_eq!(PosetM::new_crown(k).dimension(), k);
+        }
+        assert_eq!(boolean_lattice(3).dimension(), 3);
+        assert_eq!(PosetM::new_fence(6).dimension(), 2);
+        let crown = PosetM::new_crown(4);
+        assert_eq!(critical_pairs(&crown).len(), 4);
+        check_realizer(&crown, &crown.realizer());
+    }
+}
@@ -0,0 +1,114 @@
+//! Export of Hasse diagrams in the DOT language of [Graphviz](https://graphviz.org).
+//!
+//! The output lists the elements, an edge from $x$ to $y$ for each cover $x \lessdot y$, and a
+//! `rank=same` group for each level of the poset, i.e., the elements with equally long chains below them.
+//! With `rankdir=BT` the minimal elements are drawn at the bottom and every edge points upwards. Elements
+//! are named by their indices, and optionally labeled with text of the caller's choosing.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_chain(2);
+//! assert_eq!(
+//!     p.to_dot(),
+//!     "digraph {\n  rankdir=BT;\n  0;\n  1;\n  0 -> 1;\n}\n"
+//! );
+//! ```
+
+use crate::relation::{cover_matrix, levels, strict_matrix};
+use crate::Poset;
+
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Writes the Hasse diagram of p to w, labeling element i with labels\[i\] if labels are given. Panics if
+/// there are fewer labels than elements.
+pub fn write_dot<P, W, L>(p: &P, labels: Option<&[L]>, w: &mut W) -> io::Result<()>
+where
+    P: Poset + ?Sized,
+    W: Write,
+    L: Display,
+{
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+    let level = levels(&lt);
+
+    writeln!(w, "digraph {{")?;
+    writeln!(w, "  rankdir=BT;")?;
+    for x in 0..n {
+        match labels {
+            Some(labels) => writeln!(w, "  {} [label=\"{}\"];", x, escape(&labels[x]))?,
+            None => writeln!(w, "  {};", x)?,
+        }
+    }
+    for (x, row) in covers.iter().enumerate() {
+        for y in (0..n).filter(|&y| row[y]) {
+            writeln!(w, "  {} -> {};", x, y)?;
+        }
+    }
+    for l in 0..level.iter().max().map_or(0, |l| l + 1) {
+        let same: Vec<String> = (0..n)
+            .filter(|&x| level[x] == l)
+            .map(|x| x.to_string())
+            .collect();
+        if same.len() > 1 {
+            writeln!(w, "  {{ rank=same; {}; }}", same.join("; "))?;
+        }
+    }
+    writeln!(w, "}}")
+}
+
+/// Returns the Hasse diagram of p as a string (see [write_dot]).
+pub fn to_dot<P, L>(p: &P, labels: Option<&[L]>) -> String
+where
+    P: Poset + ?Sized,
+    L: Display,
+{
+    let mut dot = vec![];
+    write_dot(p, labels, &mut dot).expect("writing to a vector does not fail");
+    String::from_utf8(dot).expect("the output is valid UTF-8")
+}
+
+/// Escapes quotes and backslashes, for use in a quoted DOT string.
+fn escape<L: Display>(label: &L) -> String {
+    label.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_diamond() {
+        let mut p = PosetG::new_antichain(2);
+        p.adjoin_bot();
+        p.adjoin_top();
+        let expected = "digraph {\n  rankdir=BT;\n  0;\n  1;\n  2;\n  3;\n  0 -> 3;\n  1 -> 3;\n  \
+                        2 -> 0;\n  2 -> 1;\n  { rank=same; 0; 1; }\n}\n";
+        assert_eq!(p.to_dot(), expected);
+        assert_eq!(PosetH::from_poset(&p).to_dot(), expected);
+    }
+
+    #[test]
+    fn test_labels_and_writer() {
+        let p = PosetM::new_antichain(2);
+        let mut out = vec![];
+        p.write_dot(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), p.to_dot());
+        assert_eq!(
+            to_dot(&p, Some(&["say \"hi\"", "a\\b"])),
+            "digraph {\n  rankdir=BT;\n  0 [label=\"say \\\"hi\\\"\"];\n  1 [label=\"a\\\\b\"];\n  \
+             { rank=same; 0; 1; }\n}\n"
+        );
+        assert_eq!(
+            PosetM::new_chain(0).to_dot(),
+            "digraph {\n  rankdir=BT;\n}\n"
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Posets in any representation behind a single type.
+//!
+//! The [Poset] trait is object safe: a `&dyn Poset` or a `Box<dyn Poset>` answers every query that does not
+//! require `Self: Sized`, i.e., all but the constructors, the operations returning posets of the same type,
+//! and the methods generic over a second poset or a callback. Every free function of the crate taking a
+//! `P: Poset + ?Sized` accepts one too.
+//!
+//! [DynPoset] holds a poset in any of the five representations and implements the whole trait, so that
+//! collections mixing representations can be processed uniformly. Unary operations such as [Poset::op],
+//! [Poset::sub], [Poset::adjoin_bot], and [Poset::add_relation] keep the representation, and binary ones
+//! such as [Poset::product] convert the second poset to the representation of the first. The constructors,
+//! and the operations that the trait builds through a matrix (e.g., [Poset::canonical_form]), give
+//! [DynPoset::Matrix].
+//!
+//! ```
+//! use fin_pos::dynamic::DynPoset;
+//! use fin_pos::poseth::PosetH;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let mixed: Vec<Box<dyn Poset>> = vec![
+//!     Box::new(PosetM::new_boolean(2)),
+//!     Box::new(PosetH::new_chain(3)),
+//! ];
+//! assert_eq!(mixed.iter().map(|p| p.width()).collect::<Vec<_>>(), vec![2, 1]);
+//!
+//! let p = DynPoset::from(PosetH::new_antichain(2));
+//! let q = p.ordinal_sum(&DynPoset::from(PosetM::new_chain(1)));
+//! assert!(matches!(q, DynPoset::Hasse(_)));
+//! assert!(q.is_isomorphic(&PosetM::new_pentagon().sub(&[0, 2, 4].into()).0));
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::posetg::PosetG;
+use crate::poseth::PosetH;
+use crate::posetm::PosetM;
+use crate::poseto::PosetO;
+use crate::posetr::PosetR;
+use crate::snapshot::Kind;
+use crate::{AnElement, Elements, MetaData, PartialOrdering, Poset};
+
+use std::fmt;
+
+/// A poset in one of the representations.
+#[derive(Debug, PartialEq)]
+pub enum DynPoset {
+    Graph(PosetG),
+    Matrix(PosetM),
+    Hasse(PosetH),
+    Ordered(PosetO),
+    Indexed(PosetR),
+}
+
+impl DynPoset {
+    /// The poset as a trait object.
+    pub fn as_dyn(&self) -> &dyn Poset {
+        match self {
+            DynPoset::Graph(p) => p,
+            DynPoset::Matrix(p) => p,
+            DynPoset::Hasse(p) => p,
+            DynPoset::Ordered(p) => p,
+            DynPoset::Indexed(p) => p,
+        }
+    }
+
+    /// The poset as a mutable trait object.
+    pub fn as_dyn_mut(&mut self) -> &mut dyn Poset {
+        match self {
+            DynPoset::Graph(p) => p,
+            DynPoset::Matrix(p) => p,
+            DynPoset::Hasse(p) => p,
+            DynPoset::Ordered(p) => p,
+            DynPoset::Indexed(p) => p,
+        }
+    }
+
+    /// The poset converted to the representation T, keeping the [MetaData].
+    pub fn convert<T>(&self) -> T
+    where
+        T: FromPoset<PosetG>
+            + FromPoset<PosetM>
+            + FromPoset<PosetH>
+            + FromPoset<PosetO>
+            + FromPoset<PosetR>,
+    {
+        match self {
+            DynPoset::Graph(p) => T::from_poset(p),
+            DynPoset::Matrix(p) => T::from_poset(p),
+            DynPoset::Hasse(p) => T::from_poset(p),
+            DynPoset::Ordered(p) => T::from_poset(p),
+            DynPoset::Indexed(p) => T::from_poset(p),
+        }
+    }
+
+    /// The poset converted to the representation of other.
+    fn converted_like(&self, other: &DynPoset) -> DynPoset {
+        match other {
+            DynPoset::Graph(_) => DynPoset::Graph(self.convert()),
+            DynPoset::Matrix(_) => DynPoset::Matrix(self.convert()),
+            DynPoset::Hasse(_) => DynPoset::Hasse(self.convert()),
+            DynPoset::Ordered(_) => DynPoset::Ordered(self.convert()),
+            DynPoset::Indexed(_) => DynPoset::Indexed(self.convert()),
+        }
+    }
+
+    /// Applies a binary operation of the representation of self, after converting other to it.
+    fn combine(
+        &self,
+        other: &DynPoset,
+        g: impl Fn(&PosetG, &PosetG) -> PosetG,
+        m: impl Fn(&PosetM, &PosetM) -> PosetM,
+        h: impl Fn(&PosetH, &PosetH) -> PosetH,
+        o: impl Fn(&PosetO, &PosetO) -> PosetO,
+        r: impl Fn(&PosetR, &PosetR) -> PosetR,
+    ) -> DynPoset {
+        match (self, &other.converted_like(self)) {
+            (DynPoset::Graph(p), DynPoset::Graph(q)) => DynPoset::Graph(g(p, q)),
+            (DynPoset::Matrix(p), DynPoset::Matrix(q)) => DynPoset::Matrix(m(p, q)),
+            (DynPoset::Hasse(p), DynPoset::Hasse(q)) => DynPoset::Hasse(h(p, q)),
+            (DynPoset::Ordered(p), DynPoset::Ordered(q)) => DynPoset::Ordered(o(p, q)),
+            (DynPoset::Indexed(p), DynPoset::Indexed(q)) => DynPoset::Indexed(r(p, q)),
+            _ => unreachable!("converted to the same representation"),
+        }
+    }
+
+    /// The representation of the poset, as chosen when restoring a snapshot (see
+    /// [from_snapshot](crate::snapshot::from_snapshot)).
+    pub fn kind(&self) -> Kind {
+        match self {
+            DynPoset::Graph(_) => Kind::Graph,
+            DynPoset::Matrix(_) => Kind::Matrix,
+            DynPoset::Hasse(_) => Kind::Hasse,
+            DynPoset::Ordered(_) => Kind::Ordered,
+            DynPoset::Indexed(_) => Kind::Indexed,
+        }
+    }
+}
+
+impl From<PosetG> for DynPoset {
+    fn from(p: PosetG) -> DynPoset {
+        DynPoset::Graph(p)
+    }
+}
+
+impl From<PosetM> for DynPoset {
+    fn from(p: PosetM) -> DynPoset {
+        DynPoset::Matrix(p)
+    }
+}
+
+impl From<PosetH> for DynPoset {
+    fn from(p: PosetH) -> DynPoset {
+        DynPoset::Hasse(p)
+    }
+}
+
+impl From<PosetO> for DynPoset {
+    fn from(p: PosetO) -> DynPoset {
+        DynPoset::Ordered(p)
+    }
+}
+
+impl From<PosetR> for DynPoset {
+    fn from(p: PosetR) -> DynPoset {
+        DynPoset::Indexed(p)
+    }
+}
+
+/// Operations that the trait builds through a matrix give [DynPoset::Matrix].
+impl FromPoset<PosetM> for DynPoset {
+    fn from_poset(p: &PosetM) -> DynPoset {
+        DynPoset::Matrix(PosetM::from_poset(p))
+    }
+}
+
+/// Draws the Hasse diagram as the representation does.
+impl fmt::Display for DynPoset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynPoset::Graph(p) => p.fmt(f),
+            DynPoset::Matrix(p) => p.fmt(f),
+            DynPoset::Hasse(p) => p.fmt(f),
+            DynPoset::Ordered(p) => p.fmt(f),
+            DynPoset::Indexed(p) => p.fmt(f),
+        }
+    }
+}
+
+impl Poset for DynPoset {
+    fn md(&self) -> &MetaData {
+        self.as_dyn().md()
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        self.as_dyn_mut().md_mut()
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
+        self.as_dyn().elements()
+    }
+
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.as_dyn().leq(x, y)
+    }
+
+    fn compare(&self, x: AnElement, y: AnElement) -> PartialOrdering {
+        self.as_dyn().compare(x, y)
+    }
+
+    fn covers(&self, x: AnElement, y: AnElement) -> bool {
+        self.as_dyn().covers(x, y)
+    }
+
+    fn upper_covers(&self, x: AnElement) -> Elements {
+        self.as_dyn().upper_covers(x)
+    }
+
+    fn lower_covers(&self, x: AnElement) -> Elements {
+        self.as_dyn().lower_covers(x)
+    }
+
+    fn up_set(&self, x: AnElement) -> Elements {
+        self.as_dyn().up_set(x)
+    }
+
+    fn relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        self.as_dyn().relations()
+    }
+
+    fn cover_relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        self.as_dyn().cover_relations()
+    }
+
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        self.as_dyn().is_valid_poset()
+    }
+
+    fn find_bot(&mut self) {
+        self.as_dyn_mut().find_bot()
+    }
+
+    fn find_top(&mut self) {
+        self.as_dyn_mut().find_top()
+    }
+
+    fn find_minimals(&mut self) {
+        self.as_dyn_mut().find_minimals()
+    }
+
+    fn find_maximals(&mut self) {
+        self.as_dyn_mut().find_maximals()
+    }
+
+    fn find_height(&mut self) {
+        self.as_dyn_mut().find_height()
+    }
+
+    fn longest_chain(&self) -> Vec<AnElement> {
+        self.as_dyn().longest_chain()
+    }
+
+    fn op(&self) -> DynPoset {
+        match self {
+            DynPoset::Graph(p) => DynPoset::Graph(p.op()),
+            DynPoset::Matrix(p) => DynPoset::Matrix(p.op()),
+            DynPoset::Hasse(p) => DynPoset::Hasse(p.op()),
+            DynPoset::Ordered(p) => DynPoset::Ordered(p.op()),
+            DynPoset::Indexed(p) => DynPoset::Indexed(p.op()),
+        }
+    }
+
+    fn new_chain(n: usize) -> DynPoset {
+        DynPoset::Matrix(PosetM::new_chain(n))
+    }
+
+    fn new_antichain(n: usize) -> DynPoset {
+        DynPoset::Matrix(PosetM::new_antichain(n))
+    }
+
+    fn product(&self, other: &DynPoset) -> DynPoset {
+        self.combine(
+            other,
+            PosetG::product,
+            PosetM::product,
+            PosetH::product,
+            PosetO::product,
+            PosetR::product,
+        )
+    }
+
+    fn coproduct(&self, other: &DynPoset) -> DynPoset {
+        self.combine(
+            other,
+            PosetG::coproduct,
+            PosetM::coproduct,
+            PosetH::coproduct,
+            PosetO::coproduct,
+            PosetR::coproduct,
+        )
+    }
+
+    fn ordinal_sum(&self, other: &DynPoset) -> DynPoset {
+        self.combine(
+            other,
+            PosetG::ordinal_sum,
+            PosetM::ordinal_sum,
+            PosetH::ordinal_sum,
+            PosetO::ordinal_sum,
+            PosetR::ordinal_sum,
+        )
+    }
+
+    fn adjoin_bot(&mut self) {
+        self.as_dyn_mut().adjoin_bot()
+    }
+
+    fn adjoin_top(&mut self) {
+        self.as_dyn_mut().adjoin_top()
+    }
+
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        match self {
+            DynPoset::Graph(p) => p.add_relation(x, y),
+            DynPoset::Matrix(p) => p.add_relation(x, y),
+            DynPoset::Hasse(p) => p.add_relation(x, y),
+            DynPoset::Ordered(p) => p.add_relation(x, y),
+            DynPoset::Indexed(p) => p.add_relation(x, y),
+        }
+    }
+
+    fn remove_element(&mut self, x: AnElement) {
+        match self {
+            DynPoset::Graph(p) => p.remove_element(x),
+            DynPoset::Matrix(p) => p.remove_element(x),
+            DynPoset::Hasse(p) => p.remove_element(x),
+            DynPoset::Ordered(p) => p.remove_element(x),
+            DynPoset::Indexed(p) => p.remove_element(x),
+        }
+    }
+
+    fn sub(&self, s_0: &Elements) -> (DynPoset, Vec<AnElement>) {
+        match self {
+            DynPoset::Graph(p) => {
+                let (q, back) = p.sub(s_0);
+                (DynPoset::Graph(q), back)
+            }
+            DynPoset::Matrix(p) => {
+                let (q, back) = p.sub(s_0);
+                (DynPoset::Matrix(q), back)
+            }
+            DynPoset::Hasse(p) => {
+                let (q, back) = p.sub(s_0);
+                (DynPoset::Hasse(q), back)
+            }
+            DynPoset::Ordered(p) => {
+                let (q, back) = p.sub(s_0);
+                (DynPoset::Ordered(q), back)
+            }
+            DynPoset::Indexed(p) => {
+                let (q, back) = p.sub(s_0);
+                (DynPoset::Indexed(q), back)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+
+    fn all_kinds(p: &PosetM) -> Vec<DynPoset> {
+        vec![
+            DynPoset::Graph(PosetG::from_poset(p)),
+            DynPoset::Matrix(PosetM::from_poset(p)),
+            DynPoset::Hasse(PosetH::from_poset(p)),
+            DynPoset::Ordered(PosetO::from_poset(p)),
+            DynPoset::Indexed(PosetR::from_poset(p)),
+        ]
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        for mut p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let mut boxed: Vec<Box<dyn Poset>> = vec![
+                Box::new(PosetG::from_poset(&p)),
+                Box::new(PosetH::from_poset(&p)),
+                Box::new(PosetO::from_poset(&p)),
+                Box::new(PosetR::from_poset(&p)),
+            ];
+            for q in &mut boxed {
+                assert!(p.same_order(q.as_ref()));
+                assert_eq!(q.width(), p.width());
+                assert_eq!(q.height(), p.height());
+                assert_eq!(q.count_linear_extensions(), p.count_linear_extensions());
+            }
+        }
+    }
+
+    #[test]
+    fn test_operations_keep_the_representation() {
+        let ps: Vec<PosetM> = corpus()
+            .into_iter()
+            .filter(|p| (1..=6).contains(&p.md.n))
+            .collect();
+        for (p, q) in ps.iter().zip(ps.iter().skip(1)) {
+            for (a, b) in all_kinds(p).iter().zip(all_kinds(q).iter().rev()) {
+                let kind = std::mem::discriminant(a);
+                assert!(a.same_order(p) && a.op().same_order(&p.op()));
+                for (c, expected) in [
+                    (a.product(b), p.product(q)),
+                    (a.coproduct(b), p.coproduct(q)),
+                    (a.ordinal_sum(b), p.ordinal_sum(q)),
+                    (a.sub(&[0].into()).0, p.sub(&[0].into()).0),
+                ] {
+                    assert_eq!(std::mem::discriminant(&c), kind);
+                    assert!(c.same_order(&expected));
+                    assert_eq!(c.is_valid_poset(), Ok(()));
+                }
+                let mut c = a.op();
+                c.adjoin_top();
+                c.add_relation(0, 0).unwrap();
+                c.remove_element(0);
+                assert_eq!(std::mem::discriminant(&c), kind);
+                assert!(a.is_isomorphic(&a.convert::<PosetM>()));
+            }
+        }
+        assert!(matches!(DynPoset::new_chain(2), DynPoset::Matrix(_)));
+    }
+}
@@ -0,0 +1,156 @@
+//! Bit-vector encodings of posets, i.e., embeddings into Boolean lattices.
+//!
+//! An encoding of a poset in k bits assigns every element x a subset $c(x)$ of $\{0, ..., k-1\}$ such that
+//! $x \le y$ if, and only if, $c(x) \subseteq c(y)$, so that comparing two elements takes a bitwise
+//! implication, as used for type hierarchies. The smallest such k is the *2-dimension* of the poset, the
+//! least k for which it embeds into the Boolean lattice $2^k$. Unless the poset has a single element, which
+//! needs no bits, it is at least the [dimension](crate::dimension). It is also at least $\log_2 n$ and at
+//! most n, and computing it is NP-hard.
+//!
+//! Bit i of an encoding is set on an up-set $U_i$, and the up-sets encode the order as soon as every pair
+//! $x \nleq y$ is *separated* by one of them, containing x but not y. It is enough to separate the pairs in
+//! which everything strictly below x is below y and everything strictly above y is above x.
+//! [boolean_encoding] searches for the fewest up-sets separating those pairs by iterative deepening over
+//! all the up-sets of the poset, which is exponential: it is meant for posets of a dozen elements or so.
+//!
+//! ```
+//! use fin_pos::encoding::boolean_encoding;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // Three incomparable elements need three bits, as the largest antichains of subsets of two bits have
+//! // two elements.
+//! assert_eq!(PosetM::new_antichain(3).two_dimension(), 3);
+//! let p = PosetM::new_boolean(2);
+//! let codes = boolean_encoding(&p);
+//! assert_eq!(codes.len(), 4);
+//! assert!(codes[1].is_subset(&codes[3]) && !codes[1].is_subset(&codes[2]));
+//! ```
+
+use crate::bitset::{BitMatrix, BitRow};
+use crate::ideals::all_downsets;
+use crate::{AnElement, Poset};
+
+/// Returns an encoding of p in as few bits as possible, as the set of bits $c(x)$ of every element x, each a
+/// subset of $\{0, ..., k-1\}$ where k is the 2-dimension.
+pub fn boolean_encoding<P: Poset + ?Sized>(p: &P) -> Vec<BitRow> {
+    let b = BitMatrix::new(p);
+    let n = b.n();
+    let below = |x: AnElement, y: AnElement| x != y && b.leq(x, y);
+    let pairs: Vec<(AnElement, AnElement)> = (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| {
+            !b.leq(x, y)
+                && (0..n).all(|z| !below(z, x) || b.leq(z, y))
+                && (0..n).all(|z| !below(y, z) || b.leq(x, z))
+        })
+        .collect();
+    let upsets: Vec<BitRow> = all_downsets(&b)
+        .into_iter()
+        .map(|mut d| {
+            d.not_assign();
+            d
+        })
+        .filter(|u| !u.none() && u.count_ones() < n)
+        .collect();
+    let candidates: Vec<Vec<usize>> = pairs
+        .iter()
+        .map(|&(x, y)| {
+            (0..upsets.len())
+                .filter(|&i| upsets[i].get(x) && !upsets[i].get(y))
+                .collect()
+        })
+        .collect();
+    let height = p.longest_chain().len();
+    let lower = (usize::BITS - n.saturating_sub(1).leading_zeros()) as usize;
+    let mut chosen = vec![];
+    let k = (lower.max(height.saturating_sub(1))..)
+        .find(|&k| separate(&pairs, &candidates, &upsets, k, &mut chosen))
+        .unwrap();
+    debug_assert_eq!(chosen.len(), k);
+    (0..n)
+        .map(|x| {
+            let mut code = BitRow::new(k);
+            (0..k)
+                .filter(|&i| upsets[chosen[i]].get(x))
+                .for_each(|i| code.set(i));
+            code
+        })
+        .collect()
+}
+
+/// Extends chosen by at most k up-sets in total so that every pair is separated, branching on the
+/// candidates for the first pair not yet separated.
+fn separate(
+    pairs: &[(AnElement, AnElement)],
+    candidates: &[Vec<usize>],
+    upsets: &[BitRow],
+    k: usize,
+    chosen: &mut Vec<usize>,
+) -> bool {
+    let open = pairs.iter().position(|&(x, y)| {
+        !chosen
+            .iter()
+            .any(|&i| upsets[i].get(x) && !upsets[i].get(y))
+    });
+    let Some(open) = open else {
+        return true;
+    };
+    if chosen.len() == k {
+        return false;
+    }
+    for &i in &candidates[open] {
+        chosen.push(i);
+        if separate(pairs, candidates, upsets, k, chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    fn check_encoding<P: Poset>(p: &P, codes: &[BitRow]) {
+        let n = p.md().n;
+        assert_eq!(codes.len(), n);
+        for x in 0..n {
+            for y in 0..n {
+                assert_eq!(codes[x].is_subset(&codes[y]), p.leq(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encodings() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 8) {
+            let codes = boolean_encoding(&p);
+            check_encoding(&p, &codes);
+            let k = p.two_dimension();
+            assert!(codes.iter().all(|c| c.len() == k));
+            // A single element has dimension 1 but needs no bits.
+            assert!(p.dimension() <= k || p.md.n <= 1);
+            assert!(k <= p.md.n);
+        }
+    }
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(PosetM::new_chain(0).two_dimension(), 0);
+        assert_eq!(PosetM::new_chain(1).two_dimension(), 0);
+        assert_eq!(PosetM::new_chain(5).two_dimension(), 4);
+        for k in 0..4 {
+            assert_eq!(boolean_lattice(k).two_dimension(), k as usize);
+        }
+        // Sperner: six incomparable elements fit in four bits, as the 2-subsets of a 4-set.
+        assert_eq!(PosetM::new_antichain(6).two_dimension(), 4);
+        assert_eq!(PosetM::new_antichain(7).two_dimension(), 5);
+        let crown = PosetM::new_crown(3);
+        assert_eq!(crown.two_dimension(), 3);
+        check_encoding(&crown, &boolean_encoding(&crown));
+    }
+}
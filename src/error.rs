@@ -0,0 +1,72 @@
+//! The error type shared by operations of the different representations.
+
+use crate::AnElement;
+
+use std::error::Error;
+use std::fmt;
+
+/// The ways in which an operation on a poset can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PosetError {
+    /// The element is not one of $0, ..., n-1$.
+    IndexOutOfRange { index: AnElement, n: usize },
+    /// The operation needs y to cover x, which it does not.
+    NotACover { x: AnElement, y: AnElement },
+    /// Nothing is stored for the element x, e.g., a matrix row that is missing or too short.
+    Missing { x: AnElement },
+    /// The relation does not have $x \le x$.
+    NotReflexive { x: AnElement },
+    /// The relation has $x \le y$ and $y \le x$ for $x \ne y$.
+    NotAntisymmetric { x: AnElement, y: AnElement },
+    /// The relation has $x \le y$ and $y \le z$ but not $x \le z$.
+    NotTransitive {
+        x: AnElement,
+        y: AnElement,
+        z: AnElement,
+    },
+    /// The cover $x \lessdot y$ of a Hasse diagram lies on a cycle (y = x for a loop).
+    Cycle { x: AnElement, y: AnElement },
+    /// A map has $x \le y$ but not $f(x) \le f(y)$.
+    NotMonotone { x: AnElement, y: AnElement },
+    /// The element x lies in more than one block of a partition.
+    Overlap { x: AnElement },
+    /// The block of a partition has no elements.
+    EmptyBlock { block: usize },
+    /// The elements x and y have no join or no meet.
+    NotALattice { x: AnElement, y: AnElement },
+    /// The cached [MetaData](crate::MetaData) field disagrees with the order.
+    InconsistentMetaData { field: &'static str },
+}
+
+impl fmt::Display for PosetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PosetError::IndexOutOfRange { index, n } => {
+                write!(f, "element {} is out of range for {} elements", index, n)
+            }
+            PosetError::NotACover { x, y } => write!(f, "{} does not cover {}", y, x),
+            PosetError::Missing { x } => write!(f, "nothing is stored for element {}", x),
+            PosetError::NotReflexive { x } => write!(f, "{} is not below itself", x),
+            PosetError::NotAntisymmetric { x, y } => {
+                write!(f, "{} and {} are distinct but below each other", x, y)
+            }
+            PosetError::NotTransitive { x, y, z } => {
+                write!(f, "{} <= {} <= {} but not {} <= {}", x, y, z, x, z)
+            }
+            PosetError::Cycle { x, y } => write!(f, "the cover {} -> {} lies on a cycle", x, y),
+            PosetError::NotMonotone { x, y } => {
+                write!(f, "{} <= {} but their images are not in order", x, y)
+            }
+            PosetError::Overlap { x } => write!(f, "{} lies in more than one block", x),
+            PosetError::EmptyBlock { block } => write!(f, "block {} is empty", block),
+            PosetError::NotALattice { x, y } => {
+                write!(f, "{} and {} have no join or no meet", x, y)
+            }
+            PosetError::InconsistentMetaData { field } => {
+                write!(f, "the cached {} disagrees with the order", field)
+            }
+        }
+    }
+}
+
+impl Error for PosetError {}
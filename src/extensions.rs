@@ -0,0 +1,142 @@
+//! Generating linear extensions, i.e., listings of the elements in which every element comes after all
+//! the elements below it.
+//!
+//! [linear_extension] returns the lexicographically smallest one. [LinearExtensions] lists all of them with
+//! the Varol–Rotem algorithm: the elements are relabeled along a first linear extension, so that the
+//! identity is one, and every further extension is obtained from the previous one by moving a single
+//! element, in $O(1)$ amortized steps per extension. There can be as many as $n!$ of them (see
+//! [Poset::count_linear_extensions] to count them first).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let mut p = PosetM::new_antichain(2);
+//! p.adjoin_bot();
+//! assert_eq!(p.linear_extension(), vec![2, 0, 1]);
+//! let all: Vec<Vec<usize>> = p.all_linear_extensions().collect();
+//! assert_eq!(all, vec![vec![2, 0, 1], vec![2, 1, 0]]);
+//! ```
+
+use crate::relation::strict_matrix;
+use crate::schedule::schedule_waves;
+use crate::{AnElement, Poset};
+
+/// Returns the lexicographically smallest linear extension of p: at every step, the smallest element all
+/// of whose predecessors are already listed.
+pub fn linear_extension<P: Poset + ?Sized>(p: &P) -> Vec<AnElement> {
+    schedule_waves(p, 1, |_| 0).concat()
+}
+
+/// An iterator over all the linear extensions of a poset, starting with [linear_extension]. Each
+/// extension is listed once.
+pub struct LinearExtensions {
+    /// The elements in the order of the first extension; the algorithm works with positions in it.
+    labels: Vec<AnElement>,
+    /// The strict order on the labels.
+    lt: Vec<Vec<bool>>,
+    /// The current extension, as labels, and the position of every label in it.
+    perm: Vec<usize>,
+    pos: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl LinearExtensions {
+    /// Returns an iterator over the linear extensions of p.
+    pub fn new<P: Poset + ?Sized>(p: &P) -> LinearExtensions {
+        let labels = linear_extension(p);
+        let lt = strict_matrix(p);
+        let n = labels.len();
+        LinearExtensions {
+            lt: labels
+                .iter()
+                .map(|&x| labels.iter().map(|&y| lt[x][y]).collect())
+                .collect(),
+            labels,
+            perm: (0..n).collect(),
+            pos: (0..n).collect(),
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Moves to the next extension, returning false if there is none. Label i moves one place to the right
+    /// if it can; otherwise it is put back in place i and the next label is tried. Labels below i are in
+    /// their places throughout.
+    fn advance(&mut self) -> bool {
+        let n = self.perm.len();
+        let mut i = 0;
+        while i + 1 < n {
+            let k = self.pos[i];
+            if k + 1 < n && !self.lt[i][self.perm[k + 1]] {
+                let j = self.perm[k + 1];
+                self.perm.swap(k, k + 1);
+                self.pos[i] = k + 1;
+                self.pos[j] = k;
+                return true;
+            }
+            for j in (i + 1..=k).rev() {
+                self.perm[j] = self.perm[j - 1];
+                self.pos[self.perm[j]] = j;
+            }
+            self.perm[i] = i;
+            self.pos[i] = i;
+            i += 1;
+        }
+        false
+    }
+}
+
+impl Iterator for LinearExtensions {
+    type Item = Vec<AnElement>;
+
+    fn next(&mut self) -> Option<Vec<AnElement>> {
+        if self.done {
+            return None;
+        }
+        if self.started && !self.advance() {
+            self.done = true;
+            return None;
+        }
+        self.started = true;
+        Some(self.perm.iter().map(|&l| self.labels[l]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetm::PosetM;
+    use crate::refinement::is_linear_extension;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_all_extensions() {
+        for p in corpus()
+            .into_iter()
+            .filter(|p| p.count_linear_extensions() <= 5000)
+        {
+            let all: Vec<_> = p.all_linear_extensions().collect();
+            assert_eq!(all[0], p.linear_extension());
+            assert_eq!(all.len() as u128, p.count_linear_extensions());
+            assert!(all.iter().all(|l| is_linear_extension(&p, l)));
+            assert_eq!(all.iter().collect::<HashSet<_>>().len(), all.len());
+        }
+    }
+
+    #[test]
+    fn test_small_cases() {
+        let empty: Vec<_> = PosetM::new_antichain(0).all_linear_extensions().collect();
+        assert_eq!(empty, vec![Vec::<AnElement>::new()]);
+        assert_eq!(PosetM::new_chain(4).all_linear_extensions().count(), 1);
+        assert_eq!(
+            PosetM::new_antichain(5).all_linear_extensions().count(),
+            120
+        );
+        let p = PosetM::new_chain(3).op();
+        assert_eq!(p.linear_extension(), vec![2, 1, 0]);
+    }
+}
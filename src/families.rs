@@ -0,0 +1,483 @@
+//! Standard families of posets whose elements are mathematical objects, returned as [LabeledPoset]s that
+//! map every element to the object it stands for.
+//!
+//! The elements are numbered along a linear extension of the order, so that element 0 is minimal.
+//!
+//! ```
+//! use fin_pos::families::new_divisor_lattice;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let d = new_divisor_lattice::<PosetM>(12);
+//! assert_eq!(d.labels(), &[1, 2, 3, 4, 6, 12]);
+//! assert_eq!(d.leq_labels(&2, &6), Some(true));
+//! assert_eq!(d.leq_labels(&4, &6), Some(false));
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::labeled::LabeledPoset;
+use crate::posetm::PosetM;
+use crate::{BoolMatrix, Poset};
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Labels the elements of the poset on labels ordered by leq, which the order of labels must extend.
+fn from_labels<L, P, F>(labels: Vec<L>, leq: F) -> LabeledPoset<L, P>
+where
+    L: Eq + Hash + Clone,
+    P: Poset + FromPoset<PosetM>,
+    F: Fn(&L, &L) -> bool,
+{
+    let m: BoolMatrix = labels
+        .iter()
+        .map(|a| labels.iter().map(|b| leq(a, b)).collect())
+        .collect();
+    LabeledPoset::new(P::from_poset(&PosetM::new(&m)), labels)
+}
+
+/// The divisors of n ordered by divisibility, labeled by their values in increasing order. It is a
+/// distributive lattice. Panics if n is 0, which every integer divides.
+pub fn new_divisor_lattice<P: Poset + FromPoset<PosetM>>(n: u64) -> LabeledPoset<u64, P> {
+    assert!(n > 0, "0 has infinitely many divisors");
+    let mut divisors: Vec<u64> = (1..)
+        .take_while(|&d| d <= n / d)
+        .filter(|&d| n.is_multiple_of(d))
+        .flat_map(|d| [d, n / d])
+        .collect();
+    divisors.sort_unstable();
+    divisors.dedup();
+    from_labels(divisors, |a, b| b.is_multiple_of(*a))
+}
+
+/// A set partition, as its blocks, each sorted, in order of their smallest elements.
+pub type SetPartition = Vec<Vec<usize>>;
+
+/// The partitions of $\{0, ..., k-1\}$ ordered by refinement, a partition being below the partitions
+/// whose blocks are unions of its blocks. The elements are numbered by decreasing number of blocks, so the
+/// partition into singletons is element 0 and the one-block partition is last, and each partition is
+/// labeled by its blocks. There are Bell number $B_k$ of them, which is 877 for $k = 7$.
+pub fn new_partition_lattice<P: Poset + FromPoset<PosetM>>(
+    k: usize,
+) -> LabeledPoset<SetPartition, P> {
+    // Restricted growth strings: entry i is the block of i, at most one more than the blocks before it.
+    let block_count = |s: &Vec<usize>| s.iter().max().map_or(0, |&b| b + 1);
+    let mut strings: Vec<Vec<usize>> = vec![vec![]];
+    for _ in 0..k {
+        strings = strings
+            .into_iter()
+            .flat_map(|s| (0..=block_count(&s)).map(move |b| [s.clone(), vec![b]].concat()))
+            .collect();
+    }
+    strings.sort_by_key(|s| std::cmp::Reverse(block_count(s)));
+    let labels = strings
+        .iter()
+        .map(|s| {
+            let mut blocks = vec![vec![]; block_count(s)];
+            s.iter().enumerate().for_each(|(i, &b)| blocks[b].push(i));
+            blocks
+        })
+        .collect();
+    from_labels(labels, |a: &SetPartition, b: &SetPartition| {
+        a.iter()
+            .all(|block| b.iter().any(|c| block.iter().all(|x| c.contains(x))))
+    })
+}
+
+/// An integer partition, as its parts in weakly decreasing order.
+pub type IntegerPartition = Vec<usize>;
+
+/// The integer partitions of $0, 1, ..., n$ ordered by containment of their Young diagrams, i.e., $\lambda
+/// \le \mu$ if $\lambda_i \le \mu_i$ for every part. This is the part of Young's lattice of rank at most
+/// n. The elements are numbered by increasing size, and partitions of equal size in decreasing
+/// lexicographic order, so the empty partition is element 0 and `[n]` comes before `[1; n]`.
+pub fn new_young_lattice_up_to<P: Poset + FromPoset<PosetM>>(
+    n: usize,
+) -> LabeledPoset<IntegerPartition, P> {
+    let labels = (0..=n).flat_map(|m| partitions(m, m)).collect();
+    from_labels(labels, |a: &IntegerPartition, b: &IntegerPartition| {
+        a.len() <= b.len() && a.iter().zip(b).all(|(x, y)| x <= y)
+    })
+}
+
+/// The partitions of m into parts of at most max, in decreasing lexicographic order.
+fn partitions(m: usize, max: usize) -> Vec<IntegerPartition> {
+    if m == 0 {
+        return vec![vec![]];
+    }
+    (1..=max.min(m))
+        .rev()
+        .flat_map(|first| {
+            partitions(m - first, first)
+                .into_iter()
+                .map(move |rest| [vec![first], rest].concat())
+        })
+        .collect()
+}
+
+/// The face lattice of the d-simplex: the faces are spanned by the subsets of its $d + 1$ vertices
+/// $0, ..., d$, ordered by inclusion, from the empty face (the bottom) to the simplex itself (the top), so
+/// no further bounds need adjoining. As a poset this is the Boolean lattice of [Poset::new_boolean], with
+/// the same numbering and [MetaData](crate::MetaData), each face labeled by its sorted vertices.
+pub fn new_simplex_face_lattice<P: Poset>(d: usize) -> LabeledPoset<Vec<usize>, P> {
+    let vertices = d as u32 + 1;
+    let labels = (0..1usize << vertices)
+        .map(|face| (0..=d).filter(|&v| face >> v & 1 == 1).collect())
+        .collect();
+    LabeledPoset::new(P::new_boolean(vertices), labels)
+}
+
+/// A permutation of $\{0, ..., k-1\}$ in one-line notation, entry i being the image of i.
+pub type Permutation = Vec<usize>;
+
+/// The permutations of $\{0, ..., k-1\}$ by increasing number of inversions, and in lexicographic order
+/// among those with equally many, so that the identity comes first and the reversal last.
+fn permutations(k: usize) -> Vec<Permutation> {
+    let mut all: Vec<Permutation> = vec![vec![]];
+    for _ in 0..k {
+        all = all
+            .into_iter()
+            .flat_map(|w| {
+                let free: Vec<usize> = (0..k).filter(|x| !w.contains(x)).collect();
+                free.into_iter().map(move |x| [w.clone(), vec![x]].concat())
+            })
+            .collect();
+    }
+    all.sort_by_key(|w| inversions(w).len());
+    all
+}
+
+/// The pairs of positions $i < j$ with $w(i) > w(j)$.
+fn inversions(w: &[usize]) -> Vec<(usize, usize)> {
+    let k = w.len();
+    (0..k)
+        .flat_map(|i| (i + 1..k).map(move |j| (i, j)))
+        .filter(|&(i, j)| w[i] > w[j])
+        .collect()
+}
+
+/// The symmetric group $S_k$ under the (strong) Bruhat order, in which $u \le w$ if w is obtained from u
+/// by a sequence of transpositions each increasing the number of inversions. Each element is labeled by
+/// its permutation, and the elements are numbered by increasing number of inversions (which is the rank)
+/// and then lexicographically. There are $k!$ of them, which is 5040 for $k = 7$.
+pub fn new_bruhat_order<P: Poset + FromPoset<PosetM>>(k: usize) -> LabeledPoset<Permutation, P> {
+    // The tableau criterion: u <= w if, for all i and j, no more of u(0), ..., u(i) are at least j than
+    // of w(0), ..., w(i).
+    let counts = |w: &Permutation| -> Vec<Vec<usize>> {
+        (0..k)
+            .map(|i| {
+                (0..k)
+                    .map(|j| w[..=i].iter().filter(|&&x| x >= j).count())
+                    .collect()
+            })
+            .collect()
+    };
+    let labels = permutations(k);
+    let table: Vec<Vec<Vec<usize>>> = labels.iter().map(counts).collect();
+    let index: HashMap<&Permutation, usize> =
+        labels.iter().enumerate().map(|(i, w)| (w, i)).collect();
+    from_labels(labels.clone(), |u, w| {
+        let (a, b) = (&table[index[u]], &table[index[w]]);
+        a.iter()
+            .zip(b)
+            .all(|(r, s)| r.iter().zip(s).all(|(x, y)| x <= y))
+    })
+}
+
+/// Which side the weak order on a symmetric group multiplies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// $w$ is covered by $s_i w$, i.e., the values i and $i + 1$ are swapped when i comes first in w.
+    Left,
+    /// $w$ is covered by $w s_i$, i.e., the entries at positions i and $i + 1$ are swapped when they are
+    /// increasing.
+    Right,
+}
+
+/// The symmetric group $S_k$ under the left or right weak order, numbered and labeled as in
+/// [new_bruhat_order]. Each is a lattice graded by the number of inversions, the two are isomorphic via
+/// $w \mapsto w^{-1}$, and both are weaker than the Bruhat order. In the right weak order $u \le w$ if every
+/// pair of values inverted in u is inverted in w, and in the left weak order if every pair of positions
+/// inverted in u is inverted in w.
+pub fn new_weak_order<P: Poset + FromPoset<PosetM>>(
+    k: usize,
+    side: Side,
+) -> LabeledPoset<Permutation, P> {
+    let inverse = |w: &Permutation| {
+        let mut v = vec![0; w.len()];
+        w.iter().enumerate().for_each(|(i, &x)| v[x] = i);
+        v
+    };
+    let labels = permutations(k);
+    // The inversions of the inverse are the inverted pairs of values.
+    let sets: Vec<Vec<(usize, usize)>> = labels
+        .iter()
+        .map(|w| match side {
+            Side::Left => inversions(w),
+            Side::Right => inversions(&inverse(w)),
+        })
+        .collect();
+    let index: HashMap<&Permutation, usize> =
+        labels.iter().enumerate().map(|(i, w)| (w, i)).collect();
+    from_labels(labels.clone(), |u, w| {
+        let (a, b) = (&sets[index[u]], &sets[index[w]]);
+        a.iter().all(|pair| b.contains(pair))
+    })
+}
+
+/// The subgroups of the group of permutations of $\{0, ..., k-1\}$ generated by the given ones, ordered by
+/// inclusion. Each subgroup is labeled by its elements in lexicographic order, and the subgroups are
+/// numbered by increasing order and then lexicographically by label, so the trivial group is element 0
+/// and the whole group last. Panics if a generator is not a permutation of $\{0, ..., k-1\}$.
+///
+/// The subgroups are found by adjoining one element at a time to the subgroups found so far, starting
+/// from the trivial group, which takes a closure for every subgroup and element: this is only feasible
+/// for groups with at most a few hundred elements.
+pub fn new_subgroup_lattice<P: Poset + FromPoset<PosetM>>(
+    k: usize,
+    generators: &[Permutation],
+) -> LabeledPoset<Vec<Permutation>, P> {
+    for g in generators {
+        let mut sorted = g.clone();
+        sorted.sort_unstable();
+        assert!(
+            sorted.into_iter().eq(0..k),
+            "{:?} is not a permutation of {} elements",
+            g,
+            k
+        );
+    }
+    // The group, closed under multiplication by the generators, with its multiplication table.
+    let compose =
+        |u: &Permutation, w: &Permutation| -> Permutation { w.iter().map(|&x| u[x]).collect() };
+    let mut group: Vec<Permutation> = vec![(0..k).collect()];
+    let mut index: HashMap<Permutation, usize> = HashMap::from([(group[0].clone(), 0)]);
+    let mut next = 0;
+    while next < group.len() {
+        for g in generators {
+            let h = compose(&group[next], g);
+            if !index.contains_key(&h) {
+                index.insert(h.clone(), group.len());
+                group.push(h);
+            }
+        }
+        next += 1;
+    }
+    let product: Vec<Vec<usize>> = group
+        .iter()
+        .map(|u| group.iter().map(|w| index[&compose(u, w)]).collect())
+        .collect();
+    // The subgroup generated by a subgroup h and an element g, as the indices of its elements.
+    let join = |h: &[usize], g: usize| -> Vec<usize> {
+        let mut member = vec![false; group.len()];
+        let mut elements = h.to_vec();
+        elements.iter().for_each(|&x| member[x] = true);
+        let mut next = 0;
+        while next < elements.len() {
+            let x = elements[next];
+            for y in h.iter().copied().chain([g]) {
+                let z = product[x][y];
+                if !member[z] {
+                    member[z] = true;
+                    elements.push(z);
+                }
+            }
+            next += 1;
+        }
+        elements.sort_unstable();
+        elements
+    };
+    let mut subgroups: Vec<Vec<usize>> = vec![vec![0]];
+    let mut found: HashSet<Vec<usize>> = subgroups.iter().cloned().collect();
+    let mut next = 0;
+    while next < subgroups.len() {
+        for g in 0..group.len() {
+            if subgroups[next].binary_search(&g).is_err() {
+                let h = join(&subgroups[next], g);
+                if found.insert(h.clone()) {
+                    subgroups.push(h);
+                }
+            }
+        }
+        next += 1;
+    }
+    let mut labels: Vec<Vec<Permutation>> = subgroups
+        .iter()
+        .map(|h| {
+            let mut elements: Vec<Permutation> = h.iter().map(|&x| group[x].clone()).collect();
+            elements.sort();
+            elements
+        })
+        .collect();
+    labels.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+    from_labels(labels, |a: &Vec<Permutation>, b: &Vec<Permutation>| {
+        a.iter().all(|x| b.binary_search(x).is_ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::boolean_lattice;
+    use crate::poseth::PosetH;
+    use crate::Elt;
+
+    #[test]
+    fn test_divisor_lattice() {
+        let d = new_divisor_lattice::<PosetM>(1);
+        assert_eq!(d.labels(), &[1]);
+        // Square-free numbers give Boolean lattices, prime powers chains.
+        let d = new_divisor_lattice::<PosetH>(2 * 3 * 5);
+        assert!(d.poset().is_isomorphic(&boolean_lattice(3)));
+        let d = new_divisor_lattice::<PosetM>(64);
+        assert!(d.poset().same_order(&PosetM::new_chain(7)));
+        let d = new_divisor_lattice::<PosetM>(360);
+        assert_eq!(d.labels().len(), 24);
+        assert!(d.poset().is_distributive());
+        assert_eq!(d.poset().longest_chain().len(), 7);
+        assert_eq!(d.element(&360), Some(23));
+    }
+
+    #[test]
+    fn test_partition_lattice() {
+        let bell = [1, 1, 2, 5, 15, 52, 203];
+        for (k, &b) in bell.iter().enumerate() {
+            let p = new_partition_lattice::<PosetM>(k);
+            assert_eq!(p.labels().len(), b);
+            assert!(p.poset().is_lattice());
+            assert_eq!(p.poset().longest_chain().len(), k.max(1));
+        }
+        let p = new_partition_lattice::<PosetH>(3);
+        assert_eq!(p.label(0), &vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(p.label(4), &vec![vec![0, 1, 2]]);
+        let a = p.element(&vec![vec![0, 2], vec![1]]).unwrap();
+        assert_eq!(Poset::upper_covers(p.poset(), a), [4].into_iter().collect());
+        // The partition lattice of 3 elements is M3, which is modular but not distributive.
+        assert!(p.poset().is_modular() && !p.poset().is_distributive());
+        assert!(!new_partition_lattice::<PosetM>(4).poset().is_modular());
+    }
+
+    #[test]
+    fn test_young_lattice() {
+        let p = new_young_lattice_up_to::<PosetM>(4);
+        // 1 + 1 + 2 + 3 + 5 partitions of 0, ..., 4.
+        assert_eq!(p.labels().len(), 12);
+        assert!(p.label(0).is_empty());
+        assert_eq!(&p.labels()[4..7], &[vec![3], vec![2, 1], vec![1, 1, 1]]);
+        assert_eq!(p.poset().longest_chain().len(), 5);
+        let x = p.element(&vec![2, 1]).unwrap();
+        let mut covers: Vec<_> = Poset::upper_covers(p.poset(), x)
+            .into_iter()
+            .map(|y| p.label(y).clone())
+            .collect();
+        covers.sort();
+        assert_eq!(covers, vec![vec![2, 1, 1], vec![2, 2], vec![3, 1]]);
+        // Cut off at rank n >= 2, the maximal partitions have no join.
+        assert!(new_young_lattice_up_to::<PosetH>(1)
+            .poset()
+            .same_order(&PosetM::new_chain(2)));
+        assert!(!p.poset().is_lattice());
+    }
+
+    #[test]
+    fn test_simplex_face_lattice() {
+        let triangle = new_simplex_face_lattice::<PosetH>(2);
+        assert_eq!(triangle.labels().len(), 8);
+        assert!(triangle.label(0).is_empty());
+        assert_eq!(triangle.label(7), &vec![0, 1, 2]);
+        assert_eq!(triangle.poset().md.top, Some(Elt::A(7)));
+        assert_eq!(triangle.poset().md.bot, Some(Elt::A(0)));
+        let edge = triangle.element(&vec![0, 2]).unwrap();
+        assert_eq!(Poset::lower_covers(triangle.poset(), edge).len(), 2);
+        assert_eq!(triangle.leq_labels(&vec![1], &vec![0, 1]), Some(true));
+        // The 0-simplex is a point, with the empty face below it.
+        let point = new_simplex_face_lattice::<PosetM>(0);
+        assert_eq!(point.labels(), &[vec![], vec![0]]);
+    }
+
+    #[test]
+    fn test_bruhat_order() {
+        let sizes = [1, 1, 2, 6, 24];
+        for (k, &size) in sizes.iter().enumerate() {
+            let p = new_bruhat_order::<PosetM>(k);
+            assert_eq!(p.labels().len(), size);
+            // Graded by the number of inversions, with every interval Eulerian.
+            let ranks: Vec<usize> = p.labels().iter().map(|w| inversions(w).len()).collect();
+            assert_eq!(p.poset().rank_function(), Some(ranks));
+            assert!(p.poset().is_eulerian());
+        }
+        let p = new_bruhat_order::<PosetH>(3);
+        assert_eq!(p.label(0), &vec![0, 1, 2]);
+        assert_eq!(p.label(5), &vec![2, 1, 0]);
+        // 102 < 201 by a transposition of values, although 102 is not below 201 in the weak orders.
+        assert_eq!(p.leq_labels(&vec![1, 0, 2], &vec![2, 0, 1]), Some(true));
+        assert_eq!(p.leq_labels(&vec![1, 0, 2], &vec![0, 2, 1]), Some(false));
+        assert!(!p.poset().is_lattice());
+    }
+
+    #[test]
+    fn test_subgroup_lattice() {
+        let cycle = |k: usize| -> Permutation { (0..k).map(|i| (i + 1) % k).collect() };
+        let s3 = new_subgroup_lattice::<PosetM>(3, &[cycle(3), vec![1, 0, 2]]);
+        assert_eq!(s3.labels().len(), 6);
+        assert_eq!(s3.label(0), &vec![vec![0, 1, 2]]);
+        assert_eq!(s3.label(5).len(), 6);
+        assert!(s3.poset().is_modular() && !s3.poset().is_distributive());
+        // The subgroups of a cyclic group correspond to the divisors of its order.
+        let z12 = new_subgroup_lattice::<PosetH>(12, &[cycle(12)]);
+        assert!(z12
+            .poset()
+            .is_isomorphic(new_divisor_lattice::<PosetM>(12).poset()));
+        let s4 = new_subgroup_lattice::<PosetM>(4, &[cycle(4), vec![1, 0, 2, 3]]);
+        assert_eq!(s4.labels().len(), 30);
+        assert!(s4.poset().is_lattice());
+        // The Klein four-group has three subgroups of order 2, so its lattice is M3.
+        let v4 = new_subgroup_lattice::<PosetM>(4, &[vec![1, 0, 3, 2], vec![2, 3, 0, 1]]);
+        assert!(v4
+            .poset()
+            .is_isomorphic(new_partition_lattice::<PosetM>(3).poset()));
+        let trivial = new_subgroup_lattice::<PosetM>(2, &[]);
+        assert_eq!(trivial.labels(), &[vec![vec![0, 1]]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a permutation")]
+    fn test_subgroup_lattice_checks_generators() {
+        new_subgroup_lattice::<PosetM>(3, &[vec![0, 0, 1]]);
+    }
+
+    #[test]
+    fn test_weak_order() {
+        for k in 0..5 {
+            let right = new_weak_order::<PosetM>(k, Side::Right);
+            let left = new_weak_order::<PosetM>(k, Side::Left);
+            let bruhat = new_bruhat_order::<PosetM>(k);
+            assert!(right.poset().is_lattice());
+            assert!(right.poset().is_isomorphic(left.poset()));
+            assert_eq!(
+                right.poset().rank_function(),
+                bruhat.poset().rank_function()
+            );
+            for x in 0..right.labels().len() {
+                for y in 0..right.labels().len() {
+                    assert!(!right.poset().leq(x, y) || bruhat.poset().leq(x, y));
+                    assert!(!left.poset().leq(x, y) || bruhat.poset().leq(x, y));
+                }
+            }
+        }
+        let right = new_weak_order::<PosetH>(3, Side::Right);
+        let left = new_weak_order::<PosetH>(3, Side::Left);
+        // Swapping the positions 1 and 2 of 102 gives 120, and swapping the values 1 and 2 gives 201.
+        let covers = |p: &LabeledPoset<Permutation, PosetH>| {
+            let x = p.element(&vec![1, 0, 2]).unwrap();
+            let mut covers: Vec<Permutation> = Poset::upper_covers(p.poset(), x)
+                .into_iter()
+                .map(|y| p.label(y).clone())
+                .collect();
+            covers.sort();
+            covers
+        };
+        assert_eq!(covers(&right), vec![vec![1, 2, 0]]);
+        assert_eq!(covers(&left), vec![vec![2, 0, 1]]);
+    }
+}
@@ -0,0 +1,291 @@
+//! Isomorphism-invariant numeric features of a poset, for use as input to statistical or machine-learning
+//! pipelines.
+//!
+//! The feature vector has a fixed length and a fixed meaning per position, listed by [feature_names] and
+//! versioned by [FEATURE_VERSION]. New features are only ever appended, together with a version bump, so
+//! that a model trained on version $k$ can keep reading the first entries of the vectors of later versions.
+//!
+//! | position | name | meaning |
+//! |---|---|---|
+//! | 0 | `n` | number of elements |
+//! | 1 | `relation_density` | pairs $x<y$ divided by $\binom{n}{2}$ |
+//! | 2 | `cover_density` | cover pairs divided by $\binom{n}{2}$ |
+//! | 3 | `height` | number of elements in a longest chain |
+//! | 4 | `width_lower` | a lower bound on the width (the exact width when computed) |
+//! | 5 | `width_upper` | an upper bound on the width (the exact width when computed) |
+//! | 6 | `minimals` | number of minimal elements |
+//! | 7 | `maximals` | number of maximal elements |
+//! | 8 | `has_top` | 1 if the poset has a top element, 0 otherwise |
+//! | 9 | `has_bot` | 1 if the poset has a bottom element, 0 otherwise |
+//! | 10 | `components` | connected components of the comparability graph |
+//! | 11 | `upset_mean` | mean size of the principal up-sets $\{y \mid x\le y\}$ |
+//! | 12 | `upset_var` | (population) variance of the principal up-set sizes |
+//! | 13 | `cover_outdeg_mean` | mean number of upper covers |
+//! | 14 | `cover_outdeg_var` | (population) variance of the number of upper covers |
+//! | 15 | `chains_2` | number of 2-element chains |
+//! | 16 | `chains_3` | number of 3-element chains |
+//! | 17 | `chains_4` | number of 4-element chains |
+//!
+//! Densities and means of the empty poset are 0. Features that the [FeatureConfig] asks to skip are
+//! reported as `f64::NAN`, so the length of the vector never changes.
+
+use crate::analysis::{strict_width, uncancelled, AnalysisContext};
+use crate::relation::{cover_matrix, levels, strict_matrix, topological_order};
+use crate::Poset;
+
+/// The version of the feature list. Bumped whenever features are appended.
+pub const FEATURE_VERSION: u32 = 1;
+
+const NAMES: [&str; 18] = [
+    "n",
+    "relation_density",
+    "cover_density",
+    "height",
+    "width_lower",
+    "width_upper",
+    "minimals",
+    "maximals",
+    "has_top",
+    "has_bot",
+    "components",
+    "upset_mean",
+    "upset_var",
+    "cover_outdeg_mean",
+    "cover_outdeg_var",
+    "chains_2",
+    "chains_3",
+    "chains_4",
+];
+
+/// Returns the names of the features, in the order they appear in the feature vector.
+pub fn feature_names() -> Vec<&'static str> {
+    NAMES.to_vec()
+}
+
+/// Controls which of the more expensive features are computed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureConfig {
+    /// Compute the exact width (via a maximum matching) for posets with at most this many elements.
+    /// Larger posets only get the cheap bounds. Use 0 to always skip the exact computation.
+    pub exact_width_up_to: usize,
+    /// Compute the chain counts `chains_3` and `chains_4`. The count `chains_2` is always computed.
+    pub chain_counts: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        FeatureConfig {
+            exact_width_up_to: 500,
+            chain_counts: true,
+        }
+    }
+}
+
+/// Computes the feature vector of p, as documented in the [module level documentation](self).
+pub fn feature_vector<P: Poset + ?Sized>(p: &P, config: &FeatureConfig) -> Vec<f64> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+
+    let pairs = if n < 2 { 0.0 } else { (n * (n - 1) / 2) as f64 };
+    let density = |c: usize| if n < 2 { 0.0 } else { c as f64 / pairs };
+
+    let relations = lt.iter().flatten().filter(|&&b| b).count();
+    let cover_count = covers.iter().flatten().filter(|&&b| b).count();
+
+    let minimals = (0..n).filter(|&j| (0..n).all(|i| !lt[i][j])).count();
+    let maximals = (0..n).filter(|&i| (0..n).all(|j| !lt[i][j])).count();
+    // In a finite poset a unique maximal (minimal) element is the top (bottom).
+    let has_top = maximals == 1;
+    let has_bot = minimals == 1;
+
+    let levels = levels(&lt);
+    let height = levels.iter().map(|&l| l + 1).max().unwrap_or(0);
+    let (width_lower, width_upper) = if n <= config.exact_width_up_to {
+        let w = uncancelled(strict_width(&lt, &mut AnalysisContext::new()));
+        (w, w)
+    } else {
+        width_bounds(&lt, &levels)
+    };
+
+    let upsets: Vec<f64> = lt
+        .iter()
+        .map(|row| (row.iter().filter(|&&b| b).count() + 1) as f64)
+        .collect();
+    let outdegs: Vec<f64> = covers
+        .iter()
+        .map(|row| row.iter().filter(|&&b| b).count() as f64)
+        .collect();
+    let (upset_mean, upset_var) = mean_var(&upsets);
+    let (outdeg_mean, outdeg_var) = mean_var(&outdegs);
+
+    let (chains_3, chains_4) = if config.chain_counts {
+        let c = chain_counts(&lt, 4);
+        (c[2] as f64, c[3] as f64)
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+
+    vec![
+        n as f64,
+        density(relations),
+        density(cover_count),
+        height as f64,
+        width_lower as f64,
+        width_upper as f64,
+        minimals as f64,
+        maximals as f64,
+        has_top as u8 as f64,
+        has_bot as u8 as f64,
+        components(&lt) as f64,
+        upset_mean,
+        upset_var,
+        outdeg_mean,
+        outdeg_var,
+        relations as f64,
+        chains_3,
+        chains_4,
+    ]
+}
+
+fn mean_var(v: &[f64]) -> (f64, f64) {
+    if v.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = v.len() as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let var = v.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+    (mean, var)
+}
+
+/// Cheap bounds on the width: the largest level is an antichain, and a greedy partition into chains
+/// bounds the width from above.
+fn width_bounds(lt: &[Vec<bool>], levels: &[usize]) -> (usize, usize) {
+    let mut level_sizes = vec![0; levels.iter().max().map_or(0, |l| l + 1)];
+    for &l in levels {
+        level_sizes[l] += 1;
+    }
+    let lower = level_sizes.into_iter().max().unwrap_or(0);
+
+    let mut chain_tops: Vec<usize> = vec![];
+    for j in topological_order(lt) {
+        match chain_tops.iter().position(|&t| lt[t][j]) {
+            Some(c) => chain_tops[c] = j,
+            None => chain_tops.push(j),
+        }
+    }
+    (lower, chain_tops.len())
+}
+
+fn components(lt: &[Vec<bool>]) -> usize {
+    let n = lt.len();
+    let mut seen = vec![false; n];
+    let mut count = 0;
+    for s in 0..n {
+        if seen[s] {
+            continue;
+        }
+        count += 1;
+        seen[s] = true;
+        let mut stack = vec![s];
+        while let Some(i) = stack.pop() {
+            for j in 0..n {
+                if !seen[j] && (lt[i][j] || lt[j][i]) {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Entry k-1 is the number of chains with k elements, for k up to max_len.
+fn chain_counts(lt: &[Vec<bool>], max_len: usize) -> Vec<u64> {
+    let n = lt.len();
+    // ending[j] is the number of chains of the current length whose largest element is j.
+    let mut ending = vec![1u64; n];
+    let mut counts = vec![n as u64];
+    for _ in 1..max_len {
+        ending = (0..n)
+            .map(|j| (0..n).filter(|&i| lt[i][j]).map(|i| ending[i]).sum())
+            .collect();
+        counts.push(ending.iter().sum());
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::boolean_lattice;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_feature_names() {
+        assert_eq!(feature_names().len(), NAMES.len());
+        assert_eq!(
+            feature_vector(&PosetG::new_chain(3), &FeatureConfig::default()).len(),
+            feature_names().len()
+        );
+    }
+
+    #[test]
+    fn test_b3_fixture() {
+        let expected = vec![
+            8.0,
+            19.0 / 28.0,
+            12.0 / 28.0,
+            4.0,
+            3.0,
+            3.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+            3.375,
+            4.234375,
+            1.5,
+            0.75,
+            19.0,
+            18.0,
+            6.0,
+        ];
+        assert_eq!(boolean_lattice(3).feature_vector(), expected);
+    }
+
+    #[test]
+    fn test_relabeling_invariance() {
+        let b3 = boolean_lattice(3);
+        let perm = [5, 2, 7, 0, 3, 6, 1, 4];
+        let m = (0..8)
+            .map(|i| (0..8).map(|j| b3.m[perm[i]][perm[j]]).collect())
+            .collect();
+        assert_eq!(PosetM::new(&m).feature_vector(), b3.feature_vector());
+
+        let mut vee = PosetG::new_antichain(2);
+        vee.adjoin_bot();
+        let mut eev = PosetG::new_antichain(2);
+        eev.adjoin_top();
+        let eev = eev.op();
+        assert_eq!(vee.feature_vector(), eev.feature_vector());
+    }
+
+    #[test]
+    fn test_degraded_features() {
+        let config = FeatureConfig {
+            exact_width_up_to: 0,
+            chain_counts: false,
+        };
+        let v = feature_vector(&boolean_lattice(3), &config);
+        assert!(v[4] <= 3.0 && v[5] >= 3.0);
+        assert!(v[16].is_nan() && v[17].is_nan());
+
+        let v = feature_vector(&PosetG::new_antichain(0), &config);
+        assert_eq!(v[0], 0.0);
+        assert_eq!(v[1], 0.0);
+        assert_eq!(v[8], 0.0);
+    }
+}
@@ -0,0 +1,247 @@
+//! Galois connections of binary relations, and the concept lattices of Formal Concept Analysis.
+//!
+//! A relation $I \subseteq G \times M$ between a set $G$ of objects and a set $M$ of attributes induces a
+//! Galois connection between their power sets: a set $A$ of objects is sent to the attributes $A'$ shared
+//! by all of them (its *intent*), and a set $B$ of attributes to the objects $B'$ having all of them (its
+//! *extent*). Both maps reverse inclusion, and the composites $A \mapsto A''$ and $B \mapsto B''$ are
+//! closure operators. A *concept* is a pair $(A, B)$ with $A' = B$ and $B' = A$; ordered by inclusion of
+//! the extents (equivalently, reverse inclusion of the intents), the concepts form a complete lattice, and
+//! every finite lattice arises this way.
+//!
+//! The concepts are enumerated by Ganter's NextClosure algorithm, one closure per concept and attribute,
+//! without keeping the concepts found so far in a lookup table.
+//!
+//! ```
+//! use fin_pos::galois::GaloisConnection;
+//! use fin_pos::Poset;
+//!
+//! // Two objects, one with the attributes 0 and 1, the other with 1 and 2.
+//! let gc = GaloisConnection::new(&[vec![true, true, false], vec![false, true, true]], 3);
+//! assert_eq!(gc.close_attributes(&[0].into()), [0, 1].into());
+//! let (lattice, concepts) = gc.concept_lattice();
+//! assert_eq!(lattice.md.n, 4);
+//! assert_eq!(concepts[0].extent, [].into());
+//! assert_eq!(concepts[3].intent, [1].into());
+//! assert!(lattice.is_lattice());
+//! ```
+
+use crate::bitset::BitRow;
+use crate::posetg::PosetG;
+use crate::{AnElement, BiPaGraph, Elements, Elt};
+
+/// The Galois connection of a relation between objects $0, ..., n-1$ and attributes $0, ..., m-1$.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GaloisConnection {
+    /// The attributes of each object.
+    attributes_of: Vec<BitRow>,
+    /// The objects having each attribute.
+    objects_with: Vec<BitRow>,
+}
+
+/// A pair of an extent and an intent that determine each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Concept {
+    pub extent: Elements,
+    pub intent: Elements,
+}
+
+impl GaloisConnection {
+    /// The connection of the relation in which object g has attribute a if relation\[g\]\[a\]. Panics if a
+    /// row does not have one entry per attribute.
+    pub fn new(relation: &[Vec<bool>], attributes: usize) -> GaloisConnection {
+        let n = relation.len();
+        let mut attributes_of = vec![BitRow::new(attributes); n];
+        let mut objects_with = vec![BitRow::new(n); attributes];
+        for (g, row) in relation.iter().enumerate() {
+            assert_eq!(row.len(), attributes, "row {} has the wrong length", g);
+            for a in (0..attributes).filter(|&a| row[a]) {
+                attributes_of[g].set(a);
+                objects_with[a].set(g);
+            }
+        }
+        GaloisConnection {
+            attributes_of,
+            objects_with,
+        }
+    }
+
+    /// The number of objects.
+    pub fn objects(&self) -> usize {
+        self.attributes_of.len()
+    }
+
+    /// The number of attributes.
+    pub fn attributes(&self) -> usize {
+        self.objects_with.len()
+    }
+
+    /// The attributes shared by all the given objects, i.e., $A'$.
+    pub fn intent(&self, objects: &Elements) -> Elements {
+        common(
+            &self.attributes_of,
+            self.attributes(),
+            objects.iter().copied(),
+        )
+        .into_ones()
+        .collect()
+    }
+
+    /// The objects having all the given attributes, i.e., $B'$.
+    pub fn extent(&self, attributes: &Elements) -> Elements {
+        common(
+            &self.objects_with,
+            self.objects(),
+            attributes.iter().copied(),
+        )
+        .into_ones()
+        .collect()
+    }
+
+    /// The closure $A''$ of a set of objects: the objects having every attribute that all of A have.
+    pub fn close_objects(&self, objects: &Elements) -> Elements {
+        self.extent(&self.intent(objects))
+    }
+
+    /// The closure $B''$ of a set of attributes: the attributes of every object having all of B.
+    pub fn close_attributes(&self, attributes: &Elements) -> Elements {
+        self.intent(&self.extent(attributes))
+    }
+
+    /// The closure of a set of attributes given as a row of bits.
+    fn close(&self, attributes: &BitRow) -> BitRow {
+        let extent = common(&self.objects_with, self.objects(), attributes.iter_ones());
+        common(&self.attributes_of, self.attributes(), extent.into_ones())
+    }
+
+    /// The concepts, ordered by increasing extent in the sense that a concept never comes after one with
+    /// a larger extent. The first concept has the smallest extent, and the last has all the objects.
+    pub fn concepts(&self) -> Vec<Concept> {
+        let m = self.attributes();
+        // NextClosure: the closed sets of attributes in lectic order, which extends inclusion.
+        let mut intents = vec![];
+        let mut current = self.close(&BitRow::new(m));
+        loop {
+            intents.push(current.clone());
+            let next = (0..m).rev().filter(|&i| !current.get(i)).find_map(|i| {
+                let mut candidate = current.clone();
+                (i..m).for_each(|j| candidate.unset(j));
+                candidate.set(i);
+                let closed = self.close(&candidate);
+                (0..i)
+                    .all(|j| closed.get(j) == current.get(j))
+                    .then_some(closed)
+            });
+            match next {
+                Some(closed) => current = closed,
+                None => break,
+            }
+        }
+        intents
+            .into_iter()
+            .rev()
+            .map(|intent| Concept {
+                extent: common(&self.objects_with, self.objects(), intent.iter_ones())
+                    .into_ones()
+                    .collect(),
+                intent: intent.into_ones().collect(),
+            })
+            .collect()
+    }
+
+    /// The concept lattice, with the concepts numbered as in [GaloisConnection::concepts]. The bottom is
+    /// the first concept and the top the last one.
+    pub fn concept_lattice(&self) -> (PosetG, Vec<Concept>) {
+        let concepts = self.concepts();
+        let k = concepts.len();
+        let g: BiPaGraph = (0..k)
+            .map(|i| {
+                let up = (i..k).filter(|&j| concepts[i].extent.is_subset(&concepts[j].extent));
+                (i, up.collect())
+            })
+            .collect();
+        let mut lattice = PosetG::new(&g);
+        lattice.md.bot = Some(Elt::A(0));
+        lattice.md.top = Some(Elt::A(k - 1));
+        lattice.md.minimals = Some([0].into());
+        lattice.md.maximals = Some([k - 1].into());
+        (lattice, concepts)
+    }
+}
+
+/// The intersection of the given rows, all of the given length (the full row if there are none).
+fn common(rows: &[BitRow], len: usize, of: impl Iterator<Item = AnElement>) -> BitRow {
+    let mut result = BitRow::full(len);
+    for x in of {
+        result.and_assign(&rows[x]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::Poset;
+
+    #[test]
+    fn test_concepts_are_closed() {
+        let relation = vec![
+            vec![true, false, true, false],
+            vec![true, true, false, false],
+            vec![false, true, true, false],
+            vec![true, true, true, false],
+        ];
+        let gc = GaloisConnection::new(&relation, 4);
+        let concepts = gc.concepts();
+        for c in &concepts {
+            assert_eq!(gc.intent(&c.extent), c.intent);
+            assert_eq!(gc.extent(&c.intent), c.extent);
+        }
+        // Every closed set of attributes occurs once.
+        let closed: Vec<Elements> = (0..16u32)
+            .map(|s| gc.close_attributes(&(0..4).filter(|&a| s & (1 << a) != 0).collect()))
+            .collect();
+        for c in &concepts {
+            assert!(closed.contains(&c.intent));
+        }
+        assert!(closed
+            .iter()
+            .all(|b| concepts.iter().any(|c| c.intent == *b)));
+        let mut intents: Vec<Vec<AnElement>> = concepts
+            .iter()
+            .map(|c| {
+                let mut b: Vec<AnElement> = c.intent.iter().copied().collect();
+                b.sort_unstable();
+                b
+            })
+            .collect();
+        intents.sort();
+        intents.dedup();
+        assert_eq!(intents.len(), concepts.len());
+        // The attribute 3 belongs to no object, so the bottom concept has every attribute.
+        assert_eq!(concepts[0].intent, (0..4).collect());
+        assert_eq!(gc.close_objects(&[0, 1].into()), [0, 1, 3].into());
+    }
+
+    #[test]
+    fn test_lattice_of_order_relation() {
+        // The concept lattice of the order relation of a nonempty lattice is isomorphic to the lattice.
+        for p in corpus()
+            .into_iter()
+            .filter(|p| p.md.n > 0 && p.is_lattice())
+        {
+            let n = p.md.n;
+            let relation: Vec<Vec<bool>> = (0..n)
+                .map(|x| (0..n).map(|y| p.leq(x, y)).collect())
+                .collect();
+            let (lattice, concepts) = GaloisConnection::new(&relation, n).concept_lattice();
+            assert!(lattice.is_isomorphic(&p));
+            assert_eq!(lattice.is_valid_poset(), Ok(()));
+            assert_eq!(concepts.last().unwrap().extent.len(), n);
+        }
+        // With no objects and no attributes, the only concept is the empty one.
+        let (lattice, concepts) = GaloisConnection::new(&[], 0).concept_lattice();
+        assert_eq!(lattice.md.n, 1);
+        assert_eq!(concepts[0].extent, Elements::new());
+    }
+}
@@ -0,0 +1,330 @@
+//! Graded posets and their rank functions.
+//!
+//! A *rank function* assigns an integer $\rho(x)$ to every element such that $\rho(y) = \rho(x) + 1$
+//! whenever $x \lessdot y$. A poset with a rank function is *graded*. Then every maximal chain of an
+//! interval $[x, y]$ has $\rho(y) - \rho(x)$ covers, so all maximal chains between two comparable elements
+//! have the same length. The converse fails: the covers $a \lessdot b \lessdot c$, $f \lessdot c$,
+//! $f \lessdot g$, and $a \lessdot g$ admit no rank function, although every interval has a single maximal
+//! chain. A rank function is unique up to adding a constant on each connected component, and the one
+//! returned gives the rank 0 to the lowest elements of each component. The minimal elements need not all
+//! have rank 0, nor the maximal ones the same rank.
+//!
+//! A graded poset with a bottom and a top is *Eulerian* if its Möbius function is
+//! $\mu(x, y) = (-1)^{\rho(y) - \rho(x)}$ for all $x \le y$, or equivalently, if every interval $[x, y]$
+//! with $x < y$ has as many elements of even rank as of odd rank. It is *thin* if every interval of length
+//! 2 is a diamond, i.e., has exactly two elements strictly inside. Face lattices of polytopes and Bruhat
+//! orders are Eulerian, and Eulerian posets are thin.
+//!
+//! The *characteristic polynomial* of a graded poset with a bottom $\hat 0$ and rank $r = \max \rho$ is
+//! $\chi(t) = \sum_x \mu(\hat 0, x) t^{r - \rho(x)}$. For the lattice of flats of a hyperplane
+//! arrangement, $(-1)^r \chi(-1)$ counts the regions of its complement (Zaslavsky's theorem). The
+//! Boolean lattice $2^k$ has $\chi(t) = (t - 1)^k$, and the partition lattice $\Pi_k$ has
+//! $\chi(t) = (t - 1)(t - 2) \cdots (t - k + 1)$.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let mut p = PosetM::new_antichain(2);
+//! p.adjoin_bot();
+//! p.adjoin_top();
+//! assert_eq!(p.rank_function(), Some(vec![1, 1, 0, 2]));
+//!
+//! // The pentagon has maximal chains of two lengths between its bottom and top.
+//! let pentagon = PosetM::new(&vec![
+//!     vec![true, true, true, true, true],
+//!     vec![false, true, true, false, true],
+//!     vec![false, false, true, false, true],
+//!     vec![false, false, false, true, true],
+//!     vec![false, false, false, false, true],
+//! ]);
+//! assert!(!pentagon.is_graded());
+//!
+//! assert!(PosetM::new_boolean(3).is_eulerian());
+//! assert!(PosetM::new_boolean(3).is_thin());
+//! assert!(!PosetM::new_chain(3).is_thin());
+//! assert_eq!(PosetM::new_boolean(2).characteristic_polynomial(), vec![1, -2, 1]);
+//! ```
+
+use crate::incidence_algebra::IncidenceAlgebra;
+use crate::relation::{cover_matrix, strict_matrix};
+use crate::Poset;
+
+/// Returns the rank function of p normalized as in the [module level documentation](self), or None if p
+/// is not graded. The ranks are propagated along the covers in $O(n^2)$ steps once the covers are known.
+pub fn rank_function<P: Poset + ?Sized>(p: &P) -> Option<Vec<usize>> {
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = covers.len();
+    let mut rank: Vec<Option<i64>> = vec![None; n];
+    let mut result = vec![0; n];
+    for root in 0..n {
+        if rank[root].is_some() {
+            continue;
+        }
+        rank[root] = Some(0);
+        let mut component = vec![root];
+        let mut stack = vec![root];
+        while let Some(x) = stack.pop() {
+            let r = rank[x].unwrap();
+            let neighbours = (0..n)
+                .filter(|&y| covers[x][y])
+                .map(|y| (y, r + 1))
+                .chain((0..n).filter(|&y| covers[y][x]).map(|y| (y, r - 1)));
+            for (y, s) in neighbours {
+                match rank[y] {
+                    Some(t) if t != s => return None,
+                    Some(_) => {}
+                    None => {
+                        rank[y] = Some(s);
+                        component.push(y);
+                        stack.push(y);
+                    }
+                }
+            }
+        }
+        let lowest = component.iter().map(|&x| rank[x].unwrap()).min().unwrap();
+        for x in component {
+            result[x] = (rank[x].unwrap() - lowest) as usize;
+        }
+    }
+    Some(result)
+}
+
+/// The strict order relation and the rank function of p, if p is graded with a bottom and a top.
+fn bounded_ranks<P: Poset + ?Sized>(p: &P) -> Option<(Vec<Vec<bool>>, Vec<usize>)> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let bounded = (0..n).any(|x| (0..n).all(|y| y == x || lt[x][y]))
+        && (0..n).any(|x| (0..n).all(|y| y == x || lt[y][x]));
+    if !bounded {
+        return None;
+    }
+    rank_function(p).map(|rank| (lt, rank))
+}
+
+/// Returns true if p is graded with a bottom and a top, and its Möbius function is
+/// $\mu(x, y) = (-1)^{\rho(y) - \rho(x)}$ for all $x \le y$.
+pub fn is_eulerian<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some((lt, rank)) = bounded_ranks(p) else {
+        return false;
+    };
+    let mu = IncidenceAlgebra::new(p).mobius();
+    let n = lt.len();
+    (0..n).all(|x| {
+        (0..n)
+            .filter(|&y| x == y || lt[x][y])
+            .all(|y| mu.value(x, y) == if (rank[y] - rank[x]) % 2 == 0 { 1 } else { -1 })
+    })
+}
+
+/// Returns true if p is graded with a bottom and a top, and every interval of length 2 has exactly four
+/// elements.
+pub fn is_thin<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some((lt, rank)) = bounded_ranks(p) else {
+        return false;
+    };
+    let n = lt.len();
+    (0..n).all(|x| {
+        (0..n)
+            .filter(|&y| lt[x][y] && rank[y] == rank[x] + 2)
+            .all(|y| (0..n).filter(|&z| lt[x][z] && lt[z][y]).count() == 2)
+    })
+}
+
+/// Returns the coefficients of the characteristic polynomial of p, that of $t^i$ at index i, or an empty
+/// vector if p is not graded or has no bottom.
+pub fn characteristic_polynomial<P: Poset + ?Sized>(p: &P) -> Vec<i128> {
+    let n = p.md().n;
+    let Some(bot) = (0..n).find(|&x| (0..n).all(|y| p.leq(x, y))) else {
+        return vec![];
+    };
+    let Some(rank) = rank_function(p) else {
+        return vec![];
+    };
+    let r = rank.iter().copied().max().unwrap_or(0);
+    let mu = IncidenceAlgebra::new(p).mobius();
+    let mut coefficients = vec![0; r + 1];
+    for x in 0..n {
+        coefficients[r - rank[x]] += mu.value(bot, x) as i128;
+    }
+    coefficients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_rank_function() {
+        for p in corpus() {
+            let lt = strict_matrix(&p);
+            let covers = cover_matrix(&lt);
+            let Some(rank) = p.rank_function() else {
+                continue;
+            };
+            let n = p.md.n;
+            for x in 0..n {
+                for y in (0..n).filter(|&y| lt[x][y]) {
+                    assert_eq!(covers[x][y], rank[y] == rank[x] + 1);
+                    let (interval, _) = p.interval(x, y);
+                    assert!(interval
+                        .maximal_chains()
+                        .iter()
+                        .all(|c| c.len() == rank[y] - rank[x] + 1));
+                }
+            }
+        }
+        let b = boolean_lattice(4);
+        let ranks: Vec<usize> = (0..16u32).map(|i| i.count_ones() as usize).collect();
+        assert_eq!(b.rank_function(), Some(ranks));
+        assert!(PosetM::new_chain(0).is_graded());
+        assert_eq!(PosetM::new_antichain(3).rank_function(), Some(vec![0; 3]));
+    }
+
+    #[test]
+    fn test_chain_condition_is_not_enough() {
+        // a = 0, b = 1, c = 2, f = 3, g = 4, as in the module level documentation.
+        let mut p = PosetM::new_antichain(5);
+        for (x, y) in [(0, 1), (1, 2), (3, 2), (3, 4), (0, 4)] {
+            p.add_relation(x, y).unwrap();
+        }
+        assert_eq!(p.rank_function(), None);
+        // Each component is normalized separately: 3 is minimal but has rank 1, and 4 is isolated.
+        let mut q = PosetM::new_antichain(5);
+        for (x, y) in [(0, 1), (1, 2), (3, 2)] {
+            q.add_relation(x, y).unwrap();
+        }
+        assert_eq!(q.rank_function(), Some(vec![0, 1, 2, 1, 0]));
+    }
+
+    /// Divides out the roots of the polynomial among $0, ..., n$, returning them if they account for its
+    /// whole degree.
+    fn integer_roots(mut c: Vec<i128>, n: usize) -> Option<Vec<i128>> {
+        let mut roots = vec![];
+        'divide: while c.len() > 1 {
+            for a in 0..=n as i128 {
+                // Synthetic division by t - a, from the leading coefficient down.
+                let mut quotient = vec![0; c.len() - 1];
+                let mut carry = 0;
+                for i in (1..c.len()).rev() {
+                    carry = c[i] + a * carry;
+                    quotient[i - 1] = carry;
+                }
+                if c[0] + a * carry == 0 {
+                    roots.push(a);
+                    c = quotient;
+                    continue 'divide;
+                }
+            }
+            return None;
+        }
+        Some(roots)
+    }
+
+    #[test]
+    fn test_characteristic_polynomial() {
+        for mut p in corpus().into_iter().filter(|p| p.md.n <= 16) {
+            let chi = p.characteristic_polynomial();
+            let has_bot = p.elements().any(|x| p.elements().all(|y| p.leq(x, y)));
+            if !has_bot || !p.is_graded() {
+                assert!(chi.is_empty());
+                continue;
+            }
+            assert_eq!(chi.len(), p.height());
+            assert_eq!(chi.last(), Some(&1));
+            // The Möbius function sums to 0 over a nontrivial interval.
+            if p.md.n > 1 && p.elements().any(|x| p.elements().all(|y| p.leq(y, x))) {
+                assert_eq!(chi.iter().sum::<i128>(), 0);
+            }
+            // The characteristic polynomial of a supersolvable lattice has nonnegative integer roots.
+            if p.is_supersolvable() {
+                assert!(integer_roots(chi, p.md.n).is_some());
+            }
+        }
+        for k in 0..5 {
+            let roots = integer_roots(boolean_lattice(k).characteristic_polynomial(), 4);
+            assert_eq!(roots, Some(vec![1; k as usize]));
+        }
+        let pi4 = crate::families::new_partition_lattice::<PosetM>(4);
+        assert_eq!(pi4.poset().characteristic_polynomial(), vec![-6, 11, -6, 1]);
+        assert_eq!(
+            PosetM::new_diamond().characteristic_polynomial(),
+            vec![2, -3, 1]
+        );
+        assert_eq!(
+            PosetM::new_chain(4).characteristic_polynomial(),
+            vec![0, 0, -1, 1]
+        );
+        assert_eq!(
+            PosetM::new_corolla(3).characteristic_polynomial(),
+            vec![-3, 1]
+        );
+        assert!(PosetM::new_pentagon()
+            .characteristic_polynomial()
+            .is_empty());
+        assert!(PosetM::new_chain(0).characteristic_polynomial().is_empty());
+    }
+
+    #[test]
+    fn test_eulerian_and_thin() {
+        for p in corpus() {
+            let Some((lt, rank)) = bounded_ranks(&p) else {
+                assert!(!p.is_eulerian() && !p.is_thin());
+                continue;
+            };
+            // Every nontrivial interval has as many elements of even rank as of odd rank.
+            let n = p.md.n;
+            let balanced = (0..n).all(|x| {
+                (0..n).filter(|&y| lt[x][y]).all(|y| {
+                    let even = (0..n)
+                        .filter(|&z| (z == x || lt[x][z]) && (z == y || lt[z][y]))
+                        .filter(|&z| rank[z] % 2 == 0)
+                        .count();
+                    2 * even == (0..n).filter(|&z| lt[x][z] && lt[z][y]).count() + 2
+                })
+            });
+            assert_eq!(p.is_eulerian(), balanced);
+            assert!(!p.is_eulerian() || p.is_thin());
+        }
+        assert!(PosetM::new_chain(2).is_eulerian());
+        assert!(!PosetM::new_chain(3).is_eulerian());
+        assert!(!PosetM::new_antichain(2).is_thin());
+        // The face lattice of a square.
+        let mut square = PosetM::new_antichain(8);
+        for (vertex, edges) in [(0, [4, 5]), (1, [5, 6]), (2, [6, 7]), (3, [7, 4])] {
+            for edge in edges {
+                square.add_relation(vertex, edge).unwrap();
+            }
+        }
+        square.adjoin_bot();
+        square.adjoin_top();
+        assert!(square.is_eulerian());
+        // A 2-sphere made of two vertices, two edges, and two faces is Eulerian, but two disjoint copies
+        // of it are only thin: the whole poset has Möbius function 3 instead of 1.
+        let spheres = |copies: usize| {
+            let mut p = PosetM::new_antichain(6 * copies);
+            for c in 0..copies {
+                for (x, y) in [
+                    (0, 2),
+                    (0, 3),
+                    (1, 2),
+                    (1, 3),
+                    (2, 4),
+                    (2, 5),
+                    (3, 4),
+                    (3, 5),
+                ] {
+                    p.add_relation(6 * c + x, 6 * c + y).unwrap();
+                }
+            }
+            p.adjoin_bot();
+            p.adjoin_top();
+            p
+        };
+        assert!(spheres(1).is_eulerian());
+        assert!(spheres(2).is_thin());
+        assert!(!spheres(2).is_eulerian());
+    }
+}
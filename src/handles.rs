@@ -0,0 +1,164 @@
+//! Stable element identities that survive index-changing operations.
+//!
+//! Elements of a poset are named by their index in $\{0, 1, ..., n-1\}$, and several operations (taking
+//! subposets, removing elements, forming quotients) renumber the elements. A caller that holds on to an
+//! index across such an operation silently ends up pointing at a different element. Pinning an element
+//! with [Poset::pin](crate::Poset::pin) instead returns an [ElementHandle], which the poset keeps up to date
+//! in its [HandleTable] and which can be turned back into the current index with
+//! [Poset::resolve](crate::Poset::resolve).
+//!
+//! ```
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::Poset;
+//!
+//! let mut p = PosetG::new_chain(3);
+//! let h = p.pin(1);
+//! p.adjoin_bot();
+//! assert_eq!(p.resolve(&h), Some(1));
+//! ```
+
+use crate::AnElement;
+
+use std::collections::HashMap;
+
+/// An opaque token naming an element of a poset independently of its current index. Handles are only
+/// meaningful with respect to the poset that issued them (or a poset derived from it, which inherits the
+/// [HandleTable]).
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementHandle(u64);
+
+/// The table of pinned elements of a poset. Each entry is either `Some(i)`, the current index of the pinned
+/// element, or `None` if the element has been removed from the poset.
+#[derive(PartialEq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandleTable {
+    next: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::sorted_entries"))]
+    slots: HashMap<ElementHandle, Option<AnElement>>,
+}
+
+impl HandleTable {
+    /// Creates an empty table.
+    pub fn new() -> HandleTable {
+        HandleTable::default()
+    }
+
+    /// Returns true if no element has been pinned.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Issues a new handle for the element currently at index x.
+    pub fn pin(&mut self, x: AnElement) -> ElementHandle {
+        let h = ElementHandle(self.next);
+        self.next += 1;
+        self.slots.insert(h, Some(x));
+        h
+    }
+
+    /// Forgets a handle. Resolving it afterwards returns None.
+    pub fn unpin(&mut self, h: &ElementHandle) {
+        self.slots.remove(h);
+    }
+
+    /// Returns the current index of the pinned element, or None if the element was removed (or the handle
+    /// was not issued by this table).
+    pub fn resolve(&self, h: &ElementHandle) -> Option<AnElement> {
+        self.slots.get(h).copied().flatten()
+    }
+
+    /// Updates the table after the elements of the poset were renumbered. The function f sends an old index
+    /// to its new index, or to None if the element no longer exists.
+    pub fn remap<F>(&mut self, f: F)
+    where
+        F: Fn(AnElement) -> Option<AnElement>,
+    {
+        for slot in self.slots.values_mut() {
+            *slot = slot.and_then(&f);
+        }
+    }
+
+    /// The handles and their slots, sorted by handle.
+    pub(crate) fn sorted_slots(&self) -> Vec<(ElementHandle, Option<AnElement>)> {
+        let mut slots: Vec<_> = self.slots.iter().map(|(&h, &s)| (h, s)).collect();
+        slots.sort_unstable();
+        slots
+    }
+
+    /// Returns a copy of the table transferred along the renumbering f (see [HandleTable::remap]). This is
+    /// how operations that produce a new poset pass the pinned elements on to their result.
+    pub fn remapped<F>(&self, f: F) -> HandleTable
+    where
+        F: Fn(AnElement) -> Option<AnElement>,
+    {
+        let mut t = self.clone();
+        t.remap(f);
+        t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+    use crate::Poset;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_pin_and_resolve() {
+        let mut t = HandleTable::new();
+        let a = t.pin(3);
+        let b = t.pin(5);
+        assert_ne!(a, b);
+        assert_eq!(t.resolve(&a), Some(3));
+        assert_eq!(t.resolve(&b), Some(5));
+
+        t.remap(|x| if x == 3 { None } else { Some(x - 1) });
+        assert_eq!(t.resolve(&a), None);
+        assert_eq!(t.resolve(&b), Some(4));
+
+        t.unpin(&b);
+        assert_eq!(t.resolve(&b), None);
+    }
+
+    #[test]
+    fn test_handles_survive_mutations() {
+        let mut p = PosetG::new_chain(4);
+        let h1 = p.pin(1);
+        let h3 = p.pin(3);
+
+        p.adjoin_bot();
+        p.adjoin_top();
+        assert_eq!(p.resolve(&h1), Some(1));
+        assert_eq!(p.resolve(&h3), Some(3));
+
+        let mut p = p.op();
+        assert_eq!(p.resolve(&h1), Some(1));
+        assert!(p.leq(p.resolve(&h3).unwrap(), p.resolve(&h1).unwrap()));
+
+        let bot = p.pin(5);
+        let s_0: HashSet<usize> = vec![0, 1, 5].into_iter().collect();
+        let (q, back) = p.sub(&s_0);
+        assert_eq!(back, vec![0, 1, 5]);
+        assert_eq!(q.resolve(&h1), Some(1));
+        assert_eq!(q.resolve(&h3), None);
+        assert_eq!(q.resolve(&bot), Some(2));
+        assert!(q.leq(q.resolve(&bot).unwrap(), q.resolve(&h1).unwrap()));
+    }
+
+    #[test]
+    fn test_handles_on_matrix() {
+        let mut p = PosetM::new_antichain(2);
+        let h = p.pin(1);
+        p.adjoin_top();
+        p.adjoin_bot();
+        assert_eq!(p.resolve(&h), Some(1));
+        assert!(p.leq(3, p.resolve(&h).unwrap()));
+        // Pinning does not change what the meta data says about the order.
+        let before = p.md.clone();
+        p.pin(0);
+        assert_eq!(p.md, before);
+    }
+}
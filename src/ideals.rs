@@ -0,0 +1,573 @@
+//! Order ideals (down-sets) of a poset.
+//!
+//! A down-set is a set $D$ of elements such that $x \le y$ and $y\in D$ imply $x \in D$. The down-sets,
+//! ordered by inclusion, form a distributive lattice whose covers add (or remove) a single element. That
+//! lattice is often far too large to build, but it can be walked one cover at a time, e.g., to enumerate
+//! the schedules compatible with a set of precedence constraints. When it is small enough, [ideal_lattice]
+//! builds it; by Birkhoff's theorem every finite distributive lattice arises this way. Without building
+//! it, [count_ideals_by_size] finds its rank generating function and [sample_ideal] draws a uniformly random
+//! down-set of a given size.
+//!
+//! ```
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::Poset;
+//! use std::collections::HashSet;
+//!
+//! let p = PosetG::new_corolla(2);
+//! let bottom: HashSet<usize> = vec![2].into_iter().collect();
+//! assert_eq!(p.downset_successors(&HashSet::new()).unwrap(), vec![bottom.clone()]);
+//! assert_eq!(p.downset_successors(&bottom).unwrap().len(), 2);
+//!
+//! let (j, ideals) = p.ideal_lattice();
+//! assert_eq!(j.md.n, 5);
+//! assert_eq!(ideals[1], bottom);
+//! ```
+
+use crate::analysis::chain_partition;
+use crate::bitset::{BitMatrix, BitRow};
+use crate::posetg::PosetG;
+use crate::rng::SplitMix64;
+use crate::{AnElement, BiPaGraph, Elements, Elt, Poset};
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when a set that should be a down-set is not one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotADownSet {
+    /// The set contains `member` but not the element `missing` below it.
+    Missing {
+        member: AnElement,
+        missing: AnElement,
+    },
+    /// The set contains `member`, which is not one of the $n$ elements of the poset.
+    OutOfRange { member: AnElement, n: usize },
+}
+
+impl fmt::Display for NotADownSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotADownSet::Missing { member, missing } => write!(
+                f,
+                "not a down-set: contains {} but not {} below it",
+                member, missing
+            ),
+            NotADownSet::OutOfRange { member, n } => write!(
+                f,
+                "not a down-set: contains {}, which is out of range for {} elements",
+                member, n
+            ),
+        }
+    }
+}
+
+impl Error for NotADownSet {}
+
+/// Checks that d is a down-set of p.
+pub fn check_downset<P: Poset + ?Sized>(p: &P, d: &HashSet<AnElement>) -> Result<(), NotADownSet> {
+    let n = p.md().n;
+    if let Some(&member) = d.iter().filter(|&&x| x >= n).min() {
+        return Err(NotADownSet::OutOfRange { member, n });
+    }
+    for &member in d {
+        if let Some(missing) = p.elements().find(|&x| !d.contains(&x) && p.leq(x, member)) {
+            return Err(NotADownSet::Missing { member, missing });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the principal down-set $\{y \mid y \le x\}$ of x.
+pub fn down_set<P: Poset + ?Sized>(p: &P, x: AnElement) -> HashSet<AnElement> {
+    p.elements().filter(|&y| p.leq(y, x)).collect()
+}
+
+/// Returns the principal up-set $\{y \mid x \le y\}$ of x.
+pub fn up_set<P: Poset + ?Sized>(p: &P, x: AnElement) -> HashSet<AnElement> {
+    p.elements().filter(|&y| p.leq(x, y)).collect()
+}
+
+/// Returns the smallest down-set of p containing s.
+pub fn down_closure<P: Poset + ?Sized>(p: &P, s: &HashSet<AnElement>) -> HashSet<AnElement> {
+    p.elements()
+        .filter(|&x| s.iter().any(|&y| p.leq(x, y)))
+        .collect()
+}
+
+/// Returns the smallest up-set of p containing s.
+pub fn up_closure<P: Poset + ?Sized>(p: &P, s: &HashSet<AnElement>) -> HashSet<AnElement> {
+    p.elements()
+        .filter(|&x| s.iter().any(|&y| p.leq(y, x)))
+        .collect()
+}
+
+/// Returns the down-sets covering d, each obtained by adding a minimal element of the complement of d,
+/// ordered by the added element.
+pub fn downset_successors<P: Poset + ?Sized>(
+    p: &P,
+    d: &HashSet<AnElement>,
+) -> Result<Vec<HashSet<AnElement>>, NotADownSet> {
+    check_downset(p, d)?;
+    Ok(p.elements()
+        .filter(|&x| !d.contains(&x))
+        .filter(|&x| p.elements().all(|y| d.contains(&y) || !p.lt(y, x)))
+        .map(|x| {
+            let mut e = d.clone();
+            e.insert(x);
+            e
+        })
+        .collect())
+}
+
+/// Returns the down-sets covered by d, each obtained by removing a maximal element of d, ordered by the
+/// removed element.
+pub fn downset_predecessors<P: Poset + ?Sized>(
+    p: &P,
+    d: &HashSet<AnElement>,
+) -> Result<Vec<HashSet<AnElement>>, NotADownSet> {
+    check_downset(p, d)?;
+    let mut maximal: Vec<AnElement> = d
+        .iter()
+        .copied()
+        .filter(|&x| d.iter().all(|&y| !p.lt(x, y)))
+        .collect();
+    maximal.sort_unstable();
+    Ok(maximal
+        .into_iter()
+        .map(|x| {
+            let mut e = d.clone();
+            e.remove(&x);
+            e
+        })
+        .collect())
+}
+
+/// Returns the lattice $J(P)$ of the down-sets of p ordered by inclusion, together with the down-set each
+/// of its elements stands for. The down-sets are numbered by size, and those of equal size in the
+/// lexicographic order of their sorted elements, so the empty down-set is element 0 and the whole poset is
+/// last.
+pub fn ideal_lattice<P: Poset + ?Sized>(p: &P) -> (PosetG, Vec<Elements>) {
+    let downsets = all_downsets(&BitMatrix::new(p));
+    let g: BiPaGraph = downsets
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let up = (i..downsets.len()).filter(|&j| d.is_subset(&downsets[j]));
+            (i, up.collect())
+        })
+        .collect();
+    let mut j = PosetG::new(&g);
+    let top = downsets.len() - 1;
+    j.md.bot = Some(Elt::A(0));
+    j.md.top = Some(Elt::A(top));
+    j.md.minimals = Some([0].into_iter().collect());
+    j.md.maximals = Some([top].into_iter().collect());
+    j.md.height = Some(p.md().n + 1);
+    (
+        j,
+        downsets
+            .into_iter()
+            .map(|d| d.into_ones().collect())
+            .collect(),
+    )
+}
+
+/// All the down-sets of the poset b, numbered as in [ideal_lattice].
+pub(crate) fn all_downsets(b: &BitMatrix) -> Vec<BitRow> {
+    let n = b.n();
+    let mut downsets = vec![BitRow::new(n)];
+    let mut layer = downsets.clone();
+    for _ in 0..n {
+        let mut next: Vec<(Vec<AnElement>, BitRow)> = layer
+            .iter()
+            .flat_map(|d| {
+                (0..n).filter_map(move |x| {
+                    let mut e = d.clone();
+                    e.set(x);
+                    (!d.get(x) && b.down_row(x).is_subset(&e)).then_some(e)
+                })
+            })
+            .map(|e| (e.iter_ones().collect(), e))
+            .collect();
+        next.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        next.dedup_by(|a, b| a.0 == b.0);
+        layer = next.into_iter().map(|(_, e)| e).collect();
+        downsets.extend(layer.iter().cloned());
+    }
+    downsets
+}
+
+/// Returns the number of down-sets of p of each size $0, ..., n$, the rank generating function of $J(P)$.
+///
+/// The count is a dynamic program over a partition of the poset into as few chains $C_0, ..., C_{k-1}$ as
+/// possible, $k$ being the [width](Poset::width). A down-set meets every chain in a prefix, and is determined
+/// by the lengths $t_0, ..., t_{k-1}$ of those prefixes. The lengths are chosen one chain at a time: the top
+/// element of the prefix of $C_i$ needs the elements below it in the later chains, and the first element left
+/// out of $C_i$ rules out the elements above it, so the choices made so far bound each later length from
+/// below and from above. The number of down-sets of each size is memoized by the chain and those bounds, so
+/// the work grows with the number of distinct bounds met, which is at most exponential in $k$ rather than in
+/// $n$.
+///
+/// Panics if a count does not fit in a `u128`.
+pub fn count_ideals_by_size<P: Poset + ?Sized>(p: &P) -> Vec<u128> {
+    let mut counter = IdealCounter::new(p);
+    counter.count(0, &counter.start())
+}
+
+/// Returns a down-set of p with the given number of elements, drawn uniformly at random with the given seed,
+/// or None if p has fewer elements. The prefix lengths of [count_ideals_by_size] are chosen one chain at a
+/// time, each with probability proportional to the number of down-sets of the given size it leaves.
+pub fn sample_ideal<P: Poset + ?Sized>(p: &P, size: usize, seed: u64) -> Option<Elements> {
+    let mut counter = IdealCounter::new(p);
+    let mut bounds = counter.start();
+    let total = *counter.count(0, &bounds).get(size)?;
+    let mut rng = SplitMix64::new(seed);
+    let mut pick = rng.below_u128(total);
+    let (mut ideal, mut left) = (Elements::new(), size);
+    for i in 0..counter.chains.len() {
+        let (lo, hi) = bounds[0];
+        for t in lo..=hi.min(left) {
+            let Some(next) = counter.restrict(i, t, &bounds[1..]) else {
+                continue;
+            };
+            let ways = counter
+                .count(i + 1, &next)
+                .get(left - t)
+                .copied()
+                .unwrap_or(0);
+            if pick < ways {
+                ideal.extend(&counter.chains[i][..t]);
+                left -= t;
+                bounds = next;
+                break;
+            }
+            pick -= ways;
+        }
+    }
+    Some(ideal)
+}
+
+/// The least and the greatest allowed prefix length of each remaining chain.
+type Bounds = Vec<(usize, usize)>;
+
+/// The dynamic program of [count_ideals_by_size], memoized by the chain and the bounds.
+struct IdealCounter {
+    chains: Vec<Vec<AnElement>>,
+    /// Entry j of row x is the number of elements of chain j below or equal to x.
+    below: Vec<Vec<usize>>,
+    /// Entry j of row x is the position in chain j of the lowest element above or equal to x, or the
+    /// length of chain j if there is none.
+    above: Vec<Vec<usize>>,
+    memo: HashMap<(usize, Bounds), Vec<u128>>,
+}
+
+impl IdealCounter {
+    fn new<P: Poset + ?Sized>(p: &P) -> IdealCounter {
+        let b = BitMatrix::new(p);
+        let lt: Vec<Vec<bool>> = (0..b.n())
+            .map(|x| (0..b.n()).map(|y| x != y && b.leq(x, y)).collect())
+            .collect();
+        let chains = chain_partition(&lt);
+        let below = (0..b.n())
+            .map(|x| {
+                let row = b.down_row(x);
+                chains
+                    .iter()
+                    .map(|c| c.iter().filter(|&&y| row.get(y)).count())
+                    .collect()
+            })
+            .collect();
+        let above = (0..b.n())
+            .map(|x| {
+                let row = b.up_row(x);
+                let lowest = |c: &Vec<AnElement>| c.iter().position(|&y| row.get(y));
+                chains
+                    .iter()
+                    .map(|c| lowest(c).unwrap_or(c.len()))
+                    .collect()
+            })
+            .collect();
+        IdealCounter {
+            chains,
+            below,
+            above,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// The bounds on the prefix lengths before any choice.
+    fn start(&self) -> Bounds {
+        self.chains.iter().map(|c| (0, c.len())).collect()
+    }
+
+    /// The bounds on the chains after chain i, given the bounds rest on them, once the prefix of chain i
+    /// has t elements, or None if they leave no choice for some chain.
+    fn restrict(&self, i: usize, t: usize, rest: &[(usize, usize)]) -> Option<Bounds> {
+        let chain = &self.chains[i];
+        let (j0, k) = (i + 1, rest.len());
+        (0..k)
+            .map(|d| {
+                let (mut lo, mut hi) = rest[d];
+                if t > 0 {
+                    lo = lo.max(self.below[chain[t - 1]][j0 + d]);
+                }
+                if t < chain.len() {
+                    hi = hi.min(self.above[chain[t]][j0 + d]);
+                }
+                (lo <= hi).then_some((lo, hi))
+            })
+            .collect()
+    }
+
+    /// Entry s is the number of ways of choosing the prefixes of the chains from i on, within bounds,
+    /// with s elements in total.
+    fn count(&mut self, i: usize, bounds: &[(usize, usize)]) -> Vec<u128> {
+        if i == self.chains.len() {
+            return vec![1];
+        }
+        let key = (i, bounds.to_vec());
+        if let Some(c) = self.memo.get(&key) {
+            return c.clone();
+        }
+        let (lo, hi) = bounds[0];
+        let mut counts: Vec<u128> = vec![];
+        for t in lo..=hi {
+            let Some(next) = self.restrict(i, t, &bounds[1..]) else {
+                continue;
+            };
+            let rest = self.count(i + 1, &next);
+            if counts.len() < t + rest.len() {
+                counts.resize(t + rest.len(), 0);
+            }
+            for (s, c) in rest.into_iter().enumerate() {
+                counts[t + s] = counts[t + s]
+                    .checked_add(c)
+                    .expect("the number of down-sets does not fit in a u128");
+            }
+        }
+        self.memo.insert(key, counts.clone());
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_principal_sets_and_closures() {
+        for p in corpus() {
+            let g = PosetG::from_poset(&p);
+            let h = PosetH::from_poset(&p);
+            for x in p.elements() {
+                let down = p.down_set(x);
+                let up = p.up_set(x);
+                assert_eq!(check_downset(&p, &down), Ok(()));
+                assert_eq!(down, p.down_closure(&[x].into_iter().collect()));
+                assert_eq!(up, p.up_closure(&[x].into_iter().collect()));
+                assert_eq!(up, p.op().down_set(x));
+                assert_eq!((g.down_set(x), g.up_set(x)), (down.clone(), up.clone()));
+                assert_eq!((h.down_set(x), Poset::up_set(&h, x)), (down, up));
+            }
+        }
+        let p = boolean_lattice(3);
+        let s: HashSet<AnElement> = [1, 2].into_iter().collect();
+        assert_eq!(p.down_closure(&s), [0, 1, 2].into_iter().collect());
+        assert_eq!(p.up_closure(&s), [1, 2, 3, 5, 6, 7].into_iter().collect());
+        assert!(p.up_closure(&HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_successors_of_empty() {
+        for mut p in corpus() {
+            let successors = p.downset_successors(&HashSet::new()).unwrap();
+            p.find_minimals();
+            let mut minimals: Vec<usize> = p.md.minimals.unwrap().into_iter().collect();
+            minimals.sort_unstable();
+            let expected: Vec<HashSet<usize>> = minimals
+                .into_iter()
+                .map(|x| [x].into_iter().collect())
+                .collect();
+            assert_eq!(successors, expected);
+        }
+    }
+
+    #[test]
+    fn test_walks_take_n_steps() {
+        for p in corpus() {
+            let n = p.md.n;
+            for pick_last in [false, true] {
+                let mut d = HashSet::new();
+                let mut steps = 0;
+                loop {
+                    let succ = p.downset_successors(&d).unwrap();
+                    match if pick_last { succ.last() } else { succ.first() } {
+                        Some(e) => d = e.clone(),
+                        None => break,
+                    }
+                    steps += 1;
+                    assert_eq!(p.downset_rank(&d), steps);
+                }
+                assert_eq!(steps, n);
+                assert_eq!(d.len(), n);
+
+                while let Some(e) = p.downset_predecessors(&d).unwrap().pop() {
+                    d = e;
+                    steps -= 1;
+                }
+                assert_eq!(steps, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cover_counts() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 8) {
+            let n = p.md.n;
+            let downsets: Vec<HashSet<usize>> = (0..1usize << n)
+                .map(|mask| (0..n).filter(|i| mask >> i & 1 == 1).collect())
+                .filter(|s| check_downset(&p, s).is_ok())
+                .collect();
+            let covers = downsets
+                .iter()
+                .flat_map(|a| downsets.iter().map(move |b| (a, b)))
+                .filter(|(a, b)| a.is_subset(b) && b.len() == a.len() + 1)
+                .count();
+            let up: usize = downsets
+                .iter()
+                .map(|d| p.downset_successors(d).unwrap().len())
+                .sum();
+            let down: usize = downsets
+                .iter()
+                .map(|d| p.downset_predecessors(d).unwrap().len())
+                .sum();
+            assert_eq!(up, covers);
+            assert_eq!(down, covers);
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let p = boolean_lattice(2);
+        let s: HashSet<usize> = [3].into_iter().collect();
+        let Err(NotADownSet::Missing { member, missing }) = p.downset_successors(&s) else {
+            panic!("{{3}} is not a down-set");
+        };
+        assert_eq!(member, 3);
+        assert!(p.leq(missing, 3) && missing != 3);
+        assert!(p.downset_predecessors(&s).is_err());
+        let outside: HashSet<usize> = [0, 4, 7].into_iter().collect();
+        assert_eq!(
+            check_downset(&p, &outside),
+            Err(NotADownSet::OutOfRange { member: 4, n: 4 })
+        );
+        assert!(p.downset_predecessors(&outside).is_err());
+
+        let closed = down_closure(&p, &s);
+        assert_eq!(closed.len(), 4);
+        assert_eq!(p.downset_predecessors(&closed).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_ideal_lattice() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let (j, ideals) = p.ideal_lattice();
+            assert_eq!(j.is_valid_poset(), Ok(()));
+            assert_eq!(j.md.n as u128, p.count_antichains());
+            assert!(ideals.iter().all(|d| check_downset(&p, d).is_ok()));
+            assert!(ideals.windows(2).all(|w| w[0].len() <= w[1].len()));
+            let mut sorted: Vec<Vec<AnElement>> = ideals
+                .iter()
+                .map(|d| {
+                    let mut v: Vec<_> = d.iter().copied().collect();
+                    v.sort_unstable();
+                    v
+                })
+                .collect();
+            sorted.dedup();
+            assert_eq!(sorted.len(), j.md.n);
+            for x in j.elements() {
+                for y in j.elements() {
+                    assert_eq!(j.leq(x, y), ideals[x].is_subset(&ideals[y]));
+                }
+                let succ = p.downset_successors(&ideals[x]).unwrap();
+                assert_eq!(succ.len(), Poset::upper_covers(&j, x).len());
+            }
+            assert!(j.is_distributive());
+            let mut fresh = PosetG::new(&j.g);
+            fresh.find_bot();
+            fresh.find_top();
+            fresh.find_minimals();
+            fresh.find_maximals();
+            fresh.find_height();
+            assert_eq!(fresh.md, j.md);
+        }
+    }
+
+    #[test]
+    fn test_ideal_lattice_of_chains() {
+        // The down-sets of an antichain are its subsets, and those of a product of chains are lattice paths.
+        let (j, _) = PosetM::new_antichain(4).ideal_lattice();
+        assert_eq!(j.md.n, 16);
+        assert_eq!(j.longest_chain().len(), 5);
+        let (j, ideals) = PosetM::new_chain(3).ideal_lattice();
+        assert_eq!(j.longest_chain().len(), 4);
+        assert_eq!(ideals[2], [0, 1].into_iter().collect());
+        let (j, _) = PosetM::new_chain(2)
+            .product(&PosetM::new_chain(3))
+            .ideal_lattice();
+        assert_eq!(j.md.n, 10);
+    }
+
+    #[test]
+    fn test_count_ideals_by_size() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            let (_, ideals) = p.ideal_lattice();
+            let mut sizes = vec![0u128; p.md.n + 1];
+            ideals.iter().for_each(|d| sizes[d.len()] += 1);
+            assert_eq!(p.count_ideals_by_size(), sizes);
+        }
+        // The down-sets of the product of chains of 2 and 3 elements are the partitions in a 2 by 3 box,
+        // counted by the Gaussian binomial coefficient [5 choose 2].
+        let box_2_3 = PosetM::new_chain(2).product(&PosetM::new_chain(3));
+        assert_eq!(box_2_3.count_ideals_by_size(), vec![1, 1, 2, 2, 2, 1, 1]);
+        assert_eq!(
+            boolean_lattice(4)
+                .count_ideals_by_size()
+                .iter()
+                .sum::<u128>(),
+            168
+        );
+        assert_eq!(PosetM::new_antichain(0).count_ideals_by_size(), vec![1]);
+    }
+
+    #[test]
+    fn test_sample_ideal() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 8) {
+            let n = p.md.n;
+            assert_eq!(p.sample_ideal(n + 1, 0), None);
+            for size in 0..=n {
+                let d = p.sample_ideal(size, size as u64).unwrap();
+                assert_eq!(d.len(), size);
+                assert_eq!(check_downset(&p, &d), Ok(()));
+            }
+        }
+        // Every 2-element down-set of the 4-element antichain turns up.
+        let p = PosetM::new_antichain(4);
+        let mut seen: Vec<Vec<AnElement>> = (0..200)
+            .map(|seed| {
+                let mut d: Vec<_> = p.sample_ideal(2, seed).unwrap().into_iter().collect();
+                d.sort_unstable();
+                d
+            })
+            .collect();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), 6);
+    }
+}
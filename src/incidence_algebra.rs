@@ -0,0 +1,203 @@
+//! The incidence algebra of a poset over the integers.
+//!
+//! The incidence algebra consists of the functions $f$ assigning an integer to every interval $[x, y]$,
+//! i.e., to every pair $x \le y$, and vanishing on other pairs. It is a ring under pointwise addition and
+//! the convolution
+//! $$(f * g)(x, y) = \sum_{x \le z \le y} f(x, z) g(z, y),$$
+//! whose unit is the delta function ($\delta(x, y) = 1$ if $x = y$ and 0 otherwise). The zeta function is 1
+//! on every interval, and its inverse is the Möbius function $\mu$. A function is invertible over the
+//! integers if, and only if, it is $\pm 1$ on every one-element interval $[x, x]$.
+//!
+//! Functions are stored as $n \times n$ matrices, entry $(x, y)$ holding $f(x, y)$. Listing the elements
+//! along a linear extension makes these matrices upper triangular, and the zeta function is the
+//! [zeta matrix](crate::Poset::zeta_matrix) of the poset.
+//!
+//! ```
+//! use fin_pos::incidence_algebra::IncidenceAlgebra;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let a = IncidenceAlgebra::new(&PosetM::new_boolean(2));
+//! let mu = a.mobius();
+//! assert_eq!((mu.value(0, 1), mu.value(0, 3)), (-1, 1));
+//! assert_eq!(a.convolve(&a.zeta(), &mu), a.delta());
+//! ```
+
+use crate::parallel::map_range;
+use crate::{AnElement, BoolMatrix, Poset};
+
+/// A function on the intervals of a poset, as a matrix vanishing off the pairs $x \le y$.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IncidenceFunction {
+    values: Vec<Vec<i64>>,
+}
+
+impl IncidenceFunction {
+    /// The value on the interval $[x, y]$, which is 0 if $x \nleq y$.
+    pub fn value(&self, x: AnElement, y: AnElement) -> i64 {
+        self.values[x][y]
+    }
+
+    /// The function as a matrix.
+    pub fn matrix(&self) -> &[Vec<i64>] {
+        &self.values
+    }
+}
+
+/// The incidence algebra of a poset, which remembers the order so that it can build and multiply
+/// [IncidenceFunction]s.
+#[derive(Debug, Clone)]
+pub struct IncidenceAlgebra {
+    leq: BoolMatrix,
+    /// A linear extension, along which convolutions and inverses are computed.
+    order: Vec<AnElement>,
+}
+
+impl IncidenceAlgebra {
+    pub fn new<P: Poset + ?Sized>(p: &P) -> IncidenceAlgebra {
+        IncidenceAlgebra {
+            leq: p
+                .elements()
+                .map(|x| p.elements().map(|y| p.leq(x, y)).collect())
+                .collect(),
+            order: p.linear_extension(),
+        }
+    }
+
+    /// The number of elements of the poset.
+    pub fn n(&self) -> usize {
+        self.leq.len()
+    }
+
+    /// The function taking the value f(x, y) on every interval $[x, y]$. f is not called on other pairs.
+    pub fn function<F: Fn(AnElement, AnElement) -> i64>(&self, f: F) -> IncidenceFunction {
+        let n = self.n();
+        IncidenceFunction {
+            values: (0..n)
+                .map(|x| {
+                    (0..n)
+                        .map(|y| if self.leq[x][y] { f(x, y) } else { 0 })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn zeta(&self) -> IncidenceFunction {
+        self.function(|_, _| 1)
+    }
+
+    pub fn delta(&self) -> IncidenceFunction {
+        self.function(|x, y| (x == y) as i64)
+    }
+
+    /// The Möbius function, the inverse of [IncidenceAlgebra::zeta].
+    pub fn mobius(&self) -> IncidenceFunction {
+        self.inverse(&self.zeta()).unwrap()
+    }
+
+    /// The convolution f * g. Panics if a function belongs to a poset of another size.
+    pub fn convolve(&self, f: &IncidenceFunction, g: &IncidenceFunction) -> IncidenceFunction {
+        self.check_size(f);
+        self.check_size(g);
+        let n = self.n();
+        self.function(|x, y| {
+            (0..n)
+                .filter(|&z| self.leq[x][z] && self.leq[z][y])
+                .map(|z| f.values[x][z] * g.values[z][y])
+                .sum()
+        })
+    }
+
+    /// The inverse of f under convolution, or None if f is not invertible over the integers, i.e., if
+    /// some $f(x, x)$ is not $\pm 1$. The inverse is found one interval at a time along a linear
+    /// extension, from $g(x, y) f(y, y) = -\sum_{x \le z < y} g(x, z) f(z, y)$.
+    pub fn inverse(&self, f: &IncidenceFunction) -> Option<IncidenceFunction> {
+        self.check_size(f);
+        let n = self.n();
+        if (0..n).any(|x| f.values[x][x].abs() != 1) {
+            return None;
+        }
+        // The rows are independent, and are computed in parallel with the `rayon` feature.
+        let g = map_range(n, |x| {
+            let mut row = vec![0; n];
+            // Dividing by f(y, y) = ±1 is multiplying by it.
+            for (i, &y) in self.order.iter().enumerate() {
+                if !self.leq[x][y] {
+                    continue;
+                }
+                row[y] = if x == y {
+                    f.values[y][y]
+                } else {
+                    let sum: i64 = self.order[..i]
+                        .iter()
+                        .filter(|&&z| self.leq[x][z] && self.leq[z][y])
+                        .map(|&z| row[z] * f.values[z][y])
+                        .sum();
+                    -sum * f.values[y][y]
+                };
+            }
+            row
+        });
+        Some(IncidenceFunction { values: g })
+    }
+
+    fn check_size(&self, f: &IncidenceFunction) {
+        assert_eq!(
+            f.values.len(),
+            self.n(),
+            "the function belongs to a poset of another size"
+        );
+    }
+}
+
+/// The zeta matrix of p: entry $(x, y)$ is 1 if $x \le y$ and 0 otherwise.
+pub fn zeta_matrix<P: Poset + ?Sized>(p: &P) -> Vec<Vec<i64>> {
+    p.elements()
+        .map(|x| p.elements().map(|y| p.leq(x, y) as i64).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_ring_laws() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 8) {
+            let a = IncidenceAlgebra::new(&p);
+            let (zeta, delta, mu) = (a.zeta(), a.delta(), a.mobius());
+            assert_eq!(zeta.matrix(), p.zeta_matrix().as_slice());
+            assert_eq!(a.convolve(&mu, &zeta), delta);
+            assert_eq!(a.convolve(&zeta, &mu), delta);
+            assert_eq!(a.convolve(&delta, &zeta), zeta);
+            // zeta * zeta counts the elements of each interval.
+            let count = a.convolve(&zeta, &zeta);
+            for x in p.elements() {
+                for y in p.elements().filter(|&y| p.leq(x, y)) {
+                    let size = p.elements().filter(|&z| p.leq(x, z) && p.leq(z, y)).count();
+                    assert_eq!(count.value(x, y), size as i64);
+                }
+            }
+            let f = a.function(|x, y| if x == y { -1 } else { (x + 2 * y) as i64 });
+            let g = a.inverse(&f).unwrap();
+            assert_eq!(a.convolve(&f, &g), delta);
+            assert_eq!(a.convolve(&g, &f), delta);
+        }
+    }
+
+    #[test]
+    fn test_known_mobius_values() {
+        // On a chain, mu is 1 on the diagonal and -1 on covers.
+        let mu = IncidenceAlgebra::new(&PosetM::new_chain(4)).mobius();
+        assert_eq!(mu.matrix()[0], vec![1, -1, 0, 0]);
+        // On the Boolean lattice, mu(S, T) = (-1)^{|T| - |S|}.
+        let mu = IncidenceAlgebra::new(&PosetM::new_boolean(4)).mobius();
+        assert_eq!(mu.value(0, 15), 1);
+        assert_eq!(mu.value(1, 15), -1);
+        let a = IncidenceAlgebra::new(&PosetM::new_antichain(2));
+        assert_eq!(a.inverse(&a.function(|_, _| 2)), None);
+    }
+}
@@ -0,0 +1,285 @@
+//! Posets that grow one element or relation at a time.
+//!
+//! An [IncrementalPoset] stores the up-set and the down-set of every element as rows of bits, in a
+//! [BitMatrix], and keeps them transitively closed as relations are added, in the manner of Italiano's
+//! algorithm: adding $x \le y$ gives every $a \le x$ not yet below y the up-set of y, and every $b \ge y$
+//! not yet above x the down-set of x. Only the rows that change are touched, each in $O(n / 64)$ word
+//! operations, so a sequence of insertions costs $O(n^3 / 64)$ in total rather than per insertion, and
+//! [Poset::leq] is a single bit lookup throughout. [IncrementalPoset::add_element] appends an element
+//! unrelated to the others.
+//!
+//! The [MetaData] is maintained as by the other representations (see [MetaData::add_relation]).
+//!
+//! ```
+//! use fin_pos::error::PosetError;
+//! use fin_pos::incremental::IncrementalPoset;
+//! use fin_pos::Poset;
+//!
+//! let mut p = IncrementalPoset::new_antichain(0);
+//! let (a, b, c) = (p.add_element(), p.add_element(), p.add_element());
+//! p.add_relation(a, b).unwrap();
+//! p.add_relation(b, c).unwrap();
+//! assert!(p.leq(a, c));
+//! assert_eq!(p.add_relation(c, a), Err(PosetError::NotAntisymmetric { x: c, y: a }));
+//! assert_eq!(p.relations().collect::<Vec<_>>(), vec![(0, 1), (0, 2), (1, 2)]);
+//! ```
+
+use crate::bitset::BitMatrix;
+use crate::error::PosetError;
+use crate::validate::check_new_relation;
+use crate::{sub_table, AnElement, Elements, Elt, MetaData, PartialOrdering, Poset};
+
+/// A poset built for adding elements and relations, as described in the [module level
+/// documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncrementalPoset {
+    pub md: MetaData,
+    order: BitMatrix,
+}
+
+impl IncrementalPoset {
+    /// Builds the poset on $0, ..., n-1$ ordered by leq, which must be a partial order.
+    pub(crate) fn from_fn<F: Fn(AnElement, AnElement) -> bool>(n: usize, leq: F) -> Self {
+        IncrementalPoset {
+            md: MetaData::new(n),
+            order: BitMatrix::from_fn(n, leq),
+        }
+    }
+
+    /// Appends a new element, unrelated to the others, and returns it.
+    pub fn add_element(&mut self) -> AnElement {
+        let mut point = MetaData::new(1);
+        point.top = Some(Elt::A(0));
+        point.bot = Some(Elt::A(0));
+        point.minimals = Some([0].into());
+        point.maximals = Some([0].into());
+        point.height = Some(1);
+        point.atoms = Some(Elements::new());
+        point.coatoms = Some(Elements::new());
+        let handles = std::mem::take(&mut self.md.handles);
+        self.md = self.md.coproduct(&point);
+        self.md.handles = handles;
+        self.order.push()
+    }
+
+    /// The order relation, kept transitively closed.
+    pub fn bits(&self) -> &BitMatrix {
+        &self.order
+    }
+}
+
+impl Poset for IncrementalPoset {
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
+        Box::new(0..self.md.n)
+    }
+
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.order.leq(x, y)
+    }
+
+    fn compare(&self, x: AnElement, y: AnElement) -> PartialOrdering {
+        PartialOrdering::from_leqs(self.order.leq(x, y), self.order.leq(y, x))
+    }
+
+    fn up_set(&self, x: AnElement) -> Elements {
+        self.order.up_row(x).iter_ones().collect()
+    }
+
+    fn relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(self.elements().flat_map(move |x| {
+            let above = self.order.up_row(x).iter_ones();
+            above.filter(move |&y| y != x).map(move |y| (x, y))
+        }))
+    }
+
+    /// Updates only the rows that change, see [BitMatrix] and the [module level documentation](self).
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        if check_new_relation(self, x, y)? {
+            self.order.insert(x, y);
+            self.md.add_relation(x, y);
+        }
+        Ok(())
+    }
+
+    fn find_bot(&mut self) {
+        let n = self.md.n;
+        self.md.bot = Some(
+            match (0..n).find(|&x| self.order.up_row(x).count_ones() == n) {
+                Some(x) => Elt::A(x),
+                None => Elt::NotPresent,
+            },
+        );
+    }
+
+    fn find_top(&mut self) {
+        let n = self.md.n;
+        self.md.top = Some(
+            match (0..n).find(|&x| self.order.down_row(x).count_ones() == n) {
+                Some(x) => Elt::A(x),
+                None => Elt::NotPresent,
+            },
+        );
+    }
+
+    fn find_minimals(&mut self) {
+        let minimals = (0..self.md.n).filter(|&x| self.order.down_row(x).count_ones() == 1);
+        self.md.minimals = Some(minimals.collect());
+    }
+
+    fn find_maximals(&mut self) {
+        let maximals = (0..self.md.n).filter(|&x| self.order.up_row(x).count_ones() == 1);
+        self.md.maximals = Some(maximals.collect());
+    }
+
+    fn op(&self) -> Self {
+        IncrementalPoset {
+            md: self.md.op(),
+            order: self.order.transpose(),
+        }
+    }
+
+    fn new_chain(n: usize) -> Self {
+        IncrementalPoset::from_fn(n, |x, y| x <= y)
+    }
+
+    fn new_antichain(n: usize) -> Self {
+        IncrementalPoset::from_fn(n, |x, y| x == y)
+    }
+
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        IncrementalPoset::from_fn(self.md.n * m, |a, b| {
+            self.leq(a / m, b / m) && other.leq(a % m, b % m)
+        })
+    }
+
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut p = IncrementalPoset::from_fn(n + other.md.n, |a, b| match (a < n, b < n) {
+            (true, true) => self.leq(a, b),
+            (false, false) => other.leq(a - n, b - n),
+            _ => false,
+        });
+        p.md = self.md.coproduct(&other.md);
+        p
+    }
+
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut p = IncrementalPoset::from_fn(n + other.md.n, |a, b| match (a < n, b < n) {
+            (true, true) => self.leq(a, b),
+            (false, false) => other.leq(a - n, b - n),
+            (below, _) => below,
+        });
+        p.md = self.md.ordinal_sum(&other.md);
+        p
+    }
+
+    fn adjoin_bot(&mut self) {
+        let minimals = self.minimals().clone();
+        let x = self.order.push();
+        minimals.into_iter().for_each(|y| self.order.insert(x, y));
+        self.md.adjoin_bot();
+    }
+
+    fn adjoin_top(&mut self) {
+        let maximals = self.maximals().clone();
+        let y = self.order.push();
+        maximals.into_iter().for_each(|x| self.order.insert(x, y));
+        self.md.adjoin_top();
+    }
+
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        let back = sub_table(s_0, self.md.n);
+        let mut p = IncrementalPoset::from_fn(back.len(), |a, b| self.leq(back[a], back[b]));
+        p.md = self.md.sub(&back);
+        (p, back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::{corpus, random_poset};
+    use crate::posetm::PosetM;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_against_matrix() {
+        for seed in 0..10 {
+            let n = 40;
+            let mut p = IncrementalPoset::new_antichain(0);
+            let mut m = PosetM::new_antichain(n);
+            (0..n).for_each(|_| {
+                p.add_element();
+            });
+            let mut rng = SplitMix64::new(seed);
+            for _ in 0..3 * n {
+                let (x, y) = (rng.below(n), rng.below(n));
+                assert_eq!(p.add_relation(x, y), m.add_relation(x, y));
+            }
+            assert!(p.same_order(&m));
+            assert_eq!(p.is_valid_poset(), Ok(()));
+            assert!(p.op().same_order(&m.op()));
+            assert_eq!(
+                p.bits().down_row(0).count_ones(),
+                m.elements().filter(|&y| m.leq(y, 0)).count()
+            );
+        }
+    }
+
+    /// Asserts that every field known in md agrees with the field computed from scratch for p.
+    fn assert_known_fields_exact(p: &IncrementalPoset) {
+        let md = &p.md;
+        let mut fresh = p.clone();
+        fresh.md.invalidate();
+        fresh.find_top();
+        fresh.find_bot();
+        fresh.find_minimals();
+        fresh.find_maximals();
+        fresh.find_height();
+        fresh.find_atoms();
+        fresh.find_coatoms();
+        let exact = &fresh.md;
+        assert!(md.top.is_none() || md.top == exact.top);
+        assert!(md.bot.is_none() || md.bot == exact.bot);
+        assert!(md.minimals.is_none() || md.minimals == exact.minimals);
+        assert!(md.maximals.is_none() || md.maximals == exact.maximals);
+        assert!(md.height.is_none() || md.height == exact.height);
+        assert!(md.atoms.is_none() || md.atoms == exact.atoms);
+        assert!(md.coatoms.is_none() || md.coatoms == exact.coatoms);
+    }
+
+    #[test]
+    fn test_metadata() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 12) {
+            let mut q = IncrementalPoset::from_poset(&p);
+            assert!(q.same_order(&p));
+            let x = q.add_element();
+            assert_known_fields_exact(&q);
+            q.add_relation(0, x).unwrap();
+            assert_known_fields_exact(&q);
+            q.adjoin_bot();
+            q.adjoin_top();
+            assert_known_fields_exact(&q);
+            assert_eq!(q.md.top, Some(Elt::A(x + 2)));
+            assert!(q.op().same_order(&PosetM::from_poset(&q).op()));
+        }
+        let p = random_poset(9, 30, 7);
+        let q = IncrementalPoset::from_poset(&p);
+        assert!(q.product(&q).same_order(&p.product(&p)));
+        assert!(q.coproduct(&q).same_order(&p.coproduct(&p)));
+        assert!(q.ordinal_sum(&q).same_order(&p.ordinal_sum(&p)));
+        let s: Elements = [1, 4, 5, 8].into();
+        assert!(q.sub(&s).0.same_order(&p.sub(&s).0));
+    }
+}
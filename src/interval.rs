@@ -0,0 +1,112 @@
+//! Intervals $[x, y] = \{z \mid x \le z \le y\}$ and other induced subposets, renumbered as posets in their
+//! own right.
+//!
+//! As with [Poset::sub], the posets returned here are on $\{0, ..., k-1\}$, with a table mapping each of
+//! their elements back to the element of the ambient poset it came from. The elements keep their relative
+//! order, and pinned handles follow them.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let b3 = PosetM::new_boolean(3);
+//! // The subsets between {0} and {0, 1, 2}: a square.
+//! let (square, back): (PosetM, _) = b3.interval(1, 7);
+//! assert_eq!(back, vec![1, 3, 5, 7]);
+//! assert!(square.same_order(&PosetM::new_boolean(2)));
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::posetm::PosetM;
+use crate::{AnElement, BoolMatrix, Poset};
+
+/// Returns the subposet of p induced on the given elements, renumbered in increasing order of the
+/// elements, together with the element of p behind each new element. Repeated elements count once. This is
+/// [Poset::sub] for any poset and any representation of the result.
+pub fn induced<P, Q>(p: &P, elements: &[AnElement]) -> (Q, Vec<AnElement>)
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let mut back = elements.to_vec();
+    back.sort_unstable();
+    back.dedup();
+    let (q, back) = induced_matrix(p, back);
+    (Q::from_poset(&q), back)
+}
+
+/// The subposet of p on the sorted elements back as a matrix, with the [MetaData](crate::MetaData)
+/// restricted as by [Poset::sub], which [PosetM] implements by this.
+pub(crate) fn induced_matrix<P: Poset + ?Sized>(
+    p: &P,
+    back: Vec<AnElement>,
+) -> (PosetM, Vec<AnElement>) {
+    let m: BoolMatrix = back
+        .iter()
+        .map(|&a| back.iter().map(|&b| p.leq(a, b)).collect())
+        .collect();
+    let mut q = PosetM::new(&m);
+    q.md = p.md().sub(&back);
+    (q, back)
+}
+
+/// Returns the interval $[x, y]$ of p as a poset of its own, with the map back to p (see [induced]). The
+/// interval is empty if $x \nleq y$.
+pub fn interval<P, Q>(p: &P, x: AnElement, y: AnElement) -> (Q, Vec<AnElement>)
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let between: Vec<AnElement> = p
+        .elements()
+        .filter(|&z| p.leq(x, z) && p.leq(z, y))
+        .collect();
+    induced(p, &between)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+
+    #[test]
+    fn test_intervals_of_corpus() {
+        for p in corpus() {
+            for x in p.elements() {
+                for y in p.elements() {
+                    let (q, back): (PosetH, _) = interval(&p, x, y);
+                    if !p.leq(x, y) {
+                        assert!(back.is_empty() && q.md.n == 0);
+                        continue;
+                    }
+                    assert_eq!(q.md.n, back.len());
+                    assert!(back.contains(&x) && back.contains(&y));
+                    for a in 0..back.len() {
+                        assert!(p.leq(x, back[a]) && p.leq(back[a], y));
+                        for b in 0..back.len() {
+                            assert_eq!(q.leq(a, b), p.leq(back[a], back[b]));
+                        }
+                    }
+                    // An interval is bounded by its ends.
+                    assert_eq!(q.lower_covers(back.binary_search(&x).unwrap()).len(), 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_handles_follow() {
+        let mut p = PosetG::new_chain(5);
+        let inside = p.pin(3);
+        let outside = p.pin(0);
+        let (q, back): (PosetG, _) = p.interval(2, 4);
+        assert_eq!(back, vec![2, 3, 4]);
+        assert_eq!(q.resolve(&inside), Some(1));
+        assert_eq!(q.resolve(&outside), None);
+        let (r, back): (PosetM, _) = induced(&p, &[4, 1, 4]);
+        assert_eq!(back, vec![1, 4]);
+        assert!(r.same_order(&PosetM::new_chain(2)));
+    }
+}
@@ -0,0 +1,603 @@
+//! Posets whose elements carry names.
+//!
+//! A [LabeledPoset] pairs a poset with a table of distinct labels, one for each element, so that the
+//! elements can be addressed by name instead of by index. Several labeled posets over overlapping sets of
+//! names can be combined with [merge_labeled] into one poset over the union of the names, and a
+//! [PosetBuilder] assembles a labeled poset from named cover pairs. A [Labeled] poset instead attaches
+//! arbitrary data to its elements and keeps it attached through the usual constructions.
+//!
+//! ```
+//! use fin_pos::labeled::{merge_labeled, LabeledPoset};
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let ab = LabeledPoset::new(PosetM::new_chain(2), vec!["a", "b"]);
+//! let bc = LabeledPoset::new(PosetM::new_chain(2), vec!["b", "c"]);
+//! let merged = merge_labeled(&[ab, bc]).unwrap();
+//! assert_eq!(merged.labels(), &["a", "b", "c"]);
+//! assert_eq!(merged.leq_labels(&"a", &"c"), Some(true));
+//! ```
+
+use crate::bitset::BitRow;
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::parallel::for_each_mut;
+use crate::posetm::PosetM;
+use crate::smallset::SmallSet;
+use crate::{dot, validate};
+use crate::{AnElement, BoolMatrix, Elements, Poset};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::hash::Hash;
+
+/// A poset together with a distinct label for each of its elements.
+#[derive(Debug, Clone)]
+pub struct LabeledPoset<L, P> {
+    poset: P,
+    labels: Vec<L>,
+    index: HashMap<L, AnElement>,
+}
+
+impl<L: Eq + Hash + Clone, P: Poset> LabeledPoset<L, P> {
+    /// Labels the element i of poset by labels[i]. Panics if the number of labels differs from the size of
+    /// the poset, or if two elements get the same label.
+    pub fn new(poset: P, labels: Vec<L>) -> LabeledPoset<L, P> {
+        assert_eq!(
+            labels.len(),
+            poset.md().n,
+            "the number of labels differs from the size of the poset"
+        );
+        let index: HashMap<L, AnElement> = labels
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (l.clone(), i))
+            .collect();
+        assert_eq!(index.len(), labels.len(), "the labels are not distinct");
+        LabeledPoset {
+            poset,
+            labels,
+            index,
+        }
+    }
+
+    /// Returns the underlying poset.
+    pub fn poset(&self) -> &P {
+        &self.poset
+    }
+
+    /// Returns the labels, indexed by the elements.
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    /// Returns the label of the element x. Panics if x is out of range.
+    pub fn label(&self, x: AnElement) -> &L {
+        &self.labels[x]
+    }
+
+    /// Returns the element with the given label, if there is one.
+    pub fn element(&self, label: &L) -> Option<AnElement> {
+        self.index.get(label).copied()
+    }
+
+    /// Compares the elements with the given labels, returning None if either label is unknown.
+    pub fn leq_labels(&self, a: &L, b: &L) -> Option<bool> {
+        Some(self.poset.leq(self.element(a)?, self.element(b)?))
+    }
+
+    /// The Hasse diagram in the DOT language, with the elements labeled (see [dot]).
+    pub fn to_dot(&self) -> String
+    where
+        L: Display,
+    {
+        dot::to_dot(&self.poset, Some(&self.labels))
+    }
+
+    /// Separates the poset from its labels.
+    pub fn into_parts(self) -> (P, Vec<L>) {
+        (self.poset, self.labels)
+    }
+}
+
+/// A poset together with arbitrary data for each of its elements. Unlike the labels of a [LabeledPoset], the
+/// data need not be distinct or hashable, so elements cannot be looked up by it. The constructions below
+/// apply the operation of the same name to the poset and carry the data along to the new indices.
+///
+/// ```
+/// use fin_pos::labeled::Labeled;
+/// use fin_pos::posetg::PosetG;
+/// use fin_pos::Poset;
+///
+/// let mut tasks = Labeled::new(PosetG::new_antichain(2), vec![1.5, 2.5]);
+/// tasks.adjoin_bot(0.0);
+/// let grid = tasks.product(&Labeled::new(PosetG::new_chain(2), vec!['x', 'y']));
+/// assert_eq!(grid.data(), &[(1.5, 'x'), (1.5, 'y'), (2.5, 'x'), (2.5, 'y'), (0.0, 'x'), (0.0, 'y')]);
+/// assert!(grid.poset().leq(4, 3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Labeled<P, T> {
+    poset: P,
+    data: Vec<T>,
+}
+
+impl<P: Poset, T> Labeled<P, T> {
+    /// Attaches data\[i\] to the element i of poset. Panics if the length of data differs from the size of
+    /// the poset.
+    pub fn new(poset: P, data: Vec<T>) -> Labeled<P, T> {
+        assert_eq!(
+            data.len(),
+            poset.md().n,
+            "the amount of data differs from the size of the poset"
+        );
+        Labeled { poset, data }
+    }
+
+    /// Returns the underlying poset.
+    pub fn poset(&self) -> &P {
+        &self.poset
+    }
+
+    /// Returns the data, indexed by the elements.
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the data of the element x. Panics if x is out of range.
+    pub fn get(&self, x: AnElement) -> &T {
+        &self.data[x]
+    }
+
+    /// Returns the data of the element x for updating. Panics if x is out of range.
+    pub fn get_mut(&mut self, x: AnElement) -> &mut T {
+        &mut self.data[x]
+    }
+
+    /// The Hasse diagram in the DOT language, with the elements labeled by their data (see [dot]).
+    pub fn to_dot(&self) -> String
+    where
+        T: Display,
+    {
+        dot::to_dot(&self.poset, Some(&self.data))
+    }
+
+    /// Separates the poset from its data.
+    pub fn into_parts(self) -> (P, Vec<T>) {
+        (self.poset, self.data)
+    }
+
+    /// Replaces the data of each element by f applied to it.
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Labeled<P, U> {
+        Labeled {
+            poset: self.poset,
+            data: self.data.into_iter().map(f).collect(),
+        }
+    }
+
+    /// Converts the poset to the representation Q, keeping the data.
+    pub fn convert<Q: Poset + FromPoset<P>>(self) -> Labeled<Q, T> {
+        Labeled {
+            poset: Q::from_poset(&self.poset),
+            data: self.data,
+        }
+    }
+
+    /// See [Poset::op]. The elements keep their indices, and so their data.
+    pub fn op(&self) -> Labeled<P, T>
+    where
+        T: Clone,
+    {
+        Labeled {
+            poset: self.poset.op(),
+            data: self.data.clone(),
+        }
+    }
+
+    /// See [Poset::sub]. The elements of the subposet keep their data.
+    pub fn sub(&self, elements: &Elements) -> Labeled<P, T>
+    where
+        T: Clone,
+    {
+        let (poset, back) = self.poset.sub(elements);
+        Labeled {
+            poset,
+            data: back.into_iter().map(|x| self.data[x].clone()).collect(),
+        }
+    }
+
+    /// See [Poset::adjoin_bot]. The new bottom carries label.
+    pub fn adjoin_bot(&mut self, label: T) {
+        self.poset.adjoin_bot();
+        self.data.push(label);
+    }
+
+    /// See [Poset::adjoin_top]. The new top carries label.
+    pub fn adjoin_top(&mut self, label: T) {
+        self.poset.adjoin_top();
+        self.data.push(label);
+    }
+
+    /// See [Poset::product]. The pair $(a, b)$ carries the pair of their data.
+    pub fn product<U: Clone>(&self, other: &Labeled<P, U>) -> Labeled<P, (T, U)>
+    where
+        T: Clone,
+    {
+        Labeled {
+            poset: self.poset.product(&other.poset),
+            data: self
+                .data
+                .iter()
+                .flat_map(|a| other.data.iter().map(move |b| (a.clone(), b.clone())))
+                .collect(),
+        }
+    }
+
+    /// See [Poset::coproduct]. The data of other follows that of the poset.
+    pub fn coproduct(&self, other: &Labeled<P, T>) -> Labeled<P, T>
+    where
+        T: Clone,
+    {
+        Labeled {
+            poset: self.poset.coproduct(&other.poset),
+            data: self.data.iter().chain(&other.data).cloned().collect(),
+        }
+    }
+
+    /// See [Poset::ordinal_sum]. The data of other follows that of the poset.
+    pub fn ordinal_sum(&self, other: &Labeled<P, T>) -> Labeled<P, T>
+    where
+        T: Clone,
+    {
+        Labeled {
+            poset: self.poset.ordinal_sum(&other.poset),
+            data: self.data.iter().chain(&other.data).cloned().collect(),
+        }
+    }
+}
+
+impl<L: Eq + Hash + Clone, P: Poset> From<LabeledPoset<L, P>> for Labeled<P, L> {
+    fn from(p: LabeledPoset<L, P>) -> Labeled<P, L> {
+        let (poset, labels) = p.into_parts();
+        Labeled::new(poset, labels)
+    }
+}
+
+/// The error returned when merged posets order distinct labels in a cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict<L> {
+    /// The labels that the inputs force to be mutually below each other, in order of first appearance.
+    pub labels: Vec<L>,
+    /// The indices of the inputs relating two of these labels.
+    pub inputs: Vec<usize>,
+}
+
+impl<L: fmt::Debug> fmt::Display for MergeConflict<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "inputs {:?} order the labels {:?} in a cycle",
+            self.inputs, self.labels
+        )
+    }
+}
+
+impl<L: fmt::Debug> Error for MergeConflict<L> {}
+
+/// Merges posets over overlapping sets of labels into one poset over the union of the labels, in which
+/// $a \le b$ holds when a chain of relations of the inputs leads from $a$ to $b$. The labels are numbered in
+/// order of first appearance.
+pub fn merge_labeled<L: Eq + Hash + Clone>(
+    posets: &[LabeledPoset<L, PosetM>],
+) -> Result<LabeledPoset<L, PosetM>, MergeConflict<L>> {
+    let mut labels: Vec<L> = vec![];
+    let mut index: HashMap<L, AnElement> = HashMap::new();
+    // The relations of the inputs, translated through the shared labels, with the input they come from.
+    let mut relations: Vec<(AnElement, AnElement, usize)> = vec![];
+    for (k, p) in posets.iter().enumerate() {
+        let shared: Vec<AnElement> = p
+            .labels()
+            .iter()
+            .map(|l| {
+                *index.entry(l.clone()).or_insert_with(|| {
+                    labels.push(l.clone());
+                    labels.len() - 1
+                })
+            })
+            .collect();
+        for x in p.poset().elements() {
+            for y in p.poset().elements() {
+                if p.poset().lt(x, y) {
+                    relations.push((shared[x], shared[y], k));
+                }
+            }
+        }
+    }
+
+    let n = labels.len();
+    let up = closure(n, relations.iter().map(|&(x, y, _)| (x, y)));
+    if let Some((x, _)) = (0..n)
+        .flat_map(|x| up[x].iter_ones().map(move |y| (x, y)))
+        .find(|&(x, y)| x != y && up[y].get(x))
+    {
+        let cycle: Vec<AnElement> = (0..n).filter(|&y| up[x].get(y) && up[y].get(x)).collect();
+        let mut inputs: Vec<usize> = relations
+            .iter()
+            .filter(|(a, b, _)| cycle.contains(a) && cycle.contains(b))
+            .map(|&(_, _, k)| k)
+            .collect();
+        inputs.sort_unstable();
+        inputs.dedup();
+        return Err(MergeConflict {
+            labels: cycle.into_iter().map(|y| labels[y].clone()).collect(),
+            inputs,
+        });
+    }
+
+    Ok(LabeledPoset::new(PosetM::new(&to_matrix(&up)), labels))
+}
+
+/// The up-sets of the reflexive and transitive closure of the relation on $0, ..., n-1$, by Warshall's
+/// algorithm on rows of bits. The rows are updated in parallel with the `rayon` feature.
+fn closure(n: usize, relations: impl IntoIterator<Item = (AnElement, AnElement)>) -> Vec<BitRow> {
+    let mut up: Vec<BitRow> = (0..n)
+        .map(|x| {
+            let mut r = BitRow::new(n);
+            r.set(x);
+            r
+        })
+        .collect();
+    for (x, y) in relations {
+        up[x].set(y);
+    }
+    for k in 0..n {
+        let row = up[k].clone();
+        for_each_mut(&mut up, |r| {
+            if r.get(k) {
+                r.or_assign(&row);
+            }
+        });
+    }
+    up
+}
+
+fn to_matrix(up: &[BitRow]) -> BoolMatrix {
+    up.iter()
+        .map(|r| (0..up.len()).map(|y| r.get(y)).collect())
+        .collect()
+}
+
+/// Assembles a labeled poset from named elements and cover pairs, numbering the labels in order of first
+/// appearance. The pairs need not be the covers of the result: any pairs generating the order will do, and
+/// redundant ones are dropped.
+///
+/// ```
+/// use fin_pos::labeled::PosetBuilder;
+/// use fin_pos::poseth::PosetH;
+/// use fin_pos::Poset;
+///
+/// let builder = PosetBuilder::new()
+///     .cover("bot", "left")
+///     .cover("bot", "right")
+///     .covers([("left", "top"), ("right", "top")])
+///     .element("isolated");
+/// let diamond = builder.build::<PosetH>().unwrap();
+/// assert_eq!(diamond.leq_labels(&"bot", &"top"), Some(true));
+/// assert_eq!(diamond.poset().md().n, 5);
+///
+/// assert!(builder.cover("top", "bot").build::<PosetH>().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PosetBuilder<L> {
+    labels: Vec<L>,
+    index: HashMap<L, AnElement>,
+    covers: Vec<(AnElement, AnElement)>,
+}
+
+impl<L: Eq + Hash + Clone> Default for PosetBuilder<L> {
+    fn default() -> Self {
+        PosetBuilder {
+            labels: vec![],
+            index: HashMap::new(),
+            covers: vec![],
+        }
+    }
+}
+
+impl<L: Eq + Hash + Clone> PosetBuilder<L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an element with the given label, unless there is one already.
+    pub fn element(mut self, label: L) -> Self {
+        self.intern(label);
+        self
+    }
+
+    /// Declares that upper covers lower, adding either label as an element if it is new.
+    pub fn cover(mut self, lower: L, upper: L) -> Self {
+        let x = self.intern(lower);
+        let y = self.intern(upper);
+        self.covers.push((x, y));
+        self
+    }
+
+    /// Declares each of the pairs (lower, upper) as a cover.
+    pub fn covers<I: IntoIterator<Item = (L, L)>>(self, pairs: I) -> Self {
+        pairs
+            .into_iter()
+            .fold(self, |b, (lower, upper)| b.cover(lower, upper))
+    }
+
+    /// Returns the labels added so far, indexed by the elements they will become.
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    /// Builds the poset in the representation P. Fails with [PosetError::Cycle] if the covers run in a
+    /// cycle (including a label covering itself), naming two elements on it by their indices in
+    /// [PosetBuilder::labels].
+    pub fn build<P>(&self) -> Result<LabeledPoset<L, P>, PosetError>
+    where
+        P: Poset + FromPoset<PosetM>,
+    {
+        let n = self.labels.len();
+        let mut h: Vec<SmallSet> = vec![SmallSet::new(); n];
+        for &(x, y) in &self.covers {
+            h[x].insert(y);
+        }
+        validate::check_acyclic(&h, n)?;
+        let m = PosetM::new(&to_matrix(&closure(n, self.covers.iter().copied())));
+        Ok(LabeledPoset::new(P::from_poset(&m), self.labels.clone()))
+    }
+
+    fn intern(&mut self, label: L) -> AnElement {
+        if let Some(&x) = self.index.get(&label) {
+            return x;
+        }
+        self.labels.push(label.clone());
+        self.index.insert(label, self.labels.len() - 1);
+        self.labels.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+
+    fn chain(labels: &[&'static str]) -> LabeledPoset<&'static str, PosetM> {
+        LabeledPoset::new(PosetM::new_chain(labels.len()), labels.to_vec())
+    }
+
+    #[test]
+    fn test_merge_chains() {
+        let merged = merge_labeled(&[
+            chain(&["a", "b", "c"]),
+            chain(&["c", "d"]),
+            chain(&["b", "e"]),
+        ])
+        .unwrap();
+        assert_eq!(merged.labels(), &["a", "b", "c", "d", "e"]);
+        let below = |a, b| merged.leq_labels(&a, &b).unwrap();
+        assert!(below("a", "d") && below("b", "d") && below("a", "e"));
+        assert!(!below("c", "e") && !below("e", "c") && !below("d", "e"));
+        assert!(!below("d", "a"));
+        assert_eq!(merged.leq_labels(&"a", &"z"), None);
+
+        let same = merge_labeled(&[chain(&["a", "b"]), chain(&["a", "b"])]).unwrap();
+        assert!(same.poset().same_order(&PosetM::new_chain(2)));
+    }
+
+    #[test]
+    fn test_conflict_names_labels() {
+        let err = merge_labeled(&[
+            chain(&["x", "y"]),
+            chain(&["a", "b"]),
+            chain(&["b", "c"]),
+            chain(&["y", "z"]),
+            chain(&["c", "a"]),
+        ])
+        .unwrap_err();
+        assert_eq!(err.labels, vec!["a", "b", "c"]);
+        assert_eq!(err.inputs, vec![1, 2, 4]);
+        assert_eq!(
+            err.to_string(),
+            "inputs [1, 2, 4] order the labels [\"a\", \"b\", \"c\"] in a cycle"
+        );
+    }
+
+    #[test]
+    fn test_merge_nothing() {
+        let merged = merge_labeled::<String>(&[]).unwrap();
+        assert!(merged.labels().is_empty());
+        assert_eq!(merged.poset().md.n, 0);
+    }
+
+    #[test]
+    fn test_builder_representations() {
+        let builder = PosetBuilder::new()
+            .covers([("a", "b"), ("b", "c"), ("a", "c"), ("a", "d")])
+            .element("e")
+            .element("a");
+        assert_eq!(builder.labels(), &["a", "b", "c", "d", "e"]);
+        let m = builder.build::<PosetM>().unwrap();
+        let g = builder.build::<PosetG>().unwrap();
+        let h = builder.build::<PosetH>().unwrap();
+        assert_eq!(m.poset().is_valid_poset(), Ok(()));
+        assert!(m.poset().same_order(g.poset()));
+        assert!(m.poset().same_order(h.poset()));
+        assert_eq!(h.labels(), builder.labels());
+        // The redundant pair a < c is not a cover.
+        assert_eq!(h.poset().layout_edges(), vec![(0, 1), (0, 3), (1, 2)]);
+        assert_eq!(h.leq_labels(&"e", &"a"), Some(false));
+        assert_eq!(h.leq_labels(&"a", &"f"), None);
+        assert!(h.to_dot().contains("  4 [label=\"e\"];\n"));
+    }
+
+    #[test]
+    fn test_builder_cycles() {
+        let cycle = PosetBuilder::new().covers([(1, 2), (2, 3), (3, 1)]);
+        assert!(matches!(
+            cycle.build::<PosetM>(),
+            Err(PosetError::Cycle { .. })
+        ));
+        let looped = PosetBuilder::new().cover("x".to_string(), "x".to_string());
+        assert_eq!(
+            looped.build::<PosetH>().unwrap_err(),
+            PosetError::Cycle { x: 0, y: 0 }
+        );
+        let empty = PosetBuilder::<u8>::new().build::<PosetG>().unwrap();
+        assert_eq!(empty.poset().md.n, 0);
+    }
+
+    #[test]
+    fn test_labeled_constructions() {
+        let new_vee = || {
+            Labeled::new(
+                PosetG::new(&[(0, [0, 1, 2].into()), (1, [1].into()), (2, [2].into())].into()),
+                vec!["root", "left", "right"],
+            )
+        };
+        let vee = new_vee();
+        let op = vee.op();
+        assert!(op.poset().leq(1, 0));
+        assert_eq!(op.data(), vee.data());
+
+        let leaves = vee.sub(&[2, 1].into());
+        assert_eq!(leaves.data(), &["left", "right"]);
+        assert!(leaves.poset().is_antichain());
+
+        let mut capped = new_vee();
+        capped.adjoin_top("top");
+        assert_eq!(capped.get(3), &"top");
+        assert!(capped.poset().leq(1, 3));
+
+        let sum = vee.ordinal_sum(&capped);
+        assert_eq!(sum.data().len(), 7);
+        assert_eq!(sum.get(6), &"top");
+        assert!(sum.poset().leq(1, 4));
+        let apart = vee.coproduct(&vee);
+        assert_eq!(apart.get(4), &"left");
+        assert!(!apart.poset().leq(0, 4));
+
+        let pairs = vee.product(&new_vee().map(str::len));
+        assert_eq!(pairs.get(5), &("left", 5));
+        assert!(pairs.poset().leq(1, 4) && !pairs.poset().leq(1, 5));
+
+        let data = pairs.data().to_vec();
+        let h: Labeled<PosetH, _> = vee.product(&new_vee().map(str::len)).convert();
+        assert!(h.poset().same_order(pairs.poset()));
+        assert_eq!(h.data(), data);
+    }
+
+    #[test]
+    fn test_labeled_from_labeled_poset() {
+        let mut p: Labeled<PosetM, _> = chain(&["a", "b"]).into();
+        p.adjoin_bot("c");
+        *p.get_mut(0) = "z";
+        assert_eq!(p.into_parts().1, vec!["z", "b", "c"]);
+    }
+}
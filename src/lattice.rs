@@ -0,0 +1,553 @@
+//! Lattices and two classical classes of them.
+//!
+//! A poset is a lattice if every pair of elements $x, y$ has a least upper bound, the join $x \vee y$, and a
+//! greatest lower bound, the meet $x \wedge y$. [LatticeOps] tabulates both operations once, in $O(n^3/64)$
+//! word operations, after which each is a lookup.
+//!
+//! A lattice is *modular* if $x \le z$ implies $x \vee (y \wedge z) = (x \vee y) \wedge z$, and
+//! *distributive* if meets distribute over joins. Rather than testing the identities on all triples, the
+//! checks look for the forbidden sublattices of Dedekind's and Birkhoff's theorems: a lattice is modular
+//! if, and only if, it has no sublattice isomorphic to the pentagon $N_5$, and distributive if, and only
+//! if, it has neither $N_5$ nor the diamond $M_3$. Both searches return a witness.
+//!
+//! A *complement* of x is an element y with $x \vee y = \hat 1$ and $x \wedge y = \hat 0$, and a lattice is
+//! *complemented* if every element has one. In a distributive lattice complements are unique, and the
+//! complemented distributive lattices are the *Boolean* ones, isomorphic to the subsets of a set; the
+//! diamond and the pentagon are complemented but not distributive, the pentagon's element 2 having the
+//! two complements 0 and 1.
+//!
+//! A lattice is *upper semimodular* if $a \wedge b \lessdot a$ implies $b \lessdot a \vee b$, and *lower
+//! semimodular* if, dually, $a \lessdot a \vee b$ implies $a \wedge b \lessdot b$; it is modular if, and
+//! only if, it is both. It is *supersolvable* if it has a maximal chain C, an *M-chain*, that generates a
+//! distributive sublattice together with any other chain. Then its characteristic polynomial factors
+//! into linear factors, as for the partition lattices and all modular lattices. By a theorem of McNamara
+//! and Thomas, a lattice is supersolvable if, and only if, it is graded and has a maximal chain of
+//! *left-modular* elements, those x with $(y \vee x) \wedge z = y \vee (x \wedge z)$ for all $y \le z$,
+//! which [Poset::is_supersolvable] looks for in $O(n^3)$ steps.
+//!
+//! The *atoms* are the elements covering the bottom element $\hat 0$, and the *coatoms* those covered by
+//! the top element $\hat 1$. A poset with a bottom is *atomic* if every other element is above an atom,
+//! which always holds for finite posets, so [Poset::is_atomic] amounts to having a bottom. A lattice is
+//! *atomistic* if every element is the join of the atoms below it, like the Boolean lattices, the
+//! partition lattices, and the lattices of flats of matroids, but not the chains of more than two
+//! elements.
+//!
+//! An element is *join-irreducible* if it covers exactly one element, so that it is not the join of the
+//! elements strictly below it. Birkhoff's representation theorem says that a finite distributive lattice
+//! $L$ is isomorphic to the lattice of down-sets of its join-irreducibles, $J(\mathrm{irr}(L))$, and a
+//! poset $P$ to the join-irreducibles of $J(P)$; [join_irreducibles] and [Poset::ideal_lattice] go
+//! back and forth.
+//!
+//! ```
+//! use fin_pos::lattice::{m3_sublattice, LatticeOps};
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // The diamond M3: a bottom, three atoms and a top.
+//! let p = PosetM::new_diamond();
+//! assert!(p.is_modular());
+//! assert!(!p.is_distributive());
+//! let ops = LatticeOps::new(&p).unwrap();
+//! assert_eq!(ops.join(0, 1), 4);
+//! assert_eq!(m3_sublattice(&ops), Some([3, 0, 1, 2, 4]));
+//!
+//! // The join-irreducibles of the subsets of {0, 1, 2} are the singletons.
+//! let b3 = PosetM::new_boolean(3);
+//! assert!(b3.join_irreducibles().is_antichain());
+//! ```
+
+use crate::bitset::{BitMatrix, BitRow};
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::interval::induced;
+use crate::posetm::PosetM;
+use crate::relation::{cover_matrix, strict_matrix};
+use crate::{AnElement, Elements, Poset};
+
+use std::collections::HashSet;
+
+/// The join and meet tables of a lattice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatticeOps {
+    join: Vec<Vec<AnElement>>,
+    meet: Vec<Vec<AnElement>>,
+}
+
+impl LatticeOps {
+    /// Tabulates the joins and meets of p, or returns None if some pair lacks one, i.e., if p is not a
+    /// lattice. The empty poset is (vacuously) a lattice.
+    pub fn new<P: Poset + ?Sized>(p: &P) -> Option<LatticeOps> {
+        LatticeOps::try_new(p).ok()
+    }
+
+    /// Like [LatticeOps::new], but names the first pair x, y, in increasing order with $x \le y$ as indices,
+    /// lacking a join or a meet in a [PosetError::NotALattice].
+    pub fn try_new<P: Poset + ?Sized>(p: &P) -> Result<LatticeOps, PosetError> {
+        let b = BitMatrix::new(p);
+        let n = b.n();
+        // The least upper bound is the one whose up-set contains all the upper bounds, and dually.
+        let least = |bounds: &BitRow, up: bool| {
+            let row = |z| if up { b.up_row(z) } else { b.down_row(z) };
+            bounds.iter_ones().find(|&z| bounds.is_subset(row(z)))
+        };
+        let mut join = vec![vec![0; n]; n];
+        let mut meet = vec![vec![0; n]; n];
+        for x in 0..n {
+            for y in x..n {
+                let mut upper = b.up_row(x).clone();
+                upper.and_assign(b.up_row(y));
+                let mut lower = b.down_row(x).clone();
+                lower.and_assign(b.down_row(y));
+                let missing = PosetError::NotALattice { x, y };
+                join[x][y] = least(&upper, true).ok_or(missing.clone())?;
+                meet[x][y] = least(&lower, false).ok_or(missing)?;
+                join[y][x] = join[x][y];
+                meet[y][x] = meet[x][y];
+            }
+        }
+        Ok(LatticeOps { join, meet })
+    }
+
+    /// The number of elements.
+    pub fn n(&self) -> usize {
+        self.join.len()
+    }
+
+    pub fn join(&self, x: AnElement, y: AnElement) -> AnElement {
+        self.join[x][y]
+    }
+
+    pub fn meet(&self, x: AnElement, y: AnElement) -> AnElement {
+        self.meet[x][y]
+    }
+
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.join[x][y] == y
+    }
+}
+
+/// Returns a sublattice isomorphic to the pentagon $N_5$, as `[bottom, a, b, c, top]` with $a < b$ and
+/// c incomparable to both, or None if the lattice is modular. Any $a < b$ and c with $a \wedge c = b
+/// \wedge c$ and $a \vee c = b \vee c$ span one.
+pub fn n5_sublattice(ops: &LatticeOps) -> Option<[AnElement; 5]> {
+    let n = ops.n();
+    for a in 0..n {
+        for b in (0..n).filter(|&b| b != a && ops.leq(a, b)) {
+            for c in 0..n {
+                if ops.meet(a, c) == ops.meet(b, c) && ops.join(a, c) == ops.join(b, c) {
+                    return Some([ops.meet(a, c), a, b, c, ops.join(a, c)]);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns a sublattice isomorphic to the diamond $M_3$, as `[bottom, x, y, z, top]` with x, y and z
+/// pairwise incomparable, or None if there is none. Any three distinct elements with the same pairwise
+/// meets and the same pairwise joins span one.
+pub fn m3_sublattice(ops: &LatticeOps) -> Option<[AnElement; 5]> {
+    let n = ops.n();
+    for x in 0..n {
+        for y in x + 1..n {
+            let (bot, top) = (ops.meet(x, y), ops.join(x, y));
+            if bot == x || bot == y {
+                continue;
+            }
+            for z in y + 1..n {
+                if [ops.meet(x, z), ops.meet(y, z)] == [bot; 2]
+                    && [ops.join(x, z), ops.join(y, z)] == [top; 2]
+                {
+                    return Some([bot, x, y, z, top]);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns true if p is a modular lattice, and false if it is not a lattice.
+pub fn is_modular<P: Poset + ?Sized>(p: &P) -> bool {
+    LatticeOps::new(p).is_some_and(|ops| n5_sublattice(&ops).is_none())
+}
+
+/// Returns true if p is a distributive lattice, and false if it is not a lattice.
+pub fn is_distributive<P: Poset + ?Sized>(p: &P) -> bool {
+    LatticeOps::new(p)
+        .is_some_and(|ops| n5_sublattice(&ops).is_none() && m3_sublattice(&ops).is_none())
+}
+
+/// Returns the complements of x in the lattice, and the empty set if p is not a lattice.
+pub fn complements<P: Poset + ?Sized>(p: &P, x: AnElement) -> HashSet<AnElement> {
+    match LatticeOps::new(p) {
+        Some(ops) => complements_in(&ops, x),
+        None => HashSet::new(),
+    }
+}
+
+fn complements_in(ops: &LatticeOps, x: AnElement) -> HashSet<AnElement> {
+    let n = ops.n();
+    // The join of all the elements is the top, and their meet the bottom.
+    let top = (0..n).fold(x, |t, y| ops.join(t, y));
+    let bot = (0..n).fold(x, |b, y| ops.meet(b, y));
+    (0..n)
+        .filter(|&y| ops.join(x, y) == top && ops.meet(x, y) == bot)
+        .collect()
+}
+
+/// Returns true if p is a lattice in which every element has a complement, and false if it is not a
+/// lattice.
+pub fn is_complemented<P: Poset + ?Sized>(p: &P) -> bool {
+    LatticeOps::new(p).is_some_and(|ops| (0..ops.n()).all(|x| !complements_in(&ops, x).is_empty()))
+}
+
+/// Returns true if p is a Boolean lattice, i.e., a complemented distributive lattice. The empty poset is
+/// not one, as the subsets of a set include the empty set.
+pub fn is_boolean<P: Poset + ?Sized>(p: &P) -> bool {
+    LatticeOps::new(p).is_some_and(|ops| {
+        ops.n() > 0
+            && (0..ops.n()).all(|x| !complements_in(&ops, x).is_empty())
+            && n5_sublattice(&ops).is_none()
+            && m3_sublattice(&ops).is_none()
+    })
+}
+
+/// Returns true if p is an upper semimodular lattice, and false if it is not a lattice.
+pub fn is_upper_semimodular<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some(ops) = LatticeOps::new(p) else {
+        return false;
+    };
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = ops.n();
+    (0..n).all(|a| (0..n).all(|b| !covers[ops.meet(a, b)][a] || covers[b][ops.join(a, b)]))
+}
+
+/// Returns true if p is a lower semimodular lattice, and false if it is not a lattice.
+pub fn is_lower_semimodular<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some(ops) = LatticeOps::new(p) else {
+        return false;
+    };
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = ops.n();
+    (0..n).all(|a| (0..n).all(|b| !covers[a][ops.join(a, b)] || covers[ops.meet(a, b)][b]))
+}
+
+/// Returns true if p is a supersolvable lattice, and false if it is not a lattice. The empty poset is not
+/// supersolvable, having no maximal chain from a bottom to a top.
+pub fn is_supersolvable<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some(ops) = LatticeOps::new(p) else {
+        return false;
+    };
+    let n = ops.n();
+    if n == 0 || !p.is_graded() {
+        return false;
+    }
+    let left_modular: Vec<bool> = (0..n)
+        .map(|x| {
+            (0..n).all(|y| {
+                (0..n)
+                    .filter(|&z| ops.leq(y, z))
+                    .all(|z| ops.meet(ops.join(y, x), z) == ops.join(y, ops.meet(x, z)))
+            })
+        })
+        .collect();
+    // Search for a path along the covers from the bottom to the top through left-modular elements.
+    let covers = cover_matrix(&strict_matrix(p));
+    let bot = (0..n).fold(0, |b, y| ops.meet(b, y));
+    let top = (0..n).fold(0, |t, y| ops.join(t, y));
+    let mut reached = vec![false; n];
+    reached[bot] = true;
+    let mut stack = vec![bot];
+    while let Some(x) = stack.pop() {
+        for y in (0..n).filter(|&y| covers[x][y] && left_modular[y]) {
+            if !reached[y] {
+                reached[y] = true;
+                stack.push(y);
+            }
+        }
+    }
+    reached[top]
+}
+
+/// Returns the elements of p covering its bottom element, and the empty set if it has none.
+pub fn atoms<P: Poset + ?Sized>(p: &P) -> Elements {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+    match (0..n).find(|&x| (0..n).all(|y| y == x || lt[x][y])) {
+        Some(bot) => (0..n).filter(|&y| covers[bot][y]).collect(),
+        None => Elements::new(),
+    }
+}
+
+/// The atoms of p, as stored in its [MetaData](crate::MetaData) if known.
+pub(crate) fn known_atoms<P: Poset + ?Sized>(p: &P) -> Elements {
+    p.md().atoms.clone().unwrap_or_else(|| atoms(p))
+}
+
+/// Returns the elements of p covered by its top element, and the empty set if it has none.
+pub fn coatoms<P: Poset + ?Sized>(p: &P) -> Elements {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+    match (0..n).find(|&x| (0..n).all(|y| y == x || lt[y][x])) {
+        Some(top) => (0..n).filter(|&y| covers[y][top]).collect(),
+        None => Elements::new(),
+    }
+}
+
+/// Returns true if p has a bottom element and every other element is above one of its atoms.
+pub fn is_atomic<P: Poset + ?Sized>(p: &P) -> bool {
+    let n = p.md().n;
+    let Some(bot) = (0..n).find(|&x| (0..n).all(|y| p.leq(x, y))) else {
+        return false;
+    };
+    let atoms = known_atoms(p);
+    (0..n).all(|x| x == bot || atoms.iter().any(|&a| p.leq(a, x)))
+}
+
+/// Returns true if p is a lattice in which every element is the join of the atoms below it, and false if
+/// it is not a lattice. The empty poset is not atomistic, having no bottom.
+pub fn is_atomistic<P: Poset + ?Sized>(p: &P) -> bool {
+    let Some(ops) = LatticeOps::new(p) else {
+        return false;
+    };
+    let n = ops.n();
+    if n == 0 {
+        return false;
+    }
+    let bot = (0..n).fold(0, |b, y| ops.meet(b, y));
+    let atoms = known_atoms(p);
+    (0..n).all(|x| {
+        atoms
+            .iter()
+            .filter(|&&a| ops.leq(a, x))
+            .fold(bot, |j, &a| ops.join(j, a))
+            == x
+    })
+}
+
+/// Returns the subposet of p induced on its join-irreducible elements, those covering exactly one
+/// element, with the map back to p (see [induced]). It need not be a lattice for the result to make
+/// sense, but Birkhoff's theorem only applies to distributive lattices.
+pub fn join_irreducibles<P, Q>(p: &P) -> (Q, Vec<AnElement>)
+where
+    P: Poset + ?Sized,
+    Q: FromPoset<PosetM>,
+{
+    let covers = cover_matrix(&strict_matrix(p));
+    let irreducible: Vec<AnElement> = p
+        .elements()
+        .filter(|&x| p.elements().filter(|&y| covers[y][x]).count() == 1)
+        .collect();
+    induced(p, &irreducible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    /// The identities checked on every triple.
+    fn brute_force(ops: &LatticeOps) -> (bool, bool) {
+        let n = ops.n();
+        let triples =
+            || (0..n).flat_map(move |x| (0..n).flat_map(move |y| (0..n).map(move |z| (x, y, z))));
+        let modular = triples().all(|(x, y, z)| {
+            !ops.leq(x, z) || ops.join(x, ops.meet(y, z)) == ops.meet(ops.join(x, y), z)
+        });
+        let distributive = triples().all(|(x, y, z)| {
+            ops.meet(x, ops.join(y, z)) == ops.join(ops.meet(x, y), ops.meet(x, z))
+        });
+        (modular, distributive)
+    }
+
+    #[test]
+    fn test_small_lattices() {
+        let p = PosetM::new_pentagon();
+        assert!(p.is_lattice());
+        assert!(!p.is_modular());
+        let ops = LatticeOps::new(&p).unwrap();
+        assert_eq!(n5_sublattice(&ops), Some([3, 0, 1, 2, 4]));
+        assert_eq!(m3_sublattice(&ops), None);
+        for k in 0..4 {
+            assert!(boolean_lattice(k).is_distributive());
+        }
+        assert!(PosetM::new_chain(5).is_distributive());
+        assert!(!PosetM::new_antichain(2).is_lattice());
+        assert_eq!(
+            LatticeOps::try_new(&PosetM::new_corolla(2)),
+            Err(PosetError::NotALattice { x: 0, y: 1 })
+        );
+        assert!(!PosetM::new_corolla(2).is_modular());
+    }
+
+    #[test]
+    fn test_against_identities() {
+        for p in corpus() {
+            let Some(ops) = LatticeOps::new(&p) else {
+                assert!(!p.is_modular() && !p.is_distributive());
+                continue;
+            };
+            assert_eq!((p.is_modular(), p.is_distributive()), brute_force(&ops));
+            for x in 0..ops.n() {
+                for y in 0..ops.n() {
+                    let (j, m) = (ops.join(x, y), ops.meet(x, y));
+                    assert!(p.leq(x, j) && p.leq(y, j) && p.leq(m, x) && p.leq(m, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_complements() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 16) {
+            if !p.is_lattice() {
+                assert!(p.elements().all(|x| p.complements(x).is_empty()));
+                assert!(!p.is_complemented() && !p.is_boolean());
+                continue;
+            }
+            for x in p.elements() {
+                let c = p.complements(x);
+                assert!(c.iter().all(|&y| p.complements(y).contains(&x)));
+                assert!(!p.is_distributive() || c.len() <= 1);
+            }
+            let k = p.md.n.trailing_zeros();
+            let boolean = p.md.n.is_power_of_two() && p.is_isomorphic(&boolean_lattice(k));
+            assert_eq!(p.is_boolean(), boolean);
+        }
+        let b = boolean_lattice(3);
+        assert!((0..8).all(|x| b.complements(x) == HashSet::from([x ^ 7])));
+        assert!(!PosetM::new_chain(3).is_complemented());
+        assert!(PosetM::new_chain(2).is_boolean());
+        assert!(PosetM::new_diamond().is_complemented() && !PosetM::new_diamond().is_boolean());
+        let p = PosetM::new_pentagon();
+        assert!(p.is_complemented() && !p.is_boolean());
+        assert_eq!(p.complements(2), HashSet::from([0, 1]));
+        assert!(PosetM::new_chain(0).is_complemented() && !PosetM::new_chain(0).is_boolean());
+    }
+
+    /// Returns true if some maximal chain generates a distributive sublattice together with every maximal
+    /// chain, which is the definition of supersolvability.
+    fn has_m_chain(p: &PosetM, ops: &LatticeOps) -> bool {
+        let generated = |seed: Vec<AnElement>| {
+            let mut s = seed;
+            loop {
+                let k = s.len();
+                for i in 0..k {
+                    for j in 0..k {
+                        for z in [ops.join(s[i], s[j]), ops.meet(s[i], s[j])] {
+                            if !s.contains(&z) {
+                                s.push(z);
+                            }
+                        }
+                    }
+                }
+                if s.len() == k {
+                    return s;
+                }
+            }
+        };
+        let chains = p.maximal_chains();
+        chains.iter().any(|c| {
+            chains.iter().all(|d| {
+                let (q, _): (PosetM, _) = induced(p, &generated([c.clone(), d.clone()].concat()));
+                q.is_distributive()
+            })
+        })
+    }
+
+    #[test]
+    fn test_semimodularity() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 12) {
+            let (upper, lower) = (p.is_upper_semimodular(), p.is_lower_semimodular());
+            assert_eq!(lower, p.op().is_upper_semimodular());
+            assert_eq!(p.is_modular(), upper && lower);
+            let Some(ops) = LatticeOps::new(&p) else {
+                assert!(!upper && !lower && !p.is_supersolvable());
+                continue;
+            };
+            if p.md.n > 0 {
+                assert_eq!(p.is_supersolvable(), has_m_chain(&p, &ops));
+                assert!(!p.is_modular() || p.is_supersolvable());
+            }
+        }
+        let pi4 = crate::families::new_partition_lattice::<PosetM>(4);
+        let pi4 = pi4.poset();
+        assert!(pi4.is_upper_semimodular() && !pi4.is_lower_semimodular());
+        assert!(pi4.is_supersolvable());
+        assert!(PosetM::new_diamond().is_supersolvable());
+        let p = PosetM::new_pentagon();
+        assert!(!p.is_upper_semimodular() && !p.is_lower_semimodular() && !p.is_supersolvable());
+        assert!(!PosetM::new_chain(0).is_supersolvable());
+    }
+
+    #[test]
+    fn test_atoms() {
+        for mut p in corpus() {
+            let atoms = p.atoms().clone();
+            let bot = p.elements().find(|&x| p.elements().all(|y| p.leq(x, y)));
+            match bot {
+                Some(b) => assert_eq!(atoms, p.upper_covers(b)),
+                None => assert!(atoms.is_empty()),
+            }
+            assert_eq!(p.is_atomic(), bot.is_some());
+            assert_eq!(*p.op().coatoms(), atoms);
+            // A lattice is atomistic if, and only if, its join-irreducibles are its atoms.
+            if p.md.n > 0 && p.is_lattice() {
+                let (_, irreducible): (PosetM, _) = join_irreducibles(&p);
+                let irreducible: Elements = irreducible.into_iter().collect();
+                assert_eq!(p.is_atomistic(), irreducible == atoms);
+            }
+        }
+        let mut b = boolean_lattice(3);
+        assert_eq!(*b.atoms(), [1, 2, 4].into());
+        assert_eq!(*b.coatoms(), [3, 5, 6].into());
+        assert!(b.is_atomistic());
+        assert!(PosetM::new_diamond().is_atomistic());
+        assert!(!PosetM::new_pentagon().is_atomistic());
+        assert!(!PosetM::new_chain(3).is_atomistic() && PosetM::new_chain(3).is_atomic());
+        let pi4 = crate::families::new_partition_lattice::<PosetM>(4);
+        assert!(pi4.poset().is_atomistic());
+        assert_eq!(atoms(pi4.poset()).len(), 6);
+        assert!(!PosetM::new_chain(0).is_atomic() && !PosetM::new_chain(0).is_atomistic());
+        let mut p = PosetM::new_corolla(2).op();
+        p.find_atoms();
+        p.find_coatoms();
+        assert_eq!(p.md.atoms, Some(Elements::new()));
+        assert_eq!(*p.coatoms(), [0, 1].into());
+    }
+
+    #[test]
+    fn test_birkhoff_round_trip() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 9) {
+            let (j, ideals) = p.ideal_lattice();
+            if j.md.n > 16 {
+                continue;
+            }
+            let (irr, back): (PosetM, _) = join_irreducibles(&j);
+            assert!(irr.is_isomorphic(&p));
+            // The join-irreducible down-sets are the principal ones.
+            for x in back {
+                let d = &ideals[x];
+                assert!(p.elements().any(|y| *d == p.down_set(y)));
+            }
+            let (l, _) = irr.ideal_lattice();
+            assert!(l.is_isomorphic(&j));
+        }
+        for p in corpus()
+            .into_iter()
+            .filter(|p| p.md.n > 0 && p.is_distributive())
+        {
+            let (l, _) = p.join_irreducibles().ideal_lattice();
+            assert!(l.is_isomorphic(&p));
+        }
+        let b = boolean_lattice(4);
+        assert!(b.join_irreducibles().is_antichain());
+        assert_eq!(b.join_irreducibles().md.n, 4);
+        assert!(PosetM::new_chain(5)
+            .join_irreducibles()
+            .same_order(&PosetM::new_chain(4)));
+    }
+}
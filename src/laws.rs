@@ -0,0 +1,495 @@
+//! Checkers for the algebraic laws that poset constructions are expected to satisfy.
+//!
+//! Each checker returns `Ok(())` when the law holds for the given input, and otherwise a [LawViolation]
+//! naming the law and carrying the offending posets in a compact text form. The checkers only use the
+//! [Poset] trait, so implementors of the trait outside this crate can run them against their own types.
+//!
+//! The laws are stated for the order relation only: two posets are considered equal if they have the same
+//! elements and the same order ([Poset::same_order]), and isomorphic if they agree up to relabeling
+//! ([Poset::is_isomorphic]). Cached [MetaData](crate::MetaData) is not compared.
+//!
+//! Laws currently checked:
+//! - [check_op_involution]: $(P^{op})^{op} = P$.
+//! - [check_op_reverses_order]: $x \le y$ in $P^{op}$ if, and only if, $y \le x$ in $P$.
+//! - [check_product_order]: $(a, b) \le (c, d)$ in $P \times Q$ if, and only if, $a \le c$ in $P$ and
+//!   $b \le d$ in $Q$.
+//! - [check_product_commutes]: $P \times Q$ is isomorphic to $Q \times P$.
+//! - [check_coproduct_order]: $P + Q$ orders each summand as before and relates no element of $P$ to one
+//!   of $Q$.
+//! - [check_ordinal_sum_order]: $P \oplus Q$ orders each summand as before and places every element of $P$
+//!   below every element of $Q$.
+//! - [check_ordinal_sum_associative]: $(P \oplus Q) \oplus R = P \oplus (Q \oplus R)$.
+//! - [check_sub_composition]: taking the subposet on $T$ of the subposet on $S$ gives the subposet on
+//!   $S \cap T$, ordered as in $P$.
+//! - [check_op_coproduct]: $(P + Q)^{op} = P^{op} + Q^{op}$.
+//! - [check_op_product]: $(P \times Q)^{op} = P^{op} \times Q^{op}$.
+//! - [check_product_distributes]: $P \times (Q + R)$ is isomorphic to $P \times Q + P \times R$.
+//! - [check_ideal_lattice_op]: the lattice of down-sets of $P^{op}$ is the dual of that of $P$, by taking
+//!   complements.
+//!
+//! ```
+//! use fin_pos::laws;
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetG::new_corolla(3);
+//! assert!(laws::check_op_involution(&p).is_ok());
+//! ```
+
+use crate::relation::{cover_matrix, strict_matrix};
+use crate::{AnElement, Poset};
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// A failure of one of the laws, together with the posets witnessing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LawViolation {
+    /// The name of the law (the name of the checker without the `check_` prefix).
+    pub law: &'static str,
+    /// The posets involved, in the compact form produced by [compact].
+    pub counterexample: Vec<String>,
+}
+
+impl fmt::Display for LawViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "law {} violated by {}",
+            self.law,
+            self.counterexample.join(" and ")
+        )
+    }
+}
+
+impl Error for LawViolation {}
+
+/// Encodes p as the number of elements followed by its cover pairs, e.g., `3:0<1,0<2` for the vee.
+pub fn compact<P: Poset + ?Sized>(p: &P) -> String {
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = covers.len();
+    let pairs: Vec<String> = (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| covers[x][y])
+        .map(|(x, y)| format!("{}<{}", x, y))
+        .collect();
+    format!("{}:{}", n, pairs.join(","))
+}
+
+fn violation(law: &'static str, posets: &[&dyn Fn() -> String]) -> LawViolation {
+    LawViolation {
+        law,
+        counterexample: posets.iter().map(|f| f()).collect(),
+    }
+}
+
+/// Checks that taking the opposite twice gives back the original order.
+pub fn check_op_involution<P: Poset>(p: &P) -> Result<(), LawViolation> {
+    if p.op().op().same_order(p) {
+        Ok(())
+    } else {
+        Err(violation("op_involution", &[&|| compact(p)]))
+    }
+}
+
+/// Checks that the opposite reverses the order while keeping the elements.
+pub fn check_op_reverses_order<P: Poset>(p: &P) -> Result<(), LawViolation> {
+    let q = p.op();
+    let holds = q.elements().eq(p.elements())
+        && p.elements()
+            .all(|x| p.elements().all(|y| q.leq(x, y) == p.leq(y, x)));
+    if holds {
+        Ok(())
+    } else {
+        Err(violation("op_reverses_order", &[&|| compact(p)]))
+    }
+}
+
+/// Checks that the product is ordered componentwise, on the pairs numbered as in [Poset::product].
+pub fn check_product_order<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    let pq = p.product(q);
+    let m = q.elements().count();
+    let n = p.elements().count() * m;
+    let holds = pq.elements().eq(0..n)
+        && (0..n)
+            .all(|x| (0..n).all(|y| pq.leq(x, y) == (p.leq(x / m, y / m) && q.leq(x % m, y % m))));
+    if holds {
+        Ok(())
+    } else {
+        Err(violation(
+            "product_order",
+            &[&|| compact(p), &|| compact(q)],
+        ))
+    }
+}
+
+/// Checks that swapping the factors of a product gives an isomorphic poset.
+pub fn check_product_commutes<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    if p.product(q).is_isomorphic(&q.product(p)) {
+        Ok(())
+    } else {
+        Err(violation(
+            "product_commutes",
+            &[&|| compact(p), &|| compact(q)],
+        ))
+    }
+}
+
+/// Checks that the coproduct is the disjoint union, with the elements of q shifted as in [Poset::coproduct].
+pub fn check_coproduct_order<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    if sum_ordered(p, q, &p.coproduct(q), false) {
+        Ok(())
+    } else {
+        Err(violation(
+            "coproduct_order",
+            &[&|| compact(p), &|| compact(q)],
+        ))
+    }
+}
+
+/// Checks that the ordinal sum stacks q above p, with the elements of q shifted as in [Poset::coproduct].
+pub fn check_ordinal_sum_order<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    if sum_ordered(p, q, &p.ordinal_sum(q), true) {
+        Ok(())
+    } else {
+        Err(violation(
+            "ordinal_sum_order",
+            &[&|| compact(p), &|| compact(q)],
+        ))
+    }
+}
+
+/// Checks that the two ways of stacking three posets give the same order.
+pub fn check_ordinal_sum_associative<P: Poset>(p: &P, q: &P, r: &P) -> Result<(), LawViolation> {
+    if p.ordinal_sum(q)
+        .ordinal_sum(r)
+        .same_order(&p.ordinal_sum(&q.ordinal_sum(r)))
+    {
+        Ok(())
+    } else {
+        Err(violation(
+            "ordinal_sum_associative",
+            &[&|| compact(p), &|| compact(q), &|| compact(r)],
+        ))
+    }
+}
+
+/// Checks that restricting p to s and then to t, given as elements of p, agrees with restricting p to
+/// their intersection at once, in the order and in the maps back to p.
+pub fn check_sub_composition<P: Poset>(
+    p: &P,
+    s: &HashSet<AnElement>,
+    t: &HashSet<AnElement>,
+) -> Result<(), LawViolation> {
+    let (ps, back_s) = p.sub(s);
+    let t_in_s: HashSet<AnElement> = ps.elements().filter(|x| t.contains(&back_s[*x])).collect();
+    let (pst, back_st) = ps.sub(&t_in_s);
+    let (direct, back) = p.sub(&s.intersection(t).copied().collect());
+    let holds = pst.same_order(&direct)
+        && back_st.iter().map(|&x| back_s[x]).eq(back.iter().copied())
+        && direct.elements().all(|x| {
+            direct
+                .elements()
+                .all(|y| direct.leq(x, y) == p.leq(back[x], back[y]))
+        });
+    if holds {
+        Ok(())
+    } else {
+        let (s, t) = (sorted(s), sorted(t));
+        Err(violation(
+            "sub_composition",
+            &[&|| compact(p), &|| s.clone(), &|| t.clone()],
+        ))
+    }
+}
+
+/// Checks that the opposite of a coproduct is the coproduct of the opposites.
+pub fn check_op_coproduct<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    if p.coproduct(q).op().same_order(&p.op().coproduct(&q.op())) {
+        Ok(())
+    } else {
+        Err(violation("op_coproduct", &[&|| compact(p), &|| compact(q)]))
+    }
+}
+
+/// Checks that the opposite of a product is the product of the opposites.
+pub fn check_op_product<P: Poset>(p: &P, q: &P) -> Result<(), LawViolation> {
+    if p.product(q).op().same_order(&p.op().product(&q.op())) {
+        Ok(())
+    } else {
+        Err(violation("op_product", &[&|| compact(p), &|| compact(q)]))
+    }
+}
+
+/// Checks that $P \times (Q + R)$ and $P \times Q + P \times R$ have the same order once the pair $(a, b)$
+/// of the former is sent to $(a, b)$ in the first summand of the latter if $b$ is in $Q$, and to
+/// $(a, b - |Q|)$ in the second summand if it is in $R$.
+pub fn check_product_distributes<P: Poset>(p: &P, q: &P, r: &P) -> Result<(), LawViolation> {
+    let left = p.product(&q.coproduct(r));
+    let right = p.product(q).coproduct(&p.product(r));
+    let (np, nq, nr) = (
+        p.elements().count(),
+        q.elements().count(),
+        r.elements().count(),
+    );
+    let to_right = |x: AnElement| {
+        let (a, b) = (x / (nq + nr), x % (nq + nr));
+        if b < nq {
+            a * nq + b
+        } else {
+            np * nq + a * nr + b - nq
+        }
+    };
+    let n = np * (nq + nr);
+    let holds = left.elements().eq(0..n)
+        && right.elements().eq(0..n)
+        && (0..n).all(|x| (0..n).all(|y| left.leq(x, y) == right.leq(to_right(x), to_right(y))));
+    if holds {
+        Ok(())
+    } else {
+        Err(violation(
+            "product_distributes",
+            &[&|| compact(p), &|| compact(q), &|| compact(r)],
+        ))
+    }
+}
+
+/// Checks that taking complements maps the down-sets of $P^{op}$ onto the down-sets of $P$ and reverses
+/// inclusion, as numbered in the two lattices returned by [Poset::ideal_lattice].
+pub fn check_ideal_lattice_op<P: Poset>(p: &P) -> Result<(), LawViolation> {
+    let (j, downsets) = p.ideal_lattice();
+    let (j_op, downsets_op) = p.op().ideal_lattice();
+    let index: HashMap<Vec<AnElement>, AnElement> = downsets
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (sorted_vec(d), i))
+        .collect();
+    let complements: Option<Vec<AnElement>> = downsets_op
+        .iter()
+        .map(|u| {
+            let complement: Vec<AnElement> = p.elements().filter(|x| !u.contains(x)).collect();
+            index.get(&complement).copied()
+        })
+        .collect();
+    let holds = downsets.len() == downsets_op.len()
+        && complements.is_some_and(|c| {
+            j_op.elements()
+                .all(|x| j_op.elements().all(|y| j_op.leq(x, y) == j.leq(c[y], c[x])))
+        });
+    if holds {
+        Ok(())
+    } else {
+        Err(violation("ideal_lattice_op", &[&|| compact(p)]))
+    }
+}
+
+fn sorted_vec(s: &HashSet<AnElement>) -> Vec<AnElement> {
+    let mut s: Vec<AnElement> = s.iter().copied().collect();
+    s.sort_unstable();
+    s
+}
+
+fn sorted(s: &HashSet<AnElement>) -> String {
+    format!("{:?}", sorted_vec(s))
+}
+
+/// Whether sum orders p and the shifted q as they are, with p below q if stacked and unrelated to q if not.
+fn sum_ordered<P: Poset>(p: &P, q: &P, sum: &P, stacked: bool) -> bool {
+    let n = p.elements().count();
+    let m = n + q.elements().count();
+    sum.elements().eq(0..m)
+        && (0..m).all(|x| {
+            (0..m).all(|y| {
+                sum.leq(x, y)
+                    == match (x < n, y < n) {
+                        (true, true) => p.leq(x, y),
+                        (false, false) => q.leq(x - n, y - n),
+                        (true, false) => stacked,
+                        (false, true) => false,
+                    }
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus, find_all, for_each_representation};
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_compact() {
+        let mut vee = PosetG::new_antichain(2);
+        vee.adjoin_bot();
+        assert_eq!(compact(&vee), "3:2<0,2<1");
+        assert_eq!(compact(&PosetG::new_antichain(0)), "0:");
+    }
+
+    #[test]
+    fn test_op_laws_on_corpus() {
+        for p in corpus() {
+            for_each_representation(&[&p], |ps| {
+                assert_eq!(check_op_involution(&ps[0]), Ok(()));
+                assert_eq!(check_op_reverses_order(&ps[0]), Ok(()));
+            });
+        }
+    }
+
+    #[test]
+    fn test_product_laws() {
+        let square = PosetH::new_chain(2).product(&PosetH::new_chain(2));
+        assert!(square.same_order(&boolean_lattice(2)));
+        assert_eq!(square.cover_set(0).as_slice(), &[1, 2]);
+        let small: Vec<_> = corpus().into_iter().filter(|p| p.md.n <= 4).collect();
+        for p in &small {
+            for q in &small {
+                for_each_representation(&[p, q], |ps| {
+                    assert_eq!(check_product_order(&ps[0], &ps[1]), Ok(()));
+                });
+            }
+        }
+        for p in &small {
+            for q in small.iter().filter(|q| p.md.n * q.md.n <= 6) {
+                assert_eq!(check_product_commutes(p, q), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sub_laws() {
+        for p in corpus() {
+            let n = p.md.n;
+            let evens: HashSet<AnElement> = (0..n).step_by(2).collect();
+            let low: HashSet<AnElement> = (0..n / 2 + 1).filter(|&x| x < n).collect();
+            let all: HashSet<AnElement> = (0..n).collect();
+            for (s, t) in [
+                (&evens, &low),
+                (&low, &evens),
+                (&all, &evens),
+                (&evens, &all),
+            ] {
+                for_each_representation(&[&p], |ps| {
+                    assert_eq!(check_sub_composition(&ps[0], s, t), Ok(()));
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_coproduct_laws() {
+        let small: Vec<_> = corpus().into_iter().filter(|p| p.md.n <= 5).collect();
+        let empty = PosetM::new_antichain(0);
+        for p in small.iter().chain([&empty]) {
+            for q in small.iter().chain([&empty]) {
+                for_each_representation(&[p, q], |ps| {
+                    assert_eq!(check_coproduct_order(&ps[0], &ps[1]), Ok(()));
+                    let mut pq = ps[0].coproduct(&ps[1]);
+                    let merged = pq.md().clone();
+                    find_all(&mut pq);
+                    assert_eq!(merged, *pq.md());
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_ordinal_sum_laws() {
+        let small: Vec<_> = corpus().into_iter().filter(|p| p.md.n <= 4).collect();
+        let empty = PosetM::new_antichain(0);
+        for p in small.iter().chain([&empty]) {
+            for q in small.iter().chain([&empty]) {
+                for_each_representation(&[p, q], |ps| {
+                    assert_eq!(check_ordinal_sum_order(&ps[0], &ps[1]), Ok(()));
+                    let mut pq = ps[0].ordinal_sum(&ps[1]);
+                    let merged = pq.md().clone();
+                    find_all(&mut pq);
+                    assert_eq!(merged, *pq.md());
+                });
+            }
+        }
+        for_each_representation(&[&small[1], &small[2], &small[3]], |ps| {
+            assert_eq!(
+                check_ordinal_sum_associative(&ps[0], &ps[1], &ps[2]),
+                Ok(())
+            );
+        });
+    }
+
+    #[test]
+    fn test_ordinal_sum_shapes() {
+        let fence = PosetH::new_antichain(2)
+            .ordinal_sum(&PosetH::new_antichain(2))
+            .ordinal_sum(&PosetH::new_antichain(1));
+        assert_eq!(compact(&fence), "5:0<2,0<3,1<2,1<3,2<4,3<4");
+        assert_eq!(fence.cover_set(0).as_slice(), &[2, 3]);
+        let chain = PosetG::new_chain(2).ordinal_sum(&PosetG::new_chain(3));
+        assert!(chain.same_order(&PosetG::new_chain(5)));
+        let diamond = PosetM::new_chain(1)
+            .ordinal_sum(&PosetM::new_antichain(2))
+            .ordinal_sum(&PosetM::new_chain(1));
+        assert!(diamond.is_isomorphic(&boolean_lattice(2)));
+    }
+
+    #[test]
+    fn test_coproduct_keeps_weights_and_handles() {
+        let mut p = PosetH::new_chain(2);
+        p.set_cover_weight(0, 1, 2.5).unwrap();
+        let a = p.pin(1);
+        let mut q = PosetH::new_chain(3);
+        q.set_cover_weight(1, 2, 0.5).unwrap();
+        let pq = p.coproduct(&q);
+        assert_eq!(pq.cover_weight(0, 1), Some(2.5));
+        assert_eq!(pq.cover_weight(3, 4), Some(0.5));
+        assert_eq!(pq.cover_weight(2, 3), Some(1.0));
+        assert_eq!(pq.resolve(&a), Some(1));
+        assert!(pq.incomparable(1, 2));
+    }
+
+    #[test]
+    fn test_op_construction_laws() {
+        let small: Vec<_> = corpus().into_iter().filter(|p| p.md.n <= 4).collect();
+        let empty = PosetM::new_antichain(0);
+        for p in small.iter().chain([&empty]) {
+            for q in small.iter().chain([&empty]) {
+                for_each_representation(&[p, q], |ps| {
+                    assert_eq!(check_op_coproduct(&ps[0], &ps[1]), Ok(()));
+                    assert_eq!(check_op_product(&ps[0], &ps[1]), Ok(()));
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_product_distributes() {
+        let small: Vec<_> = corpus().into_iter().filter(|p| p.md.n <= 3).collect();
+        let empty = PosetM::new_antichain(0);
+        for p in small.iter().chain([&empty]) {
+            for q in small.iter().chain([&empty]) {
+                for r in small.iter().chain([&empty]) {
+                    assert_eq!(check_product_distributes(p, q, r), Ok(()));
+                }
+            }
+        }
+        for_each_representation(&[&small[1], &small[2], &small[3]], |ps| {
+            assert_eq!(check_product_distributes(&ps[0], &ps[1], &ps[2]), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_ideal_lattice_op() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 10) {
+            for_each_representation(&[&p], |ps| {
+                assert_eq!(check_ideal_lattice_op(&ps[0]), Ok(()));
+            });
+        }
+    }
+
+    #[test]
+    fn test_violation_report() {
+        let v = violation("op_involution", &[&|| "2:0<1".to_string()]);
+        assert_eq!(v.to_string(), "law op_involution violated by 2:0<1");
+    }
+}
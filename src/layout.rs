@@ -0,0 +1,397 @@
+//! Coordinates for drawing the Hasse diagram of a poset, independent of any output format.
+//!
+//! Each element is placed on a horizontal layer determined by its depth (or coheight), so that every cover
+//! edge points upwards; in a graded poset, the depth is the rank. Within the layers, the elements are
+//! ordered by a few sweeps up and down the layers, each moving every element to the average (the
+//! barycenter heuristic) or the median (the median heuristic) horizontal position of its covering
+//! neighbours on the previously processed side, which tends to reduce edge crossings. Ties are broken by a
+//! pseudo-random key derived from [LayoutOptions::seed], so the layout is deterministic.
+//!
+//! [hasse_layout] collects the coordinates together with the edges and the layers in a [Layout], which is
+//! what a drawing needs, e.g., the text diagrams of [ascii](crate::ascii).
+//!
+//! ```
+//! use fin_pos::layout::LayoutOptions;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_chain(3);
+//! let coords = p.layout(&LayoutOptions::default());
+//! assert_eq!(coords, vec![(0.0, 0.0), (0.0, 1.0), (0.0, 2.0)]);
+//! assert_eq!(p.layout_edges(), vec![(0, 1), (1, 2)]);
+//!
+//! let drawing = p.hasse_layout(&LayoutOptions::default());
+//! assert_eq!(drawing.layers, vec![vec![0], vec![1], vec![2]]);
+//! assert_eq!(drawing.crossings(), 0);
+//! ```
+
+use crate::relation::{colevels, cover_matrix, levels, strict_matrix};
+use crate::rng::mix;
+use crate::{AnElement, Poset};
+
+use std::cmp::Ordering;
+
+/// Which quantity determines the layer (the y-coordinate) of an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layering {
+    /// The number of elements in a longest chain below the element. Minimal elements are on layer 0.
+    Depth,
+    /// The height of the poset minus one minus the number of elements in a longest chain above the
+    /// element. Maximal elements are on the top layer.
+    Coheight,
+}
+
+/// How a sweep orders the elements of a layer, by the horizontal positions of their neighbours on the
+/// previously processed side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// The average of the positions.
+    Barycenter,
+    /// The median of the positions, the average of the two middle ones for an even number.
+    Median,
+}
+
+/// Parameters for [Poset::layout](crate::Poset::layout).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutOptions {
+    /// Vertical distance between consecutive layers.
+    pub layer_spacing: f64,
+    /// Horizontal distance between consecutive elements of a layer.
+    pub node_spacing: f64,
+    /// How the layers are determined.
+    pub layering: Layering,
+    /// How the sweeps order the layers.
+    pub heuristic: Heuristic,
+    /// Number of up-and-down sweeps.
+    pub iterations: usize,
+    /// Seed for breaking ties between elements with equal barycenters.
+    pub seed: u64,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            layer_spacing: 1.0,
+            node_spacing: 1.0,
+            layering: Layering::Depth,
+            heuristic: Heuristic::Barycenter,
+            iterations: 4,
+            seed: 0,
+        }
+    }
+}
+
+/// A drawing of the Hasse diagram of a poset, as computed by [hasse_layout].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// Entry x is the position of element x.
+    pub coords: Vec<(f64, f64)>,
+    /// The cover pairs, as by [layout_edges].
+    pub edges: Vec<(AnElement, AnElement)>,
+    /// The elements of each layer, from the bottom up, each from left to right.
+    pub layers: Vec<Vec<AnElement>>,
+}
+
+impl Layout {
+    /// The smallest and the largest coordinates, as ((left, bottom), (right, top)), or None if there are no
+    /// elements.
+    pub fn bounding_box(&self) -> Option<((f64, f64), (f64, f64))> {
+        let first = *self.coords.first()?;
+        Some(
+            self.coords
+                .iter()
+                .fold((first, first), |(lo, hi), &(x, y)| {
+                    ((lo.0.min(x), lo.1.min(y)), (hi.0.max(x), hi.1.max(y)))
+                }),
+        )
+    }
+
+    /// The number of pairs of edges, drawn as straight segments, that cross away from their ends.
+    pub fn crossings(&self) -> usize {
+        let side = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| {
+            let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+            cross.partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+        };
+        let crossing = |&(a, b): &(AnElement, AnElement), &(c, d): &(AnElement, AnElement)| {
+            let (a, b, c, d) = (
+                self.coords[a],
+                self.coords[b],
+                self.coords[c],
+                self.coords[d],
+            );
+            let apart =
+                |s: Ordering, t: Ordering| s != Ordering::Equal && t != Ordering::Equal && s != t;
+            apart(side(a, b, c), side(a, b, d)) && apart(side(c, d, a), side(c, d, b))
+        };
+        let edges = &self.edges;
+        (0..edges.len())
+            .map(|i| {
+                edges[i + 1..]
+                    .iter()
+                    .filter(|e| crossing(&edges[i], e))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+/// Computes the coordinates of the elements of p. Entry i of the result is the position of element i.
+pub fn layout<P: Poset + ?Sized>(p: &P, opts: &LayoutOptions) -> Vec<(f64, f64)> {
+    hasse_layout(p, opts).coords
+}
+
+/// Computes the coordinates of the elements of p, as by [layout], together with the edges and the layers.
+pub fn hasse_layout<P: Poset + ?Sized>(p: &P, opts: &LayoutOptions) -> Layout {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    let covers = cover_matrix(&lt);
+    let layer_of = match opts.layering {
+        Layering::Depth => levels(&lt),
+        Layering::Coheight => {
+            let colevel = colevels(&lt);
+            let top = colevel.iter().max().copied().unwrap_or(0);
+            colevel.into_iter().map(|c| top - c).collect()
+        }
+    };
+    let layer_count = layer_of.iter().max().map_or(0, |l| l + 1);
+
+    let tie: Vec<u64> = (0..n).map(|x| mix(opts.seed ^ x as u64)).collect();
+    let mut layers: Vec<Vec<usize>> = vec![vec![]; layer_count];
+    for x in 0..n {
+        layers[layer_of[x]].push(x);
+    }
+    for layer in layers.iter_mut() {
+        layer.sort_by_key(|&x| tie[x]);
+    }
+
+    // x-position of each element, centered around 0 in its layer.
+    let mut pos = vec![0.0; n];
+    let place = |layers: &Vec<Vec<usize>>, pos: &mut Vec<f64>| {
+        for layer in layers {
+            let mid = (layer.len() as f64 - 1.0) / 2.0;
+            for (k, &x) in layer.iter().enumerate() {
+                pos[x] = k as f64 - mid;
+            }
+        }
+    };
+    place(&layers, &mut pos);
+
+    let sweep = |l: usize, below: bool, layers: &mut Vec<Vec<usize>>, pos: &mut Vec<f64>| {
+        let mut bary: Vec<(usize, f64)> = layers[l]
+            .iter()
+            .map(|&x| {
+                let mut nbrs: Vec<f64> = (0..n)
+                    .filter(|&y| if below { covers[y][x] } else { covers[x][y] })
+                    .map(|y| pos[y])
+                    .collect();
+                let k = nbrs.len();
+                if k == 0 {
+                    return (x, pos[x]);
+                }
+                match opts.heuristic {
+                    Heuristic::Barycenter => (x, nbrs.iter().sum::<f64>() / k as f64),
+                    Heuristic::Median => {
+                        nbrs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                        (x, (nbrs[(k - 1) / 2] + nbrs[k / 2]) / 2.0)
+                    }
+                }
+            })
+            .collect();
+        bary.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(Ordering::Equal)
+                .then(tie[a.0].cmp(&tie[b.0]))
+        });
+        layers[l] = bary.into_iter().map(|(x, _)| x).collect();
+        place(layers, pos);
+    };
+    for _ in 0..opts.iterations {
+        for l in 1..layer_count {
+            sweep(l, true, &mut layers, &mut pos);
+        }
+        for l in (0..layer_count.saturating_sub(1)).rev() {
+            sweep(l, false, &mut layers, &mut pos);
+        }
+    }
+
+    let coords = (0..n)
+        .map(|x| {
+            (
+                pos[x] * opts.node_spacing,
+                layer_of[x] as f64 * opts.layer_spacing,
+            )
+        })
+        .collect();
+    let edges = (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| covers[x][y])
+        .collect();
+    Layout {
+        coords,
+        edges,
+        layers,
+    }
+}
+
+/// Returns the cover pairs $(x, y)$, i.e., the edges of the Hasse diagram, sorted lexicographically.
+pub fn layout_edges<P: Poset + ?Sized>(p: &P) -> Vec<(usize, usize)> {
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = covers.len();
+    (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| covers[x][y])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    fn diamond() -> PosetM {
+        let m = vec![
+            vec![true, true, true, true],
+            vec![false, true, false, true],
+            vec![false, false, true, true],
+            vec![false, false, false, true],
+        ];
+        PosetM::new(&m)
+    }
+
+    #[test]
+    fn test_edges_point_up() {
+        let mut p = PosetG::new_antichain(3);
+        p.adjoin_bot();
+        p.adjoin_top();
+        let mut q = PosetG::new_chain(2);
+        q.adjoin_top();
+        for layering in [Layering::Depth, Layering::Coheight] {
+            let opts = LayoutOptions {
+                layering,
+                ..LayoutOptions::default()
+            };
+            for (coords, edges) in [
+                (p.layout(&opts), p.layout_edges()),
+                (q.layout(&opts), q.layout_edges()),
+                (diamond().layout(&opts), diamond().layout_edges()),
+            ] {
+                for (x, y) in edges {
+                    assert!(coords[x].1 < coords[y].1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_diamond() {
+        let coords = diamond().layout(&LayoutOptions::default());
+        assert_eq!(
+            diamond().layout_edges(),
+            vec![(0, 1), (0, 2), (1, 3), (2, 3)]
+        );
+        assert_eq!(coords[1].1, coords[2].1);
+        assert_ne!(coords[1].0, coords[2].0);
+        assert_eq!(coords[0], (0.0, 0.0));
+        assert_eq!(coords[3], (0.0, 2.0));
+    }
+
+    #[test]
+    fn test_coheight_layering() {
+        // 0 < 1 < 2 and 3 < 2.
+        let m = vec![
+            vec![true, true, true, false],
+            vec![false, true, true, false],
+            vec![false, false, true, false],
+            vec![false, false, true, true],
+        ];
+        let p = PosetM::new(&m);
+        assert_eq!(p.layout(&LayoutOptions::default())[3].1, 0.0);
+        let opts = LayoutOptions {
+            layering: Layering::Coheight,
+            layer_spacing: 2.0,
+            ..LayoutOptions::default()
+        };
+        let coords = p.layout(&opts);
+        assert_eq!(coords[3].1, 2.0);
+        assert_eq!(coords[2].1, 4.0);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut p = PosetG::new_antichain(5);
+        p.adjoin_bot();
+        p.adjoin_top();
+        let opts = LayoutOptions {
+            seed: 17,
+            ..LayoutOptions::default()
+        };
+        let first = p.layout(&opts);
+        for _ in 0..5 {
+            assert_eq!(p.layout(&opts), first);
+        }
+    }
+
+    #[test]
+    fn test_hasse_layout() {
+        for p in crate::corpus::corpus() {
+            for heuristic in [Heuristic::Barycenter, Heuristic::Median] {
+                let opts = LayoutOptions {
+                    heuristic,
+                    ..LayoutOptions::default()
+                };
+                let drawing = p.hasse_layout(&opts);
+                assert_eq!(drawing.coords, p.layout(&opts));
+                assert_eq!(drawing.edges, p.layout_edges());
+                let mut all: Vec<usize> = drawing.layers.iter().flatten().copied().collect();
+                all.sort_unstable();
+                assert_eq!(all, p.elements().collect::<Vec<_>>());
+                for (l, layer) in drawing.layers.iter().enumerate() {
+                    for pair in layer.windows(2) {
+                        let (a, b) = (drawing.coords[pair[0]], drawing.coords[pair[1]]);
+                        assert!(a.0 < b.0 && a.1 == l as f64 && b.1 == l as f64);
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            PosetM::new_antichain(0)
+                .hasse_layout(&LayoutOptions::default())
+                .bounding_box(),
+            None
+        );
+        let drawing = diamond().hasse_layout(&LayoutOptions::default());
+        assert_eq!(drawing.bounding_box(), Some(((-0.5, 0.0), (0.5, 2.0))));
+        assert_eq!(drawing.crossings(), 0);
+    }
+
+    #[test]
+    fn test_crossings() {
+        // Two edges swapping sides cross, and edges sharing an end do not.
+        let drawing = Layout {
+            coords: vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)],
+            edges: vec![(0, 3), (1, 2), (0, 2)],
+            layers: vec![vec![0, 1], vec![2, 3]],
+        };
+        assert_eq!(drawing.crossings(), 1);
+        // The crown on 3 + 3 elements cannot be drawn on two layers without crossings, and the median
+        // heuristic keeps at most the crossings of the barycenter heuristic on it.
+        let m = (0..6)
+            .map(|x| {
+                (0..6)
+                    .map(|y| x == y || (x < 3 && y >= 3 && y != x + 3))
+                    .collect()
+            })
+            .collect();
+        let crown = PosetM::new(&m);
+        let count = |heuristic| {
+            let opts = LayoutOptions {
+                heuristic,
+                ..LayoutOptions::default()
+            };
+            crown.hasse_layout(&opts).crossings()
+        };
+        assert!(count(Heuristic::Barycenter) > 0);
+        assert!(count(Heuristic::Median) > 0);
+    }
+}
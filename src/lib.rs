@@ -33,7 +33,8 @@
 //! where $x\le y$ holds if, and only if, $y\in G(x)$.
 //!
 //! We provide tools to convert between the different representations and to perform various manipulations
-//! in each form.
+//! in each form. For large sparse posets, [PosetR](posetr::PosetR) keeps only the Hasse diagram and an index
+//! over a decomposition into chains, which answers $x \le y$ by a single lookup (see [posetr]).
 //!
 //! ## Poset generalities
 //! In each representation of a poset, there is meta data and the actual poset encoding. The meta data
@@ -41,13 +42,95 @@
 //! across the different representations. The actual poset encoding differs, but still has one aspect in
 //! common: the underlying 'set' is taken to be $\{0, 1, 2, ..., n-1\}$. The precise way this set in encoded
 //! depends on the details of the presentation.
+//!
+//! With the `serde` feature, [PosetM](posetm::PosetM), [PosetG](posetg::PosetG), [PosetH](poseth::PosetH),
+//! [MetaData], and [Elt] implement `Serialize` and `Deserialize`, writing their sets in sorted order so
+//! that the output is deterministic (see the `serde_impls` module). With the `petgraph` feature, they
+//! convert to and from petgraph's `DiGraph` (see the `petgraph_impls` module). The `homology` feature adds
+//! [Poset::homology_mod2], the mod-2 homology of the order complex (see [topology]). The `rayon` feature
+//! spreads the heavy analyses over threads: transitive closures, the Möbius function, counting linear
+//! extensions, the color refinement of the isomorphism search, and the width, which splits over the
+//! connected components. Their results do not depend on the feature.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use std::collections::{HashMap, HashSet};
+use analysis::{AnalysisContext, Cancelled};
+use handles::{ElementHandle, HandleTable};
+use smallset::SmallSet;
 
+pub mod analysis;
+pub mod antichain;
+pub mod ascii;
+pub mod bitset;
+pub mod bottleneck;
+pub mod canonical;
+pub mod census;
+pub mod chains;
+pub mod collapse;
+pub mod composition;
+pub mod congruence;
 pub mod convertors;
+pub mod cover_list;
+pub mod dimension;
+pub mod dot;
+pub mod dynamic;
+pub mod encoding;
+pub mod error;
+pub mod extensions;
+pub mod families;
+pub mod features;
+pub mod galois;
+pub mod generate;
+pub mod graded;
+pub mod handles;
+pub mod ideals;
+pub mod incidence_algebra;
+pub mod incremental;
+pub mod interval;
+pub mod labeled;
+pub mod lattice;
+pub mod laws;
+pub mod layout;
+pub mod matroid;
+pub mod modules;
+pub mod monotone;
+pub mod order_polynomial;
+mod parallel;
+#[cfg(feature = "petgraph")]
+pub mod petgraph_impls;
 pub mod posetg;
 pub mod poseth;
 pub mod posetm;
+pub mod poseto;
+pub mod posetr;
+pub mod redundancy;
+pub mod refinement;
+mod relation;
+mod rng;
+pub mod sage;
+pub mod schedule;
+#[cfg(feature = "serde")]
+pub mod serde_impls;
+pub mod series_parallel;
+pub mod smallset;
+pub mod snapshot;
+pub mod sorting;
+pub mod summary;
+pub mod tikz;
+pub mod topology;
+pub mod validate;
+
+#[cfg(test)]
+mod corpus;
+
+// Type aliases:
+type AnElement = usize;
+type Elements = HashSet<AnElement>;
+type Hasse = Vec<SmallSet>;
+type BoolMatrix = Vec<Vec<bool>>;
+type BiPaGraph = HashMap<AnElement, Elements>;
+type SortedGraph = BTreeMap<AnElement, BTreeSet<AnElement>>;
 
 /// Provides variants for naming elements in a poset.
 /// # Usefulness illustration
@@ -65,15 +148,8 @@ pub mod posetm;
 /// p.find_top();
 /// assert_eq!(p.md.top, Some(Elt::A(2)));
 /// ```
-
-// Type aliases:
-type AnElement = usize;
-type Elements = HashSet<AnElement>;
-type Hasse = HashMap<AnElement, Elements>;
-type BoolMatrix = Vec<Vec<bool>>;
-type BiPaGraph = HashMap<AnElement, Elements>;
-
-#[derive(PartialEq, Debug, Hash, Eq)]
+#[derive(PartialEq, Debug, Hash, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Elt {
     /// A wrapper for the name of the element. If one imagines that the underlying set consists of the
     /// $n$ elements $/{a_1, ..., a_n/}$, then this notation makes sense.
@@ -83,9 +159,73 @@ pub enum Elt {
     NotPresent,
 }
 
+/// The four ways in which two elements of a poset can be related, as returned by [Poset::compare].
+/// ```
+/// use fin_pos::posetm::PosetM;
+/// use fin_pos::{PartialOrdering, Poset};
+/// use std::cmp::Ordering;
+///
+/// let p = PosetM::new_corolla(2);
+/// assert_eq!(p.compare(2, 0), PartialOrdering::Less);
+/// assert_eq!(p.compare(0, 1), PartialOrdering::Incomparable);
+/// assert_eq!(Option::<Ordering>::from(p.compare(0, 2)), Some(Ordering::Greater));
+/// ```
+#[derive(PartialEq, Debug, Hash, Eq, Clone, Copy)]
+pub enum PartialOrdering {
+    /// $x < y$.
+    Less,
+    /// $x = y$.
+    Equal,
+    /// $x > y$.
+    Greater,
+    /// Neither $x \le y$ nor $y \le x$.
+    Incomparable,
+}
+
+impl PartialOrdering {
+    /// Classifies the pair from the two answers $x \le y$ and $y \le x$.
+    pub fn from_leqs(leq: bool, geq: bool) -> PartialOrdering {
+        match (leq, geq) {
+            (true, true) => PartialOrdering::Equal,
+            (true, false) => PartialOrdering::Less,
+            (false, true) => PartialOrdering::Greater,
+            (false, false) => PartialOrdering::Incomparable,
+        }
+    }
+
+    /// The classification of the pair with its two elements swapped.
+    pub fn reverse(self) -> PartialOrdering {
+        match self {
+            PartialOrdering::Less => PartialOrdering::Greater,
+            PartialOrdering::Greater => PartialOrdering::Less,
+            o => o,
+        }
+    }
+}
+
+impl From<PartialOrdering> for Option<Ordering> {
+    /// Maps incomparability to None, as [PartialOrd::partial_cmp] does.
+    fn from(o: PartialOrdering) -> Option<Ordering> {
+        match o {
+            PartialOrdering::Less => Some(Ordering::Less),
+            PartialOrdering::Equal => Some(Ordering::Equal),
+            PartialOrdering::Greater => Some(Ordering::Greater),
+            PartialOrdering::Incomparable => None,
+        }
+    }
+}
+
 /// This struct is part of any representation of a poset. It holds information about the poset
 /// that can, albeit with difficulty, be computed from the encoded poset.
-#[derive(PartialEq, Debug)]
+///
+/// It acts as a cache: a field is None until it is known, the accessors such as [Poset::top] and
+/// [Poset::minimals] compute and store missing fields, and the operations of the [Poset] trait that change
+/// the order keep the known fields right, updating those they can and forgetting the others (see
+/// [MetaData::adjoin_bot], [MetaData::add_relation], and [MetaData::remove]). Changing the order directly
+/// through the fields of a representation bypasses this, and should be followed by
+/// [MetaData::invalidate].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetaData {
     /// The size of the underlying set.
     pub n: usize,
@@ -95,10 +235,39 @@ pub struct MetaData {
     pub bot: Option<Elt>,
     /// An element $m$ is minimal if no element is less than it. This field holds the set of all minimal
     /// element (its cardinality is between $1$ and the size of the poset).
+    #[cfg_attr(feature = "serde", serde(with = "serde_impls::sorted_opt_set"))]
     pub minimals: Option<HashSet<usize>>,
     /// An element $M$ is maximal if no element is greater than it. This field holds the set of all
     /// maximal elements (its cardinality is between $1$ and the size of the poset).
+    #[cfg_attr(feature = "serde", serde(with = "serde_impls::sorted_opt_set"))]
     pub maximals: Option<HashSet<usize>>,
+    /// The height of the poset, i.e., the number of elements in a longest chain.
+    pub height: Option<usize>,
+    /// The atoms, i.e., the elements covering the bottom element. The set is empty if there is no bottom.
+    #[cfg_attr(feature = "serde", serde(with = "serde_impls::sorted_opt_set"))]
+    pub atoms: Option<HashSet<usize>>,
+    /// The coatoms, i.e., the elements covered by the top element. The set is empty if there is no top.
+    #[cfg_attr(feature = "serde", serde(with = "serde_impls::sorted_opt_set"))]
+    pub coatoms: Option<HashSet<usize>>,
+    /// The elements pinned by [Poset::pin], kept up to date by operations that renumber elements. They are
+    /// not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub handles: HandleTable,
+}
+
+/// The handles say which elements the caller pinned, not anything about the order, so they do not take
+/// part in comparisons.
+impl PartialEq for MetaData {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+            && self.top == other.top
+            && self.bot == other.bot
+            && self.minimals == other.minimals
+            && self.maximals == other.maximals
+            && self.height == other.height
+            && self.atoms == other.atoms
+            && self.coatoms == other.coatoms
+    }
 }
 
 impl MetaData {
@@ -109,18 +278,374 @@ impl MetaData {
             bot: None,
             minimals: None,
             maximals: None,
+            height: None,
+            atoms: None,
+            coatoms: None,
+            handles: HandleTable::new(),
+        }
+    }
+
+    /// The [MetaData] of the coproduct of posets with the meta data self and other, where the elements of
+    /// other are shifted by self.n. Minimal and maximal elements are the unions, and the height the maximum,
+    /// of those of the summands, when known for both. A top or bottom exists only if one summand is empty,
+    /// in which case it is that of the other summand, and so are the atoms and coatoms. The handles of self
+    /// carry over.
+    pub fn coproduct(&self, other: &MetaData) -> MetaData {
+        let shift = self.n;
+        let union = |a: &Option<Elements>, b: &Option<Elements>| {
+            let (a, b) = (a.as_ref()?, b.as_ref()?);
+            Some(
+                a.iter()
+                    .copied()
+                    .chain(b.iter().map(|&x| x + shift))
+                    .collect(),
+            )
+        };
+        let extreme = |a: Option<Elt>, b: Option<Elt>| match (self.n, other.n) {
+            (_, 0) => a,
+            (0, _) => b,
+            _ => Some(Elt::NotPresent),
+        };
+        // Without a top or bottom, there are no coatoms or atoms.
+        let next_to_extreme = |a: &Option<Elements>, b: &Option<Elements>| match (self.n, other.n) {
+            (_, 0) => a.clone(),
+            (0, _) => b.clone(),
+            _ => Some(Elements::new()),
+        };
+        MetaData {
+            n: self.n + other.n,
+            top: extreme(self.top, other.top),
+            bot: extreme(self.bot, other.bot),
+            minimals: union(&self.minimals, &other.minimals),
+            maximals: union(&self.maximals, &other.maximals),
+            height: self.height.zip(other.height).map(|(a, b)| a.max(b)),
+            atoms: next_to_extreme(&self.atoms, &other.atoms),
+            coatoms: next_to_extreme(&self.coatoms, &other.coatoms),
+            handles: self.handles.clone(),
+        }
+    }
+
+    /// The [MetaData] of the ordinal sum of posets with the meta data self and other, where the elements of
+    /// other are shifted by self.n. The bottom and the minimal elements are those of self, and the top and
+    /// the maximal elements those of other, unless that summand is empty. The atoms are those of self,
+    /// unless it has a single element, in which case they are the minimal elements of other, and dually for
+    /// the coatoms. The height is the sum of the heights, when known for both. The handles of self carry
+    /// over.
+    pub fn ordinal_sum(&self, other: &MetaData) -> MetaData {
+        let shift = self.n;
+        let lower = |a: &Option<Elements>, b: &Option<Elements>| match self.n {
+            0 => b.clone(),
+            _ => a.clone(),
+        };
+        let upper = |a: &Option<Elements>, b: &Option<Elements>| match other.n {
+            0 => a.clone(),
+            _ => b.as_ref().map(|b| b.iter().map(|&x| x + shift).collect()),
+        };
+        let shifted =
+            |b: &Option<Elements>| b.as_ref().map(|b| b.iter().map(|&x| x + shift).collect());
+        let atoms = match self.n {
+            0 => other.atoms.clone(),
+            1 => shifted(&other.minimals),
+            _ => self.atoms.clone(),
+        };
+        let coatoms = match other.n {
+            0 => self.coatoms.clone(),
+            1 => self.maximals.clone(),
+            _ => shifted(&other.coatoms),
+        };
+        let top = match (other.n, other.top) {
+            (0, _) => self.top,
+            (_, Some(Elt::A(x))) => Some(Elt::A(x + shift)),
+            (_, top) => top,
+        };
+        MetaData {
+            n: self.n + other.n,
+            top,
+            bot: if self.n == 0 { other.bot } else { self.bot },
+            minimals: lower(&self.minimals, &other.minimals),
+            maximals: upper(&self.maximals, &other.maximals),
+            height: self.height.zip(other.height).map(|(a, b)| a + b),
+            atoms,
+            coatoms,
+            handles: self.handles.clone(),
+        }
+    }
+
+    /// Updates the meta data for the relation $x \le y$ added between incomparable elements x and y, with
+    /// everything it implies. A top or bottom stays one, but one known not to exist may now exist; y is no
+    /// longer minimal, nor x maximal; and the height, atoms and coatoms become unknown.
+    pub fn add_relation(&mut self, x: AnElement, y: AnElement) {
+        for extreme in [&mut self.top, &mut self.bot] {
+            if *extreme == Some(Elt::NotPresent) {
+                *extreme = None;
+            }
+        }
+        if let Some(minimals) = self.minimals.as_mut() {
+            minimals.remove(&y);
+        }
+        if let Some(maximals) = self.maximals.as_mut() {
+            maximals.remove(&x);
+        }
+        self.height = None;
+        self.atoms = None;
+        self.coatoms = None;
+    }
+
+    /// Updates the meta data for a new bottom element n, where n is the old size. The new element is the
+    /// only minimal element, and is covered by the old minimal elements, which become the atoms, and the
+    /// height grows by one. The rest stays as it was, except when the poset was empty, the new element then
+    /// being the top and the only maximal element too, or a single element, which then covers the new one.
+    pub fn adjoin_bot(&mut self) {
+        let n = self.n;
+        let single: Elements = [n].into();
+        // A single element has no coatoms, and the empty poset no maximal elements.
+        self.coatoms = match n {
+            0 => self.maximals.clone(),
+            1 => Some(single.clone()),
+            _ => self.coatoms.take(),
+        };
+        self.atoms = self.minimals.take();
+        if n == 0 {
+            self.top = Some(Elt::A(n));
+            self.maximals = Some(single.clone());
+        }
+        self.bot = Some(Elt::A(n));
+        self.minimals = Some(single);
+        self.height = self.height.map(|h| h + 1);
+        self.n += 1;
+    }
+
+    /// Updates the meta data for a new top element n, where n is the old size, as [MetaData::adjoin_bot]
+    /// does for a bottom.
+    pub fn adjoin_top(&mut self) {
+        let n = self.n;
+        let single: Elements = [n].into();
+        self.atoms = match n {
+            0 => self.minimals.clone(),
+            1 => Some(single.clone()),
+            _ => self.atoms.take(),
+        };
+        self.coatoms = self.maximals.take();
+        if n == 0 {
+            self.bot = Some(Elt::A(n));
+            self.minimals = Some(single.clone());
+        }
+        self.top = Some(Elt::A(n));
+        self.maximals = Some(single);
+        self.height = self.height.map(|h| h + 1);
+        self.n += 1;
+    }
+
+    /// The [MetaData] of the opposite poset (see [Poset::op]), in which the elements keep their indices. The
+    /// top and the bottom trade places, and so do the minimal and maximal elements and the atoms and
+    /// coatoms, while the height and the handles stay as they are.
+    pub fn op(&self) -> MetaData {
+        MetaData {
+            n: self.n,
+            top: self.bot,
+            bot: self.top,
+            minimals: self.maximals.clone(),
+            maximals: self.minimals.clone(),
+            height: self.height,
+            atoms: self.coatoms.clone(),
+            coatoms: self.atoms.clone(),
+            handles: self.handles.clone(),
+        }
+    }
+
+    /// Forgets every field that can be computed from the order, keeping the size and the handles. This is
+    /// for when the order was changed directly through the fields of a representation, which the meta data
+    /// cannot follow.
+    pub fn invalidate(&mut self) {
+        *self = MetaData {
+            handles: std::mem::take(&mut self.handles),
+            ..MetaData::new(self.n)
+        };
+    }
+
+    /// The [MetaData] of the poset with the element x removed, and the elements above x moved down by one.
+    /// Whatever did not depend on x carries over, renumbered: a top or bottom other than x, and the minimal
+    /// (maximal) elements if x was not one of them. The rest becomes unknown. Handles of x are marked as
+    /// removed.
+    pub fn remove(&self, x: AnElement) -> MetaData {
+        let shift = |y: AnElement| if y > x { y - 1 } else { y };
+        let extreme = |e: Option<Elt>| match e {
+            Some(Elt::A(y)) if y != x => Some(Elt::A(shift(y))),
+            _ => None,
+        };
+        let extremals = |s: &Option<Elements>| match s {
+            Some(s) if !s.contains(&x) => Some(s.iter().map(|&y| shift(y)).collect()),
+            _ => None,
+        };
+        MetaData {
+            n: self.n - 1,
+            top: extreme(self.top),
+            bot: extreme(self.bot),
+            minimals: extremals(&self.minimals),
+            maximals: extremals(&self.maximals),
+            height: None,
+            atoms: None,
+            coatoms: None,
+            handles: self.handles.remapped(|y| (y != x).then(|| shift(y))),
+        }
+    }
+
+    /// The [MetaData] of the poset with element x renamed perm\[x\], for a permutation perm of
+    /// $0, ..., n-1$ (see [Poset::relabel]). Every field carries over, renamed, and so do the handles.
+    pub fn relabel(&self, perm: &[AnElement]) -> MetaData {
+        let extreme = |e: Option<Elt>| match e {
+            Some(Elt::A(x)) => Some(Elt::A(perm[x])),
+            e => e,
+        };
+        let renamed =
+            |s: &Option<Elements>| s.as_ref().map(|s| s.iter().map(|&x| perm[x]).collect());
+        MetaData {
+            n: self.n,
+            top: extreme(self.top),
+            bot: extreme(self.bot),
+            minimals: renamed(&self.minimals),
+            maximals: renamed(&self.maximals),
+            height: self.height,
+            atoms: renamed(&self.atoms),
+            coatoms: renamed(&self.coatoms),
+            handles: self.handles.remapped(|x| Some(perm[x])),
+        }
+    }
+
+    /// The [MetaData] of the subposet on the elements back\[0\] < back\[1\] < ..., renumbered as
+    /// $0, 1, ...$ (see [Poset::sub]). A top or bottom among these elements carries over, and the rest
+    /// becomes unknown. Handles of the other elements are marked as removed.
+    pub fn sub(&self, back: &[AnElement]) -> MetaData {
+        let extreme = |e: Option<Elt>| match e {
+            Some(Elt::A(y)) => back.binary_search(&y).ok().map(Elt::A),
+            _ => None,
+        };
+        MetaData {
+            top: extreme(self.top),
+            bot: extreme(self.bot),
+            handles: self.handles.remapped(|y| back.binary_search(&y).ok()),
+            ..MetaData::new(back.len())
         }
     }
 }
 
+/// The elements of s_0 in increasing order, i.e., the table mapping the elements of a subposet on s_0 back to
+/// the poset of size n. Panics if an element is out of range.
+pub(crate) fn sub_table(s_0: &Elements, n: usize) -> Vec<AnElement> {
+    let mut back: Vec<AnElement> = s_0.iter().copied().collect();
+    back.sort_unstable();
+    if let Some(&x) = back.last() {
+        assert!(x < n, "element {} is out of range for {} elements", x, n);
+    }
+    back
+}
+
 /// Functionality that can be performed on an existing poset.
+///
+/// The trait is object safe, the methods that are not (constructors, operations returning posets of the
+/// same type, and generic methods) requiring `Self: Sized`. See [dynamic] for processing posets of
+/// different representations together.
 pub trait Poset {
+    /// Returns the poset's [MetaData].
+    fn md(&self) -> &MetaData;
+
+    /// Returns the poset's [MetaData] for updating.
+    fn md_mut(&mut self) -> &mut MetaData;
+
     /// Returns an iterator over the elements of the poset.
     fn elements(&self) -> Box<dyn Iterator<Item = AnElement>>;
 
     /// Returns true if $x\le y$ and false if $x\nleq y$ (i.e., if either $x > y$ or $x$ and $y$ are incomparable).
     fn leq(&self, x: AnElement, y: AnElement) -> bool;
 
+    /// Classifies how x and y are related. This is the primitive from which [Poset::lt], [Poset::gt],
+    /// [Poset::geq], and [Poset::incomparable] are derived. The default implementation asks [Poset::leq]
+    /// in both directions, and representations that can answer both at once should override it.
+    fn compare(&self, x: AnElement, y: AnElement) -> PartialOrdering {
+        if x == y {
+            return PartialOrdering::Equal;
+        }
+        PartialOrdering::from_leqs(self.leq(x, y), self.leq(y, x))
+    }
+
+    /// Returns true if $x < y$, i.e., $x \le y$ and $x \ne y$.
+    fn lt(&self, x: AnElement, y: AnElement) -> bool {
+        self.compare(x, y) == PartialOrdering::Less
+    }
+
+    /// Returns true if $x > y$.
+    fn gt(&self, x: AnElement, y: AnElement) -> bool {
+        self.compare(x, y) == PartialOrdering::Greater
+    }
+
+    /// Returns true if $x \ge y$.
+    fn geq(&self, x: AnElement, y: AnElement) -> bool {
+        matches!(
+            self.compare(x, y),
+            PartialOrdering::Greater | PartialOrdering::Equal
+        )
+    }
+
+    /// Returns true if x and y are incomparable.
+    fn incomparable(&self, x: AnElement, y: AnElement) -> bool {
+        self.compare(x, y) == PartialOrdering::Incomparable
+    }
+
+    /// Returns the order as a `partial_cmp`-like callable, for use with generic sorting and searching code.
+    fn comparator(&self) -> impl Fn(&AnElement, &AnElement) -> Option<Ordering> + '_
+    where
+        Self: Sized,
+    {
+        |x, y| self.compare(*x, *y).into()
+    }
+
+    /// Returns true if y covers x, i.e., $x < y$ with no element strictly in between.
+    fn covers(&self, x: AnElement, y: AnElement) -> bool {
+        self.lt(x, y) && !self.elements().any(|z| self.lt(x, z) && self.lt(z, y))
+    }
+
+    /// The elements covering x. The default implementation keeps the minimal elements of the strict up-set.
+    fn upper_covers(&self, x: AnElement) -> Elements {
+        let above: Vec<AnElement> = self.elements().filter(|&y| self.lt(x, y)).collect();
+        above
+            .iter()
+            .copied()
+            .filter(|&y| !above.iter().any(|&z| self.lt(z, y)))
+            .collect()
+    }
+
+    /// The elements covered by x. The default implementation keeps the maximal elements of the strict
+    /// down-set.
+    fn lower_covers(&self, x: AnElement) -> Elements {
+        let below: Vec<AnElement> = self.elements().filter(|&y| self.lt(y, x)).collect();
+        below
+            .iter()
+            .copied()
+            .filter(|&y| !below.iter().any(|&z| self.lt(y, z)))
+            .collect()
+    }
+
+    /// Streams the pairs $(x, y)$ with $x < y$ in lexicographic order. The default implementation sorts the
+    /// strict up-set of each element in turn.
+    fn relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(self.elements().flat_map(move |x| {
+            let mut above: Vec<AnElement> =
+                self.up_set(x).into_iter().filter(|&y| y != x).collect();
+            above.sort_unstable();
+            above.into_iter().map(move |y| (x, y))
+        }))
+    }
+
+    /// Streams the pairs $(x, y)$ with y covering x in lexicographic order. The default implementation sorts
+    /// the upper covers of each element in turn.
+    fn cover_relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(self.elements().flat_map(move |x| {
+            let mut covers: Vec<AnElement> = self.upper_covers(x).into_iter().collect();
+            covers.sort_unstable();
+            covers.into_iter().map(move |y| (x, y))
+        }))
+    }
+
     /// Updates the poset's [MetaData] with information about its bottom element.
     fn find_bot(&mut self);
 
@@ -133,21 +658,296 @@ pub trait Poset {
     /// Updates the poset's [MetaData] with the set of maximal elements.
     fn find_maximals(&mut self);
 
-    /// Returns the opposite of the poset.
-    fn op(&self) -> Self;
+    /// The top element, or [Elt::NotPresent] if there is none. The top stored in the [MetaData] is used if
+    /// known, and is otherwise found by [Poset::find_top] and stored.
+    fn top(&mut self) -> Elt {
+        if self.md().top.is_none() {
+            self.find_top();
+        }
+        self.md().top.expect("find_top stores the top")
+    }
+
+    /// The bottom element, or [Elt::NotPresent] if there is none, found by [Poset::find_bot] and stored if
+    /// not known.
+    fn bot(&mut self) -> Elt {
+        if self.md().bot.is_none() {
+            self.find_bot();
+        }
+        self.md().bot.expect("find_bot stores the bottom")
+    }
+
+    /// The minimal elements, found by [Poset::find_minimals] and stored if not known.
+    fn minimals(&mut self) -> &Elements {
+        if self.md().minimals.is_none() {
+            self.find_minimals();
+        }
+        self.md()
+            .minimals
+            .as_ref()
+            .expect("find_minimals stores the minimal elements")
+    }
+
+    /// The maximal elements, found by [Poset::find_maximals] and stored if not known.
+    fn maximals(&mut self) -> &Elements {
+        if self.md().maximals.is_none() {
+            self.find_maximals();
+        }
+        self.md()
+            .maximals
+            .as_ref()
+            .expect("find_maximals stores the maximal elements")
+    }
+
+    /// Updates the poset's [MetaData] with the height of the poset. The default implementation computes the
+    /// longest chains from the order relation in $O(n^2)$ comparisons.
+    fn find_height(&mut self) {
+        let levels = relation::levels(&relation::strict_matrix(self));
+        self.md_mut().height = Some(levels.iter().max().map_or(0, |l| l + 1));
+    }
+
+    /// The number of elements in a longest chain, found by [Poset::find_height] and stored if not known.
+    fn height(&mut self) -> usize {
+        if self.md().height.is_none() {
+            self.find_height();
+        }
+        self.md().height.expect("find_height stores the height")
+    }
+
+    /// Updates the poset's [MetaData] with the atoms.
+    fn find_atoms(&mut self) {
+        self.md_mut().atoms = Some(lattice::atoms(self));
+    }
+
+    /// Updates the poset's [MetaData] with the coatoms.
+    fn find_coatoms(&mut self) {
+        self.md_mut().coatoms = Some(lattice::coatoms(self));
+    }
+
+    /// The atoms, i.e., the elements covering the bottom element, and the empty set if there is no bottom,
+    /// found by [Poset::find_atoms] and stored if not known.
+    fn atoms(&mut self) -> &Elements {
+        if self.md().atoms.is_none() {
+            self.find_atoms();
+        }
+        self.md()
+            .atoms
+            .as_ref()
+            .expect("find_atoms stores the atoms")
+    }
+
+    /// The coatoms, i.e., the elements covered by the top element, and the empty set if there is no top,
+    /// found by [Poset::find_coatoms] and stored if not known.
+    fn coatoms(&mut self) -> &Elements {
+        if self.md().coatoms.is_none() {
+            self.find_coatoms();
+        }
+        self.md()
+            .coatoms
+            .as_ref()
+            .expect("find_coatoms stores the coatoms")
+    }
+
+    /// Returns true if the poset has a bottom element and every other element is above an atom (see
+    /// [lattice]).
+    fn is_atomic(&self) -> bool {
+        lattice::is_atomic(self)
+    }
+
+    /// Returns true if the poset is a lattice in which every element is the join of the atoms below it (see
+    /// [lattice]).
+    fn is_atomistic(&self) -> bool {
+        lattice::is_atomistic(self)
+    }
+
+    /// A chain with the most elements, listed from the bottom up. It is empty only if the poset is.
+    fn longest_chain(&self) -> Vec<AnElement> {
+        relation::longest_chain(&relation::strict_matrix(self))
+    }
+
+    /// Returns the opposite of the poset. The elements keep their indices, and the [MetaData] is dualized as
+    /// described in [MetaData::op], so pinned handles carry over.
+    fn op(&self) -> Self
+    where
+        Self: Sized;
+
+    /// Replaces the poset by its opposite, as [Poset::op] does.
+    fn dualize(&mut self)
+    where
+        Self: Sized,
+    {
+        *self = self.op();
+    }
 
     /// Creates a linearly ordered chain $\{a_1 < a_2 < \cdots < a_n\}$ of $n$ elements.
-    fn new_chain(n: usize) -> Self;
+    fn new_chain(n: usize) -> Self
+    where
+        Self: Sized;
 
     /// Creates an anti-chain of $n$ incomparable elements.
-    fn new_antichain(n: usize) -> Self;
+    fn new_antichain(n: usize) -> Self
+    where
+        Self: Sized;
+
+    /// Computes the product of the poset with other, ordered componentwise: $(a, b) \le (c, d)$ if, and only
+    /// if, $a \le c$ and $b \le d$. The pair $(a, b)$ is the element $a \cdot m + b$, where $m$ is the size
+    /// of other. The [MetaData] of the product starts out empty.
+    fn product(&self, other: &Self) -> Self
+    where
+        Self: Sized;
+
+    /// Computes the coproduct (disjoint union) of the poset with other, with no relations between the two
+    /// summands. The elements of the poset keep their indices, and element $x$ of other becomes $x + n$,
+    /// where $n$ is the size of the poset, so pinned handles of the poset carry over. The [MetaData] is
+    /// merged as described in [MetaData::coproduct].
+    fn coproduct(&self, other: &Self) -> Self
+    where
+        Self: Sized;
+
+    /// Computes the ordinal sum of the poset with other: the [coproduct](Poset::coproduct), numbered the
+    /// same way, with every element of the poset placed below every element of other. The [MetaData] is
+    /// merged as described in [MetaData::ordinal_sum].
+    fn ordinal_sum(&self, other: &Self) -> Self
+    where
+        Self: Sized;
+
+    /// Computes the lexicographic product of the poset with other: $(a, b) \le (c, d)$ if, and only if,
+    /// $a < c$, or $a = c$ and $b \le d$. The pairs are numbered as in [Poset::product], and the [MetaData]
+    /// starts out empty (see [composition]).
+    fn lex_product(&self, other: &Self) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        composition::lex_product(self, other)
+    }
+
+    /// Computes the [coproduct](Poset::coproduct) of the poset with other, numbered the same way, with $x$
+    /// placed below element $y$ of other for every pair $(x, y)$ of relations, and closed transitively.
+    /// Fails if an element is out of range (see [composition]).
+    fn disjoint_sum_with_relations(
+        &self,
+        other: &Self,
+        relations: &[(AnElement, AnElement)],
+    ) -> Result<Self, error::PosetError>
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        composition::disjoint_sum_with_relations(self, other, relations)
+    }
+
+    /// Returns the intersection of the poset with the order other on the same elements, in which $x \le y$
+    /// if it holds in both (see [composition]). Panics if the sizes differ.
+    fn intersect(&self, other: &Self) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        composition::intersect(self, other)
+    }
+
+    /// Returns the transitive closure of the union of the poset with the order other on the same elements,
+    /// or [error::PosetError::NotAntisymmetric] if they contradict each other (see [composition]). Panics
+    /// if the sizes differ.
+    fn try_union(&self, other: &Self) -> Result<Self, error::PosetError>
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        composition::try_union(self, other)
+    }
+
+    /// Returns the connected components of the comparability graph of the poset, in order of their smallest
+    /// elements (see [composition]).
+    fn connected_components(&self) -> Vec<Elements> {
+        composition::connected_components(self)
+    }
 
-    /// Add a new bottom element to the poset.
+    /// Returns the maximal ordinal summands of the poset, from the bottom up (see [composition]).
+    fn ordinal_decomposition(&self) -> Vec<Elements> {
+        composition::ordinal_decomposition(self)
+    }
+
+    /// Add a new bottom element to the poset. The new element is $n$, so the existing indices are unchanged.
+    /// The [MetaData] is updated as described in [MetaData::adjoin_bot].
     fn adjoin_bot(&mut self);
 
-    /// Add a new top element to the poset.
+    /// Add a new top element to the poset. The new element is $n$, so the existing indices are unchanged.
+    /// The [MetaData] is updated as described in [MetaData::adjoin_top].
     fn adjoin_top(&mut self);
 
+    /// Adds the relation $x \le y$ and everything it implies by transitivity, i.e., $a \le b$ whenever
+    /// $a \le x$ and $y \le b$. Nothing changes if $x \le y$ already holds. Fails if an element is out of
+    /// range, or with [error::PosetError::NotAntisymmetric] if $y < x$. The [MetaData] is updated as
+    /// described in [MetaData::add_relation]. The default implementation rebuilds the poset, and
+    /// representations that store the relation override it to update it in place.
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), error::PosetError>
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        if !validate::check_new_relation(self, x, y)? {
+            return Ok(());
+        }
+        let n = self.md().n;
+        let m: BoolMatrix = (0..n)
+            .map(|a| {
+                (0..n)
+                    .map(|b| self.leq(a, b) || (self.leq(a, x) && self.leq(y, b)))
+                    .collect()
+            })
+            .collect();
+        let mut q = posetm::PosetM::new(&m);
+        q.md = self.md().clone();
+        q.md.add_relation(x, y);
+        *self = Self::from_poset(&q);
+        Ok(())
+    }
+
+    /// Removes the element x from the poset, moving the elements above it down by one to keep the indices
+    /// $0, ..., n-2$. The [MetaData] is updated as described in [MetaData::remove]. Panics if x is not an
+    /// element.
+    fn remove_element(&mut self, x: AnElement)
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        let n = self.md().n;
+        assert!(x < n, "element {} is out of range for {} elements", x, n);
+        let rest: Vec<AnElement> = (0..n).filter(|&y| y != x).collect();
+        let m: BoolMatrix = rest
+            .iter()
+            .map(|&a| rest.iter().map(|&b| self.leq(a, b)).collect())
+            .collect();
+        let mut q = posetm::PosetM::new(&m);
+        q.md = self.md().remove(x);
+        *self = Self::from_poset(&q);
+    }
+
+    /// Removes the top element, if there is one, as [Poset::remove_element] does, and returns whether there
+    /// was one to remove.
+    fn remove_top(&mut self) -> bool
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        match self.top() {
+            Elt::A(top) => {
+                self.remove_element(top);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the bottom element, if there is one, as [Poset::remove_element] does, and returns whether
+    /// there was one to remove.
+    fn remove_bot(&mut self) -> bool
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        match self.bot() {
+            Elt::A(bot) => {
+                self.remove_element(bot);
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Creates a new corolla with n leaves and one root.
     fn new_corolla(n: usize) -> Self
     where
@@ -158,46 +958,788 @@ pub trait Poset {
         c_n
     }
 
-    /// Computes a new poset consisting of the specified set s_0 of elmenets as a subposet of the given poset.
-    fn sub(&self, s_0: &Elements) -> Self;
+    /// Creates the crown $S_k^0$, also known as the standard example $S_k$: the minimal elements
+    /// $0, ..., k-1$ and the maximal elements $k, ..., 2k-1$, with $i < k + j$ if, and only if, $i \ne j$.
+    /// For $k \ge 2$ its dimension is k.
+    fn new_crown(k: usize) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        let m: BoolMatrix = (0..2 * k)
+            .map(|x| {
+                (0..2 * k)
+                    .map(|y| x == y || (x < k && y >= k && y - k != x))
+                    .collect()
+            })
+            .collect();
+        Self::from_poset(&posetm::PosetM::new(&m))
+    }
+
+    /// Creates the fence (zigzag) $0 < 1 > 2 < 3 > \cdots$ of k elements, in which the even elements are
+    /// minimal and each element is comparable only to its neighbours.
+    fn new_fence(k: usize) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        let m: BoolMatrix = (0..k)
+            .map(|x| {
+                (0..k)
+                    .map(|y| x == y || (x % 2 == 0 && x.abs_diff(y) == 1))
+                    .collect()
+            })
+            .collect();
+        Self::from_poset(&posetm::PosetM::new(&m))
+    }
+
+    /// Creates the diamond $M_3$: the atoms 0, 1, 2 between the bottom 3 and the top 4.
+    fn new_diamond() -> Self
+    where
+        Self: Sized,
+    {
+        let mut d = Self::new_antichain(3);
+        d.adjoin_bot();
+        d.adjoin_top();
+        d
+    }
+
+    /// Creates the pentagon $N_5$: the chain $0 < 1$ beside the element 2, between the bottom 3 and the
+    /// top 4.
+    fn new_pentagon() -> Self
+    where
+        Self: Sized,
+    {
+        let mut p = Self::new_chain(2).coproduct(&Self::new_chain(1));
+        p.adjoin_bot();
+        p.adjoin_top();
+        p
+    }
+
+    /// Creates the Boolean lattice $2^k$ of the subsets of $\{0, ..., k-1\}$ ordered by inclusion, the
+    /// subset $S$ being the element $\sum_{i \in S} 2^i$. It is built as a product of 2-chains, and its
+    /// top, bottom, minimal and maximal elements and height are filled in.
+    fn new_boolean(k: u32) -> Self
+    where
+        Self: Sized,
+    {
+        let two = Self::new_chain(2);
+        let mut b = (0..k).fold(Self::new_chain(1), |b, _| b.product(&two));
+        let top = (1 << k) - 1;
+        let md = b.md_mut();
+        md.bot = Some(Elt::A(0));
+        md.top = Some(Elt::A(top));
+        md.minimals = Some([0].into_iter().collect());
+        md.maximals = Some([top].into_iter().collect());
+        md.height = Some(k as usize + 1);
+        b
+    }
+
+    /// Computes the subposet induced on the set s_0 of elements, renumbered as $0, ..., k-1$ in increasing
+    /// order, together with the element of the poset behind each new element. The [MetaData] is restricted
+    /// as described in [MetaData::sub], so pinned handles follow their elements. Panics if an element of s_0
+    /// is out of range.
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>)
+    where
+        Self: Sized;
+
+    /// Returns the isomorphic poset in which element x of the poset is renamed perm\[x\], with the [MetaData]
+    /// renamed along (see [MetaData::relabel]). Panics if perm is not a permutation of $0, ..., n-1$.
+    fn relabel(&self, perm: &[AnElement]) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        let n = self.md().n;
+        let mut back = vec![n; n];
+        assert_eq!(perm.len(), n, "a permutation of {} elements is needed", n);
+        for (x, &y) in perm.iter().enumerate() {
+            assert!(y < n && back[y] == n, "{:?} is not a permutation", perm);
+            back[y] = x;
+        }
+        let m: BoolMatrix = (0..n)
+            .map(|a| (0..n).map(|b| self.leq(back[a], back[b])).collect())
+            .collect();
+        let mut q = posetm::PosetM::new(&m);
+        q.md = self.md().relabel(perm);
+        Self::from_poset(&q)
+    }
 
     /// Checks if the poset is an anti-chain. The default implementation is usually not efficient. If checking whether the poset
     /// is an anti-chain is a frequent operation with your representation of the poset, consider implementing this method manually.
     fn is_antichain(&self) -> bool {
         for x in self.elements() {
             for y in self.elements() {
-                if self.leq(x, y) && x != y {
+                if self.lt(x, y) {
                     return false;
                 }
             }
         }
-        return true;
+        true
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::posetg::PosetG;
-    use crate::posetm::PosetM;
+    /// Checks that the stored data describes a poset on $\{0, ..., n-1\}$, reporting the offending elements
+    /// otherwise (see [validate]). The default implementation checks the axioms with [Poset::leq], and the
+    /// representations first check that their data is well formed.
+    fn is_valid_poset(&self) -> Result<(), error::PosetError> {
+        validate::check_order(self)
+    }
 
-    #[test]
-    fn test_new_corolla() {
-        let n = 3;
+    /// Returns true if the two posets have the same elements and the same order, regardless of their
+    /// representations and of their [MetaData].
+    fn same_order<Q: Poset + ?Sized>(&self, other: &Q) -> bool
+    where
+        Self: Sized,
+    {
+        self.elements().eq(other.elements())
+            && self
+                .elements()
+                .all(|x| self.elements().all(|y| self.leq(x, y) == other.leq(x, y)))
+    }
 
-        let mut c = PosetG::new_corolla(n);
-        assert_eq!(c.md.n, n + 1);
+    /// Returns true if the two posets are isomorphic, by comparing their canonical forms (see [canonical]).
+    fn is_isomorphic<Q: Poset + ?Sized>(&self, other: &Q) -> bool
+    where
+        Self: Sized,
+    {
+        canonical::CanonicalPoset::new(self) == canonical::CanonicalPoset::new(other)
+    }
 
-        c.find_top();
-        c.find_maximals();
+    /// Returns a hash of the poset that is the same for isomorphic posets, but may also coincide for some
+    /// posets that are not isomorphic (see [canonical::struct_hash]).
+    fn struct_hash(&self) -> u64 {
+        canonical::struct_hash(self)
+    }
 
-        assert_eq!(c.md.top, Some(Elt::NotPresent));
-        assert_ne!(c.md.bot, None);
-        assert_eq!(c.md.minimals.unwrap().len(), 1);
-        assert_eq!(c.md.maximals.unwrap().len(), n);
+    /// Returns the canonically labeled isomorph of the poset: isomorphic posets have canonical forms with the
+    /// same order (see [canonical]).
+    fn canonical_form(&self) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        canonical::canonical_form(self).0
+    }
 
-        let mut c = PosetM::new_corolla(n);
-        assert_eq!(c.md.n, n + 1);
+    /// Like [Poset::is_isomorphic], but observing the hooks of ctx (see [analysis]).
+    fn is_isomorphic_with<Q: Poset + ?Sized>(
+        &self,
+        other: &Q,
+        ctx: &mut AnalysisContext,
+    ) -> Result<bool, Cancelled>
+    where
+        Self: Sized,
+    {
+        analysis::is_isomorphic(self, other, ctx)
+    }
+
+    /// Returns the width of the poset, i.e., the size of a largest antichain.
+    fn width(&self) -> usize {
+        analysis::uncancelled(self.width_with(&mut AnalysisContext::new()))
+    }
+
+    /// Like [Poset::width], but observing the hooks of ctx (see [analysis]).
+    fn width_with(&self, ctx: &mut AnalysisContext) -> Result<usize, Cancelled> {
+        analysis::width(self, ctx)
+    }
+
+    /// Returns a largest antichain of the poset, of [Poset::width] elements.
+    fn max_antichain(&self) -> Elements {
+        analysis::uncancelled(self.max_antichain_with(&mut AnalysisContext::new()))
+    }
+
+    /// Like [Poset::max_antichain], but observing the hooks of ctx (see [analysis]).
+    fn max_antichain_with(&self, ctx: &mut AnalysisContext) -> Result<Elements, Cancelled> {
+        analysis::max_antichain(self, ctx)
+    }
+
+    /// Iterates over all the antichains of the poset, the empty one included (see [antichain]).
+    fn antichains(&self) -> impl Iterator<Item = Elements>
+    where
+        Self: Sized,
+    {
+        antichain::Antichains::new(self)
+    }
+
+    /// Returns $\Omega(P, k)$, the number of monotone maps from the poset to the chain of k elements (see
+    /// [order_polynomial]).
+    fn order_polynomial(&self, k: usize) -> u128 {
+        order_polynomial::order_polynomial(self, k)
+    }
+
+    /// Returns the coefficients $c_j$ of $\Omega(P, k) = \sum_j c_j \binom{k}{j}$, where $c_j$ counts the
+    /// monotone maps onto the $j$-chain (see [order_polynomial]).
+    fn order_polynomial_coeffs(&self) -> Vec<i128> {
+        order_polynomial::order_polynomial_coeffs(self)
+    }
+
+    /// Returns the number of antichains of the poset, which is also the number of its down-sets.
+    fn count_antichains(&self) -> u128 {
+        antichain::count_antichains(self)
+    }
+
+    /// Returns the weight of a heaviest antichain and its elements, entry x of weights being the weight of
+    /// element x (see [antichain]).
+    fn heaviest_antichain(&self, weights: &[u64]) -> (u64, Vec<AnElement>) {
+        antichain::heaviest_antichain(self, weights)
+    }
+
+    /// Collapses the classes of elements with the same elements strictly above and strictly below, returning
+    /// the collapsed poset, the class sizes, and the class of every element (see [collapse]).
+    fn collapse_equivalent(&self) -> (Self, Vec<usize>, Vec<usize>)
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        collapse::collapse_equivalent(self)
+    }
+
+    /// Collapses every block of the partition to a single element, block i becoming element i, or fails if
+    /// the blocks are not a partition whose induced order is antisymmetric (see [collapse::quotient]).
+    fn quotient(&self, partition: &[HashSet<AnElement>]) -> Result<Self, error::PosetError>
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        collapse::quotient(self, partition)
+    }
+
+    /// Returns the number of linear extensions of the poset, i.e., of total orders refining it.
+    fn count_linear_extensions(&self) -> u128 {
+        analysis::uncancelled(self.count_linear_extensions_with(&mut AnalysisContext::new()))
+    }
+
+    /// Like [Poset::count_linear_extensions], but observing the hooks of ctx (see [analysis]).
+    fn count_linear_extensions_with(&self, ctx: &mut AnalysisContext) -> Result<u128, Cancelled> {
+        analysis::count_linear_extensions(self, ctx)
+    }
+
+    /// Returns the lexicographically smallest linear extension of the poset (see [extensions]).
+    fn linear_extension(&self) -> Vec<AnElement> {
+        extensions::linear_extension(self)
+    }
+
+    /// Iterates over all the linear extensions of the poset, by the Varol–Rotem algorithm (see
+    /// [extensions]).
+    fn all_linear_extensions(&self) -> impl Iterator<Item = Vec<AnElement>>
+    where
+        Self: Sized,
+    {
+        extensions::LinearExtensions::new(self)
+    }
+
+    /// Returns true if every pair of elements has a join and a meet (see [lattice]).
+    fn is_lattice(&self) -> bool {
+        lattice::LatticeOps::new(self).is_some()
+    }
+
+    /// Returns true if the poset has a rank function (see [graded]).
+    fn is_graded(&self) -> bool {
+        graded::rank_function(self).is_some()
+    }
+
+    /// Returns the rank function normalized to 0 at the lowest elements of each connected component, or
+    /// None if the poset is not graded (see [graded]).
+    fn rank_function(&self) -> Option<Vec<usize>> {
+        graded::rank_function(self)
+    }
+
+    /// Returns true if the poset is graded with a bottom and a top, and every interval of positive length has
+    /// as many elements of even rank as of odd rank (see [graded]).
+    fn is_eulerian(&self) -> bool {
+        graded::is_eulerian(self)
+    }
+
+    /// Returns true if the poset is graded with a bottom and a top, and every interval of length 2 has four
+    /// elements (see [graded]).
+    fn is_thin(&self) -> bool {
+        graded::is_thin(self)
+    }
+
+    /// Returns the coefficients of the characteristic polynomial $\sum_x \mu(\hat 0, x) t^{r - \rho(x)}$,
+    /// lowest degree first, or an empty vector if the poset is not graded or has no bottom (see [graded]).
+    fn characteristic_polynomial(&self) -> Vec<i128> {
+        graded::characteristic_polynomial(self)
+    }
+
+    /// Returns true if the poset is series-parallel, i.e., N-free (see [series_parallel]).
+    fn is_series_parallel(&self) -> bool {
+        series_parallel::sp_decomposition(self).is_some()
+    }
+
+    /// Returns the decomposition of the poset into series and parallel compositions, or None if it is not
+    /// series-parallel (see [series_parallel]).
+    fn sp_decomposition(&self) -> Option<series_parallel::SpTree> {
+        series_parallel::sp_decomposition(self)
+    }
+
+    /// Returns the tree of strong modules of the poset (see [modules]).
+    fn modular_decomposition(&self) -> modules::ModuleTree {
+        modules::modular_decomposition(self)
+    }
+
+    /// Returns the strong modules of the poset, each sorted, with every module listed before its
+    /// submodules (see [modules]).
+    fn modules(&self) -> Vec<Vec<AnElement>> {
+        modules::modular_decomposition(self).modules()
+    }
+
+    /// Returns the Dushnik–Miller dimension, the smallest number of linear extensions whose intersection is
+    /// the order (see [dimension]).
+    fn dimension(&self) -> usize {
+        dimension::realizer(self).len()
+    }
+
+    /// Returns true if the dimension is at most k (see [dimension]).
+    fn dimension_at_most(&self, k: usize) -> bool {
+        dimension::find_realizer(self, k).is_some()
+    }
+
+    /// Returns a realizer of the smallest size, i.e., [Poset::dimension] linear extensions whose
+    /// intersection is the order (see [dimension]).
+    fn realizer(&self) -> Vec<Vec<AnElement>> {
+        dimension::realizer(self)
+    }
+
+    /// Returns the 2-dimension, the smallest k such that the poset embeds into the Boolean lattice $2^k$
+    /// (see [encoding]).
+    fn two_dimension(&self) -> usize {
+        encoding::boolean_encoding(self)
+            .first()
+            .map_or(0, bitset::BitRow::len)
+    }
+
+    /// Returns true if the poset is a modular lattice, i.e., a lattice without a pentagon $N_5$.
+    fn is_modular(&self) -> bool {
+        lattice::is_modular(self)
+    }
+
+    /// Returns true if the poset is a distributive lattice, i.e., a lattice without a pentagon $N_5$ or a
+    /// diamond $M_3$.
+    fn is_distributive(&self) -> bool {
+        lattice::is_distributive(self)
+    }
+
+    /// Returns the complements of x, the elements y with $x \vee y = \hat 1$ and $x \wedge y = \hat 0$, and
+    /// the empty set if the poset is not a lattice (see [lattice]).
+    fn complements(&self, x: AnElement) -> HashSet<usize> {
+        lattice::complements(self, x)
+    }
+
+    /// Returns true if the poset is a lattice in which every element has a complement.
+    fn is_complemented(&self) -> bool {
+        lattice::is_complemented(self)
+    }
+
+    /// Returns true if the poset is a Boolean lattice, i.e., a complemented distributive lattice.
+    fn is_boolean(&self) -> bool {
+        lattice::is_boolean(self)
+    }
+
+    /// Returns true if the poset is an upper semimodular lattice: $a \wedge b \lessdot a$ implies
+    /// $b \lessdot a \vee b$ (see [lattice]).
+    fn is_upper_semimodular(&self) -> bool {
+        lattice::is_upper_semimodular(self)
+    }
+
+    /// Returns true if the poset is a lower semimodular lattice: $a \lessdot a \vee b$ implies
+    /// $a \wedge b \lessdot b$ (see [lattice]).
+    fn is_lower_semimodular(&self) -> bool {
+        lattice::is_lower_semimodular(self)
+    }
+
+    /// Returns true if the poset is a supersolvable lattice, i.e., has a maximal chain generating a
+    /// distributive sublattice with every other chain (see [lattice]).
+    fn is_supersolvable(&self) -> bool {
+        lattice::is_supersolvable(self)
+    }
+
+    /// Returns true if the poset is a geometric lattice, i.e., an atomistic semimodular lattice (see
+    /// [matroid]).
+    fn is_geometric_lattice(&self) -> bool {
+        matroid::is_geometric_lattice(self)
+    }
+
+    /// Returns the simple matroid whose lattice of flats is the poset, or None if the poset is not a
+    /// geometric lattice (see [matroid]).
+    fn matroid(&self) -> Option<matroid::SimpleMatroid> {
+        matroid::matroid(self)
+    }
+
+    /// Returns the subposet induced on the join-irreducible elements, those covering exactly one element. For
+    /// a distributive lattice, the lattice of down-sets of the result is isomorphic to it (see [lattice]).
+    fn join_irreducibles(&self) -> Self
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        lattice::join_irreducibles(self).0
+    }
+
+    /// Returns the lattice of congruences of the lattice ordered by refinement, together with the congruence
+    /// behind each of its elements, or [NotALattice](error::PosetError::NotALattice) if the poset is not a
+    /// lattice (see [congruence]).
+    fn congruence_lattice(
+        &self,
+    ) -> Result<(posetg::PosetG, Vec<families::SetPartition>), error::PosetError> {
+        let ops = lattice::LatticeOps::try_new(self)?;
+        Ok(congruence::congruence_lattice(&ops))
+    }
+
+    /// Returns the smallest congruence of the lattice identifying x and y, as the partition into its
+    /// classes (see [congruence]).
+    fn principal_congruence(
+        &self,
+        x: AnElement,
+        y: AnElement,
+    ) -> Result<families::SetPartition, error::PosetError> {
+        let n = self.md().n;
+        if let Some(&index) = [x, y].iter().find(|&&i| i >= n) {
+            return Err(error::PosetError::IndexOutOfRange { index, n });
+        }
+        let ops = lattice::LatticeOps::try_new(self)?;
+        Ok(congruence::principal_congruence(&ops, x, y))
+    }
+
+    /// Returns the Dedekind–MacNeille completion of the poset, the smallest lattice it embeds in.
+    fn dedekind_macneille(&self) -> posetm::PosetM {
+        analysis::uncancelled(self.dedekind_macneille_with(&mut AnalysisContext::new()))
+    }
+
+    /// Like [Poset::dedekind_macneille], but observing the hooks of ctx (see [analysis]).
+    fn dedekind_macneille_with(
+        &self,
+        ctx: &mut AnalysisContext,
+    ) -> Result<posetm::PosetM, Cancelled> {
+        analysis::dedekind_macneille(self, ctx)
+    }
+
+    /// Returns true if every relation of coarser also holds in the poset (see [refinement]).
+    fn refines<Q: Poset + ?Sized>(&self, coarser: &Q) -> Result<bool, refinement::SizeMismatch>
+    where
+        Self: Sized,
+    {
+        refinement::refines(self, coarser)
+    }
+
+    /// Returns the pairs $(x, y)$ with $x \le y$ in the poset but not in coarser, sorted.
+    fn refinement_gap<Q: Poset + ?Sized>(
+        &self,
+        coarser: &Q,
+    ) -> Result<Vec<(AnElement, AnElement)>, refinement::SizeMismatch>
+    where
+        Self: Sized,
+    {
+        refinement::refinement_gap(self, coarser)
+    }
+
+    /// Like [Poset::refines], but returns false, instead of failing, if other has a different size.
+    fn extends<Q: Poset + ?Sized>(&self, other: &Q) -> bool
+    where
+        Self: Sized,
+    {
+        refinement::extends(self, other)
+    }
+
+    /// Returns true if order lists every element exactly once, each after the elements below it, e.g., if
+    /// order is a schedule respecting the poset as dependencies (see [refinement]).
+    fn is_linear_extension(&self, order: &[AnElement]) -> bool {
+        refinement::is_linear_extension(self, order)
+    }
+
+    /// Returns true if the poset is a total order refining coarser.
+    fn is_linear_extension_of<Q: Poset + ?Sized>(
+        &self,
+        coarser: &Q,
+    ) -> Result<bool, refinement::SizeMismatch>
+    where
+        Self: Sized,
+    {
+        refinement::is_linear_extension_of(self, coarser)
+    }
+
+    /// Pins the element x, returning a handle that keeps naming the same element after operations that
+    /// renumber the elements (see [handles]).
+    fn pin(&mut self, x: AnElement) -> ElementHandle {
+        assert!(x < self.md().n, "element {} is not in the poset", x);
+        self.md_mut().handles.pin(x)
+    }
+
+    /// Returns the current index of a pinned element, or None if it is no longer in the poset.
+    fn resolve(&self, h: &ElementHandle) -> Option<AnElement> {
+        self.md().handles.resolve(h)
+    }
+
+    /// Returns the isomorphism-invariant feature vector of the poset (see [features]), computed with the
+    /// default [FeatureConfig](features::FeatureConfig).
+    fn feature_vector(&self) -> Vec<f64> {
+        features::feature_vector(self, &features::FeatureConfig::default())
+    }
+
+    /// Returns coordinates for drawing the Hasse diagram of the poset (see [layout]).
+    fn layout(&self, opts: &layout::LayoutOptions) -> Vec<(f64, f64)> {
+        layout::layout(self, opts)
+    }
+
+    /// Returns coordinates for drawing the Hasse diagram of the poset together with its edges and layers
+    /// (see [layout]).
+    fn hasse_layout(&self, opts: &layout::LayoutOptions) -> layout::Layout {
+        layout::hasse_layout(self, opts)
+    }
+
+    /// Returns the Hasse diagram of the poset in the DOT language of Graphviz, with the elements grouped by
+    /// level (see [dot]). [PosetO](poseto::PosetO) has an inherent `to_dot` without the layout hints.
+    fn to_dot(&self) -> String {
+        dot::to_dot::<_, &str>(self, None)
+    }
+
+    /// Returns the Hasse diagram of the poset as a TikZ picture, for inclusion in LaTeX documents (see
+    /// [tikz]).
+    fn to_tikz(&self) -> String {
+        tikz::to_tikz::<_, &str>(self, None)
+    }
+
+    /// Returns the poset as a cover list, naming the elements by their indices (see [cover_list]).
+    fn to_cover_list(&self) -> String {
+        let names: Vec<String> = self.elements().map(|x| x.to_string()).collect();
+        cover_list::write_cover_list(self, &names)
+    }
+
+    /// Returns a call of SageMath's `Poset` constructor building the poset (see [sage]).
+    fn to_sage(&self) -> String {
+        sage::to_sage(self)
+    }
+
+    /// Writes the Hasse diagram of the poset to w, as in [Poset::to_dot].
+    fn write_dot<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()>
+    where
+        Self: Sized,
+    {
+        dot::write_dot::<_, _, &str>(self, None, w)
+    }
+
+    /// Returns the edges of the Hasse diagram, i.e., the cover pairs $(x, y)$, sorted.
+    fn layout_edges(&self) -> Vec<(usize, usize)> {
+        layout::layout_edges(self)
+    }
+
+    /// Returns the elements lying on every chain of maximum length (see [bottleneck]).
+    fn height_critical_elements(&self) -> HashSet<usize> {
+        bottleneck::height_critical_elements(self)
+    }
+
+    /// Returns the elements whose removal increases the number of connected components (see [bottleneck]).
+    fn cut_elements(&self) -> HashSet<usize> {
+        bottleneck::cut_elements(self)
+    }
+
+    /// Lists the structurally critical elements together with the ways they are critical (see [bottleneck]).
+    fn bottleneck_report(&self) -> Vec<(AnElement, bottleneck::BottleneckKind)> {
+        bottleneck::bottleneck_report(self)
+    }
+
+    /// Returns the antichain layers of the poset, bottom first (see [schedule]).
+    fn antichain_layers(&self) -> Vec<Vec<AnElement>> {
+        schedule::antichain_layers(self)
+    }
+
+    /// Partitions the poset into [Poset::height] antichains, the fewest possible (Mirsky's theorem): its
+    /// antichain layers, as sets.
+    fn antichain_decomposition(&self) -> Vec<Elements> {
+        schedule::antichain_decomposition(self)
+    }
+
+    /// Recovers a hidden linear extension of the poset by binary insertion, asking before(x, y) whether x
+    /// comes before y for incomparable x and y only (see [sorting]).
+    fn sort_by_queries<F: FnMut(AnElement, AnElement) -> bool>(&self, before: F) -> sorting::Sorted
+    where
+        Self: Sized,
+    {
+        sorting::sort_by_insertion(self, before)
+    }
+
+    /// Like [Poset::sort_by_queries], but asking about the pair that splits the remaining linear extensions
+    /// most evenly, which takes $O(\log e(P))$ questions (see [sorting]).
+    fn sort_by_balanced_queries<F: FnMut(AnElement, AnElement) -> bool>(
+        &self,
+        before: F,
+    ) -> sorting::Sorted
+    where
+        Self: Sized,
+    {
+        sorting::sort_by_balanced_queries(self, before)
+    }
+
+    /// Splits the poset into waves of at most max_width independent elements, choosing the available
+    /// elements by priority (see [schedule]).
+    fn schedule_waves<F: Fn(AnElement) -> i64>(
+        &self,
+        max_width: usize,
+        priority: F,
+    ) -> Vec<Vec<AnElement>>
+    where
+        Self: Sized,
+    {
+        schedule::schedule_waves(self, max_width, priority)
+    }
+
+    /// Cuts the poset into at most k blocks along a linear extension, returning the first and last element
+    /// of each block (see [summary]).
+    fn interval_partition(&self, k: usize) -> Vec<(AnElement, AnElement)> {
+        summary::interval_partition(self, k)
+    }
+
+    /// Returns the poset of the blocks of [Poset::interval_partition], ordered by the relation they induce.
+    fn summarize(&self, k: usize) -> posetg::PosetG {
+        summary::summarize(self, k)
+    }
+
+    /// Returns the principal down-set (ideal) $\{y \mid y \le x\}$ of x.
+    fn down_set(&self, x: AnElement) -> Elements {
+        ideals::down_set(self, x)
+    }
+
+    /// Returns the principal up-set (filter) $\{y \mid x \le y\}$ of x.
+    fn up_set(&self, x: AnElement) -> Elements {
+        ideals::up_set(self, x)
+    }
+
+    /// Returns the smallest down-set containing s.
+    fn down_closure(&self, s: &HashSet<AnElement>) -> Elements {
+        ideals::down_closure(self, s)
+    }
+
+    /// Returns the smallest up-set containing s.
+    fn up_closure(&self, s: &HashSet<AnElement>) -> Elements {
+        ideals::up_closure(self, s)
+    }
+
+    /// Iterates over all the chains of the poset, the empty chain included, each listed from the bottom up
+    /// (see [chains]).
+    fn chains(&self) -> impl Iterator<Item = Vec<AnElement>>
+    where
+        Self: Sized,
+    {
+        chains::Chains::new(self)
+    }
+
+    /// Returns the maximal chains of the poset, each listed from the bottom up.
+    fn maximal_chains(&self) -> Vec<Vec<AnElement>> {
+        chains::maximal_chains(self)
+    }
+
+    /// Returns the number of chains of every size from 0 to the height, i.e., the face vector of the order
+    /// complex with the empty face in front.
+    fn count_chains_by_length(&self) -> Vec<u64> {
+        chains::count_chains_by_length(self)
+    }
+
+    /// Returns the f-vector of the order complex, the same as [Poset::count_chains_by_length] (see
+    /// [chains]).
+    fn f_vector(&self) -> Vec<u64> {
+        chains::f_vector(self)
+    }
+
+    /// Returns the h-vector of the order complex (see [chains]).
+    fn h_vector(&self) -> Vec<i64> {
+        chains::h_vector(self)
+    }
+
+    /// Returns the value at k of the zeta polynomial, which counts the multichains
+    /// $x_1 \le \cdots \le x_{k-1}$ for $k \ge 2$ (see [chains]).
+    fn zeta_polynomial(&self, k: i64) -> i128 {
+        chains::zeta_polynomial(self, k)
+    }
+
+    /// Returns the reduced Euler characteristic of the order complex (see [topology]).
+    fn euler_characteristic(&self) -> i64 {
+        topology::euler_characteristic(self)
+    }
+
+    /// Returns the ranks of the reduced mod-2 homology groups of the order complex in degrees $0, ..., h-1$
+    /// (see [topology]).
+    #[cfg(feature = "homology")]
+    fn homology_mod2(&self) -> Vec<usize> {
+        topology::homology_mod2(self)
+    }
+
+    /// Returns the zeta matrix, with entry $(x, y)$ equal to 1 if $x \le y$ and to 0 otherwise (see
+    /// [incidence_algebra]).
+    fn zeta_matrix(&self) -> Vec<Vec<i64>> {
+        incidence_algebra::zeta_matrix(self)
+    }
+
+    /// Returns the interval $[x, y]$ as a poset on $\{0, ..., k-1\}$, together with the element of the
+    /// poset behind each of its elements (see [interval]).
+    fn interval(&self, x: AnElement, y: AnElement) -> (Self, Vec<AnElement>)
+    where
+        Self: Sized + convertors::FromPoset<posetm::PosetM>,
+    {
+        interval::interval(self, x, y)
+    }
+
+    /// Returns the down-sets covering the down-set d in the lattice of down-sets (see [ideals]).
+    fn downset_successors(
+        &self,
+        d: &HashSet<AnElement>,
+    ) -> Result<Vec<HashSet<AnElement>>, ideals::NotADownSet> {
+        ideals::downset_successors(self, d)
+    }
+
+    /// Returns the down-sets covered by the down-set d in the lattice of down-sets (see [ideals]).
+    fn downset_predecessors(
+        &self,
+        d: &HashSet<AnElement>,
+    ) -> Result<Vec<HashSet<AnElement>>, ideals::NotADownSet> {
+        ideals::downset_predecessors(self, d)
+    }
+
+    /// Returns the lattice of down-sets ordered by inclusion and the down-set each of its elements stands
+    /// for (see [ideals]).
+    fn ideal_lattice(&self) -> (posetg::PosetG, Vec<Elements>) {
+        ideals::ideal_lattice(self)
+    }
+
+    /// Returns the rank of the down-set d in the lattice of down-sets, which is its size.
+    fn downset_rank(&self, d: &HashSet<AnElement>) -> usize {
+        d.len()
+    }
+
+    /// Returns the number of down-sets of each size $0, ..., n$, i.e., the rank generating function of the
+    /// lattice of down-sets (see [ideals::count_ideals_by_size]).
+    fn count_ideals_by_size(&self) -> Vec<u128> {
+        ideals::count_ideals_by_size(self)
+    }
+
+    /// Returns a uniformly random down-set of the given size, drawn with the given seed, or None if the
+    /// poset has fewer elements.
+    fn sample_ideal(&self, size: usize, seed: u64) -> Option<Elements> {
+        ideals::sample_ideal(self, size, seed)
+    }
+
+    /// Counts the $k$-element subsets of the poset by the isomorphism type of the subposet they induce
+    /// (see [census]).
+    fn motif_census(&self, k: usize) -> HashMap<canonical::CanonicalPoset, u64> {
+        census::motif_census(self, k)
+    }
+
+    /// Estimates [Poset::motif_census] from the given number of random $k$-element subsets.
+    fn motif_census_sampled(
+        &self,
+        k: usize,
+        samples: usize,
+        seed: u64,
+    ) -> HashMap<canonical::CanonicalPoset, f64> {
+        census::motif_census_sampled(self, k, samples, seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_new_corolla() {
+        let n = 3;
+
+        let mut c = PosetG::new_corolla(n);
+        assert_eq!(c.md.n, n + 1);
 
         c.find_top();
         c.find_maximals();
@@ -206,5 +1748,414 @@ mod tests {
         assert_ne!(c.md.bot, None);
         assert_eq!(c.md.minimals.unwrap().len(), 1);
         assert_eq!(c.md.maximals.unwrap().len(), n);
+
+        let mut c = PosetM::new_corolla(n);
+        assert_eq!(c.md.n, n + 1);
+
+        c.find_top();
+        c.find_maximals();
+
+        assert_eq!(c.md.top, Some(Elt::NotPresent));
+        assert_ne!(c.md.bot, None);
+        assert_eq!(c.md.minimals.unwrap().len(), 1);
+        assert_eq!(c.md.maximals.unwrap().len(), n);
+    }
+
+    #[test]
+    fn test_standard_examples() {
+        let mut crown = poseth::PosetH::new_crown(3);
+        assert_eq!((crown.md.n, crown.height()), (6, 2));
+        assert!(crown.leq(0, 4) && !crown.leq(0, 3));
+        // Every minimal element is below two maximal ones, so the order complex is a hexagon.
+        assert_eq!(crown.euler_characteristic(), -1);
+        assert!(PosetM::new_crown(0).same_order(&PosetM::new_chain(0)));
+
+        let mut fence = PosetG::new_fence(5);
+        assert!(fence.leq(0, 1) && fence.leq(2, 1) && fence.leq(2, 3) && fence.leq(4, 3));
+        assert!(!fence.leq(0, 3));
+        fence.find_minimals();
+        assert_eq!(fence.md.minimals, Some([0, 2, 4].into()));
+        assert_eq!(fence.rank_function(), Some(vec![0, 1, 0, 1, 0]));
+
+        let diamond = PosetM::new_diamond();
+        assert!(diamond.is_modular() && !diamond.is_distributive());
+        let pentagon = poseth::PosetH::new_pentagon();
+        assert!(pentagon.is_lattice() && !pentagon.is_modular());
+        assert!(!pentagon.is_graded());
+    }
+
+    fn check_compare<P: Poset>(p: &P, expected: &[&[PartialOrdering]]) {
+        for x in p.elements() {
+            for y in p.elements() {
+                let o = p.compare(x, y);
+                assert_eq!(o, expected[x][y], "compare({}, {})", x, y);
+                assert_eq!(p.compare(y, x), o.reverse());
+                assert_eq!(p.lt(x, y), o == PartialOrdering::Less);
+                assert_eq!(p.gt(x, y), o == PartialOrdering::Greater);
+                assert_eq!(p.geq(x, y), p.leq(y, x));
+                assert_eq!(p.incomparable(x, y), !p.leq(x, y) && !p.leq(y, x));
+                assert_eq!((p.comparator())(&x, &y), o.into());
+            }
+        }
+    }
+
+    #[test]
+    fn test_covers() {
+        for p in corpus::corpus() {
+            let covers = relation::cover_matrix(&relation::strict_matrix(&p));
+            let g = PosetG::from_poset(&p);
+            let h = poseth::PosetH::from_poset(&p);
+            for x in p.elements() {
+                let upper: Elements = p.elements().filter(|&y| covers[x][y]).collect();
+                let lower: Elements = p.elements().filter(|&y| covers[y][x]).collect();
+                assert_eq!(p.upper_covers(x), upper);
+                assert_eq!(p.lower_covers(x), lower);
+                assert_eq!(g.upper_covers(x), upper);
+                assert_eq!(g.lower_covers(x), lower);
+                assert_eq!(h.upper_covers(x), upper);
+                assert_eq!(h.lower_covers(x), lower);
+                for y in p.elements() {
+                    assert_eq!(p.covers(x, y), covers[x][y]);
+                    assert_eq!(h.covers(x, y), covers[x][y]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_relation_streams() {
+        for p in corpus::corpus() {
+            let n = p.md.n;
+            let pairs = (0..n).flat_map(|x| (0..n).map(move |y| (x, y)));
+            let lt: Vec<(AnElement, AnElement)> =
+                pairs.clone().filter(|&(x, y)| p.lt(x, y)).collect();
+            let covers: Vec<(AnElement, AnElement)> =
+                pairs.filter(|&(x, y)| p.covers(x, y)).collect();
+            let reps: Vec<Box<dyn Poset>> = vec![
+                Box::new(PosetM::new(&p.m)),
+                Box::new(PosetG::from_poset(&p)),
+                Box::new(poseth::PosetH::from_poset(&p)),
+                Box::new(poseto::PosetO::from_poset(&p)),
+            ];
+            for q in &reps {
+                assert_eq!(q.relations().collect::<Vec<_>>(), lt);
+                assert_eq!(q.cover_relations().collect::<Vec<_>>(), covers);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_element() {
+        for p in corpus::corpus() {
+            let n = p.md.n;
+            for x in p.elements() {
+                let mut m = PosetM::new(&p.m);
+                m.find_top();
+                m.find_bot();
+                m.find_minimals();
+                m.find_maximals();
+                let h = m.pin(x);
+                let other = m.pin((x + 1) % n);
+                let mut g = PosetG::from_poset(&m);
+                let mut ph = poseth::PosetH::from_poset(&m);
+                m.remove_element(x);
+                g.remove_element(x);
+                ph.remove_element(x);
+                let back = |y: AnElement| if y < x { y } else { y + 1 };
+                assert_eq!(m.md.n, n - 1);
+                for a in m.elements() {
+                    for b in m.elements() {
+                        assert_eq!(m.leq(a, b), p.leq(back(a), back(b)));
+                    }
+                }
+                assert!(g.same_order(&m) && ph.same_order(&m));
+                assert_eq!(m.resolve(&h), None);
+                if n > 1 {
+                    assert_eq!(back(m.resolve(&other).unwrap()), (x + 1) % n);
+                }
+                // Whatever is still known must be right.
+                let mut fresh = PosetM::new(&m.m);
+                fresh.find_top();
+                fresh.find_bot();
+                fresh.find_minimals();
+                fresh.find_maximals();
+                let md = m.md.clone();
+                assert!(md.top.is_none() || md.top == fresh.md.top);
+                assert!(md.bot.is_none() || md.bot == fresh.md.bot);
+                assert!(md.minimals.is_none() || md.minimals == fresh.md.minimals);
+                assert!(md.maximals.is_none() || md.maximals == fresh.md.maximals);
+                assert_eq!(g.md, md);
+            }
+        }
+    }
+
+    #[test]
+    fn test_relabel() {
+        let mut rng = rng::SplitMix64::new(3);
+        for p in corpus::corpus() {
+            let n = p.md.n;
+            let mut perm: Vec<AnElement> = (0..n).collect();
+            for i in (1..n).rev() {
+                perm.swap(i, rng.below(i + 1));
+            }
+            let mut m = PosetM::new(&p.m);
+            m.find_top();
+            m.find_minimals();
+            m.find_height();
+            m.find_atoms();
+            let h = (n > 0).then(|| m.pin(0));
+            let q: poseth::PosetH = poseth::PosetH::from_poset(&m).relabel(&perm);
+            assert!(p
+                .elements()
+                .all(|x| p.elements().all(|y| p.leq(x, y) == q.leq(perm[x], perm[y]))));
+            if let Some(h) = h {
+                assert_eq!(q.resolve(&h), Some(perm[0]));
+            }
+            // The renamed meta data is what the relabeled poset computes.
+            let mut fresh = PosetM::new(&PosetM::from_poset(&q).m);
+            fresh.find_top();
+            fresh.find_minimals();
+            fresh.find_height();
+            fresh.find_atoms();
+            assert_eq!(q.md.top, fresh.md.top);
+            assert_eq!(q.md.minimals, fresh.md.minimals);
+            assert_eq!(q.md.height, fresh.md.height);
+            assert_eq!(q.md.atoms, fresh.md.atoms);
+            assert!(q.is_isomorphic(&p));
+            let mut inverse = vec![0; n];
+            perm.iter().enumerate().for_each(|(x, &y)| inverse[y] = x);
+            assert!(q.relabel(&inverse).same_order(&p));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a permutation")]
+    fn test_relabel_needs_a_permutation() {
+        PosetM::new_chain(3).relabel(&[0, 2, 2]);
+    }
+
+    fn add_random_relations<P>(mut p: P, seed: u64) -> (P, BoolMatrix)
+    where
+        P: Poset + FromPoset<PosetM>,
+    {
+        let n = p.md().n;
+        let mut rng = rng::SplitMix64::new(seed);
+        let mut expected: BoolMatrix = (0..n).map(|x| (0..n).map(|y| x == y).collect()).collect();
+        for _ in 0..2 * n {
+            let (x, y) = (rng.below(n), rng.below(n));
+            let forbidden = x != y && expected[y][x];
+            match p.add_relation(x, y) {
+                Ok(()) => {
+                    assert!(!forbidden);
+                    let above = expected[y].clone();
+                    for row in expected.iter_mut().filter(|row| row[x]) {
+                        row.iter_mut().zip(&above).for_each(|(r, &b)| *r |= b);
+                    }
+                }
+                Err(e) => {
+                    assert!(forbidden);
+                    assert_eq!(e, error::PosetError::NotAntisymmetric { x, y });
+                }
+            }
+        }
+        (p, expected)
+    }
+
+    #[test]
+    fn test_add_relation() {
+        for seed in 0..20 {
+            let (m, expected) = add_random_relations(PosetM::new_antichain(8), seed);
+            let (g, _) = add_random_relations(PosetG::new_antichain(8), seed);
+            let (o, _) = add_random_relations(poseto::PosetO::new_antichain(8), seed);
+            let mut h = poseth::PosetH::from_poset(&PosetM::new_antichain(8));
+            h.find_minimals();
+            let (h, _) = add_random_relations(h, seed);
+            let expected = PosetM::new(&expected);
+            assert_eq!(m.is_valid_poset(), Ok(()));
+            assert!(m.same_order(&expected) && g.same_order(&expected));
+            assert!(o.same_order(&expected) && h.same_order(&expected));
+            let mut fresh = PosetM::new(&expected.m);
+            fresh.find_minimals();
+            assert_eq!(h.md.minimals, fresh.md.minimals);
+        }
+        let mut p = PosetG::new_antichain(3);
+        p.find_top();
+        assert_eq!(p.md.top, Some(Elt::NotPresent));
+        p.add_relation(0, 1).unwrap();
+        p.add_relation(2, 1).unwrap();
+        assert_eq!(p.md.top, None);
+        p.find_top();
+        assert_eq!(p.md.top, Some(Elt::A(1)));
+        assert_eq!(
+            p.add_relation(1, 3),
+            Err(error::PosetError::IndexOutOfRange { index: 3, n: 3 })
+        );
+        assert_eq!(p.add_relation(0, 1), Ok(()));
+        assert_eq!(p.add_relation(0, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_top_and_bot() {
+        let mut p = PosetG::new_chain(3);
+        assert!(p.remove_top());
+        assert!(p.same_order(&PosetG::new_chain(2)));
+        assert!(p.remove_bot());
+        assert!(p.same_order(&PosetG::new_chain(1)));
+        let mut q = PosetM::new_corolla(2);
+        assert!(!q.remove_top());
+        assert_eq!(q.md.n, 3);
+        assert!(q.remove_bot());
+        assert!(q.same_order(&PosetM::new_antichain(2)));
+        q.adjoin_top();
+        assert!(q.remove_top());
+        assert!(q.same_order(&PosetM::new_antichain(2)));
+        let mut empty = PosetM::new_chain(0);
+        assert!(!empty.remove_top() && !empty.remove_bot());
+    }
+
+    #[test]
+    fn test_adjoin_keeps_metadata() {
+        let small = [PosetM::new_chain(0), PosetM::new_chain(1)];
+        for p in corpus::corpus().iter().chain(&small) {
+            for top in [false, true] {
+                corpus::for_each_representation(&[p], |ps| {
+                    let q = &mut ps[0];
+                    if top {
+                        q.adjoin_top();
+                    } else {
+                        q.adjoin_bot();
+                    }
+                    let updated = q.md().clone();
+                    corpus::find_all(q);
+                    assert_eq!(updated, *q.md());
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_op_keeps_metadata() {
+        let small = [PosetM::new_chain(0), PosetM::new_chain(1)];
+        for p in corpus::corpus().iter().chain(&small) {
+            corpus::for_each_representation(&[p], |ps| {
+                let mut q = ps[0].op();
+                let dualized = q.md().clone();
+                corpus::find_all(&mut q);
+                assert_eq!(dualized, *q.md());
+            });
+            let mut m = PosetM::new(&p.m);
+            corpus::find_all(&mut m);
+            let i = incremental::IncrementalPoset::from_poset(&m).op();
+            let mut d = PosetM::new(&p.m);
+            corpus::find_all(&mut d);
+            d.dualize();
+            let m = m.op();
+            assert_eq!(d, m);
+            assert_eq!(i.md, m.md);
+        }
+    }
+
+    #[test]
+    fn test_lazy_metadata() {
+        let mut p = PosetM::new_corolla(3);
+        p.md.invalidate();
+        assert_eq!(p.md, MetaData::new(4));
+        assert_eq!(p.top(), Elt::NotPresent);
+        assert_eq!(p.md.top, Some(Elt::NotPresent));
+        assert_eq!(*p.maximals(), HashSet::from([0, 1, 2]));
+        p.adjoin_top();
+        assert_eq!(p.top(), Elt::A(4));
+        assert_eq!(p.bot(), Elt::A(3));
+        assert_eq!(*p.coatoms(), HashSet::from([0, 1, 2]));
+        assert_eq!(p.md.height, None);
+        // A stored value is trusted.
+        p.md.minimals = Some(HashSet::new());
+        assert!(p.minimals().is_empty());
+        p.md.invalidate();
+        assert_eq!(*p.minimals(), HashSet::from([3]));
+    }
+
+    #[test]
+    fn test_height_and_longest_chain() {
+        for mut p in corpus::corpus() {
+            let mut m = PosetM::new(&p.m);
+            let mut h = poseth::PosetH::from_poset(&p);
+            m.find_height();
+            let height = m.md.height.unwrap();
+            assert_eq!(p.height(), height);
+            assert_eq!(h.height(), height);
+            for chain in [p.longest_chain(), h.longest_chain()] {
+                assert_eq!(chain.len(), height);
+                assert!(chain.windows(2).all(|w| p.covers(w[0], w[1])));
+            }
+        }
+        let mut p = PosetM::new_chain(3);
+        p.md.height = Some(7);
+        assert_eq!(p.height(), 7);
+        assert_eq!(p.longest_chain(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_new_boolean() {
+        for k in 0..5 {
+            let expected = corpus::boolean_lattice(k);
+            let m = PosetM::new_boolean(k);
+            let g = PosetG::new_boolean(k);
+            let h = poseth::PosetH::new_boolean(k);
+            let o = poseto::PosetO::new_boolean(k);
+            assert_eq!(m.m, expected.m);
+            assert!(g.same_order(&expected) && h.same_order(&expected) && o.same_order(&expected));
+            assert_eq!(h.upper_covers(0).len(), k as usize);
+            let mut found = PosetM::new(&m.m);
+            found.find_top();
+            found.find_bot();
+            found.find_minimals();
+            found.find_maximals();
+            found.find_height();
+            for md in [&m.md, &g.md, &h.md, &o.md] {
+                assert_eq!(md, &found.md);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        use PartialOrdering::*;
+
+        // The diamond: 0 and 1 are incomparable, 2 is the bottom, and 3 is the top.
+        let diamond: &[&[PartialOrdering]] = &[
+            &[Equal, Incomparable, Greater, Less],
+            &[Incomparable, Equal, Greater, Less],
+            &[Less, Less, Equal, Less],
+            &[Greater, Greater, Greater, Equal],
+        ];
+        let mut g = PosetG::new_antichain(2);
+        g.adjoin_bot();
+        g.adjoin_top();
+        check_compare(&g, diamond);
+        let mut m = PosetM::new_antichain(2);
+        m.adjoin_bot();
+        m.adjoin_top();
+        check_compare(&m, diamond);
+        let mut h = poseth::PosetH::new_antichain(2);
+        h.adjoin_bot();
+        h.adjoin_top();
+        check_compare(&h, diamond);
+
+        let antichain: Vec<Vec<PartialOrdering>> = (0..3)
+            .map(|x| {
+                (0..3)
+                    .map(|y| if x == y { Equal } else { Incomparable })
+                    .collect()
+            })
+            .collect();
+        let antichain: Vec<&[PartialOrdering]> = antichain.iter().map(|r| r.as_slice()).collect();
+        check_compare(&PosetG::new_antichain(3), &antichain);
+        check_compare(&PosetM::new_antichain(3), &antichain);
+        check_compare(&poseth::PosetH::new_antichain(3), &antichain);
+
+        let cmp = m.comparator();
+        assert!([2, 0, 1, 3].is_sorted_by(|x, y| cmp(x, y) != Some(Ordering::Greater)));
+        assert!(![3, 2].is_sorted_by(|x, y| cmp(x, y) != Some(Ordering::Greater)));
     }
 }
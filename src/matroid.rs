@@ -0,0 +1,158 @@
+//! Geometric lattices and their simple matroids.
+//!
+//! A lattice is *geometric* if it is atomistic and upper semimodular (see [lattice]). The flats of a matroid,
+//! ordered by inclusion, form a geometric lattice, and conversely a geometric lattice is the lattice of flats
+//! of a unique simple matroid (one without loops or parallel elements): its ground set is the set of atoms,
+//! and each element x of the lattice is the flat of the atoms below x. The rank of a flat is its height in
+//! the lattice, i.e., its level.
+//!
+//! The Boolean lattices are the lattices of flats of the free matroids, the partition lattice $\Pi_k$ that
+//! of the graphic matroid of the complete graph on k vertices, and the diamond $M_3$ that of the uniform
+//! matroid $U_{2,3}$ of three points on a line. The pentagon is atomistic but not semimodular.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // The diamond: three points on a line, each pair spanning it.
+//! let m = PosetM::new_diamond().matroid().unwrap();
+//! assert_eq!(m.atoms, vec![0, 1, 2]);
+//! assert_eq!(m.closure(&[0, 2]), vec![0, 1, 2]);
+//! assert!(!PosetM::new_pentagon().is_geometric_lattice());
+//! ```
+
+use crate::lattice;
+use crate::{AnElement, Poset};
+
+/// The simple matroid of a geometric lattice. Its ground set $\{0, ..., k-1\}$ stands for the atoms of the
+/// lattice, and its flats are those of the elements of the lattice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleMatroid {
+    /// The atoms of the lattice in increasing order, element i of the ground set being atoms\[i\].
+    pub atoms: Vec<AnElement>,
+    /// The flat of each element of the lattice, as the sorted indices of the atoms below it.
+    pub flats: Vec<Vec<usize>>,
+}
+
+impl SimpleMatroid {
+    /// The size of the ground set.
+    pub fn k(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// The smallest flat containing the elements of s, sorted. Flats are closed under intersections, so it
+    /// is the flat with the fewest elements among those containing s.
+    pub fn closure(&self, s: &[usize]) -> Vec<usize> {
+        self.flats
+            .iter()
+            .filter(|f| s.iter().all(|i| f.binary_search(i).is_ok()))
+            .min_by_key(|f| f.len())
+            .expect("the ground set is a flat")
+            .clone()
+    }
+}
+
+/// Returns true if p is a geometric lattice, i.e., an atomistic semimodular lattice.
+pub fn is_geometric_lattice<P: Poset + ?Sized>(p: &P) -> bool {
+    p.is_atomistic() && p.is_upper_semimodular()
+}
+
+/// Returns the simple matroid whose lattice of flats is p, or None if p is not a geometric lattice.
+pub fn matroid<P: Poset + ?Sized>(p: &P) -> Option<SimpleMatroid> {
+    if !is_geometric_lattice(p) {
+        return None;
+    }
+    let mut atoms: Vec<AnElement> = lattice::known_atoms(p).into_iter().collect();
+    atoms.sort_unstable();
+    let flats = p
+        .elements()
+        .map(|x| (0..atoms.len()).filter(|&i| p.leq(atoms[i], x)).collect())
+        .collect();
+    Some(SimpleMatroid { atoms, flats })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::families::new_partition_lattice;
+    use crate::lattice::LatticeOps;
+    use crate::posetm::PosetM;
+
+    /// Checks the axioms of flats: the ground set is one, they are closed under intersection, and the
+    /// flats covering any flat partition the elements outside it.
+    fn check_flats(m: &SimpleMatroid) {
+        let contains = |f: &Vec<usize>, i: usize| f.binary_search(&i).is_ok();
+        let subset = |f: &Vec<usize>, g: &Vec<usize>| f.iter().all(|&i| contains(g, i));
+        assert!(m.flats.iter().any(|f| f.len() == m.k()));
+        for f in &m.flats {
+            for g in &m.flats {
+                let meet: Vec<usize> = f.iter().copied().filter(|&i| contains(g, i)).collect();
+                assert!(m.flats.contains(&meet));
+            }
+            let above: Vec<&Vec<usize>> = m
+                .flats
+                .iter()
+                .filter(|g| g.len() > f.len() && subset(f, g))
+                .collect();
+            let covering: Vec<&Vec<usize>> = above
+                .iter()
+                .copied()
+                .filter(|g| !above.iter().any(|h| h.len() < g.len() && subset(h, g)))
+                .collect();
+            for i in (0..m.k()).filter(|&i| !contains(f, i)) {
+                assert_eq!(covering.iter().filter(|g| contains(g, i)).count(), 1);
+            }
+        }
+    }
+
+    /// A finite lattice is semimodular if, and only if, it is graded with a rank function satisfying
+    /// $r(a) + r(b) \ge r(a \vee b) + r(a \wedge b)$.
+    fn brute_force(p: &PosetM) -> bool {
+        let (Some(ops), Some(r)) = (LatticeOps::new(p), p.rank_function()) else {
+            return false;
+        };
+        p.elements().all(|a| {
+            p.elements()
+                .all(|b| r[a] + r[b] >= r[ops.join(a, b)] + r[ops.meet(a, b)])
+        })
+    }
+
+    #[test]
+    fn test_against_rank_inequality() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 16) {
+            assert_eq!(p.is_upper_semimodular(), brute_force(&p));
+            let Some(m) = p.matroid() else {
+                assert!(!p.is_geometric_lattice());
+                continue;
+            };
+            assert!(p.is_geometric_lattice() && p.is_atomistic());
+            check_flats(&m);
+            for x in p.elements() {
+                for y in p.elements() {
+                    let (f, g) = (&m.flats[x], &m.flats[y]);
+                    assert_eq!(p.leq(x, y), f.iter().all(|i| g.contains(i)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_matroids() {
+        // The free matroid on three elements, whose every set is a flat.
+        let m = boolean_lattice(3).matroid().unwrap();
+        assert_eq!(m.atoms, vec![1, 2, 4]);
+        assert_eq!(m.flats[5], vec![0, 2]);
+        assert_eq!(m.closure(&[1]), vec![1]);
+        // The graphic matroid of the complete graph on four vertices: six edges, and a triangle is
+        // spanned by any two of its edges.
+        let pi4 = new_partition_lattice::<PosetM>(4);
+        let m = pi4.poset().matroid().unwrap();
+        assert_eq!(m.k(), 6);
+        check_flats(&m);
+        assert_eq!(m.flats.iter().filter(|f| f.len() == 3).count(), 4);
+        assert!(PosetM::new_chain(2).is_geometric_lattice());
+        assert!(!PosetM::new_chain(3).is_geometric_lattice());
+        assert!(PosetM::new_chain(0).matroid().is_none());
+    }
+}
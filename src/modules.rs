@@ -0,0 +1,238 @@
+//! Modules (autonomous sets) and the modular decomposition of a poset.
+//!
+//! A *module* is a set M of elements that every element outside it sees in the same way: it is below all
+//! of M, above all of M, or incomparable to all of M. The empty set, the singletons, and the whole poset
+//! are modules, and the poset is *prime* if it has no others. A module is *strong* if it overlaps no
+//! other module, i.e., if every module meeting it contains it or is contained in it. The strong modules
+//! form a tree under inclusion, whose leaves are the singletons and whose root is the whole poset. Each
+//! inner node M falls into one of three cases:
+//!
+//! - *parallel*: M is disconnected, and its children are its connected components;
+//! - *series*: the incomparability graph of M is disconnected, and its children are the components of that
+//!   graph, each entirely below the next;
+//! - *prime*: its children are the maximal proper submodules of M, which partition it, and the poset that
+//!   M induces on them is prime.
+//!
+//! The series-parallel posets (see [series_parallel](crate::series_parallel)) are those whose tree has no
+//! prime nodes. Problems such as isomorphism or counting linear extensions split along the tree, being
+//! solved for the prime nodes and combined over the others.
+//!
+//! ```
+//! use fin_pos::modules::ModuleTree;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // The fence 0 < 1 > 2 < 3 is prime, and doubling its top element gives a module {3, 4}.
+//! let mut p = PosetM::new_fence(4).coproduct(&PosetM::new_chain(1));
+//! p.add_relation(2, 4).unwrap();
+//! assert!(!p.is_series_parallel());
+//! let tree = p.modular_decomposition();
+//! assert!(matches!(tree, ModuleTree::Prime(_)));
+//! assert!(p.modules().contains(&vec![3, 4]));
+//! ```
+
+use crate::relation::{components, strict_matrix};
+use crate::{AnElement, Poset};
+
+/// The tree of strong modules, as described in the [module level documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ModuleTree {
+    /// A single element.
+    Leaf(AnElement),
+    /// The connected components, listed by their smallest elements. The empty poset is the parallel node
+    /// without children.
+    Parallel(Vec<ModuleTree>),
+    /// The components of the incomparability graph, listed from the bottom up.
+    Series(Vec<ModuleTree>),
+    /// The maximal proper submodules, listed by their smallest elements.
+    Prime(Vec<ModuleTree>),
+}
+
+impl ModuleTree {
+    /// The elements at the leaves, in the order of the tree.
+    pub fn elements(&self) -> Vec<AnElement> {
+        match self {
+            ModuleTree::Leaf(x) => vec![*x],
+            ModuleTree::Parallel(children)
+            | ModuleTree::Series(children)
+            | ModuleTree::Prime(children) => {
+                children.iter().flat_map(ModuleTree::elements).collect()
+            }
+        }
+    }
+
+    /// The strong modules, each sorted, with every module listed before its submodules.
+    pub fn modules(&self) -> Vec<Vec<AnElement>> {
+        let mut elements = self.elements();
+        if elements.is_empty() {
+            return vec![];
+        }
+        elements.sort_unstable();
+        let mut modules = vec![elements];
+        if let ModuleTree::Parallel(children)
+        | ModuleTree::Series(children)
+        | ModuleTree::Prime(children) = self
+        {
+            modules.extend(children.iter().flat_map(ModuleTree::modules));
+        }
+        modules
+    }
+}
+
+/// Returns true if every element of p outside s is below all of s, above all of s, or incomparable to
+/// all of s.
+pub fn is_module<P: Poset + ?Sized>(p: &P, s: &[AnElement]) -> bool {
+    let Some(&first) = s.first() else {
+        return true;
+    };
+    p.elements()
+        .filter(|z| !s.contains(z))
+        .all(|z| s.iter().all(|&x| p.compare(z, x) == p.compare(z, first)))
+}
+
+/// Returns the modular decomposition tree of p. The children of a prime node are found by growing the
+/// smallest module around every pair of its elements, which takes $O(m^4)$ steps for a node of m elements
+/// in the worst case.
+pub fn modular_decomposition<P: Poset + ?Sized>(p: &P) -> ModuleTree {
+    let lt = strict_matrix(p);
+    let all: Vec<AnElement> = (0..lt.len()).collect();
+    if all.is_empty() {
+        return ModuleTree::Parallel(vec![]);
+    }
+    decompose(&lt, &all)
+}
+
+fn decompose(lt: &[Vec<bool>], s: &[AnElement]) -> ModuleTree {
+    if let [x] = s {
+        return ModuleTree::Leaf(*x);
+    }
+    let comparable = |x: AnElement, y: AnElement| lt[x][y] || lt[y][x];
+    let children = |parts: Vec<Vec<AnElement>>| parts.iter().map(|c| decompose(lt, c)).collect();
+    let parts = components(s, comparable);
+    if parts.len() > 1 {
+        return ModuleTree::Parallel(children(parts));
+    }
+    let mut parts = components(s, |x, y| x != y && !comparable(x, y));
+    if parts.len() > 1 {
+        parts.sort_by(|a, b| match lt[a[0]][b[0]] {
+            true => std::cmp::Ordering::Less,
+            false => std::cmp::Ordering::Greater,
+        });
+        return ModuleTree::Series(children(parts));
+    }
+    // Both s and its complement are connected, so its maximal proper modules partition it, and the one
+    // containing x holds every y whose smallest module together with x is proper.
+    let mut parts: Vec<Vec<AnElement>> = vec![];
+    let mut placed = vec![false; s.len()];
+    for i in 0..s.len() {
+        if placed[i] {
+            continue;
+        }
+        let mut part = vec![s[i]];
+        placed[i] = true;
+        for j in i + 1..s.len() {
+            if !placed[j] && closure(lt, s, &[s[i], s[j]]).len() < s.len() {
+                placed[j] = true;
+                part.push(s[j]);
+            }
+        }
+        parts.push(part);
+    }
+    ModuleTree::Prime(children(parts))
+}
+
+/// The smallest module of the subposet induced on s that contains the elements of seed.
+fn closure(lt: &[Vec<bool>], s: &[AnElement], seed: &[AnElement]) -> Vec<AnElement> {
+    // How z relates to x: below, above, or incomparable.
+    let relation = |z: AnElement, x: AnElement| (lt[z][x], lt[x][z]);
+    let first = seed[0];
+    let mut inside: Vec<AnElement> = vec![first];
+    let mut pending: Vec<AnElement> = seed[1..].to_vec();
+    while let Some(x) = pending.pop() {
+        if inside.contains(&x) {
+            continue;
+        }
+        inside.push(x);
+        // An element outside that sees x and the first element differently must join the module.
+        pending.extend(
+            s.iter()
+                .copied()
+                .filter(|z| !inside.contains(z) && relation(*z, x) != relation(*z, first)),
+        );
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetm::PosetM;
+    use crate::series_parallel::SpTree;
+
+    /// The strong modules found by checking every subset.
+    fn brute_force(p: &PosetM) -> Vec<Vec<AnElement>> {
+        let n = p.md.n;
+        let modules: Vec<u32> = (1..1u32 << n)
+            .filter(|&m| {
+                let s: Vec<AnElement> = (0..n).filter(|&x| m >> x & 1 == 1).collect();
+                is_module(p, &s)
+            })
+            .collect();
+        let mut strong: Vec<Vec<AnElement>> = modules
+            .iter()
+            .filter(|&&m| {
+                modules
+                    .iter()
+                    .all(|&o| m & o == 0 || m & o == m || m & o == o)
+            })
+            .map(|&m| (0..n).filter(|&x| m >> x & 1 == 1).collect())
+            .collect();
+        strong.sort();
+        strong
+    }
+
+    fn same_shape(t: &ModuleTree, s: &SpTree) -> bool {
+        match (t, s) {
+            (ModuleTree::Leaf(x), SpTree::Leaf(y)) => x == y,
+            (ModuleTree::Parallel(a), SpTree::Parallel(b))
+            | (ModuleTree::Series(a), SpTree::Series(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(t, s)| same_shape(t, s))
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_against_subsets() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 9) {
+            let mut modules = p.modules();
+            assert!(modules.iter().all(|m| is_module(&p, m)));
+            modules.sort();
+            assert_eq!(modules, brute_force(&p));
+        }
+    }
+
+    #[test]
+    fn test_generalizes_series_parallel() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 30) {
+            let tree = p.modular_decomposition();
+            match p.sp_decomposition() {
+                Some(sp) => assert!(same_shape(&tree, &sp)),
+                None => assert!(format!("{:?}", tree).contains("Prime")),
+            }
+        }
+        assert_eq!(
+            PosetM::new_chain(0).modular_decomposition(),
+            ModuleTree::Parallel(vec![])
+        );
+        assert!(PosetM::new_chain(0).modules().is_empty());
+        // The fence of four elements is prime.
+        let fence = PosetM::new_fence(4);
+        assert_eq!(
+            fence.modular_decomposition(),
+            ModuleTree::Prime((0..4).map(ModuleTree::Leaf).collect())
+        );
+        assert!(!is_module(&fence, &[0, 1]));
+    }
+}
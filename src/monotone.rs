@@ -0,0 +1,472 @@
+//! Monotone maps, the morphisms of the category of finite posets.
+//!
+//! A map $f\colon P \to Q$ is monotone (order-preserving) if $x \le y$ implies $f(x) \le f(y)$. A
+//! [MonotoneMap] borrows its source and target and stores the image of every element of the source; it can
+//! only be built from images that are in range and in order. It is an *embedding* if, moreover,
+//! $f(x) \le f(y)$ implies $x \le y$ (and then it is injective), and an *isomorphism* if it is a
+//! surjective embedding.
+//!
+//! ```
+//! use fin_pos::monotone::MonotoneMap;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let chain = PosetM::new_chain(3);
+//! let square = PosetM::new_boolean(2);
+//! // 0 < 1 < 2 onto the bottom, an atom, and the top.
+//! let f = MonotoneMap::new(&chain, &square, vec![0, 1, 3]).unwrap();
+//! assert!(f.is_embedding() && !f.is_surjective());
+//! assert!(MonotoneMap::new(&chain, &square, vec![1, 2, 3]).is_err());
+//! ```
+//!
+//! [monotone_maps] lists all the monotone maps between two posets. Counting those into the chain of $m$
+//! elements evaluates the order polynomial $\Omega(P, m)$ of the source.
+//!
+//! ```
+//! use fin_pos::monotone::monotone_maps;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let vee = PosetM::new_corolla(2);
+//! // A bottom and two elements above it: the sum of k^2 for k = 1, ..., m.
+//! assert_eq!(monotone_maps(&vee, &PosetM::new_chain(4)).count(), 30);
+//! ```
+//!
+//! Ordered pointwise, by $f \le g$ if $f(x) \le g(x)$ for all $x$, the monotone maps from $P$ to $Q$ form
+//! the poset [hom_poset] $Q^P$. It is the exponential of the category: monotone maps $R \times P \to Q$
+//! correspond to monotone maps $R \to Q^P$.
+//!
+//! ```
+//! use fin_pos::monotone::hom_poset_with_maps;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // The monotone maps from a 2-chain to itself: the two constants, and the identity in between.
+//! let (hom, maps) = hom_poset_with_maps(&PosetM::new_chain(2), &PosetM::new_chain(2));
+//! assert_eq!(maps, vec![vec![0, 0], vec![0, 1], vec![1, 1]]);
+//! assert!(hom.leq(0, 1) && hom.leq(1, 2));
+//! ```
+
+use crate::error::PosetError;
+use crate::posetg::PosetG;
+use crate::relation::{cover_matrix, strict_matrix, topological_order};
+use crate::{AnElement, BiPaGraph, Poset};
+
+/// An order-preserving map from source to target.
+#[derive(Debug)]
+pub struct MonotoneMap<'a, P: ?Sized, Q: ?Sized> {
+    source: &'a P,
+    target: &'a Q,
+    images: Vec<AnElement>,
+}
+
+impl<P: ?Sized, Q: ?Sized> Clone for MonotoneMap<'_, P, Q> {
+    fn clone(&self) -> Self {
+        MonotoneMap {
+            source: self.source,
+            target: self.target,
+            images: self.images.clone(),
+        }
+    }
+}
+
+impl<'a, P: Poset + ?Sized, Q: Poset + ?Sized> MonotoneMap<'a, P, Q> {
+    /// Returns the map sending element x of source to images\[x\], or the first reason it is not a
+    /// monotone map: an element without an image, an image out of range, or a pair out of order.
+    pub fn new(
+        source: &'a P,
+        target: &'a Q,
+        images: Vec<AnElement>,
+    ) -> Result<MonotoneMap<'a, P, Q>, PosetError> {
+        let (n, m) = (source.md().n, target.md().n);
+        if images.len() < n {
+            return Err(PosetError::Missing { x: images.len() });
+        }
+        if images.len() > n {
+            return Err(PosetError::IndexOutOfRange { index: n, n });
+        }
+        if let Some(&index) = images.iter().find(|&&y| y >= m) {
+            return Err(PosetError::IndexOutOfRange { index, n: m });
+        }
+        for x in source.elements() {
+            if let Some(y) = source
+                .elements()
+                .find(|&y| source.leq(x, y) && !target.leq(images[x], images[y]))
+            {
+                return Err(PosetError::NotMonotone { x, y });
+            }
+        }
+        Ok(MonotoneMap {
+            source,
+            target,
+            images,
+        })
+    }
+
+    /// The source poset.
+    pub fn source(&self) -> &'a P {
+        self.source
+    }
+
+    /// The target poset.
+    pub fn target(&self) -> &'a Q {
+        self.target
+    }
+
+    /// The image of x.
+    pub fn image(&self, x: AnElement) -> AnElement {
+        self.images[x]
+    }
+
+    /// The images of the elements of the source, in order.
+    pub fn images(&self) -> &[AnElement] {
+        &self.images
+    }
+
+    /// Returns the map that applies self and then g. Panics unless g starts at the target of self.
+    pub fn compose<R: Poset + ?Sized>(&self, g: &MonotoneMap<'a, Q, R>) -> MonotoneMap<'a, P, R> {
+        assert!(
+            std::ptr::eq(self.target, g.source),
+            "the maps do not meet at the same poset"
+        );
+        MonotoneMap {
+            source: self.source,
+            target: g.target,
+            images: self.images.iter().map(|&y| g.images[y]).collect(),
+        }
+    }
+
+    /// Returns true if distinct elements have distinct images.
+    pub fn is_injective(&self) -> bool {
+        let mut hit = vec![false; self.target.md().n];
+        self.images
+            .iter()
+            .all(|&y| !std::mem::replace(&mut hit[y], true))
+    }
+
+    /// Returns true if every element of the target is an image.
+    pub fn is_surjective(&self) -> bool {
+        let mut hit = vec![false; self.target.md().n];
+        self.images.iter().for_each(|&y| hit[y] = true);
+        hit.into_iter().all(|h| h)
+    }
+
+    /// Returns true if $f(x) \le f(y)$ holds only when $x \le y$, i.e., the source is isomorphic to its
+    /// image with the order of the target.
+    pub fn is_embedding(&self) -> bool {
+        self.source.elements().all(|x| {
+            self.source
+                .elements()
+                .all(|y| self.source.leq(x, y) || !self.target.leq(self.images[x], self.images[y]))
+        })
+    }
+
+    /// Returns true if the map is a surjective embedding, so that its inverse is monotone too.
+    pub fn is_isomorphism(&self) -> bool {
+        self.is_surjective() && self.is_embedding()
+    }
+}
+
+impl<'a, P: Poset + ?Sized> MonotoneMap<'a, P, P> {
+    /// The identity map of p.
+    pub fn identity(p: &'a P) -> MonotoneMap<'a, P, P> {
+        MonotoneMap {
+            source: p,
+            target: p,
+            images: p.elements().collect(),
+        }
+    }
+}
+
+/// Iterates over the monotone maps from p to q. The elements of p are assigned images along a linear
+/// extension, each only trying the images above those of the elements it covers, so every partial
+/// assignment extends to at least one map when q has a top, and branches that die out are cut at the first
+/// element without an image.
+pub fn monotone_maps<'a, P, Q>(p: &'a P, q: &'a Q) -> impl Iterator<Item = MonotoneMap<'a, P, Q>>
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+{
+    let lt = strict_matrix(p);
+    let covers = cover_matrix(&lt);
+    let order = topological_order(&lt);
+    let mut position = vec![0; order.len()];
+    order.iter().enumerate().for_each(|(i, &x)| position[x] = i);
+    let below = order
+        .iter()
+        .map(|&x| {
+            order
+                .iter()
+                .filter(|&&y| covers[y][x])
+                .map(|&y| position[y])
+                .collect()
+        })
+        .collect();
+    MonotoneMaps {
+        source: p,
+        target: q,
+        cursors: vec![0; order.len()],
+        order,
+        below,
+        assigned: vec![],
+        exhausted: false,
+    }
+}
+
+/// Returns the poset of the monotone maps from p to q ordered pointwise. The maps are numbered in the order
+/// of [monotone_maps]; [hom_poset_with_maps] also returns them.
+pub fn hom_poset<P, Q>(p: &P, q: &Q) -> PosetG
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+{
+    hom_poset_with_maps(p, q).0
+}
+
+/// Returns the poset of the monotone maps from p to q ordered pointwise, together with the images of the
+/// map each of its elements stands for.
+pub fn hom_poset_with_maps<P, Q>(p: &P, q: &Q) -> (PosetG, Vec<Vec<AnElement>>)
+where
+    P: Poset + ?Sized,
+    Q: Poset + ?Sized,
+{
+    let maps: Vec<Vec<AnElement>> = monotone_maps(p, q).map(|f| f.images).collect();
+    let below = |f: &[AnElement], g: &[AnElement]| f.iter().zip(g).all(|(&a, &b)| q.leq(a, b));
+    let g: BiPaGraph = maps
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let up = (0..maps.len()).filter(|&j| below(f, &maps[j]));
+            (i, up.collect())
+        })
+        .collect();
+    (PosetG::new(&g), maps)
+}
+
+struct MonotoneMaps<'a, P: ?Sized, Q: ?Sized> {
+    source: &'a P,
+    target: &'a Q,
+    /// The elements of the source along a linear extension.
+    order: Vec<AnElement>,
+    /// For each position of order, the positions of the elements it covers, which come before it.
+    below: Vec<Vec<usize>>,
+    /// The images of a prefix of order.
+    assigned: Vec<AnElement>,
+    /// The next image to try for each position of order.
+    cursors: Vec<AnElement>,
+    exhausted: bool,
+}
+
+impl<'a, P: Poset + ?Sized, Q: Poset + ?Sized> MonotoneMaps<'a, P, Q> {
+    fn current(&self) -> MonotoneMap<'a, P, Q> {
+        let mut images = vec![0; self.order.len()];
+        self.order
+            .iter()
+            .zip(&self.assigned)
+            .for_each(|(&x, &y)| images[x] = y);
+        MonotoneMap {
+            source: self.source,
+            target: self.target,
+            images,
+        }
+    }
+}
+
+impl<'a, P: Poset + ?Sized, Q: Poset + ?Sized> Iterator for MonotoneMaps<'a, P, Q> {
+    type Item = MonotoneMap<'a, P, Q>;
+
+    fn next(&mut self) -> Option<MonotoneMap<'a, P, Q>> {
+        let (n, m) = (self.order.len(), self.target.md().n);
+        if self.exhausted {
+            return None;
+        }
+        if n == 0 {
+            self.exhausted = true;
+            return Some(self.current());
+        }
+        loop {
+            let i = self.assigned.len();
+            let fits = |y: &AnElement| {
+                self.below[i]
+                    .iter()
+                    .all(|&j| self.target.leq(self.assigned[j], *y))
+            };
+            match (self.cursors[i]..m).find(fits) {
+                Some(y) => {
+                    self.cursors[i] = y + 1;
+                    self.assigned.push(y);
+                    if i + 1 == n {
+                        let map = self.current();
+                        self.assigned.pop();
+                        return Some(map);
+                    }
+                    self.cursors[i + 1] = 0;
+                }
+                None if i == 0 => {
+                    self.exhausted = true;
+                    return None;
+                }
+                None => {
+                    self.assigned.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::canonical_form;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_validation() {
+        let chain = PosetM::new_chain(3);
+        let anti = PosetM::new_antichain(2);
+        assert_eq!(
+            MonotoneMap::new(&chain, &anti, vec![0, 0]).unwrap_err(),
+            PosetError::Missing { x: 2 }
+        );
+        assert_eq!(
+            MonotoneMap::new(&chain, &anti, vec![0, 0, 0, 0]).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 3, n: 3 }
+        );
+        assert_eq!(
+            MonotoneMap::new(&chain, &anti, vec![0, 2, 0]).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 2, n: 2 }
+        );
+        assert_eq!(
+            MonotoneMap::new(&chain, &anti, vec![0, 1, 1]).unwrap_err(),
+            PosetError::NotMonotone { x: 0, y: 1 }
+        );
+        // Constant maps are monotone, and anything goes out of an antichain.
+        let f = MonotoneMap::new(&chain, &anti, vec![1, 1, 1]).unwrap();
+        assert!(!f.is_injective() && !f.is_surjective() && !f.is_embedding());
+        let g = MonotoneMap::new(&anti, &chain, vec![2, 0]).unwrap();
+        assert!(g.is_injective() && !g.is_embedding());
+    }
+
+    #[test]
+    fn test_composition_and_identity() {
+        let b = boolean_lattice(3);
+        let chain = PosetM::new_chain(4);
+        // The number of elements of a subset, from the subsets of {0, 1, 2} onto 0 < 1 < 2 < 3.
+        let size = MonotoneMap::new(
+            &b,
+            &chain,
+            (0..8usize).map(|s| s.count_ones() as usize).collect(),
+        )
+        .unwrap();
+        assert!(size.is_surjective() && !size.is_injective());
+        let id = MonotoneMap::identity(&b);
+        assert!(id.is_isomorphism());
+        assert_eq!(id.compose(&size).images(), size.images());
+        assert_eq!(
+            size.compose(&MonotoneMap::identity(&chain)).images(),
+            size.images()
+        );
+        let back = MonotoneMap::new(&chain, &b, vec![0, 1, 3, 7]).unwrap();
+        let round = back.compose(&size);
+        assert!(round.is_isomorphism());
+        assert_eq!(round.images(), MonotoneMap::identity(&chain).images());
+        assert!(!size.compose(&back).is_injective());
+    }
+
+    #[test]
+    fn test_isomorphisms_of_corpus() {
+        for p in corpus() {
+            let (q, back): (PosetM, _) = canonical_form(&p);
+            let mut to = vec![0; back.len()];
+            back.iter().enumerate().for_each(|(i, &x)| to[x] = i);
+            let f = MonotoneMap::new(&p, &q, to).unwrap();
+            assert!(f.is_isomorphism());
+            assert!(f
+                .compose(&MonotoneMap::new(&q, &p, back).unwrap())
+                .is_isomorphism());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_compose_mismatch() {
+        let (a, b) = (PosetM::new_chain(2), PosetM::new_chain(2));
+        let f = MonotoneMap::identity(&a);
+        f.compose(&MonotoneMap::identity(&b));
+    }
+
+    #[test]
+    fn test_all_monotone_maps() {
+        let small: Vec<PosetM> = corpus().into_iter().filter(|p| p.md.n <= 4).collect();
+        for p in &small {
+            for q in &small {
+                let (n, m) = (p.md.n, q.md.n);
+                let maps: Vec<Vec<AnElement>> = monotone_maps(p, q)
+                    .map(|f| MonotoneMap::new(p, q, f.images().to_vec()).unwrap())
+                    .map(|f| f.images().to_vec())
+                    .collect();
+                let brute = (0..m.pow(n as u32))
+                    .map(|k| (0..n).map(|x| k / m.pow(x as u32) % m).collect::<Vec<_>>())
+                    .filter(|images| MonotoneMap::new(p, q, images.clone()).is_ok())
+                    .count();
+                assert_eq!(maps.len(), brute);
+                let mut distinct = maps.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+                assert_eq!(distinct.len(), maps.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_order_polynomial_and_automorphisms() {
+        let chain = |m| PosetM::new_chain(m);
+        // Into a chain: m^n from an antichain, and multisets of size k from a k-chain.
+        assert_eq!(
+            monotone_maps(&PosetM::new_antichain(3), &chain(4)).count(),
+            64
+        );
+        assert_eq!(monotone_maps(&chain(3), &chain(4)).count(), 20);
+        assert_eq!(
+            monotone_maps(&chain(3), &PosetM::new_antichain(0)).count(),
+            0
+        );
+        let b = boolean_lattice(3);
+        let automorphisms = monotone_maps(&b, &b).filter(|f| f.is_isomorphism()).count();
+        assert_eq!(automorphisms, 6);
+    }
+
+    #[test]
+    fn test_hom_poset() {
+        let chain = PosetM::new_chain(2);
+        let small: Vec<PosetM> = corpus().into_iter().filter(|p| p.md.n <= 3).collect();
+        for p in &small {
+            let (hom, maps) = hom_poset_with_maps(p, &chain);
+            assert_eq!(hom.is_valid_poset(), Ok(()));
+            assert!(maps
+                .iter()
+                .all(|f| MonotoneMap::new(p, &chain, f.clone()).is_ok()));
+            // A map into 0 < 1 is the up-set of the elements sent to 1.
+            let (j, _) = p.ideal_lattice();
+            assert!(hom.is_isomorphic(&j.op()));
+            // Maps out of a point are the elements of the target.
+            let points = hom_poset(&PosetM::new_chain(1), p);
+            assert!(points.is_isomorphic(p));
+            for q in &small {
+                // Exponentials turn coproducts into products.
+                let from_sum = hom_poset(&PosetM::new_antichain(2), q);
+                assert!(from_sum.is_isomorphic(&q.product(q)));
+                // Currying: maps from p x r to q are maps from p to the maps from r to q.
+                if p.md.n + q.md.n > 4 {
+                    continue;
+                }
+                let r = PosetM::new_corolla(2);
+                let uncurried = hom_poset(&p.product(&r), q);
+                let inner = hom_poset(&r, q);
+                let curried = hom_poset(p, &inner);
+                assert!(uncurried.is_isomorphic(&curried));
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! The order polynomial $\Omega(P, k)$, the number of monotone maps from a poset $P$ to the chain of $k$
+//! elements.
+//!
+//! The preimages $f^{-1}(\{0, ..., i\})$ of a monotone map $f$ onto the chain $0 < 1 < ... < j-1$ form a
+//! strictly increasing chain of down-sets from $\emptyset$ to $P$ of length $j$, and every such chain comes
+//! from exactly one map. A map into the $k$-chain is onto a $j$-element subchain, so
+//! $$\Omega(P, k) = \sum_{j} c_j \binom{k}{j},$$
+//! where $c_j$ counts the chains of $j$ steps in the lattice of down-sets. The coefficients $c_j$ are
+//! integers, unlike those of the powers of $k$ (already $\Omega$ of a 2-chain is $(k^2 + k)/2$), and they
+//! are computed by dynamic programming over the down-sets, so the lattice of down-sets must be small
+//! enough to list.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let vee = PosetM::new_corolla(2);
+//! // A bottom below two elements maps onto the 1-chain in one way, onto the 2-chain in three ways (all
+//! // three elements but a top to 0), and onto the 3-chain in two ways.
+//! assert_eq!(vee.order_polynomial_coeffs(), vec![0, 1, 3, 2]);
+//! assert_eq!(vee.order_polynomial(4), 30);
+//! ```
+
+use crate::bitset::BitMatrix;
+use crate::ideals::all_downsets;
+use crate::Poset;
+
+/// Returns the coefficients $c_0, ..., c_n$ of the order polynomial of p in the basis of the binomial
+/// coefficients $\binom{k}{j}$: $c_j$ is the number of monotone maps from p onto the $j$-chain. Panics if one
+/// does not fit in an `i128`.
+pub fn order_polynomial_coeffs<P: Poset + ?Sized>(p: &P) -> Vec<i128> {
+    onto_chain_counts(p)
+        .into_iter()
+        .map(|c| i128::try_from(c).expect("the order polynomial does not fit in an i128"))
+        .collect()
+}
+
+/// The numbers of monotone maps from p onto the $j$-chains, for $j = 0, ..., n$.
+fn onto_chain_counts<P: Poset + ?Sized>(p: &P) -> Vec<u128> {
+    let downsets = all_downsets(&BitMatrix::new(p));
+    let n = p.md().n;
+    // chains[d][j]: the strict chains of down-sets from the empty one to downsets[d] in j steps.
+    let mut chains: Vec<Vec<u128>> = Vec::with_capacity(downsets.len());
+    for (d, dset) in downsets.iter().enumerate() {
+        let mut row = vec![0u128; n + 1];
+        if d == 0 {
+            row[0] = 1;
+        }
+        for (e, eset) in downsets[..d].iter().enumerate() {
+            if eset.is_subset(dset) {
+                for j in 1..=n {
+                    row[j] = row[j]
+                        .checked_add(chains[e][j - 1])
+                        .expect("the order polynomial does not fit in a u128");
+                }
+            }
+        }
+        chains.push(row);
+    }
+    chains.pop().unwrap()
+}
+
+/// Returns $\Omega(p, k)$, the number of monotone maps from p to the chain of k elements. Panics if it
+/// does not fit in a `u128`.
+pub fn order_polynomial<P: Poset + ?Sized>(p: &P, k: usize) -> u128 {
+    let overflow = "the order polynomial does not fit in a u128";
+    let mut binomial: u128 = 1;
+    let mut total: u128 = 0;
+    for (j, c) in onto_chain_counts(p).into_iter().enumerate() {
+        if j > k {
+            break;
+        }
+        if j > 0 {
+            binomial = binomial.checked_mul((k - j + 1) as u128).expect(overflow) / j as u128;
+        }
+        total = c
+            .checked_mul(binomial)
+            .and_then(|t| t.checked_add(total))
+            .expect(overflow);
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::monotone::monotone_maps;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_counts_maps_into_chains() {
+        for p in corpus().into_iter().filter(|p| p.md.n <= 6) {
+            let coeffs = p.order_polynomial_coeffs();
+            assert_eq!(coeffs.len(), p.md.n + 1);
+            // The maps onto the n-chain are the linear extensions.
+            assert_eq!(coeffs[p.md.n], p.count_linear_extensions() as i128);
+            for k in 0..5 {
+                let chain = PosetM::new_chain(k);
+                assert_eq!(
+                    p.order_polynomial(k),
+                    monotone_maps(&p, &chain).count() as u128
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_polynomials() {
+        // k^n for an antichain, and binomial(k + n - 1, n) for a chain.
+        assert_eq!(PosetM::new_antichain(5).order_polynomial(7), 16807);
+        assert_eq!(PosetM::new_chain(5).order_polynomial(7), 462);
+        assert_eq!(PosetM::new_chain(0).order_polynomial_coeffs(), vec![1]);
+        assert_eq!(PosetM::new_chain(0).order_polynomial(0), 1);
+        assert_eq!(PosetM::new_chain(2).order_polynomial(0), 0);
+        assert_eq!(
+            PosetM::new_antichain(2).order_polynomial_coeffs(),
+            vec![0, 1, 2]
+        );
+        // The square: pairs of monotone maps into the k-chain, one below the other.
+        assert_eq!(boolean_lattice(2).order_polynomial(3), 20);
+        assert_eq!(
+            PosetM::new_antichain(8).order_polynomial(100),
+            100u128.pow(8)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Maps over independent indices, on all threads with the `rayon` feature and in order without it.
+//!
+//! The bounds are the same either way, so that code calling these compiles identically with and without
+//! the feature.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Returns `(0..n).map(f)`, evaluating f on several threads with the `rayon` feature.
+pub(crate) fn map_range<T, F>(n: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        (0..n).into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        (0..n).map(f).collect()
+    }
+}
+
+/// Applies f to every item, on several threads with the `rayon` feature.
+pub(crate) fn for_each_mut<T, F>(items: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(&mut T) + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        items.par_iter_mut().for_each(f)
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        items.iter_mut().for_each(f)
+    }
+}
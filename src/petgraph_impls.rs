@@ -0,0 +1,145 @@
+//! Conversions to and from [petgraph] graphs, enabled by the `petgraph` feature.
+//!
+//! A poset becomes the [DiGraph] of its Hasse diagram: node i carries the element i, and there is an edge
+//! from x to y for each cover $x \lessdot y$, added in sorted order. In the other direction, the nodes of
+//! any [DiGraph] are taken as the elements, numbered by their [NodeIndex](petgraph::graph::NodeIndex), and
+//! $x \le y$ holds when a path leads from x to y. The conversion fails with [PosetError::Cycle] if the
+//! edges form a cycle, self-loops included, and ignores the weights.
+//!
+//! ```
+//! use fin_pos::poseth::PosetH;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//! use petgraph::graph::DiGraph;
+//!
+//! let mut g = DiGraph::<&str, ()>::new();
+//! let [a, b, c] = ["a", "b", "c"].map(|x| g.add_node(x));
+//! g.extend_with_edges([(a, b), (b, c), (a, c)]);
+//! let p = PosetH::try_from(&g).unwrap();
+//! assert!(p.same_order(&PosetM::new_chain(3)));
+//!
+//! let h = DiGraph::<usize, ()>::from(&p);
+//! assert_eq!(h.edge_count(), 2);
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::posetg::PosetG;
+use crate::poseth::PosetH;
+use crate::posetm::PosetM;
+use crate::smallset::SmallSet;
+use crate::validate::check_acyclic;
+use crate::{Hasse, Poset};
+
+use petgraph::graph::DiGraph;
+use petgraph::visit::EdgeRef;
+
+fn to_digraph<P: Poset + ?Sized>(p: &P) -> DiGraph<usize, ()> {
+    let mut g = DiGraph::with_capacity(p.md().n, 0);
+    for x in p.elements() {
+        g.add_node(x);
+    }
+    g.extend_with_edges(
+        p.layout_edges()
+            .into_iter()
+            .map(|(x, y)| (x as u32, y as u32)),
+    );
+    g
+}
+
+/// The order generated by the edges of g, in the representation P.
+fn from_digraph<N, E, P: FromPoset<PosetH>>(g: &DiGraph<N, E>) -> Result<P, PosetError> {
+    let mut h: Hasse = vec![SmallSet::new(); g.node_count()];
+    for e in g.edge_references() {
+        h[e.source().index()].insert(e.target().index());
+    }
+    check_acyclic(&h, g.node_count())?;
+    Ok(P::from_poset(&PosetH::from_covers(h)))
+}
+
+impl From<&PosetH> for DiGraph<usize, ()> {
+    fn from(p: &PosetH) -> Self {
+        to_digraph(p)
+    }
+}
+
+impl From<&PosetM> for DiGraph<usize, ()> {
+    fn from(p: &PosetM) -> Self {
+        to_digraph(p)
+    }
+}
+
+impl From<&PosetG> for DiGraph<usize, ()> {
+    fn from(p: &PosetG) -> Self {
+        to_digraph(p)
+    }
+}
+
+/// The covers are the edges of g that are not bypassed by a longer path.
+impl<N, E> TryFrom<&DiGraph<N, E>> for PosetH {
+    type Error = PosetError;
+
+    fn try_from(g: &DiGraph<N, E>) -> Result<Self, PosetError> {
+        from_digraph(g)
+    }
+}
+
+impl<N, E> TryFrom<&DiGraph<N, E>> for PosetM {
+    type Error = PosetError;
+
+    fn try_from(g: &DiGraph<N, E>) -> Result<Self, PosetError> {
+        from_digraph(g)
+    }
+}
+
+impl<N, E> TryFrom<&DiGraph<N, E>> for PosetG {
+    type Error = PosetError;
+
+    fn try_from(g: &DiGraph<N, E>) -> Result<Self, PosetError> {
+        from_digraph(g)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+
+    #[test]
+    fn test_round_trips() {
+        for p in corpus() {
+            let g = DiGraph::from(&p);
+            assert_eq!(g.node_count(), p.md.n);
+            assert_eq!(g.edge_count(), p.layout_edges().len());
+            assert!(g.node_indices().all(|x| g[x] == x.index()));
+            let h = PosetH::try_from(&g).unwrap();
+            assert!(h.same_order(&p));
+            assert_eq!(h.is_valid_poset(), Ok(()));
+            assert_eq!(DiGraph::from(&h).edge_count(), g.edge_count());
+            assert!(PosetG::try_from(&DiGraph::from(&PosetG::from_poset(&p)))
+                .unwrap()
+                .same_order(&p));
+            assert!(PosetM::try_from(&g).unwrap().same_order(&p));
+        }
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut g = DiGraph::<(), f64>::new();
+        let [a, b, c] = [(); 3].map(|_| g.add_node(()));
+        g.extend_with_edges([(a, b, 1.0), (b, c, 2.0)]);
+        assert!(PosetM::try_from(&g).unwrap().leq(0, 2));
+        g.add_edge(c, a, 0.5);
+        assert_eq!(
+            PosetM::try_from(&g).unwrap_err(),
+            PosetError::Cycle { x: 2, y: 0 }
+        );
+        let mut g = DiGraph::<(), ()>::new();
+        let a = g.add_node(());
+        g.add_edge(a, a, ());
+        assert_eq!(
+            PosetH::try_from(&g).unwrap_err(),
+            PosetError::Cycle { x: 0, y: 0 }
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{AnElement, BiPaGraph, Elements, Elt, MetaData, Poset};
+use crate::error::PosetError;
+use crate::validate::{check_new_relation, check_up_sets};
+use crate::{sub_table, AnElement, BiPaGraph, Elements, Elt, MetaData, Poset};
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 /// A representation of a poset encoded as a directed bipartite graph.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PosetG {
     pub md: MetaData,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::sorted_graph"))]
     pub g: BiPaGraph,
 }
 
@@ -15,10 +20,26 @@ impl PosetG {
             g: g.clone(),
         }
     }
+
+    /// Like [PosetG::new], but fails unless g encodes a partial order on $0, ..., n-1$ (see
+    /// [Poset::is_valid_poset]).
+    pub fn try_new(g: &BiPaGraph) -> Result<PosetG, PosetError> {
+        let p = PosetG::new(g);
+        p.is_valid_poset()?;
+        Ok(p)
+    }
 }
 
 // TODO: Computing bot/top when minimals/maximals are known is very easy. Can do that generically?
 impl Poset for PosetG {
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
     fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
         Box::new(0..self.md.n)
     }
@@ -26,6 +47,27 @@ impl Poset for PosetG {
     fn leq(&self, x: AnElement, y: AnElement) -> bool {
         self.g.get(&x).unwrap().contains(&y)
     }
+
+    fn up_set(&self, x: AnElement) -> Elements {
+        self.g[&x].clone()
+    }
+
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        check_up_sets(self, self.g.iter().map(|(&x, up)| (x, up.iter().copied())))
+    }
+
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        if !check_new_relation(self, x, y)? {
+            return Ok(());
+        }
+        let above = self.g[&y].clone();
+        for up in self.g.values_mut().filter(|up| up.contains(&x)) {
+            up.extend(&above);
+        }
+        self.md.add_relation(x, y);
+        Ok(())
+    }
+
     fn find_bot(&mut self) {
         self.md.bot = Some(match self.g.iter().find(|(_, s)| s.len() == self.md.n) {
             Some((&i, _)) => Elt::A(i),
@@ -77,16 +119,51 @@ impl Poset for PosetG {
                 .collect();
             g.insert(i, s);
         }
-        Self::new(&g)
+        let mut p = Self::new(&g);
+        p.md = self.md.op();
+        p
+    }
+
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        let g: BiPaGraph = (0..self.md.n * m)
+            .map(|x| {
+                let up: Elements = self.g[&(x / m)]
+                    .iter()
+                    .flat_map(|&a| other.g[&(x % m)].iter().map(move |&b| a * m + b))
+                    .collect();
+                (x, up)
+            })
+            .collect();
+        PosetG::new(&g)
+    }
+
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut g = self.g.clone();
+        for (&x, s) in &other.g {
+            g.insert(x + n, s.iter().map(|&y| y + n).collect());
+        }
+        PosetG {
+            md: self.md.coproduct(&other.md),
+            g,
+        }
+    }
+
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let (n, m) = (self.md.n, self.md.n + other.md.n);
+        let mut p = self.coproduct(other);
+        for x in 0..n {
+            p.g.get_mut(&x).unwrap().extend(n..m);
+        }
+        p.md = self.md.ordinal_sum(&other.md);
+        p
     }
 
     fn adjoin_bot(&mut self) {
         let n = self.md.n;
-        let new_bot: AnElement = n;
         self.g.insert(n, (0..=n).collect());
-        self.md.bot = Some(Elt::A(new_bot));
-        self.md.minimals = Some(vec![new_bot].iter().cloned().collect());
-        self.md.n += 1;
+        self.md.adjoin_bot();
     }
 
     fn adjoin_top(&mut self) {
@@ -95,10 +172,8 @@ impl Poset for PosetG {
         self.g.values_mut().for_each(|s| {
             s.insert(new_top);
         });
-        self.g.insert(n, vec![n].iter().cloned().collect());
-        self.md.top = Some(Elt::A(new_top));
-        self.md.maximals = Some(vec![new_top].iter().cloned().collect());
-        self.md.n += 1;
+        self.g.insert(n, [n].iter().cloned().collect());
+        self.md.adjoin_top();
     }
 
     fn new_chain(n: usize) -> PosetG {
@@ -121,22 +196,34 @@ impl Poset for PosetG {
         Self::new(&g)
     }
 
-    fn sub(&self, s_0: &Elements) -> Self {
-        let g: BiPaGraph = s_0
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        let back = sub_table(s_0, self.md.n);
+        let g: BiPaGraph = back
             .iter()
-            .map(|i| {
-                (
-                    *i,
-                    self.g.get(i).unwrap().difference(s_0).cloned().collect(),
-                )
+            .enumerate()
+            .map(|(i, x)| {
+                let up = self.g[x]
+                    .iter()
+                    .filter_map(|y| back.binary_search(y).ok())
+                    .collect();
+                (i, up)
             })
             .collect();
+        let mut p = Self::new(&g);
+        p.md = self.md.sub(&back);
+        (p, back)
+    }
+}
 
-        Self::new(&g)
+/// Draws the Hasse diagram (see [ascii](crate::ascii)).
+impl fmt::Display for PosetG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::ascii::hasse_diagram(self))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
     use std::collections::{HashMap, HashSet};
@@ -246,6 +333,12 @@ mod tests {
         g.insert(1, s_1);
         g.insert(2, s_2);
         let vee_op = PosetG::new(&g);
-        assert_eq!(vee.op(), vee_op);
+        let mut op = vee.op();
+        assert_eq!(op.g, vee_op.g);
+        // What is known of the vee is known of its opposite, dualized.
+        assert_eq!((op.md.top, op.md.bot), (bot, top));
+        assert_eq!(op.md.minimals, vee.md.maximals);
+        op.dualize();
+        assert_eq!(op, vee);
     }
 }
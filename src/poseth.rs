@@ -1,63 +1,823 @@
-use crate::{Elements, Elt, Hasse, MetaData, Poset};
+use crate::bitset::BitRow;
+use crate::error::PosetError;
+use crate::redundancy::{self, EdgeClass, EdgeClassFilter, RedundancyStats};
+use crate::relation::cover_matrix;
+use crate::smallset::SmallSet;
+use crate::validate::check_acyclic;
+use crate::{sub_table, AnElement, BoolMatrix, Elements, Elt, Hasse, MetaData, Poset};
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::OnceLock;
 
-/// A representation of a poset encoded as a Hasse diagram.
+/// A representation of a poset encoded as a Hasse diagram: entry x of h holds the upper covers of x. The
+/// covers are stored as [SmallSet]s, which keeps sparse diagrams compact.
+///
+/// Each cover $x \lessdot y$ also carries a weight, e.g., the cost of a transition from $x$ to $y$, which
+/// is [DEFAULT_WEIGHT] unless set by [PosetH::set_cover_weight]. New covers created by operations such as
+/// [Poset::adjoin_top] get the default weight.
+///
+/// [Poset::leq] is answered from the up-set of x as a [BitRow] ([PosetH::up_row]), built on the first query
+/// from the up-sets of the covers of x and kept for later queries, so that a sequence of queries costs
+/// $O(e \cdot n / 64)$ in total, where $e$ is the number of cover pairs, and a single bit lookup each once
+/// the rows are built. The operations of PosetH keep the rows up to date, but after changing h directly,
+/// call [PosetH::forget_up_rows].
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PosetH {
     pub md: MetaData,
     pub h: Hasse,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::sorted_entries"))]
+    weights: HashMap<(AnElement, AnElement), f64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reach: UpRows,
+}
+
+/// The up-sets of the elements built so far, one slot per element once the first is needed.
+#[derive(Default)]
+struct UpRows(OnceLock<Vec<OnceLock<BitRow>>>);
+
+/// The rows are a cache of what the covers determine, so they do not take part in comparisons.
+impl PartialEq for UpRows {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for UpRows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let built = self.0.get().map_or(0, |rows| {
+            rows.iter().filter(|row| row.get().is_some()).count()
+        });
+        write!(f, "UpRows {{ built: {} }}", built)
+    }
+}
+
+/// The weight of a cover whose weight was not set, so that the weight of a saturated chain is its length.
+pub const DEFAULT_WEIGHT: f64 = 1.0;
+
+/// Whether a weighted path should be as light or as heavy as possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+    Min,
+    Max,
 }
 
 impl PosetH {
-    pub fn new(h: &Hasse) -> PosetH {
+    /// Builds the poset whose element x has the upper covers h[x].
+    pub fn from_covers(h: Hasse) -> PosetH {
         PosetH {
-            md: MetaData::new(h.keys().len()),
-            h: h.to_owned(),
+            md: MetaData::new(h.len()),
+            h,
+            weights: HashMap::new(),
+            reach: UpRows::default(),
+        }
+    }
+
+    /// Like [PosetH::from_covers], but fails if the covers mention an element out of range or form a
+    /// cycle (see [Poset::is_valid_poset]). Redundant edges are accepted, as by
+    /// [PosetH::from_covers].
+    pub fn try_from_covers(h: Hasse) -> Result<PosetH, PosetError> {
+        check_acyclic(&h, h.len())?;
+        Ok(PosetH::from_covers(h))
+    }
+
+    #[deprecated(note = "the covers are now stored as `SmallSet`s; use `PosetH::from_covers`")]
+    pub fn new(h: &HashMap<AnElement, Elements>) -> PosetH {
+        PosetH::from_covers((0..h.len()).map(|x| SmallSet::from(&h[&x])).collect())
+    }
+
+    /// The upper covers of x, as stored, redundant edges included. [Poset::upper_covers] returns the true
+    /// covers as a set.
+    pub fn cover_set(&self, x: AnElement) -> &SmallSet {
+        &self.h[x]
+    }
+
+    /// The upper covers of x in the order they are stored, skipping redundant edges and loops.
+    fn stored_covers(&self, x: AnElement) -> impl Iterator<Item = AnElement> + '_ {
+        self.h[x]
+            .iter()
+            .copied()
+            .filter(move |&y| self.is_cover_edge(x, y))
+    }
+
+    /// Whether the stored edge from x to y is a cover, i.e., neither a loop nor implied by another stored
+    /// edge from x (see [redundancy]).
+    fn is_cover_edge(&self, x: AnElement, y: AnElement) -> bool {
+        x != y
+            && !self.h[x]
+                .iter()
+                .any(|&z| z != x && z != y && self.leq(z, y))
+    }
+
+    fn check_cover(&self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        for index in [x, y] {
+            if index >= self.md.n {
+                return Err(PosetError::IndexOutOfRange {
+                    index,
+                    n: self.md.n,
+                });
+            }
+        }
+        match self.covers(x, y) {
+            true => Ok(()),
+            false => Err(PosetError::NotACover { x, y }),
+        }
+    }
+
+    /// Sets the weight of the cover $x \lessdot y$. Redundant edges (see [redundancy]) and loops are not
+    /// covers and carry no weight.
+    pub fn set_cover_weight(
+        &mut self,
+        x: AnElement,
+        y: AnElement,
+        w: f64,
+    ) -> Result<(), PosetError> {
+        self.check_cover(x, y)?;
+        self.weights.insert((x, y), w);
+        Ok(())
+    }
+
+    /// Returns the weight of the cover $x \lessdot y$, or None if y does not cover x.
+    pub fn cover_weight(&self, x: AnElement, y: AnElement) -> Option<f64> {
+        self.check_cover(x, y).ok()?;
+        Some(*self.weights.get(&(x, y)).unwrap_or(&DEFAULT_WEIGHT))
+    }
+
+    /// Returns a saturated chain of maximum total weight together with its weight. A single element is a
+    /// chain of weight $0$, so with the default weights this is a longest chain, of weight one less than the
+    /// height. The chain is empty only if the poset is.
+    pub fn weighted_longest_path(&self) -> (f64, Vec<AnElement>) {
+        let n = self.md.n;
+        let mut best = vec![0.0; n];
+        let mut pred: Vec<Option<AnElement>> = vec![None; n];
+        for x in self.topological_order() {
+            for y in self.stored_covers(x) {
+                let w = best[x] + self.cover_weight(x, y).unwrap();
+                if w > best[y] {
+                    best[y] = w;
+                    pred[y] = Some(x);
+                }
+            }
+        }
+        match (0..n).reduce(|a, b| if best[b] > best[a] { b } else { a }) {
+            Some(end) => (best[end], trace(&pred, end)),
+            None => (0.0, vec![]),
+        }
+    }
+
+    /// Returns a saturated chain from x to y of minimum or maximum total weight, together with its weight,
+    /// or None if $x \nleq y$.
+    pub fn weighted_path_between(
+        &self,
+        x: AnElement,
+        y: AnElement,
+        mode: PathMode,
+    ) -> Option<(f64, Vec<AnElement>)> {
+        let n = self.md.n;
+        if x >= n || y >= n {
+            return None;
+        }
+        let better = |a: f64, b: f64| match mode {
+            PathMode::Min => a < b,
+            PathMode::Max => a > b,
+        };
+        let mut dist: Vec<Option<f64>> = vec![None; n];
+        let mut pred: Vec<Option<AnElement>> = vec![None; n];
+        dist[x] = Some(0.0);
+        for u in self.topological_order() {
+            let Some(d) = dist[u] else { continue };
+            for v in self.stored_covers(u) {
+                let w = d + self.cover_weight(u, v).unwrap();
+                if dist[v].is_none_or(|dv| better(w, dv)) {
+                    dist[v] = Some(w);
+                    pred[v] = Some(u);
+                }
+            }
+        }
+        dist[y].map(|d| (d, trace(&pred, y)))
+    }
+
+    /// Classifies every stored edge as a cover, a redundant edge, or a self-loop (see [redundancy]).
+    pub fn classify_edges(&self) -> HashMap<(AnElement, AnElement), EdgeClass> {
+        redundancy::classify_edges(self)
+    }
+
+    /// Returns the poset with only the stored edges whose class passes keep (see [redundancy]).
+    pub fn sparsify(&self, keep: EdgeClassFilter) -> PosetH {
+        redundancy::sparsify(self, keep)
+    }
+
+    /// Counts the stored edges by class and lists the sources of redundant edges and self-loops.
+    pub fn redundancy_report(&self) -> RedundancyStats {
+        redundancy::redundancy_report(self)
+    }
+
+    /// Identifies the elements x and y, where y covers x. The merged element takes the place of x, the
+    /// indices above y move one down, and handles of y move to the merged element. The covers of y become
+    /// covers of x, keeping their weights, while covers implied by longer chains after the merge are dropped
+    /// along with their weights. The rest of the [MetaData] is reset.
+    pub fn contract_cover(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        self.check_cover(x, y)?;
+        let shift = |z: AnElement| if z > y { z - 1 } else { z };
+        let renumber = |z: AnElement| if z == y { shift(x) } else { shift(z) };
+        let n = self.md.n - 1;
+        let mut up: Vec<Elements> = vec![HashSet::new(); n];
+        for (u, covers) in self.h.iter().enumerate() {
+            up[renumber(u)].extend(covers.iter().map(|&v| renumber(v)));
+        }
+        up[shift(x)].remove(&shift(x));
+        let contracted =
+            PosetH::from_covers(up.iter().map(|s| s.iter().copied().collect()).collect());
+        let h: Hasse = (0..n)
+            .map(|u| {
+                let implied: Elements = up[u]
+                    .iter()
+                    .flat_map(|&t| contracted.up_set(t).into_iter().filter(move |&v| v != t))
+                    .collect();
+                up[u].difference(&implied).copied().collect()
+            })
+            .collect();
+        let mut weights = HashMap::new();
+        for (&(u, v), &w) in &self.weights {
+            let (u, v) = (renumber(u), renumber(v));
+            if u != v && h[u].contains(&v) {
+                weights.insert((u, v), w);
+            }
+        }
+        let handles = self.md.handles.remapped(|z| Some(renumber(z)));
+        *self = PosetH {
+            md: MetaData::new(n),
+            h,
+            weights,
+            reach: UpRows::default(),
+        };
+        self.md.handles = handles;
+        Ok(())
+    }
+
+    /// The elements ordered so that every element comes after its lower covers.
+    fn topological_order(&self) -> Vec<AnElement> {
+        cover_order(&self.h)
+    }
+
+    /// The number of elements in a longest chain, computed along the covers in $O(n + e)$ steps, where $e$ is
+    /// the number of cover pairs.
+    pub(crate) fn height_from_covers(&self) -> usize {
+        cover_height(&self.h)
+    }
+
+    /// The elements without upper covers.
+    pub(crate) fn maximals_from_covers(&self) -> Elements {
+        (0..self.md.n).filter(|&x| self.h[x].is_empty()).collect()
+    }
+
+    /// The elements that cover nothing.
+    pub(crate) fn minimals_from_covers(&self) -> Elements {
+        let covering: Elements = self.h.iter().flatten().copied().collect();
+        (0..self.md.n).filter(|x| !covering.contains(x)).collect()
+    }
+
+    /// The up-set of x, built from the up-sets of its covers, which are built first as needed, by a search
+    /// upwards along the covers that stops at the rows already built. If the covers form a cycle (see
+    /// [Poset::is_valid_poset]), the rows of the elements on it are found by a plain search instead.
+    pub fn up_row(&self, x: AnElement) -> &BitRow {
+        let n = self.h.len();
+        let rows = self
+            .reach
+            .0
+            .get_or_init(|| (0..n).map(|_| OnceLock::new()).collect());
+        let mut entered = BitRow::new(n);
+        let mut stack = vec![x];
+        while let Some(&z) = stack.last() {
+            if rows[z].get().is_some() {
+                stack.pop();
+                continue;
+            }
+            let missing = self.h[z]
+                .iter()
+                .copied()
+                .filter(|&c| c != z && rows[c].get().is_none());
+            if !entered.get(z) {
+                entered.set(z);
+                let missing: Vec<AnElement> = missing.filter(|&c| !entered.get(c)).collect();
+                if !missing.is_empty() {
+                    stack.extend(missing);
+                    continue;
+                }
+            }
+            let mut row = BitRow::new(n);
+            if self.h[z].iter().all(|&c| c == z || rows[c].get().is_some()) {
+                row.set(z);
+                self.h[z]
+                    .iter()
+                    .filter_map(|&c| rows[c].get())
+                    .for_each(|c| row.or_assign(c));
+            } else {
+                // A cover of z is still waiting for z, so z lies on a cycle.
+                self.up_set(z).into_iter().for_each(|y| row.set(y));
+            }
+            let _ = rows[z].set(row);
+            stack.pop();
+        }
+        rows[x].get().expect("the row of x was built last")
+    }
+
+    /// Drops the up-sets built for [Poset::leq], which is needed after changing h directly.
+    pub fn forget_up_rows(&mut self) {
+        self.reach = UpRows::default();
+    }
+
+    /// The up-set of x, found by searching upwards along the covers.
+    pub(crate) fn up_set(&self, x: AnElement) -> Elements {
+        let mut seen: Elements = HashSet::new();
+        let mut stack = vec![x];
+        while let Some(z) = stack.pop() {
+            if seen.insert(z) {
+                stack.extend(self.h[z].iter().copied());
+            }
+        }
+        seen
+    }
+}
+
+/// The elements ordered so that every element comes after its lower covers in h (Kahn's algorithm). Loops
+/// are skipped; if the covers form a longer cycle, the elements on or above it are missing.
+pub(crate) fn cover_order(h: &Hasse) -> Vec<AnElement> {
+    let mut indegree = vec![0; h.len()];
+    for (x, covers) in h.iter().enumerate() {
+        for &y in covers.iter().filter(|&&y| y != x) {
+            indegree[y] += 1;
+        }
+    }
+    let mut order: Vec<AnElement> = (0..h.len()).filter(|&x| indegree[x] == 0).collect();
+    let mut k = 0;
+    while k < order.len() {
+        let x = order[k];
+        for &y in h[x].iter().filter(|&&y| y != x) {
+            indegree[y] -= 1;
+            if indegree[y] == 0 {
+                order.push(y);
+            }
+        }
+        k += 1;
+    }
+    order
+}
+
+/// The number of elements in a longest chain along the acyclic covers h.
+pub(crate) fn cover_height(h: &Hasse) -> usize {
+    let mut chain_to = vec![1; h.len()];
+    for x in cover_order(h) {
+        for &y in h[x].iter().filter(|&&y| y != x) {
+            chain_to[y] = chain_to[y].max(chain_to[x] + 1);
         }
     }
+    chain_to.into_iter().max().unwrap_or(0)
+}
+
+/// The chain ending at end obtained by following pred back to its start.
+fn trace(pred: &[Option<AnElement>], end: AnElement) -> Vec<AnElement> {
+    let mut path = vec![end];
+    while let Some(p) = pred[*path.last().unwrap()] {
+        path.push(p);
+    }
+    path.reverse();
+    path
+}
+
+/// In a finite poset, an element that is the only maximal (minimal) one is the top (bottom).
+pub(crate) fn unique(s: &Elements) -> Elt {
+    match s.len() {
+        1 => Elt::A(*s.iter().next().unwrap()),
+        _ => Elt::NotPresent,
+    }
 }
 
 impl Poset for PosetH {
-    fn find_bot(&mut self) {}
-    fn find_top(&mut self) {}
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
+    fn find_bot(&mut self) {
+        self.md.bot = Some(unique(&self.minimals_from_covers()));
+    }
+
+    fn find_top(&mut self) {
+        self.md.top = Some(unique(&self.maximals_from_covers()));
+    }
 
     fn find_minimals(&mut self) {
-        todo!();
+        self.md.minimals = Some(self.minimals_from_covers());
     }
 
     fn find_maximals(&mut self) {
-        todo!();
+        self.md.maximals = Some(self.maximals_from_covers());
+    }
+
+    fn find_height(&mut self) {
+        self.md.height = Some(self.height_from_covers());
+    }
+
+    /// A longest path along the covers, found in $O(n + e)$ steps, where $e$ is the number of cover pairs.
+    fn longest_chain(&self) -> Vec<AnElement> {
+        let n = self.md.n;
+        let mut chain_to = vec![1; n];
+        let mut pred: Vec<Option<AnElement>> = vec![None; n];
+        for x in self.topological_order() {
+            for &y in self.h[x].iter().filter(|&&y| y != x) {
+                if chain_to[x] + 1 > chain_to[y] {
+                    chain_to[y] = chain_to[x] + 1;
+                    pred[y] = Some(x);
+                }
+            }
+        }
+        match (0..n).max_by_key(|&x| (chain_to[x], std::cmp::Reverse(x))) {
+            Some(end) => trace(&pred, end),
+            None => vec![],
+        }
     }
 
+    /// The covers are reversed, keeping their weights.
     fn op(&self) -> Self {
-        todo!();
+        let mut h: Hasse = vec![SmallSet::new(); self.md.n];
+        for (x, covers) in self.h.iter().enumerate() {
+            for &y in covers.iter() {
+                h[y].insert(x);
+            }
+        }
+        let mut p = PosetH::from_covers(h);
+        p.weights = self
+            .weights
+            .iter()
+            .map(|(&(x, y), &w)| ((y, x), w))
+            .collect();
+        p.md = self.md.op();
+        p
     }
 
     fn new_chain(n: usize) -> Self {
-        todo!();
+        PosetH::from_covers((0..n).map(|i| (i + 1..n.min(i + 2)).collect()).collect())
     }
 
     fn new_antichain(n: usize) -> Self {
-        todo!();
+        PosetH::from_covers(vec![SmallSet::new(); n])
+    }
+
+    /// The covers of $(a, b)$ are the pairs $(c, b)$ with $c$ covering $a$ and $(a, d)$ with $d$ covering
+    /// $b$, with the default weight.
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        PosetH::from_covers(
+            (0..self.md.n * m)
+                .map(|x| {
+                    let (a, b) = (x / m, x % m);
+                    self.h[a]
+                        .iter()
+                        .map(|&c| c * m + b)
+                        .chain(other.h[b].iter().map(|&d| a * m + d))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// The covers, and their weights, of both summands carry over.
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut h = self.h.clone();
+        h.extend(other.h.iter().map(|s| s.iter().map(|&y| y + n).collect()));
+        let mut weights = self.weights.clone();
+        weights.extend(
+            other
+                .weights
+                .iter()
+                .map(|(&(x, y), &w)| ((x + n, y + n), w)),
+        );
+        PosetH {
+            md: self.md.coproduct(&other.md),
+            h,
+            weights,
+            reach: UpRows::default(),
+        }
+    }
+
+    /// The maximal elements of the poset are covered by the minimal elements of other, with covers of the
+    /// default weight, and the other covers keep their weights.
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut p = self.coproduct(other);
+        let minimals = other.minimals_from_covers();
+        for x in self.maximals_from_covers() {
+            p.h[x].extend(minimals.iter().map(|&y| y + n));
+        }
+        p.md = self.md.ordinal_sum(&other.md);
+        p
     }
 
+    /// The new bottom is covered by the old minimal elements, with covers of the default weight.
     fn adjoin_bot(&mut self) {
-        todo!();
+        let minimals = self.minimals_from_covers();
+        self.h.push(minimals.into_iter().collect());
+        self.md.adjoin_bot();
+        self.forget_up_rows();
     }
+
+    /// The new top covers the old maximal elements, with covers of the default weight.
     fn adjoin_top(&mut self) {
-        todo!();
+        let n = self.md.n;
+        for x in self.maximals_from_covers() {
+            self.h[x].insert(n);
+        }
+        self.h.push(SmallSet::new());
+        self.md.adjoin_top();
+        self.forget_up_rows();
+    }
+
+    /// The covers of the subposet are found from the order of the poset. Covers of the poset between
+    /// elements of s_0 are still covers, and keep their weights.
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        let back = sub_table(s_0, self.md.n);
+        let lt: BoolMatrix = back
+            .iter()
+            .map(|&x| back.iter().map(|&y| x != y && self.leq(x, y)).collect())
+            .collect();
+        let h: Hasse = cover_matrix(&lt)
+            .iter()
+            .map(|row| (0..row.len()).filter(|&j| row[j]).collect())
+            .collect();
+        let weights = self
+            .weights
+            .iter()
+            .filter_map(|(&(x, y), &w)| {
+                Some((
+                    (back.binary_search(&x).ok()?, back.binary_search(&y).ok()?),
+                    w,
+                ))
+            })
+            .collect();
+        let p = PosetH {
+            md: self.md.sub(&back),
+            h,
+            weights,
+            reach: UpRows::default(),
+        };
+        (p, back)
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
+        Box::new(0..self.md.n)
+    }
+
+    /// A lookup in the stored covers, skipping redundant edges (see [redundancy]).
+    fn covers(&self, x: AnElement, y: AnElement) -> bool {
+        self.h[x].contains(&y) && self.is_cover_edge(x, y)
+    }
+
+    /// Reads the stored covers, which are sorted, skipping redundant edges.
+    fn cover_relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new((0..self.md.n).flat_map(move |x| self.stored_covers(x).map(move |y| (x, y))))
+    }
+
+    fn upper_covers(&self, x: AnElement) -> Elements {
+        self.stored_covers(x).collect()
+    }
+
+    /// A search upwards along the covers.
+    fn up_set(&self, x: AnElement) -> Elements {
+        PosetH::up_set(self, x)
+    }
+
+    fn lower_covers(&self, x: AnElement) -> Elements {
+        (0..self.md.n).filter(|&y| self.covers(y, x)).collect()
+    }
+
+    /// The covers must mention only the elements $0, ..., n-1$ and form no cycle, loops included.
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        check_acyclic(&self.h, self.md.n)
+    }
+
+    /// A lookup in the up-set of x, built on the first query (see [PosetH::up_row]).
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        x == y || self.up_row(x).get(y)
+    }
+}
+
+/// Draws the Hasse diagram (see [ascii](crate::ascii)).
+impl fmt::Display for PosetH {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::ascii::hasse_diagram(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::corpus;
+
+    fn diamond() -> PosetH {
+        let mut p = PosetH::new_antichain(2);
+        p.adjoin_bot();
+        p.adjoin_top();
+        p
+    }
+
+    #[test]
+    fn test_constructors() {
+        let mut p = PosetH::new_chain(3);
+        assert!(p.leq(0, 2) && !p.leq(2, 1));
+        p.adjoin_bot();
+        assert!(p.leq(3, 0) && p.cover_set(3).as_slice() == [0]);
+        let d = diamond();
+        assert_eq!(d.cover_set(2).as_slice(), &[0, 1]);
+        assert_eq!(d.cover_set(0).as_slice(), &[3]);
+        assert_eq!(d.cover_weight(0, 3), Some(DEFAULT_WEIGHT));
+    }
+
+    #[test]
+    fn test_unit_weights() {
+        for m in corpus() {
+            let mut p = PosetH::from_poset(&m);
+            p.find_height();
+            let height = p.md.height.unwrap();
+            let (w, path) = p.weighted_longest_path();
+            assert_eq!(path.len(), height);
+            assert_eq!(w, height.saturating_sub(1) as f64);
+            for pair in path.windows(2) {
+                assert!(p.cover_weight(pair[0], pair[1]).is_some());
+            }
+        }
+        let p = PosetH::new_chain(6);
+        for mode in [PathMode::Min, PathMode::Max] {
+            assert_eq!(
+                p.weighted_path_between(1, 4, mode),
+                Some((3.0, vec![1, 2, 3, 4]))
+            );
+            assert_eq!(p.weighted_path_between(4, 1, mode), None);
+            assert_eq!(p.weighted_path_between(2, 2, mode), Some((0.0, vec![2])));
+        }
+    }
+
+    #[test]
+    fn test_sub() {
+        let mut p = PosetH::new_chain(4);
+        p.find_top();
+        p.set_cover_weight(2, 3, 5.0).unwrap();
+        let (q, back) = p.sub(&[0, 2, 3].into());
+        assert_eq!(back, vec![0, 2, 3]);
+        assert_eq!(
+            q.h,
+            vec![
+                SmallSet::from_iter([1]),
+                [2].into_iter().collect(),
+                SmallSet::new()
+            ]
+        );
+        assert_eq!(q.cover_weight(1, 2), Some(5.0));
+        assert_eq!(q.cover_weight(0, 1), Some(DEFAULT_WEIGHT));
+        assert_eq!(q.md.top, Some(Elt::A(2)));
+        assert_eq!(q.md.bot, None);
+        let (empty, back) = p.sub(&Elements::new());
+        assert_eq!((empty.md.n, back), (0, vec![]));
     }
 
-    fn sub(&self, s_0: &Elements) -> Self {
-        todo!();
+    #[test]
+    fn test_asymmetric_diamond() {
+        // The bottom 2 is covered by 0 and 1, which are covered by the top 3.
+        let mut p = diamond();
+        p.set_cover_weight(2, 0, 1.0).unwrap();
+        p.set_cover_weight(0, 3, 1.5).unwrap();
+        p.set_cover_weight(2, 1, 4.0).unwrap();
+        p.set_cover_weight(1, 3, 0.5).unwrap();
+        assert_eq!(
+            p.weighted_path_between(2, 3, PathMode::Min),
+            Some((2.5, vec![2, 0, 3]))
+        );
+        assert_eq!(
+            p.weighted_path_between(2, 3, PathMode::Max),
+            Some((4.5, vec![2, 1, 3]))
+        );
+        assert_eq!(p.weighted_longest_path(), (4.5, vec![2, 1, 3]));
+        // The opposite reverses the covers along with their weights.
+        assert_eq!(
+            p.op().weighted_path_between(3, 2, PathMode::Min),
+            Some((2.5, vec![3, 0, 2]))
+        );
     }
 
-    fn elements(&self) -> Box<dyn Iterator<Item = crate::AnElement>> {
-        todo!()
+    #[test]
+    fn test_weight_errors() {
+        let mut p = diamond();
+        assert_eq!(
+            p.set_cover_weight(2, 3, 1.0),
+            Err(PosetError::NotACover { x: 2, y: 3 })
+        );
+        assert_eq!(
+            p.set_cover_weight(0, 1, 1.0),
+            Err(PosetError::NotACover { x: 0, y: 1 })
+        );
+        assert_eq!(
+            p.set_cover_weight(0, 7, 1.0),
+            Err(PosetError::IndexOutOfRange { index: 7, n: 4 })
+        );
+        assert_eq!(p.cover_weight(2, 3), None);
+
+        // 0 < 1 < 2 with the redundant edge 0 < 2, which carries no weight.
+        let mut p = PosetH::from_covers(vec![
+            [1, 2].into_iter().collect(),
+            [2].into_iter().collect(),
+            SmallSet::new(),
+        ]);
+        assert_eq!(
+            p.set_cover_weight(0, 2, 1.0),
+            Err(PosetError::NotACover { x: 0, y: 2 })
+        );
+        assert_eq!(p.cover_weight(0, 2), None);
+        assert_eq!(
+            p.weighted_path_between(0, 2, PathMode::Min),
+            Some((2.0, vec![0, 1, 2]))
+        );
+        p.h[1].insert(1);
+        p.forget_up_rows();
+        assert_eq!(
+            p.set_cover_weight(1, 1, 1.0),
+            Err(PosetError::NotACover { x: 1, y: 1 })
+        );
     }
 
-    fn leq(&self, x: crate::AnElement, y: crate::AnElement) -> bool {
-        todo!()
+    #[test]
+    fn test_contract_cover() {
+        // Contracting 2 < 0 in the diamond leaves the chain 1 < 0 < 2 after renumbering, where the cover
+        // 0 < 2 keeps the weight of 1 < 3.
+        let mut p = diamond();
+        p.set_cover_weight(1, 3, 7.0).unwrap();
+        p.set_cover_weight(0, 3, 2.0).unwrap();
+        let top = p.pin(3);
+        p.contract_cover(2, 0).unwrap();
+        assert_eq!(p.md.n, 3);
+        assert_eq!(p.resolve(&top), Some(2));
+        assert_eq!(p.cover_set(1).as_slice(), &[0]);
+        assert_eq!(p.cover_set(0).as_slice(), &[2]);
+        assert_eq!(p.cover_weight(0, 2), Some(7.0));
+        assert_eq!(p.cover_weight(1, 2), None);
+        assert!(p.contract_cover(1, 2).is_err());
+    }
+
+    #[test]
+    fn test_redundant_edges() {
+        // 0 < 1 < 2, with the redundant edge 0 < 2 and a loop at 1.
+        let both: SmallSet = [1, 2].into_iter().collect();
+        let p = PosetH::from_covers(vec![both.clone(), both, SmallSet::new()]);
+        assert_eq!(p.cover_set(0).as_slice(), &[1, 2]);
+        assert_eq!(p.upper_covers(0), [1].into());
+        assert_eq!(p.upper_covers(1), [2].into());
+        assert_eq!(p.lower_covers(2), [1].into());
+        assert!(p.covers(0, 1) && !p.covers(0, 2) && !p.covers(1, 1));
+        assert_eq!(p.cover_relations().collect::<Vec<_>>(), [(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_loops() {
+        // 0 < 1 < 2, with a loop at 1.
+        let mut p = PosetH::from_covers(vec![
+            [1].into_iter().collect(),
+            [1, 2].into_iter().collect(),
+            SmallSet::new(),
+        ]);
+        assert_eq!(p.topological_order(), [0, 1, 2]);
+        assert_eq!(p.height_from_covers(), 3);
+        assert_eq!(p.longest_chain(), [0, 1, 2]);
+        assert_eq!(p.weighted_longest_path(), (2.0, vec![0, 1, 2]));
+        p.find_height();
+        assert_eq!(p.md.height, Some(3));
+    }
+
+    #[test]
+    fn test_up_rows() {
+        for m in corpus() {
+            let p = PosetH::from_poset(&m);
+            for x in m.elements() {
+                assert!(m.elements().all(|y| p.leq(x, y) == m.leq(x, y)));
+                assert_eq!(p.up_row(x).count_ones(), m.up_set(x).len());
+            }
+            // Building the rows does not change the poset.
+            assert_eq!(p, PosetH::from_poset(&m));
+        }
+        let mut p = PosetH::new_chain(3);
+        assert!(!p.leq(2, 0));
+        p.adjoin_top();
+        assert!(p.leq(0, 3) && p.leq(2, 3));
+        p.h[2].insert(0);
+        p.forget_up_rows();
+        assert!(p.leq(2, 0));
+        // The covers form a cycle, and the searches still end.
+        assert!(p
+            .elements()
+            .all(|x| p.up_row(x).get(x) && p.up_row(x).get(3)));
     }
 }
@@ -1,9 +1,14 @@
-use crate::{AnElement, BoolMatrix, Elements, Elt, MetaData, Poset};
+use crate::error::PosetError;
+use crate::interval;
+use crate::validate::{check_new_relation, check_order};
+use crate::{sub_table, AnElement, BoolMatrix, Elements, Elt, MetaData, PartialOrdering, Poset};
 
 use ::std::collections::HashSet;
+use std::fmt;
 
 /// A representation of a poset encoded as a matrix taking values in the boolean truth values.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PosetM {
     pub md: MetaData,
     pub m: BoolMatrix,
@@ -16,17 +21,75 @@ impl PosetM {
             m: m.to_owned(),
         }
     }
+
+    /// Like [PosetM::new], but fails unless m encodes a partial order (see [Poset::is_valid_poset]).
+    pub fn try_new(m: &BoolMatrix) -> Result<Self, PosetError> {
+        let p = PosetM::new(m);
+        p.is_valid_poset()?;
+        Ok(p)
+    }
 }
 
 impl Poset for PosetM {
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
     fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
         Box::new(0..self.md.n)
     }
 
+    /// Reads the rows of the matrix.
+    fn relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(self.m.iter().enumerate().flat_map(|(x, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |&(y, &le)| le && y != x)
+                .map(move |(y, _)| (x, y))
+        }))
+    }
+
     fn leq(&self, x: AnElement, y: AnElement) -> bool {
         self.m[x][y]
     }
 
+    fn compare(&self, x: AnElement, y: AnElement) -> PartialOrdering {
+        PartialOrdering::from_leqs(self.m[x][y], self.m[y][x])
+    }
+
+    /// The matrix must have n rows of n entries each.
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        let n = self.md.n;
+        if self.m.len() > n {
+            return Err(PosetError::IndexOutOfRange { index: n, n });
+        }
+        for x in 0..n {
+            match self.m.get(x).map(Vec::len) {
+                None => return Err(PosetError::Missing { x }),
+                Some(len) if len < n => return Err(PosetError::Missing { x }),
+                Some(len) if len > n => return Err(PosetError::IndexOutOfRange { index: n, n }),
+                _ => {}
+            }
+        }
+        check_order(self)
+    }
+
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        if !check_new_relation(self, x, y)? {
+            return Ok(());
+        }
+        let above = self.m[y].clone();
+        for row in self.m.iter_mut().filter(|row| row[x]) {
+            row.iter_mut().zip(&above).for_each(|(r, &a)| *r |= a);
+        }
+        self.md.add_relation(x, y);
+        Ok(())
+    }
+
     fn find_bot(&mut self) {
         self.md.bot = Some(
             match (0..self.md.n).find(|&i| (0..self.md.n).all(|j| self.m[i][j])) {
@@ -64,7 +127,21 @@ impl Poset for PosetM {
         for i in 0..self.md.n {
             m.push((0..self.md.n).map(|j| self.m[j][i]).collect())
         }
-        PosetM::new(&m)
+        let mut p = PosetM::new(&m);
+        p.md = self.md.op();
+        p
+    }
+
+    /// Transposes the matrix in place.
+    fn dualize(&mut self) {
+        let n = self.md.n;
+        for i in 0..n {
+            for j in i + 1..n {
+                let (upper, lower) = self.m.split_at_mut(j);
+                std::mem::swap(&mut upper[i][j], &mut lower[0][i]);
+            }
+        }
+        self.md = self.md.op();
     }
 
     fn new_chain(n: usize) -> Self {
@@ -79,15 +156,55 @@ impl Poset for PosetM {
         PosetM::new(&m)
     }
 
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        let n = self.md.n * m;
+        let matrix: BoolMatrix = (0..n)
+            .map(|x| {
+                (0..n)
+                    .map(|y| self.m[x / m][y / m] && other.m[x % m][y % m])
+                    .collect()
+            })
+            .collect();
+        PosetM::new(&matrix)
+    }
+
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let m = self.md.n + other.md.n;
+        let matrix: BoolMatrix = (0..m)
+            .map(|x| {
+                (0..m)
+                    .map(|y| match (x < n, y < n) {
+                        (true, true) => self.m[x][y],
+                        (false, false) => other.m[x - n][y - n],
+                        _ => false,
+                    })
+                    .collect()
+            })
+            .collect();
+        PosetM {
+            md: self.md.coproduct(&other.md),
+            m: matrix,
+        }
+    }
+
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let mut p = self.coproduct(other);
+        for row in &mut p.m[..self.md.n] {
+            row[self.md.n..].fill(true);
+        }
+        p.md = self.md.ordinal_sum(&other.md);
+        p
+    }
+
     fn adjoin_bot(&mut self) {
         let n = self.md.n;
         for row in self.m.iter_mut() {
             row.push(false);
         }
         self.m.push(vec![true; n + 1]);
-        self.md.n += 1;
-        self.md.bot = Some(Elt::A(n));
-        self.md.minimals = Some(vec![n].iter().cloned().collect());
+        self.md.adjoin_bot();
     }
 
     fn adjoin_top(&mut self) {
@@ -97,16 +214,23 @@ impl Poset for PosetM {
         }
         self.m.push(vec![false; n]);
         self.m[n].push(true);
-        self.md.top = Some(Elt::A(n));
-        self.md.maximals = Some(vec![n].iter().cloned().collect());
+        self.md.adjoin_top();
+    }
+
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        interval::induced_matrix(self, sub_table(s_0, self.md.n))
     }
+}
 
-    fn sub(&self, s_0: &Elements) -> Self {
-        todo!();
+/// Draws the Hasse diagram (see [ascii](crate::ascii)).
+impl fmt::Display for PosetM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::ascii::hasse_diagram(self))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
     use std::collections::HashSet;
@@ -206,6 +330,12 @@ mod tests {
             vec![true, false, true],
         ];
         let vee_op = PosetM::new(&m);
-        assert_eq!(vee.op(), vee_op);
+        let mut op = vee.op();
+        assert_eq!(op.m, vee_op.m);
+        // What is known of the vee is known of its opposite, dualized.
+        assert_eq!((op.md.top, op.md.bot), (bot, top));
+        assert_eq!(op.md.minimals, vee.md.maximals);
+        op.dualize();
+        assert_eq!(op, vee);
     }
 }
@@ -0,0 +1,366 @@
+use crate::error::PosetError;
+use crate::handles::HandleTable;
+use crate::laws::compact;
+use crate::relation::{cover_matrix, strict_matrix};
+use crate::validate::{check_new_relation, check_up_sets};
+use crate::{sub_table, AnElement, Elements, Elt, MetaData, Poset, SortedGraph};
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A representation of a poset encoded, like [PosetG](crate::posetg::PosetG), by the up-set of every
+/// element, but stored in ordered maps and sets. Iteration over the elements and the up-sets is sorted,
+/// and so are the [Debug](fmt::Debug) and [Display](fmt::Display) forms and [PosetO::to_dot], which makes
+/// them reproducible byte for byte (e.g., for golden-file tests) at the cost of slower lookups.
+///
+/// ```
+/// use fin_pos::poseto::PosetO;
+/// use fin_pos::Poset;
+///
+/// let mut p = PosetO::new_antichain(2);
+/// p.adjoin_bot();
+/// assert_eq!(p.to_string(), "3:2<0,2<1");
+/// assert_eq!(p.to_dot(), "digraph {\n  0;\n  1;\n  2;\n  2 -> 0;\n  2 -> 1;\n}\n");
+/// ```
+#[derive(PartialEq, Clone)]
+pub struct PosetO {
+    pub md: MetaData,
+    pub g: SortedGraph,
+}
+
+impl PosetO {
+    pub fn new(g: &SortedGraph) -> PosetO {
+        PosetO {
+            md: MetaData::new(g.len()),
+            g: g.clone(),
+        }
+    }
+
+    /// Like [PosetO::new], but fails unless g encodes a partial order on $0, ..., n-1$ (see
+    /// [Poset::is_valid_poset]).
+    pub fn try_new(g: &SortedGraph) -> Result<PosetO, PosetError> {
+        let p = PosetO::new(g);
+        p.is_valid_poset()?;
+        Ok(p)
+    }
+
+    /// The Hasse diagram in the DOT language of Graphviz, listing the elements and then the covers in
+    /// increasing order.
+    pub fn to_dot(&self) -> String {
+        let covers = cover_matrix(&strict_matrix(self));
+        let mut dot = String::from("digraph {\n");
+        for x in self.g.keys() {
+            dot.push_str(&format!("  {};\n", x));
+        }
+        for (x, row) in covers.iter().enumerate() {
+            for y in (0..row.len()).filter(|&y| row[y]) {
+                dot.push_str(&format!("  {} -> {};\n", x, y));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn sorted(s: &Option<Elements>) -> Option<BTreeSet<AnElement>> {
+    s.as_ref().map(|s| s.iter().copied().collect())
+}
+
+/// Prints the [MetaData] with its sets sorted.
+struct SortedMetaData<'a>(&'a MetaData);
+
+impl fmt::Debug for SortedMetaData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let md = self.0;
+        f.debug_struct("MetaData")
+            .field("n", &md.n)
+            .field("top", &md.top)
+            .field("bot", &md.bot)
+            .field("minimals", &sorted(&md.minimals))
+            .field("maximals", &sorted(&md.maximals))
+            .field("height", &md.height)
+            .field("atoms", &sorted(&md.atoms))
+            .field("coatoms", &sorted(&md.coatoms))
+            .field("handles", &SortedHandles(&md.handles))
+            .finish()
+    }
+}
+
+struct SortedHandles<'a>(&'a HandleTable);
+
+impl fmt::Debug for SortedHandles<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.sorted_slots()).finish()
+    }
+}
+
+impl fmt::Debug for PosetO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PosetO")
+            .field("md", &SortedMetaData(&self.md))
+            .field("g", &self.g)
+            .finish()
+    }
+}
+
+/// The compact form of [laws::compact](crate::laws::compact): the number of elements and the covers.
+impl fmt::Display for PosetO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", compact(self))
+    }
+}
+
+impl Poset for PosetO {
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
+        Box::new(0..self.md.n)
+    }
+
+    /// Reads the up-sets, which are stored sorted.
+    fn relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(
+            self.g
+                .iter()
+                .flat_map(|(&x, up)| up.iter().filter(move |&&y| y != x).map(move |&y| (x, y))),
+        )
+    }
+
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.g[&x].contains(&y)
+    }
+
+    fn up_set(&self, x: AnElement) -> Elements {
+        self.g[&x].iter().copied().collect()
+    }
+
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        check_up_sets(self, self.g.iter().map(|(&x, up)| (x, up.iter().copied())))
+    }
+
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        if !check_new_relation(self, x, y)? {
+            return Ok(());
+        }
+        let above = self.g[&y].clone();
+        for up in self.g.values_mut().filter(|up| up.contains(&x)) {
+            up.extend(&above);
+        }
+        self.md.add_relation(x, y);
+        Ok(())
+    }
+
+    fn find_bot(&mut self) {
+        self.md.bot = Some(match self.g.iter().find(|(_, s)| s.len() == self.md.n) {
+            Some((&i, _)) => Elt::A(i),
+            None => Elt::NotPresent,
+        })
+    }
+
+    fn find_top(&mut self) {
+        self.find_maximals();
+        let maximals = self.md.maximals.as_ref().unwrap();
+        self.md.top = Some(match maximals.len() {
+            1 => Elt::A(*maximals.iter().next().unwrap()),
+            _ => Elt::NotPresent,
+        })
+    }
+
+    fn find_minimals(&mut self) {
+        let non_minimals: BTreeSet<AnElement> = self
+            .g
+            .iter()
+            .flat_map(|(i, s)| s.iter().filter(move |&j| j != i))
+            .copied()
+            .collect();
+        self.md.minimals = Some(
+            (0..self.md.n)
+                .filter(|i| !non_minimals.contains(i))
+                .collect(),
+        )
+    }
+
+    fn find_maximals(&mut self) {
+        self.md.maximals = Some(
+            self.g
+                .iter()
+                .filter(|(_, s)| s.len() == 1)
+                .map(|(&i, _)| i)
+                .collect(),
+        )
+    }
+
+    fn op(&self) -> Self {
+        let mut g: SortedGraph = self.g.keys().map(|&i| (i, BTreeSet::new())).collect();
+        for (&i, s) in &self.g {
+            for j in s {
+                g.get_mut(j).unwrap().insert(i);
+            }
+        }
+        let mut p = PosetO::new(&g);
+        p.md = self.md.op();
+        p
+    }
+
+    fn new_chain(n: usize) -> Self {
+        PosetO::new(&(0..n).map(|i| (i, (i..n).collect())).collect())
+    }
+
+    fn new_antichain(n: usize) -> Self {
+        PosetO::new(&(0..n).map(|i| (i, [i].into_iter().collect())).collect())
+    }
+
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        let g: SortedGraph = (0..self.md.n * m)
+            .map(|x| {
+                let up = self.g[&(x / m)]
+                    .iter()
+                    .flat_map(|&a| other.g[&(x % m)].iter().map(move |&b| a * m + b))
+                    .collect();
+                (x, up)
+            })
+            .collect();
+        PosetO::new(&g)
+    }
+
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut g = self.g.clone();
+        for (&x, s) in &other.g {
+            g.insert(x + n, s.iter().map(|&y| y + n).collect());
+        }
+        PosetO {
+            md: self.md.coproduct(&other.md),
+            g,
+        }
+    }
+
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let (n, m) = (self.md.n, self.md.n + other.md.n);
+        let mut p = self.coproduct(other);
+        for x in 0..n {
+            p.g.get_mut(&x).unwrap().extend(n..m);
+        }
+        p.md = self.md.ordinal_sum(&other.md);
+        p
+    }
+
+    fn adjoin_bot(&mut self) {
+        let n = self.md.n;
+        self.g.insert(n, (0..=n).collect());
+        self.md.adjoin_bot();
+    }
+
+    fn adjoin_top(&mut self) {
+        let n = self.md.n;
+        self.g.values_mut().for_each(|s| {
+            s.insert(n);
+        });
+        self.g.insert(n, [n].into_iter().collect());
+        self.md.adjoin_top();
+    }
+
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        let back = sub_table(s_0, self.md.n);
+        let g: SortedGraph = back
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let up = self.g[x]
+                    .iter()
+                    .filter_map(|y| back.binary_search(y).ok())
+                    .collect();
+                (i, up)
+            })
+            .collect();
+        let mut p = PosetO::new(&g);
+        p.md = self.md.sub(&back);
+        (p, back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::{Convertible, MetaFields};
+    use crate::corpus::{corpus, find_all};
+    use crate::laws::{check_op_involution, check_op_reverses_order};
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_constructors() {
+        assert!(PosetO::new_chain(4).same_order(&PosetM::new_chain(4)));
+        assert!(PosetO::new_antichain(4).same_order(&PosetM::new_antichain(4)));
+        assert!(PosetO::new_corolla(3).same_order(&PosetM::new_corolla(3)));
+        let mut p = PosetO::new_chain(2);
+        let mut q = PosetM::new_chain(2);
+        p.adjoin_top();
+        q.adjoin_top();
+        p.adjoin_bot();
+        q.adjoin_bot();
+        assert!(p.same_order(&q));
+        assert_eq!(p.md, q.md);
+    }
+
+    #[test]
+    fn test_trait_agrees_with_matrix() {
+        for p in corpus() {
+            let mut o: PosetO = p.convert_into(MetaFields::NONE).unwrap();
+            assert!(o.same_order(&p));
+            assert_eq!(check_op_involution(&o), Ok(()));
+            assert_eq!(check_op_reverses_order(&o), Ok(()));
+            assert!(o.op().same_order(&p.op()));
+            let mut m = PosetM::new(&p.m);
+            find_all(&mut o);
+            find_all(&mut m);
+            assert_eq!(o.md, m.md);
+            let half: Elements = (0..p.md.n).filter(|x| x % 2 == 0).collect();
+            let (s, back) = o.sub(&half);
+            assert!(s.g.keys().copied().eq(0..half.len()));
+            assert!(s
+                .elements()
+                .all(|x| s.elements().all(|y| s.leq(x, y) == p.leq(back[x], back[y]))));
+        }
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for p in corpus() {
+            let o: PosetO = p.convert_into(MetaFields::ALL).unwrap();
+            let g: PosetG = o.convert_into(MetaFields::NONE).unwrap();
+            let h: PosetH = g.convert_into(MetaFields::NONE).unwrap();
+            let back: PosetO = h.convert_into(MetaFields::NONE).unwrap();
+            assert_eq!(back, o);
+            let m: PosetM = back.convert_into(MetaFields::NONE).unwrap();
+            assert_eq!(m.m, p.m);
+        }
+    }
+
+    #[test]
+    fn test_formatting_is_reproducible() {
+        let format = || {
+            let mut g = PosetG::new_antichain(5);
+            g.adjoin_bot();
+            g.adjoin_top();
+            let mut o: PosetO = g.convert_into(MetaFields::ALL).unwrap();
+            o.pin(3);
+            o.pin(1);
+            format!("{:?}\n{}\n{}", o, o, o.to_dot())
+        };
+        let first = format();
+        assert!(first.contains("minimals: Some({5})"));
+        assert!(first.contains("maximals: Some({6})"));
+        for _ in 0..10 {
+            assert_eq!(format(), first);
+        }
+    }
+}
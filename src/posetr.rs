@@ -0,0 +1,444 @@
+//! A representation of large sparse posets by their covers and a reachability index.
+//!
+//! [PosetR] stores the Hasse diagram, as [PosetH](crate::poseth::PosetH) does, together with an index over a
+//! decomposition of the poset into chains $C_0, ..., C_{k-1}$: for each element x and each chain $C_c$, the
+//! index holds the position in $C_c$ of the lowest element of $C_c$ above x. Then $x \le y$ holds precisely
+//! when the entry of x for the chain of y is at most the position of y, so [Poset::leq] is a single lookup,
+//! and the up-set of x is read off the chains in $O(k + |\uparrow x|)$ steps. The index takes $O(n k)$
+//! memory, instead of the $O(n^2)$ of [PosetM](crate::posetm::PosetM) and [PosetG](crate::posetg::PosetG),
+//! and is built in $O(e k)$ steps, where $e$ is the number of covers.
+//!
+//! The chains are found greedily along the covers, so k is at least, but not necessarily equal to, the width
+//! of the poset. Operations that build a new poset, such as [Poset::product] or [Poset::sub], rebuild the
+//! index, while [Poset::add_relation] updates it in place.
+//!
+//! ```
+//! use fin_pos::posetr::PosetR;
+//! use fin_pos::Poset;
+//!
+//! // 0 < 1 < 3 and 0 < 2 < 3, with the redundant edge 0 < 3.
+//! let p = PosetR::from_covers(vec![[1, 2, 3].into_iter().collect(), [3].into_iter().collect(),
+//!     [3].into_iter().collect(), Default::default()]);
+//! assert!(p.leq(0, 3) && !p.leq(1, 2));
+//! assert_eq!(p.chain_count(), 2);
+//! assert_eq!(p.cover_set(0).as_slice(), &[1, 2]);
+//! ```
+
+use crate::error::PosetError;
+use crate::poseth::{cover_height, cover_order, unique};
+use crate::relation::cover_matrix;
+use crate::smallset::SmallSet;
+use crate::validate::{check_acyclic, check_new_relation};
+use crate::{sub_table, AnElement, BoolMatrix, Elements, Hasse, MetaData, PartialOrdering, Poset};
+
+use std::fmt;
+
+/// A representation of a poset by its covers and a reachability index, as described in the [module level
+/// documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PosetR {
+    pub md: MetaData,
+    h: Hasse,
+    index: ChainIndex,
+}
+
+/// The entry of the index for a chain without elements above the element.
+const UNREACHABLE: u32 = u32::MAX;
+
+/// A decomposition into chains and, for every element, the lowest position reachable in each chain.
+#[derive(Debug, Clone, PartialEq)]
+struct ChainIndex {
+    /// The chain of each element and its position in that chain.
+    place: Vec<(u32, u32)>,
+    /// The elements of each chain, from the bottom up.
+    chains: Vec<Vec<AnElement>>,
+    /// The rows of the elements, one after the other, each holding an entry per chain.
+    reach: Vec<u32>,
+}
+
+impl ChainIndex {
+    /// Builds the index of the order generated by the covers h, listed in the topological order.
+    fn new(h: &Hasse, order: &[AnElement]) -> ChainIndex {
+        let mut place = vec![(UNREACHABLE, UNREACHABLE); h.len()];
+        let mut chains: Vec<Vec<AnElement>> = vec![];
+        // Every element is the top of its chain when it is reached, and extends the chain by a cover that
+        // is not yet placed.
+        for &x in order {
+            if place[x].0 == UNREACHABLE {
+                place[x] = (chains.len() as u32, 0);
+                chains.push(vec![x]);
+            }
+            let (c, i) = place[x];
+            if let Some(&y) = h[x].iter().find(|&&y| place[y].0 == UNREACHABLE) {
+                place[y] = (c, i + 1);
+                chains[c as usize].push(y);
+            }
+        }
+        let k = chains.len();
+        let mut reach = vec![UNREACHABLE; h.len() * k];
+        for &x in order.iter().rev() {
+            for &y in h[x].iter() {
+                for c in 0..k {
+                    reach[x * k + c] = reach[x * k + c].min(reach[y * k + c]);
+                }
+            }
+            let (c, i) = place[x];
+            reach[x * k + c as usize] = i;
+        }
+        ChainIndex {
+            place,
+            chains,
+            reach,
+        }
+    }
+
+    fn row(&self, x: AnElement) -> &[u32] {
+        let k = self.chains.len();
+        &self.reach[x * k..(x + 1) * k]
+    }
+
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        let (c, i) = self.place[y];
+        self.row(x)[c as usize] <= i
+    }
+}
+
+impl PosetR {
+    /// Builds the poset whose element x lies below the elements `h[x]`, which may include redundant edges.
+    /// Panics if the edges form a cycle or mention an element out of range, see [PosetR::try_from_covers].
+    pub fn from_covers(h: Hasse) -> PosetR {
+        PosetR::try_from_covers(h).expect("the covers must form an acyclic graph on the elements")
+    }
+
+    /// Like [PosetR::from_covers], but fails if the edges mention an element out of range or form a cycle.
+    pub fn try_from_covers(mut h: Hasse) -> Result<PosetR, PosetError> {
+        check_acyclic(&h, h.len())?;
+        let index = ChainIndex::new(&h, &cover_order(&h));
+        for covers in h.iter_mut() {
+            let redundant: Vec<AnElement> = covers
+                .iter()
+                .copied()
+                .filter(|&y| covers.iter().any(|&z| z != y && index.leq(z, y)))
+                .collect();
+            for y in redundant {
+                covers.remove(&y);
+            }
+        }
+        Ok(PosetR {
+            md: MetaData::new(h.len()),
+            h,
+            index,
+        })
+    }
+
+    /// The upper covers of x, as stored. [Poset::upper_covers] returns them as a set.
+    pub fn cover_set(&self, x: AnElement) -> &SmallSet {
+        &self.h[x]
+    }
+
+    /// The number of chains in the decomposition underlying the index.
+    pub fn chain_count(&self) -> usize {
+        self.index.chains.len()
+    }
+
+    /// The poset with the same covers, keeping the handles, whose [MetaData] is otherwise recomputed.
+    fn rebuilt(&self, h: Hasse) -> PosetR {
+        let mut p = PosetR::from_covers(h);
+        p.md.handles = self.md.handles.clone();
+        p
+    }
+
+    /// The elements that cover nothing.
+    pub(crate) fn minimals_from_covers(&self) -> Elements {
+        let covering: Elements = self.h.iter().flatten().copied().collect();
+        (0..self.md.n).filter(|x| !covering.contains(x)).collect()
+    }
+
+    /// The number of elements in a longest chain, computed along the covers.
+    pub(crate) fn height_from_covers(&self) -> usize {
+        cover_height(&self.h)
+    }
+
+    /// The elements without upper covers.
+    pub(crate) fn maximals_from_covers(&self) -> Elements {
+        (0..self.md.n).filter(|&x| self.h[x].is_empty()).collect()
+    }
+}
+
+impl Poset for PosetR {
+    fn md(&self) -> &MetaData {
+        &self.md
+    }
+
+    fn md_mut(&mut self) -> &mut MetaData {
+        &mut self.md
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = AnElement>> {
+        Box::new(0..self.md.n)
+    }
+
+    /// A lookup in the index.
+    fn leq(&self, x: AnElement, y: AnElement) -> bool {
+        self.index.leq(x, y)
+    }
+
+    fn compare(&self, x: AnElement, y: AnElement) -> PartialOrdering {
+        PartialOrdering::from_leqs(self.leq(x, y), self.leq(y, x))
+    }
+
+    fn covers(&self, x: AnElement, y: AnElement) -> bool {
+        self.h[x].contains(&y)
+    }
+
+    fn upper_covers(&self, x: AnElement) -> Elements {
+        self.h[x].iter().copied().collect()
+    }
+
+    /// Reads the stored covers, which are sorted.
+    fn cover_relations(&self) -> Box<dyn Iterator<Item = (AnElement, AnElement)> + '_> {
+        Box::new(
+            self.h
+                .iter()
+                .enumerate()
+                .flat_map(|(x, covers)| covers.iter().map(move |&y| (x, y))),
+        )
+    }
+
+    /// Reads the chains from the lowest positions reachable from x.
+    fn up_set(&self, x: AnElement) -> Elements {
+        self.index
+            .chains
+            .iter()
+            .zip(self.index.row(x))
+            .filter_map(|(chain, &i)| chain.get(i as usize..))
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    fn find_bot(&mut self) {
+        self.md.bot = Some(unique(&self.minimals_from_covers()));
+    }
+
+    fn find_top(&mut self) {
+        self.md.top = Some(unique(&self.maximals_from_covers()));
+    }
+
+    fn find_minimals(&mut self) {
+        self.md.minimals = Some(self.minimals_from_covers());
+    }
+
+    fn find_maximals(&mut self) {
+        self.md.maximals = Some(self.maximals_from_covers());
+    }
+
+    fn find_height(&mut self) {
+        self.md.height = Some(self.height_from_covers());
+    }
+
+    /// The covers made redundant by the new relation are dropped, and the rows of the elements below x take
+    /// in the row of y, in $O(n k)$ steps. The chains are unchanged.
+    fn add_relation(&mut self, x: AnElement, y: AnElement) -> Result<(), PosetError> {
+        if !check_new_relation(self, x, y)? {
+            return Ok(());
+        }
+        let below: Vec<AnElement> = self.elements().filter(|&a| self.leq(a, x)).collect();
+        for &a in &below {
+            let redundant: Vec<AnElement> = self.h[a]
+                .iter()
+                .copied()
+                .filter(|&b| self.leq(y, b))
+                .collect();
+            for b in redundant {
+                self.h[a].remove(&b);
+            }
+        }
+        self.h[x].insert(y);
+        let k = self.chain_count();
+        for a in below {
+            for c in 0..k {
+                let i = self.index.reach[y * k + c];
+                let entry = &mut self.index.reach[a * k + c];
+                *entry = (*entry).min(i);
+            }
+        }
+        self.md.add_relation(x, y);
+        Ok(())
+    }
+
+    fn op(&self) -> Self {
+        let mut h: Hasse = vec![SmallSet::new(); self.md.n];
+        for (x, y) in self.cover_relations() {
+            h[y].insert(x);
+        }
+        let mut p = self.rebuilt(h);
+        p.md = self.md.op();
+        p
+    }
+
+    fn new_chain(n: usize) -> Self {
+        PosetR::from_covers((0..n).map(|i| (i + 1..n.min(i + 2)).collect()).collect())
+    }
+
+    fn new_antichain(n: usize) -> Self {
+        PosetR::from_covers(vec![SmallSet::new(); n])
+    }
+
+    /// The covers of $(a, b)$ are the pairs $(c, b)$ with $c$ covering $a$ and $(a, d)$ with $d$ covering
+    /// $b$.
+    fn product(&self, other: &Self) -> Self {
+        let m = other.md.n;
+        PosetR::from_covers(
+            (0..self.md.n * m)
+                .map(|x| {
+                    let (a, b) = (x / m, x % m);
+                    self.h[a]
+                        .iter()
+                        .map(|&c| c * m + b)
+                        .chain(other.h[b].iter().map(|&d| a * m + d))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn coproduct(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut h = self.h.clone();
+        h.extend(other.h.iter().map(|s| s.iter().map(|&y| y + n).collect()));
+        let mut p = PosetR::from_covers(h);
+        p.md = self.md.coproduct(&other.md);
+        p
+    }
+
+    /// The maximal elements of the poset are covered by the minimal elements of other.
+    fn ordinal_sum(&self, other: &Self) -> Self {
+        let n = self.md.n;
+        let mut h = self.h.clone();
+        h.extend(other.h.iter().map(|s| s.iter().map(|&y| y + n).collect()));
+        let minimals = other.minimals_from_covers();
+        for x in self.maximals_from_covers() {
+            h[x].extend(minimals.iter().map(|&y| y + n));
+        }
+        let mut p = PosetR::from_covers(h);
+        p.md = self.md.ordinal_sum(&other.md);
+        p
+    }
+
+    /// The new bottom is covered by the old minimal elements, and the index is rebuilt.
+    fn adjoin_bot(&mut self) {
+        let mut h = std::mem::take(&mut self.h);
+        h.push(self.minimals_from_covers().into_iter().collect());
+        self.index = ChainIndex::new(&h, &cover_order(&h));
+        self.h = h;
+        self.md.adjoin_bot();
+    }
+
+    /// The new top covers the old maximal elements, and the index is rebuilt.
+    fn adjoin_top(&mut self) {
+        let n = self.md.n;
+        for x in self.maximals_from_covers() {
+            self.h[x].insert(n);
+        }
+        self.h.push(SmallSet::new());
+        self.index = ChainIndex::new(&self.h, &cover_order(&self.h));
+        self.md.adjoin_top();
+    }
+
+    /// The covers of the subposet are found from the order of the poset.
+    fn sub(&self, s_0: &Elements) -> (Self, Vec<AnElement>) {
+        let back = sub_table(s_0, self.md.n);
+        let lt: BoolMatrix = back
+            .iter()
+            .map(|&x| back.iter().map(|&y| x != y && self.leq(x, y)).collect())
+            .collect();
+        let h: Hasse = cover_matrix(&lt)
+            .iter()
+            .map(|row| (0..row.len()).filter(|&j| row[j]).collect())
+            .collect();
+        let mut p = PosetR::from_covers(h);
+        p.md = self.md.sub(&back);
+        (p, back)
+    }
+
+    /// The covers must mention only the elements $0, ..., n-1$ and form no cycle, loops included.
+    fn is_valid_poset(&self) -> Result<(), PosetError> {
+        check_acyclic(&self.h, self.md.n)
+    }
+}
+
+/// Draws the Hasse diagram (see [ascii](crate::ascii)).
+impl fmt::Display for PosetR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::ascii::hasse_diagram(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::{corpus, random_poset};
+    use crate::posetm::PosetM;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_agrees_with_matrix() {
+        for m in corpus() {
+            let p = PosetR::from_poset(&m);
+            assert!(p.same_order(&m));
+            assert_eq!(p.is_valid_poset(), Ok(()));
+            for x in m.elements() {
+                assert_eq!(p.up_set(x), m.up_set(x));
+                assert_eq!(p.upper_covers(x), m.upper_covers(x));
+            }
+            assert!(p.op().same_order(&m.op()));
+            let mut q = p.clone();
+            q.adjoin_bot();
+            q.adjoin_top();
+            let mut n = PosetM::from_poset(&m);
+            n.adjoin_bot();
+            n.adjoin_top();
+            assert!(q.same_order(&n));
+        }
+        let m = random_poset(9, 30, 2);
+        let p = PosetR::from_poset(&m);
+        assert!(p.product(&p).same_order(&m.product(&m)));
+        assert!(p.coproduct(&p).same_order(&m.coproduct(&m)));
+        assert!(p.ordinal_sum(&p).same_order(&m.ordinal_sum(&m)));
+        let s: Elements = [0, 3, 4, 8].into();
+        assert!(p.sub(&s).0.same_order(&m.sub(&s).0));
+    }
+
+    #[test]
+    fn test_add_relation() {
+        for seed in 0..10 {
+            let n = 40;
+            let mut p = PosetR::new_antichain(n);
+            let mut m = PosetM::new_antichain(n);
+            let mut rng = SplitMix64::new(seed);
+            for _ in 0..2 * n {
+                let (x, y) = (rng.below(n), rng.below(n));
+                assert_eq!(p.add_relation(x, y), m.add_relation(x, y));
+            }
+            assert!(p.same_order(&m));
+            let covers: Vec<_> = p.cover_relations().collect();
+            assert_eq!(covers, m.cover_relations().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_invalid_covers() {
+        let cycle: Hasse = vec![[1].into_iter().collect(), [0].into_iter().collect()];
+        assert!(matches!(
+            PosetR::try_from_covers(cycle),
+            Err(PosetError::Cycle { .. })
+        ));
+        let out_of_range: Hasse = vec![[2].into_iter().collect(), SmallSet::new()];
+        assert_eq!(
+            PosetR::try_from_covers(out_of_range),
+            Err(PosetError::IndexOutOfRange { index: 2, n: 2 })
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Triage of noisy cover lists before they are trusted as Hasse diagrams.
+//!
+//! A [PosetH] built with [PosetH::from_covers] stores whatever edges it is given. Machine-extracted input
+//! often lists relations that are implied by other edges, or loops from an element to itself. Each stored
+//! edge $x \to y$ is classified as
+//! - [EdgeClass::SelfLoop] if $x = y$,
+//! - [EdgeClass::Redundant] if y can also be reached from another stored successor of x, so that dropping
+//!   the edge keeps the order the edges generate,
+//! - [EdgeClass::Cover] otherwise.
+//!
+//! Dropping the redundant edges and the loops is the transitive reduction, which [PosetH::sparsify]
+//! performs selectively, after [PosetH::redundancy_report] has summarized what would be dropped. The
+//! classification assumes the edges have no cycles other than self-loops.
+//!
+//! ```
+//! use fin_pos::poseth::PosetH;
+//! use fin_pos::redundancy::{EdgeClass, EdgeClassFilter};
+//!
+//! // 0 -> 1 -> 2 together with the implied 0 -> 2 and a loop at 1.
+//! let p = PosetH::from_covers(vec![
+//!     [1, 2].into_iter().collect(),
+//!     [1, 2].into_iter().collect(),
+//!     Default::default(),
+//! ]);
+//! let classes = p.classify_edges();
+//! assert_eq!(classes[&(0, 2)], EdgeClass::Redundant);
+//! assert_eq!(classes[&(1, 1)], EdgeClass::SelfLoop);
+//! let q = p.sparsify(EdgeClassFilter::COVER);
+//! assert_eq!(q.cover_set(0).as_slice(), &[1]);
+//! assert_eq!(q.cover_set(1).as_slice(), &[2]);
+//! ```
+
+use crate::poseth::PosetH;
+use crate::{AnElement, Elements, Poset};
+
+use std::collections::HashMap;
+use std::ops::{BitOr, BitOrAssign};
+
+/// The kinds of stored edges (see the [module level documentation](self)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeClass {
+    Cover,
+    Redundant,
+    SelfLoop,
+}
+
+/// A set of [EdgeClass]es, combined with `|`, naming the edges kept by [PosetH::sparsify].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EdgeClassFilter(u8);
+
+impl EdgeClassFilter {
+    pub const NONE: EdgeClassFilter = EdgeClassFilter(0);
+    pub const COVER: EdgeClassFilter = EdgeClassFilter(1);
+    pub const REDUNDANT: EdgeClassFilter = EdgeClassFilter(1 << 1);
+    pub const SELF_LOOP: EdgeClassFilter = EdgeClassFilter(1 << 2);
+    pub const ALL: EdgeClassFilter = EdgeClassFilter((1 << 3) - 1);
+
+    /// Returns true if edges of the class c pass the filter.
+    pub fn keeps(self, c: EdgeClass) -> bool {
+        let bit = match c {
+            EdgeClass::Cover => EdgeClassFilter::COVER,
+            EdgeClass::Redundant => EdgeClassFilter::REDUNDANT,
+            EdgeClass::SelfLoop => EdgeClassFilter::SELF_LOOP,
+        };
+        self.0 & bit.0 != 0
+    }
+}
+
+impl BitOr for EdgeClassFilter {
+    type Output = EdgeClassFilter;
+
+    fn bitor(self, rhs: EdgeClassFilter) -> EdgeClassFilter {
+        EdgeClassFilter(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for EdgeClassFilter {
+    fn bitor_assign(&mut self, rhs: EdgeClassFilter) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A summary of the classification of the stored edges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RedundancyStats {
+    pub covers: usize,
+    pub redundant: usize,
+    pub self_loops: usize,
+    /// The sources of redundant edges and self-loops, with the number of such edges leaving them, most
+    /// offending first (ties broken by element).
+    pub worst_sources: Vec<(AnElement, usize)>,
+}
+
+/// Classifies every stored edge of p.
+pub fn classify_edges(p: &PosetH) -> HashMap<(AnElement, AnElement), EdgeClass> {
+    let n = p.md.n;
+    let mut reach: Vec<Option<Elements>> = vec![None; n];
+    let mut classes = HashMap::new();
+    for x in 0..n {
+        let succ: Vec<AnElement> = p.cover_set(x).iter().copied().filter(|&y| y != x).collect();
+        for &z in &succ {
+            reach[z].get_or_insert_with(|| p.up_set(z));
+        }
+        for &y in p.cover_set(x) {
+            let class = if y == x {
+                EdgeClass::SelfLoop
+            } else if succ
+                .iter()
+                .any(|&z| z != y && reach[z].as_ref().unwrap().contains(&y))
+            {
+                EdgeClass::Redundant
+            } else {
+                EdgeClass::Cover
+            };
+            classes.insert((x, y), class);
+        }
+    }
+    classes
+}
+
+/// Returns p with only the stored edges whose class passes keep. Kept edges keep their weights, and
+/// pinned handles carry over. The rest of the [MetaData](crate::MetaData) is reset.
+pub fn sparsify(p: &PosetH, keep: EdgeClassFilter) -> PosetH {
+    let classes = classify_edges(p);
+    let kept = |x: AnElement, y: AnElement| keep.keeps(classes[&(x, y)]);
+    let mut q = PosetH::from_covers(
+        (0..p.md.n)
+            .map(|x| {
+                p.cover_set(x)
+                    .iter()
+                    .copied()
+                    .filter(|&y| kept(x, y))
+                    .collect()
+            })
+            .collect(),
+    );
+    for (x, y) in p.cover_relations().filter(|&(x, y)| kept(x, y)) {
+        q.set_cover_weight(x, y, p.cover_weight(x, y).unwrap())
+            .unwrap();
+    }
+    q.md.handles = p.md.handles.clone();
+    q
+}
+
+/// Counts the stored edges of p by class and lists the worst sources.
+pub fn redundancy_report(p: &PosetH) -> RedundancyStats {
+    let mut stats = RedundancyStats::default();
+    let mut offences: HashMap<AnElement, usize> = HashMap::new();
+    for ((x, _), class) in classify_edges(p) {
+        match class {
+            EdgeClass::Cover => stats.covers += 1,
+            EdgeClass::Redundant => stats.redundant += 1,
+            EdgeClass::SelfLoop => stats.self_loops += 1,
+        }
+        if class != EdgeClass::Cover {
+            *offences.entry(x).or_insert(0) += 1;
+        }
+    }
+    stats.worst_sources = offences.into_iter().collect();
+    stats
+        .worst_sources
+        .sort_by_key(|&(x, count)| (std::cmp::Reverse(count), x));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smallset::SmallSet;
+    use crate::Poset;
+
+    fn complete_chain(n: usize) -> PosetH {
+        PosetH::from_covers((0..n).map(|i| (i + 1..n).collect()).collect())
+    }
+
+    #[test]
+    fn test_complete_chain() {
+        let n = 7;
+        let p = complete_chain(n);
+        let classes = p.classify_edges();
+        assert_eq!(classes.len(), n * (n - 1) / 2);
+        let covers: Vec<_> = classes
+            .iter()
+            .filter(|(_, &c)| c == EdgeClass::Cover)
+            .map(|(&e, _)| e)
+            .collect();
+        assert_eq!(covers.len(), n - 1);
+        assert!(covers.iter().all(|&(x, y)| y == x + 1));
+
+        let q = p.sparsify(EdgeClassFilter::COVER);
+        assert!(q.same_order(&PosetH::new_chain(n)));
+        let stats = q.redundancy_report();
+        assert_eq!(
+            (stats.covers, stats.redundant, stats.self_loops),
+            (n - 1, 0, 0)
+        );
+        assert!(stats.worst_sources.is_empty());
+
+        let stats = p.redundancy_report();
+        assert_eq!(stats.redundant, (n - 1) * (n - 2) / 2);
+        assert_eq!(stats.worst_sources[0], (0, n - 2));
+        assert_eq!(stats.worst_sources.len(), n - 2);
+    }
+
+    #[test]
+    fn test_self_loops() {
+        let mut h: Vec<SmallSet> = (0..4).map(|i| (i + 1..4.min(i + 2)).collect()).collect();
+        h[1].insert(1);
+        h[3].insert(3);
+        let mut p = PosetH::from_covers(h);
+        p.set_cover_weight(1, 2, 3.5).unwrap();
+        let stats = p.redundancy_report();
+        assert_eq!((stats.covers, stats.redundant, stats.self_loops), (3, 0, 2));
+        assert_eq!(stats.worst_sources, vec![(1, 1), (3, 1)]);
+
+        let q = p.sparsify(EdgeClassFilter::COVER | EdgeClassFilter::REDUNDANT);
+        assert_eq!(q.redundancy_report().self_loops, 0);
+        assert!(q.same_order(&p));
+        assert_eq!(q.cover_weight(1, 2), Some(3.5));
+        let all = p.sparsify(EdgeClassFilter::ALL);
+        assert_eq!(all.h, p.h);
+        assert!(p.sparsify(EdgeClassFilter::NONE).is_antichain());
+    }
+}
@@ -0,0 +1,240 @@
+//! Refinements: comparing two orders on the same set of elements.
+//!
+//! A poset $P$ *refines* (or *extends*) a poset $Q$ on the same elements if $x \le_Q y$ implies
+//! $x \le_P y$, i.e., if $P$ only adds order information to $Q$. A refinement which is a total order is a
+//! linear extension. The checks run row by row on [BitMatrix]es, so each element costs $O(n/64)$ word
+//! operations once the matrices are built. A linear extension may also be given as the list of the elements
+//! in order, e.g., a schedule of tasks to be checked against their dependencies ([is_linear_extension]).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let chain = PosetM::new_chain(3);
+//! assert!(chain.refines(&PosetM::new_antichain(3)).unwrap());
+//! assert_eq!(chain.refinement_gap(&PosetM::new_antichain(3)).unwrap(), vec![(0, 1), (0, 2), (1, 2)]);
+//! assert!(chain.refines(&PosetM::new_chain(4)).is_err());
+//! assert!(!chain.extends(&PosetM::new_chain(4)));
+//!
+//! // The vee has bottom 2 below 0 and 1.
+//! let vee = PosetM::new_corolla(2);
+//! assert!(vee.is_linear_extension(&[2, 1, 0]));
+//! assert!(!vee.is_linear_extension(&[0, 2, 1]) && !vee.is_linear_extension(&[2, 1]));
+//! ```
+
+use crate::bitset::{BitMatrix, BitRow};
+use crate::{AnElement, Poset};
+
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when comparing orders on underlying sets of different sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeMismatch {
+    /// The size of the poset expected to be finer.
+    pub finer: usize,
+    /// The size of the poset expected to be coarser.
+    pub coarser: usize,
+}
+
+impl fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot compare orders on {} and on {} elements",
+            self.finer, self.coarser
+        )
+    }
+}
+
+impl Error for SizeMismatch {}
+
+fn matrices<P: Poset + ?Sized, Q: Poset + ?Sized>(
+    p: &P,
+    coarser: &Q,
+) -> Result<(BitMatrix, BitMatrix), SizeMismatch> {
+    let (finer, coarser) = (BitMatrix::new(p), BitMatrix::new(coarser));
+    match finer.n() == coarser.n() {
+        true => Ok((finer, coarser)),
+        false => Err(SizeMismatch {
+            finer: finer.n(),
+            coarser: coarser.n(),
+        }),
+    }
+}
+
+/// Returns true if every relation of coarser also holds in p.
+pub fn refines<P: Poset + ?Sized, Q: Poset + ?Sized>(
+    p: &P,
+    coarser: &Q,
+) -> Result<bool, SizeMismatch> {
+    let (finer, coarser) = matrices(p, coarser)?;
+    Ok((0..finer.n()).all(|x| coarser.up_row(x).is_subset(finer.up_row(x))))
+}
+
+/// Returns the pairs $(x, y)$ with $x \le y$ in p but not in coarser, sorted lexicographically.
+pub fn refinement_gap<P: Poset + ?Sized, Q: Poset + ?Sized>(
+    p: &P,
+    coarser: &Q,
+) -> Result<Vec<(AnElement, AnElement)>, SizeMismatch> {
+    let (finer, coarser) = matrices(p, coarser)?;
+    Ok((0..finer.n())
+        .flat_map(|x| {
+            let mut gap: BitRow = coarser.up_row(x).clone();
+            gap.not_assign();
+            gap.and_assign(finer.up_row(x));
+            gap.into_ones().map(move |y| (x, y))
+        })
+        .collect())
+}
+
+/// Returns true if p refines coarser and is a total order.
+pub fn is_linear_extension_of<P: Poset + ?Sized, Q: Poset + ?Sized>(
+    p: &P,
+    coarser: &Q,
+) -> Result<bool, SizeMismatch> {
+    let (finer, coarser) = matrices(p, coarser)?;
+    Ok((0..finer.n()).all(|x| {
+        let mut comparable = finer.up_row(x).clone();
+        comparable.or_assign(finer.down_row(x));
+        coarser.up_row(x).is_subset(finer.up_row(x)) && comparable.count_ones() == finer.n()
+    }))
+}
+
+/// Returns true if p and other have the same size and p refines other.
+pub fn extends<P: Poset + ?Sized, Q: Poset + ?Sized>(p: &P, other: &Q) -> bool {
+    refines(p, other).unwrap_or(false)
+}
+
+/// Returns true if order lists every element of p exactly once, each after the elements below it.
+pub fn is_linear_extension<P: Poset + ?Sized>(p: &P, order: &[AnElement]) -> bool {
+    let b = BitMatrix::new(p);
+    if order.len() != b.n() {
+        return false;
+    }
+    let mut placed = BitRow::new(b.n());
+    order.iter().all(|&x| {
+        if x >= b.n() || placed.get(x) {
+            return false;
+        }
+        let mut above = b.up_row(x).clone();
+        above.and_assign(&placed);
+        placed.set(x);
+        above.none()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetm::PosetM;
+    use crate::relation::{strict_matrix, topological_order};
+
+    #[test]
+    fn test_everything_refines_the_antichain() {
+        for p in corpus() {
+            let n = p.md.n;
+            assert!(p.refines(&PosetM::new_antichain(n)).unwrap());
+            assert!(p.refines(&p).unwrap());
+            assert_eq!(p.refinement_gap(&p).unwrap(), vec![]);
+            assert_eq!(
+                p.refines(&PosetM::new_antichain(n + 1)),
+                Err(SizeMismatch {
+                    finer: n,
+                    coarser: n + 1
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_chains_refining_the_vee() {
+        // The vee has bottom 2 below 0 and 1.
+        let vee = PosetM::new_corolla(2);
+        let mut refining = vec![];
+        for perm in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            // The chain perm[0] < perm[1] < perm[2].
+            let mut pos = [0; 3];
+            for (i, &x) in perm.iter().enumerate() {
+                pos[x] = i;
+            }
+            let m = (0..3)
+                .map(|x| (0..3).map(|y| pos[x] <= pos[y]).collect())
+                .collect();
+            let chain = PosetM::new(&m);
+            assert_eq!(
+                chain.refines(&vee).unwrap(),
+                chain.is_linear_extension_of(&vee).unwrap()
+            );
+            if chain.refines(&vee).unwrap() {
+                refining.push(perm);
+            }
+        }
+        assert_eq!(refining, vec![[2, 0, 1], [2, 1, 0]]);
+        assert!(!vee
+            .is_linear_extension_of(&PosetM::new_antichain(3))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_gap_to_linear_extension() {
+        for p in corpus() {
+            let n = p.md.n;
+            let order = topological_order(&strict_matrix(&p));
+            let mut pos = vec![0; n];
+            for (i, &x) in order.iter().enumerate() {
+                pos[x] = i;
+            }
+            let m = (0..n)
+                .map(|x| (0..n).map(|y| pos[x] <= pos[y]).collect())
+                .collect();
+            let ext = PosetM::new(&m);
+            assert!(ext.is_linear_extension_of(&p).unwrap());
+            let mut expected = vec![];
+            for x in 0..n {
+                for y in 0..n {
+                    if pos[x] < pos[y] && !p.leq(x, y) {
+                        assert!(!p.leq(y, x));
+                        expected.push((x, y));
+                    }
+                }
+            }
+            assert_eq!(ext.refinement_gap(&p).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_extends_and_schedules() {
+        for p in corpus() {
+            let n = p.md.n;
+            assert!(p.extends(&PosetM::new_antichain(n)));
+            assert!(!p.extends(&PosetM::new_antichain(n + 1)));
+            assert_eq!(
+                PosetM::new_chain(n).extends(&p),
+                p.is_linear_extension(&(0..n).collect::<Vec<_>>())
+            );
+            let order = topological_order(&strict_matrix(&p));
+            assert!(p.is_linear_extension(&order));
+            let mut reversed = order.clone();
+            reversed.reverse();
+            assert_eq!(p.is_linear_extension(&reversed), p.is_antichain());
+            if n > 0 {
+                assert!(!p.is_linear_extension(&order[1..]));
+                let mut repeated = order.clone();
+                repeated[n - 1] = order[0];
+                assert_eq!(p.is_linear_extension(&repeated), n == 1);
+                let mut beyond = order.clone();
+                beyond[n - 1] = n;
+                assert!(!p.is_linear_extension(&beyond));
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Internal helpers working with the strict order relation of a poset stored as a boolean matrix, where
+//! entry $(i, j)$ holds precisely when $i < j$. Algorithms that only need the order (and not a particular
+//! representation) build this matrix once and then run in terms of it.
+
+use crate::{AnElement, Poset};
+
+/// The strict order relation of p, indexed by the elements $0, 1, ..., n-1$.
+pub(crate) fn strict_matrix<P: Poset + ?Sized>(p: &P) -> Vec<Vec<bool>> {
+    let elts: Vec<usize> = p.elements().collect();
+    elts.iter()
+        .map(|&x| elts.iter().map(|&y| p.lt(x, y)).collect())
+        .collect()
+}
+
+/// The covering relation: entry $(i, j)$ holds if $i < j$ with nothing strictly in between.
+pub(crate) fn cover_matrix(lt: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let n = lt.len();
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| lt[i][j] && !(0..n).any(|k| lt[i][k] && lt[k][j]))
+                .collect()
+        })
+        .collect()
+}
+
+/// Indices sorted so that smaller elements come first (by the size of their down-sets).
+pub(crate) fn topological_order(lt: &[Vec<bool>]) -> Vec<usize> {
+    let n = lt.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&j| (0..n).filter(|&i| lt[i][j]).count());
+    order
+}
+
+/// The level (depth) of each element: the number of elements in a longest chain strictly below it.
+pub(crate) fn levels(lt: &[Vec<bool>]) -> Vec<usize> {
+    let n = lt.len();
+    let mut level = vec![0; n];
+    for j in topological_order(lt) {
+        level[j] = (0..n)
+            .filter(|&i| lt[i][j])
+            .map(|i| level[i] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+    level
+}
+
+/// A longest chain, listed from the bottom up: it ends at the first element of largest level and steps down
+/// to the first element one level lower each time.
+pub(crate) fn longest_chain(lt: &[Vec<bool>]) -> Vec<usize> {
+    let n = lt.len();
+    let level = levels(lt);
+    let Some(top) = (0..n).max_by_key(|&x| (level[x], std::cmp::Reverse(x))) else {
+        return vec![];
+    };
+    let mut chain = vec![top];
+    while let Some(&x) = chain.last().filter(|&&x| level[x] > 0) {
+        chain.push(
+            (0..n)
+                .find(|&y| lt[y][x] && level[y] + 1 == level[x])
+                .unwrap(),
+        );
+    }
+    chain.reverse();
+    chain
+}
+
+/// The colevel of each element: the number of elements in a longest chain strictly above it.
+pub(crate) fn colevels(lt: &[Vec<bool>]) -> Vec<usize> {
+    let n = lt.len();
+    let mut colevel = vec![0; n];
+    for i in topological_order(lt).into_iter().rev() {
+        colevel[i] = (0..n)
+            .filter(|&j| lt[i][j])
+            .map(|j| colevel[j] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+    colevel
+}
+
+/// The connected components of the graph on s with the given adjacency, each sorted, in order of their
+/// smallest elements.
+pub(crate) fn components<F: Fn(AnElement, AnElement) -> bool>(
+    s: &[AnElement],
+    adjacent: F,
+) -> Vec<Vec<AnElement>> {
+    let mut seen = vec![false; s.len()];
+    let mut parts = vec![];
+    for start in 0..s.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut part = vec![start];
+        let mut next = 0;
+        while next < part.len() {
+            let i = part[next];
+            for j in 0..s.len() {
+                if !seen[j] && adjacent(s[i], s[j]) {
+                    seen[j] = true;
+                    part.push(j);
+                }
+            }
+            next += 1;
+        }
+        let mut part: Vec<AnElement> = part.into_iter().map(|i| s[i]).collect();
+        part.sort_unstable();
+        parts.push(part);
+    }
+    parts
+}
@@ -0,0 +1,34 @@
+//! A small deterministic pseudo-random number generator (SplitMix64), used where algorithms need
+//! reproducible randomness from a user-supplied seed.
+
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> SplitMix64 {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        mix(self.0)
+    }
+
+    /// A value in $\{0, ..., n-1\}$. The modulo bias is negligible for the small n used in this crate.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A value in $\{0, ..., n-1\}$, for counts that need not fit in 64 bits.
+    pub(crate) fn below_u128(&mut self, n: u128) -> u128 {
+        let high = (self.next_u64() as u128) << 64;
+        (high | self.next_u64() as u128) % n
+    }
+}
+
+/// Scrambles x into a well-distributed 64-bit value (the SplitMix64 output function).
+pub(crate) fn mix(x: u64) -> u64 {
+    let mut z = x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
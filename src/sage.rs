@@ -0,0 +1,231 @@
+//! Exchange of posets with [SageMath](https://www.sagemath.org).
+//!
+//! [Poset::to_sage](crate::Poset::to_sage) writes a call of Sage's `Poset` constructor on the elements
+//! $0, ..., n-1$ and the covers, which can be pasted into a Sage session. In the other direction,
+//! [from_sage_covers] reads the output of Sage's `P.cover_relations()`, a list of pairs `[a, b]` with
+//! $a \lessdot b$, whose elements are integers or quoted strings. Elements that cover nothing and are
+//! covered by nothing do not occur in that list, and so are lost, unless they are passed to
+//! [from_sage_covers] separately (e.g., from `P.list()`).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::sage::from_sage_covers;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_chain(3);
+//! assert_eq!(p.to_sage(), "Poset(([0, 1, 2], [[0, 1], [1, 2]]), cover_relations=True)");
+//!
+//! let q = from_sage_covers::<PosetM>("[[1, 'x'], [1, 'y']]", &[]).unwrap();
+//! assert_eq!(q.labels(), &["1", "x", "y"]);
+//! assert_eq!(q.poset().md.n, 3);
+//! ```
+
+use crate::convertors::FromPoset;
+use crate::error::PosetError;
+use crate::labeled::{LabeledPoset, PosetBuilder};
+use crate::posetm::PosetM;
+use crate::Poset;
+
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// The ways in which reading the output of Sage can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SageError {
+    /// The text is not a list of pairs, as first noticed at the given byte offset.
+    Syntax { offset: usize },
+    /// The cover of lower by upper lies on a cycle of the listed covers.
+    Cycle { lower: String, upper: String },
+}
+
+impl fmt::Display for SageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SageError::Syntax { offset } => {
+                write!(f, "not a list of cover relations at offset {}", offset)
+            }
+            SageError::Cycle { lower, upper } => {
+                write!(f, "the cover {} < {} lies on a cycle", lower, upper)
+            }
+        }
+    }
+}
+
+impl Error for SageError {}
+
+/// The argument of Sage's `Poset` constructor describing p by its elements and covers.
+pub fn to_sage<P: Poset + ?Sized>(p: &P) -> String {
+    let elements: Vec<String> = p.elements().map(|x| x.to_string()).collect();
+    let covers: Vec<String> = p
+        .layout_edges()
+        .into_iter()
+        .map(|(x, y)| format!("[{}, {}]", x, y))
+        .collect();
+    format!(
+        "Poset(([{}], [{}]), cover_relations=True)",
+        elements.join(", "),
+        covers.join(", ")
+    )
+}
+
+/// Reads the output of Sage's `cover_relations()` into a poset in the representation P, labeled by the
+/// elements as written (integers in decimal, strings without their quotes). The extra elements are added
+/// first, followed by the elements of the covers in order of first appearance.
+pub fn from_sage_covers<P>(text: &str, extra: &[&str]) -> Result<LabeledPoset<String, P>, SageError>
+where
+    P: Poset + FromPoset<PosetM>,
+{
+    let mut builder = extra
+        .iter()
+        .fold(PosetBuilder::new(), |b, &x| b.element(x.to_string()));
+    let mut parser = Parser {
+        text,
+        chars: text.char_indices().peekable(),
+    };
+    parser.expect('[')?;
+    if !parser.eat(']') {
+        loop {
+            parser.expect('[')?;
+            let lower = parser.element()?;
+            parser.expect(',')?;
+            let upper = parser.element()?;
+            parser.expect(']')?;
+            builder = builder.cover(lower, upper);
+            if parser.eat(']') {
+                break;
+            }
+            parser.expect(',')?;
+        }
+    }
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(parser.error());
+    }
+    builder.build().map_err(|e| match e {
+        PosetError::Cycle { x, y } => SageError::Cycle {
+            lower: builder.labels()[x].clone(),
+            upper: builder.labels()[y].clone(),
+        },
+        e => unreachable!("a builder only reports cycles, not {}", e),
+    })
+}
+
+/// A reader of nested lists of integers and quoted strings, skipping whitespace between the tokens.
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn error(&mut self) -> SageError {
+        let offset = self.chars.peek().map_or(self.text.len(), |&(i, _)| i);
+        SageError::Syntax { offset }
+    }
+
+    /// Consumes c if it is the next token.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if(|&(_, d)| d == c).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SageError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    /// An integer, or a string in single or double quotes.
+    fn element(&mut self) -> Result<String, SageError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some(&(start, '-' | '0'..='9')) => {
+                self.chars.next();
+                let mut end = start + 1;
+                while let Some((i, c)) = self.chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    end = i + c.len_utf8();
+                }
+                match &self.text[start..end] {
+                    "-" => Err(SageError::Syntax { offset: start }),
+                    number => Ok(number.to_string()),
+                }
+            }
+            Some(&(start, quote @ ('\'' | '"'))) => {
+                self.chars.next();
+                for (i, c) in self.chars.by_ref() {
+                    if c == quote {
+                        return Ok(self.text[start + 1..i].to_string());
+                    }
+                }
+                Err(SageError::Syntax { offset: start })
+            }
+            _ => Err(self.error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+
+    #[test]
+    fn test_round_trip() {
+        for p in corpus() {
+            // The covers as Sage prints them.
+            let covers: Vec<[usize; 2]> =
+                p.layout_edges().into_iter().map(|(x, y)| [x, y]).collect();
+            let covers = format!("{:?}", covers);
+            assert!(p.to_sage().contains(&covers));
+            let extra: Vec<String> = p.elements().map(|x| x.to_string()).collect();
+            let extra: Vec<&str> = extra.iter().map(String::as_str).collect();
+            let q = from_sage_covers::<PosetG>(&covers, &extra).unwrap();
+            assert_eq!(q.labels(), extra.as_slice());
+            assert!(q.poset().same_order(&p));
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let p = from_sage_covers::<PosetM>(" [ [ -1 ,\"a b\"],['c', 20]\n]", &["z"]).unwrap();
+        assert_eq!(p.labels(), &["z", "-1", "a b", "c", "20"]);
+        assert_eq!(p.leq_labels(&"-1".into(), &"a b".into()), Some(true));
+        assert_eq!(p.leq_labels(&"z".into(), &"c".into()), Some(false));
+        assert_eq!(
+            from_sage_covers::<PosetM>("[]\n", &[])
+                .unwrap()
+                .poset()
+                .md
+                .n,
+            0
+        );
+        for (text, offset) in [
+            ("[[1, 2]", 7),
+            ("[[1, 2]] x", 9),
+            ("[[1 2]]", 4),
+            ("[['a, 2]]", 2),
+            ("[[-, 2]]", 2),
+            ("", 0),
+        ] {
+            assert_eq!(
+                from_sage_covers::<PosetM>(text, &[]).unwrap_err(),
+                SageError::Syntax { offset },
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            from_sage_covers::<PosetM>("[[0, 1], [1, 0]]", &[])
+                .unwrap_err()
+                .to_string(),
+            "the cover 1 < 0 lies on a cycle"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Layering and list scheduling: partitions of a poset into antichains ("waves") that can be processed one
+//! after the other.
+//!
+//! Peeling off the minimal elements over and over (Kahn's algorithm) splits a poset into its
+//! [antichain layers](antichain_layers): layer $k$ holds the elements with $k$ elements in a longest chain
+//! strictly below them. A scheduler that can only run a limited number of independent tasks at
+//! once uses [schedule_waves] instead, which peels in the same way but takes at most a given number of the
+//! available elements per wave, choosing them by priority and deferring the rest. The layers are also a
+//! partition into the fewest antichains ([antichain_decomposition]).
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_corolla(3);
+//! assert_eq!(p.antichain_layers(), vec![vec![3], vec![0, 1, 2]]);
+//! assert_eq!(p.schedule_waves(2, |x| x as i64), vec![vec![3], vec![2, 1], vec![0]]);
+//! ```
+
+use crate::relation::strict_matrix;
+use crate::{AnElement, Elements, Poset};
+
+use std::cmp::Reverse;
+
+/// Returns the antichain layers of p, bottom first, each sorted by index.
+pub fn antichain_layers<P: Poset + ?Sized>(p: &P) -> Vec<Vec<AnElement>> {
+    schedule_waves(p, usize::MAX, |_| 0)
+}
+
+/// Returns the antichain layers of p as sets. By Mirsky's theorem, no partition of p into antichains has
+/// fewer parts, as a longest chain meets each part at most once.
+pub fn antichain_decomposition<P: Poset + ?Sized>(p: &P) -> Vec<Elements> {
+    antichain_layers(p)
+        .into_iter()
+        .map(|layer| layer.into_iter().collect())
+        .collect()
+}
+
+/// Splits p into waves of at most max_width elements by Kahn-style peeling. An element is available once
+/// all the elements below it are in earlier waves, and each wave takes the available elements of highest
+/// priority, ties broken by smaller index, deferring the others to later waves. The elements of a wave are
+/// listed in the order they were chosen, so concatenating the waves gives a linear extension of p. The
+/// priority is evaluated once per element. Panics if max_width is 0 and p is not empty.
+pub fn schedule_waves<P, F>(p: &P, max_width: usize, priority: F) -> Vec<Vec<AnElement>>
+where
+    P: Poset + ?Sized,
+    F: Fn(AnElement) -> i64,
+{
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    assert!(
+        max_width > 0 || n == 0,
+        "waves of width 0 cannot hold any element"
+    );
+    let key: Vec<(Reverse<i64>, AnElement)> = (0..n).map(|x| (Reverse(priority(x)), x)).collect();
+    let mut waiting_on: Vec<usize> = (0..n)
+        .map(|y| (0..n).filter(|&x| lt[x][y]).count())
+        .collect();
+    let mut available: Vec<AnElement> = (0..n).filter(|&y| waiting_on[y] == 0).collect();
+    let mut waves = vec![];
+    while !available.is_empty() {
+        available.sort_unstable_by_key(|&x| key[x]);
+        let rest = available.split_off(max_width.min(available.len()));
+        let wave = std::mem::replace(&mut available, rest);
+        for &x in &wave {
+            for y in (0..n).filter(|&y| lt[x][y]) {
+                waiting_on[y] -= 1;
+                if waiting_on[y] == 0 {
+                    available.push(y);
+                }
+            }
+        }
+        waves.push(wave);
+    }
+    waves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{corpus, random_poset};
+    use crate::posetm::PosetM;
+    use crate::refinement::is_linear_extension;
+    use crate::relation::levels;
+
+    #[test]
+    fn test_layers_are_levels() {
+        for mut p in corpus() {
+            let level = levels(&strict_matrix(&p));
+            let layers = p.antichain_layers();
+            for (k, layer) in layers.iter().enumerate() {
+                assert!(layer.is_sorted());
+                assert!(layer.iter().all(|&x| level[x] == k));
+            }
+            assert_eq!(layers.concat().len(), p.md.n);
+            assert_eq!(p.schedule_waves(usize::MAX, |_| 0), layers);
+            let parts = p.antichain_decomposition();
+            assert_eq!(parts.len(), p.height());
+            assert!(parts
+                .iter()
+                .all(|part| part.iter().all(|&x| part.iter().all(|&y| !p.lt(x, y)))));
+        }
+    }
+
+    #[test]
+    fn test_contract() {
+        for p in corpus() {
+            for w in 1..5 {
+                let priority = |x: usize| (x as i64 * 7) % 5;
+                let waves = p.schedule_waves(w, priority);
+                assert!(waves.iter().all(|wave| !wave.is_empty() && wave.len() <= w));
+                assert!(is_linear_extension(&p, &waves.concat()));
+                // An element deferred from a wave had no higher priority than anything chosen in it.
+                let mut done = vec![false; p.md.n];
+                for wave in &waves {
+                    for x in p.elements().filter(|&x| !done[x] && !wave.contains(&x)) {
+                        if p.elements().all(|y| !p.lt(y, x) || done[y]) {
+                            assert_eq!(wave.len(), w);
+                            let last = *wave.last().unwrap();
+                            assert!((Reverse(priority(last)), last) < (Reverse(priority(x)), x));
+                        }
+                    }
+                    wave.iter().for_each(|&x| done[x] = true);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_width_one_is_greedy_extension() {
+        let p = random_poset(12, 25, 4);
+        let priority = |x: usize| -((x * 5 % 12) as i64);
+        let waves = p.schedule_waves(1, priority);
+        let order: Vec<usize> = waves.concat();
+        assert_eq!(waves.len(), 12);
+        assert!(is_linear_extension(&p, &order));
+        // At every step the chosen element is the best of those whose predecessors are all placed.
+        for (i, &x) in order.iter().enumerate() {
+            let best = order[i..]
+                .iter()
+                .copied()
+                .filter(|&y| order[i..].iter().all(|&z| !p.lt(z, y)))
+                .min_by_key(|&y| (Reverse(priority(y)), y))
+                .unwrap();
+            assert_eq!(x, best);
+        }
+    }
+
+    #[test]
+    fn test_corolla() {
+        for n in 0..8 {
+            let p = PosetM::new_corolla(n);
+            let waves = p.schedule_waves(2, |_| 0);
+            assert_eq!(waves.len(), 1 + n.div_ceil(2));
+            assert_eq!(waves[0], vec![n]);
+            assert_eq!(waves[1..].concat(), (0..n).collect::<Vec<_>>());
+        }
+        assert!(PosetM::new_antichain(0).schedule_waves(0, |_| 0).is_empty());
+    }
+}
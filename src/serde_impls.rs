@@ -0,0 +1,183 @@
+//! Serialization with [serde], enabled by the `serde` feature.
+//!
+//! The hash-based collections inside the posets are written in sorted order, so that equal posets always
+//! serialize to the same bytes: sets become sorted sequences, the up-sets of a
+//! [PosetG](crate::posetg::PosetG) become a map with sorted keys, and keyed tables with non-string keys
+//! become sorted sequences of entries. Deserialization reads the data back as it is, without checking that
+//! it encodes a poset (see [Poset::is_valid_poset](crate::Poset::is_valid_poset)).
+//!
+//! In JSON, the chain $0 < 1$ as a [PosetM](crate::posetm::PosetM) reads
+//! `{"md":{"n":2,"top":{"A":1},"bot":{"A":0},"minimals":[0],"maximals":[1],"height":2,"atoms":null,
+//! "coatoms":null},"m":[[true,true],[false,true]]}`. The pinned [handles](crate::handles) are not written.
+
+use crate::smallset::SmallSet;
+use crate::AnElement;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+
+impl Serialize for SmallSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for SmallSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SmallSet, D::Error> {
+        Ok(Vec::<AnElement>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// An optional set of elements, as null or a sorted sequence.
+pub(crate) mod sorted_opt_set {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        s: &Option<HashSet<AnElement>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.as_ref()
+            .map(|s| s.iter().collect::<BTreeSet<_>>())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HashSet<AnElement>>, D::Error> {
+        Ok(Option::<Vec<AnElement>>::deserialize(deserializer)?.map(|s| s.into_iter().collect()))
+    }
+}
+
+/// The up-sets of the elements, as a map with sorted keys to sorted sequences.
+pub(crate) mod sorted_graph {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        g: &HashMap<AnElement, HashSet<AnElement>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        g.iter()
+            .map(|(&x, up)| (x, up.iter().collect::<BTreeSet<_>>()))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<AnElement, HashSet<AnElement>>, D::Error> {
+        Ok(
+            BTreeMap::<AnElement, Vec<AnElement>>::deserialize(deserializer)?
+                .into_iter()
+                .map(|(x, up)| (x, up.into_iter().collect()))
+                .collect(),
+        )
+    }
+}
+
+/// A map with keys that need not be strings, as a sequence of (key, value) pairs sorted by key.
+pub(crate) mod sorted_entries {
+    use super::*;
+
+    pub fn serialize<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Serialize + Ord,
+        V: Serialize,
+    {
+        let mut entries: Vec<(&K, &V)> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::convertors::FromPoset;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+    use crate::{Elt, MetaData, Poset};
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    fn round_trip<T: Serialize + DeserializeOwned>(x: &T) -> (String, T) {
+        let json = serde_json::to_string(x).unwrap();
+        let back = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        (json, back)
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for p in corpus() {
+            let mut g = PosetG::from_poset(&p);
+            g.find_bot();
+            if g.md.n > 0 {
+                g.pin(0);
+            }
+            let (_, back) = round_trip(&g);
+            assert_eq!(back, g);
+            let m = PosetM::from_poset(&g);
+            assert_eq!(round_trip(&m).1, m);
+            let mut h = PosetH::from_poset(&g);
+            if let Some(&(x, y)) = h.layout_edges().first() {
+                h.set_cover_weight(x, y, 2.5).unwrap();
+            }
+            assert_eq!(round_trip(&h).1, h);
+            assert_eq!(round_trip(&g.md).1, g.md);
+        }
+        assert_eq!(round_trip(&Elt::NotPresent).0, "\"NotPresent\"");
+        assert_eq!(round_trip(&Some(Elt::A(3))).0, "{\"A\":3}");
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut p = PosetG::new_antichain(40);
+        p.adjoin_bot();
+        p.find_maximals();
+        // Equal posets built in different orders serialize identically.
+        let mut q = PosetG::from_poset(&PosetM::from_poset(&p));
+        q.md = p.md.clone();
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(serde_json::to_string(&q).unwrap(), json);
+        assert!(json.contains("\"40\":[0,1,2,3,4,5,6,7,8,9,10,"));
+        let md: MetaData = serde_json::from_str(&serde_json::to_string(&p.md).unwrap()).unwrap();
+        assert_eq!(md.maximals.unwrap().len(), 40);
+    }
+
+    #[test]
+    fn test_chain_schema() {
+        let mut p = PosetM::new_chain(2);
+        p.find_top();
+        p.find_bot();
+        p.find_minimals();
+        p.find_maximals();
+        p.md.height = Some(2);
+        assert_eq!(
+            serde_json::to_string(&p).unwrap(),
+            concat!(
+                "{\"md\":{\"n\":2,\"top\":{\"A\":1},\"bot\":{\"A\":0},\"minimals\":[0],\"maximals\":[1],",
+                "\"height\":2,\"atoms\":null,\"coatoms\":null},",
+                "\"m\":[[true,true],[false,true]]}"
+            )
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! A set of elements optimized for the small sets of covers found in sparse Hasse diagrams.
+//!
+//! A [SmallSet] stores up to [INLINE] elements inline, without any heap allocation, and spills to a sorted
+//! [Vec] beyond that. The elements are always kept sorted, so iteration is in increasing order and
+//! membership costs a binary search. Sets that can be as large as the poset itself (such as up-sets or the
+//! minimal elements stored in the [MetaData](crate::MetaData)) remain `HashSet`s.
+//!
+//! ```
+//! use fin_pos::smallset::SmallSet;
+//!
+//! let mut s: SmallSet = [5, 1, 3].into_iter().collect();
+//! assert!(s.insert(2));
+//! assert!(!s.insert(3));
+//! assert_eq!(s.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 5]);
+//! assert!(!s.is_spilled());
+//! ```
+
+use crate::AnElement;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// The number of elements stored without a heap allocation.
+pub const INLINE: usize = 4;
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, items: [AnElement; INLINE] },
+    Spilled(Vec<AnElement>),
+}
+
+/// A sorted set of elements, stored inline while it has at most [INLINE] elements.
+#[derive(Clone)]
+pub struct SmallSet(Repr);
+
+impl SmallSet {
+    /// The empty set.
+    pub fn new() -> SmallSet {
+        SmallSet(Repr::Inline {
+            len: 0,
+            items: [0; INLINE],
+        })
+    }
+
+    /// The elements, in increasing order.
+    pub fn as_slice(&self) -> &[AnElement] {
+        match &self.0 {
+            Repr::Inline { len, items } => &items[..*len as usize],
+            Repr::Spilled(v) => v,
+        }
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns true if the set has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if x is in the set, by a binary search.
+    pub fn contains(&self, x: &AnElement) -> bool {
+        self.as_slice().binary_search(x).is_ok()
+    }
+
+    /// Iterates over the elements in increasing order.
+    pub fn iter(&self) -> std::slice::Iter<'_, AnElement> {
+        self.as_slice().iter()
+    }
+
+    /// Iterates over the elements of the set that are not in other, in increasing order.
+    pub fn difference<'a>(&'a self, other: &'a SmallSet) -> impl Iterator<Item = &'a AnElement> {
+        self.iter().filter(move |x| !other.contains(x))
+    }
+
+    /// Adds x to the set, returning true if it was not present.
+    pub fn insert(&mut self, x: AnElement) -> bool {
+        let i = match self.as_slice().binary_search(&x) {
+            Ok(_) => return false,
+            Err(i) => i,
+        };
+        match &mut self.0 {
+            Repr::Inline { len, items } if (*len as usize) < INLINE => {
+                items.copy_within(i..*len as usize, i + 1);
+                items[i] = x;
+                *len += 1;
+            }
+            Repr::Inline { items, .. } => {
+                let mut v = Vec::with_capacity(2 * INLINE);
+                v.extend_from_slice(items);
+                v.insert(i, x);
+                self.0 = Repr::Spilled(v);
+            }
+            Repr::Spilled(v) => v.insert(i, x),
+        }
+        true
+    }
+
+    /// Removes x from the set, returning true if it was present. A spilled set that shrinks to [INLINE]
+    /// elements moves back inline.
+    pub fn remove(&mut self, x: &AnElement) -> bool {
+        let i = match self.as_slice().binary_search(x) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        match &mut self.0 {
+            Repr::Inline { len, items } => {
+                items.copy_within(i + 1..*len as usize, i);
+                *len -= 1;
+                items[*len as usize] = 0;
+            }
+            Repr::Spilled(v) => {
+                v.remove(i);
+                if v.len() <= INLINE {
+                    *self = v.iter().copied().collect();
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns true if the elements are stored on the heap.
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.0, Repr::Spilled(_))
+    }
+
+    /// The number of bytes allocated on the heap.
+    pub fn heap_bytes(&self) -> usize {
+        match &self.0 {
+            Repr::Inline { .. } => 0,
+            Repr::Spilled(v) => v.capacity() * std::mem::size_of::<AnElement>(),
+        }
+    }
+}
+
+impl Default for SmallSet {
+    fn default() -> SmallSet {
+        SmallSet::new()
+    }
+}
+
+impl PartialEq for SmallSet {
+    fn eq(&self, other: &SmallSet) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for SmallSet {}
+
+impl Hash for SmallSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl fmt::Debug for SmallSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<AnElement> for SmallSet {
+    fn from_iter<I: IntoIterator<Item = AnElement>>(iter: I) -> SmallSet {
+        let mut s = SmallSet::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl Extend<AnElement> for SmallSet {
+    fn extend<I: IntoIterator<Item = AnElement>>(&mut self, iter: I) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a SmallSet {
+    type Item = &'a AnElement;
+    type IntoIter = std::slice::Iter<'a, AnElement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<&HashSet<AnElement>> for SmallSet {
+    fn from(s: &HashSet<AnElement>) -> SmallSet {
+        s.iter().copied().collect()
+    }
+}
+
+impl From<&SmallSet> for HashSet<AnElement> {
+    fn from(s: &SmallSet) -> HashSet<AnElement> {
+        s.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseth::PosetH;
+    use crate::rng::SplitMix64;
+    use crate::Poset;
+    use std::collections::HashMap;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_spill_and_unspill() {
+        let mut s = SmallSet::new();
+        for x in [9, 3, 7, 1] {
+            assert!(s.insert(x));
+        }
+        assert!(!s.is_spilled());
+        assert_eq!(s.heap_bytes(), 0);
+        s.insert(5);
+        assert!(s.is_spilled());
+        assert_eq!(s.as_slice(), &[1, 3, 5, 7, 9]);
+        assert!(s.contains(&7) && !s.contains(&4));
+        assert!(s.remove(&9) && !s.remove(&9));
+        assert!(!s.is_spilled());
+        assert_eq!(s, [7, 5, 3, 1].into_iter().collect());
+
+        let t: SmallSet = [3, 4].into_iter().collect();
+        assert_eq!(s.difference(&t).copied().collect::<Vec<_>>(), vec![1, 5, 7]);
+        assert_eq!(HashSet::from(&t), [4, 3].into_iter().collect());
+    }
+
+    /// The heap bytes of a hash set: one word and one control byte per bucket, plus a group of control
+    /// bytes, where the number of buckets is a power of two exceeding the capacity by at least 1/8.
+    fn hash_set_heap_bytes(s: &HashSet<AnElement>) -> usize {
+        match s.capacity() {
+            0 => 0,
+            c => {
+                let buckets = if c < 4 {
+                    4
+                } else {
+                    (c * 8 / 7).next_power_of_two()
+                };
+                buckets * (size_of::<AnElement>() + 1) + 16
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_of_random_forest() {
+        // Every element above 0 is an upper cover of a random smaller element, so the diagram is a tree rooted
+        // at the bottom 0.
+        let n = 100_000;
+        let mut rng = SplitMix64::new(7);
+        let mut covers: Vec<SmallSet> = vec![SmallSet::new(); n];
+        let mut hashed: HashMap<AnElement, HashSet<AnElement>> = HashMap::new();
+        for x in 0..n {
+            hashed.insert(x, HashSet::new());
+        }
+        for x in 1..n {
+            let parent = rng.below(x);
+            covers[parent].insert(x);
+            hashed.get_mut(&parent).unwrap().insert(x);
+        }
+        let p = PosetH::from_covers(covers);
+        assert_eq!(p.md.n, n);
+        assert!(p.leq(0, n - 1));
+
+        let small = size_of::<Vec<SmallSet>>()
+            + n * size_of::<SmallSet>()
+            + p.h.iter().map(SmallSet::heap_bytes).sum::<usize>();
+        let buckets = (n * 8 / 7).next_power_of_two();
+        let hashed_bytes = size_of::<HashMap<AnElement, HashSet<AnElement>>>()
+            + buckets * (size_of::<(AnElement, HashSet<AnElement>)>() + 1)
+            + hashed.values().map(hash_set_heap_bytes).sum::<usize>();
+        assert!(
+            2 * small < hashed_bytes,
+            "{} bytes against {} bytes",
+            small,
+            hashed_bytes
+        );
+    }
+}
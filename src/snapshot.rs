@@ -0,0 +1,697 @@
+//! A compact, versioned binary format for storing posets long-term.
+//!
+//! A snapshot records the order of a poset through its cover pairs, which determine the order and are
+//! independent of the representation, together with the [MetaData] fields computed so far. Two
+//! snapshots of the same order with the same computed fields are byte-identical, whatever the
+//! representations they were stored from, so snapshots can serve as keys of a content-addressed cache. The
+//! representation to restore into is chosen when reading (see [Kind]).
+//!
+//! # Layout
+//! All integers are unsigned LEB128 varints unless stated otherwise.
+//! - The magic bytes `FPOS`, followed by the format version (one byte).
+//! - A sequence of sections, each a tag byte, the length of its payload, and the payload. Readers skip
+//!   sections with unknown tags, which is how later versions of the format add fields without breaking
+//!   older readers.
+//!   - Tag 1, the order (required): $n$, at most [MAX_ELEMENTS], the number of cover pairs, and the cover
+//!     pairs sorted lexicographically. Each pair is stored as the increase of its lower element over the
+//!     previous pair's, followed by its upper element, as an increase over the previous pair's upper element
+//!     when the lower elements agree.
+//!   - Tag 2, the metadata (optional): a byte of flags saying which of top, bot, minimals, and maximals
+//!     are known, then the known fields in that order. The top and bot are stored as 0 for
+//!     [Elt::NotPresent] and $i+1$ for [Elt::A]$(i)$; sets are stored as their size followed by their sorted
+//!     elements, each as the increase over the previous one.
+//!   - Tag 3, more metadata (optional): a byte of flags saying which of the height, atoms, and coatoms are
+//!     known, then the known fields in that order, the height as a varint and the sets as in tag 2.
+//! - The CRC-32 checksum of everything before it (four bytes, little-endian).
+//!
+//! Pinned [handles](crate::handles) are not stored.
+//!
+//! ```
+//! use fin_pos::posetg::PosetG;
+//! use fin_pos::dynamic::DynPoset;
+//! use fin_pos::snapshot::{from_snapshot, Kind, Snapshot};
+//! use fin_pos::Poset;
+//!
+//! let mut p = PosetG::new_corolla(3);
+//! p.find_top();
+//! let q = from_snapshot(&p.to_snapshot(), Kind::Graph).unwrap();
+//! match q {
+//!     DynPoset::Graph(q) => assert_eq!(p, q),
+//!     _ => panic!("wrong representation"),
+//! }
+//! ```
+
+use crate::dynamic::DynPoset;
+use crate::parallel::map_range;
+use crate::posetg::PosetG;
+use crate::poseth::PosetH;
+use crate::posetm::PosetM;
+use crate::poseto::PosetO;
+use crate::posetr::PosetR;
+use crate::relation::{cover_matrix, strict_matrix};
+use crate::{BiPaGraph, Elt, Hasse, MetaData, Poset, SortedGraph};
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"FPOS";
+
+/// The newest format version this crate writes. It reads this version and all earlier ones.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The largest number of elements a snapshot is read with. Restoring into [Kind::Matrix] builds the
+/// $n \times n$ order, so a corrupted count is rejected before anything of that size is allocated. The
+/// other kinds allocate in proportion to the elements and relations restored.
+pub const MAX_ELEMENTS: usize = 1 << 16;
+
+const TAG_ORDER: u8 = 1;
+const TAG_META: u8 = 2;
+const TAG_META_EXTRA: u8 = 3;
+
+/// The representation a snapshot is restored into, one for each variant of [DynPoset].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Graph,
+    Matrix,
+    Hasse,
+    Ordered,
+    Indexed,
+}
+
+/// The reasons a byte buffer fails to be read as a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The buffer does not start with the magic bytes.
+    BadMagic,
+    /// The snapshot was written by a newer version of the format.
+    UnsupportedVersion(u8),
+    /// The checksum does not match the contents.
+    BadChecksum,
+    /// The buffer ends in the middle of a field.
+    Truncated,
+    /// The contents are inconsistent, e.g., a cover pair mentions a non-existent element or the cover
+    /// pairs contain a cycle.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a poset snapshot"),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "snapshot format version {} is not supported", v)
+            }
+            SnapshotError::BadChecksum => write!(f, "snapshot checksum mismatch"),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::Malformed(what) => write!(f, "malformed snapshot: {}", what),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+/// Storing posets as snapshots. Implemented by each representation, with the [Kind] that restores into it.
+pub trait Snapshot: Poset {
+    /// The representation kind of this type.
+    const KIND: Kind;
+
+    /// Encodes the poset as a snapshot.
+    fn to_snapshot(&self) -> Vec<u8> {
+        encode(self)
+    }
+}
+
+impl Snapshot for PosetG {
+    const KIND: Kind = Kind::Graph;
+}
+
+impl Snapshot for PosetM {
+    const KIND: Kind = Kind::Matrix;
+}
+
+impl Snapshot for PosetH {
+    const KIND: Kind = Kind::Hasse;
+}
+
+impl Snapshot for PosetO {
+    const KIND: Kind = Kind::Ordered;
+}
+
+impl Snapshot for PosetR {
+    const KIND: Kind = Kind::Indexed;
+}
+
+/// Encodes p as a snapshot. This is how posets of representations outside this crate are stored.
+pub fn encode<P: Poset + ?Sized>(p: &P) -> Vec<u8> {
+    let covers = cover_matrix(&strict_matrix(p));
+    let n = covers.len();
+
+    let mut order = vec![];
+    write_varint(&mut order, n as u64);
+    let pairs: Vec<(usize, usize)> = (0..n)
+        .flat_map(|x| (0..n).map(move |y| (x, y)))
+        .filter(|&(x, y)| covers[x][y])
+        .collect();
+    write_varint(&mut order, pairs.len() as u64);
+    let (mut prev_x, mut prev_y) = (0, 0);
+    for &(x, y) in pairs.iter() {
+        write_varint(&mut order, (x - prev_x) as u64);
+        let y_delta = if x == prev_x { y - prev_y } else { y };
+        write_varint(&mut order, y_delta as u64);
+        (prev_x, prev_y) = (x, y);
+    }
+
+    let md = p.md();
+    let mut meta = vec![];
+    let flags = md.top.is_some() as u8
+        | (md.bot.is_some() as u8) << 1
+        | (md.minimals.is_some() as u8) << 2
+        | (md.maximals.is_some() as u8) << 3;
+    meta.push(flags);
+    for e in [&md.top, &md.bot].into_iter().flatten() {
+        write_varint(
+            &mut meta,
+            match e {
+                Elt::NotPresent => 0,
+                Elt::A(i) => *i as u64 + 1,
+            },
+        );
+    }
+    for s in [&md.minimals, &md.maximals].into_iter().flatten() {
+        write_set(&mut meta, s);
+    }
+
+    let mut extra = vec![];
+    let flags = md.height.is_some() as u8
+        | (md.atoms.is_some() as u8) << 1
+        | (md.coatoms.is_some() as u8) << 2;
+    extra.push(flags);
+    if let Some(height) = md.height {
+        write_varint(&mut extra, height as u64);
+    }
+    for s in [&md.atoms, &md.coatoms].into_iter().flatten() {
+        write_set(&mut extra, s);
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(FORMAT_VERSION);
+    for (tag, payload) in [
+        (TAG_ORDER, order),
+        (TAG_META, meta),
+        (TAG_META_EXTRA, extra),
+    ] {
+        bytes.push(tag);
+        write_varint(&mut bytes, payload.len() as u64);
+        bytes.extend(payload);
+    }
+    let crc = crc32(&bytes);
+    bytes.extend(crc.to_le_bytes());
+    bytes
+}
+
+/// Restores a poset from a snapshot written by this or an earlier version of the format, in the
+/// representation kind.
+pub fn from_snapshot(bytes: &[u8], kind: Kind) -> Result<DynPoset, SnapshotError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    if bytes.len() < MAGIC.len() + 1 + 4 {
+        return Err(SnapshotError::Truncated);
+    }
+    let (body, crc) = bytes.split_at(bytes.len() - 4);
+    if crc32(body) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+        return Err(SnapshotError::BadChecksum);
+    }
+    let version = body[4];
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let mut r = Reader {
+        bytes: body,
+        pos: MAGIC.len() + 1,
+    };
+    let mut order: Option<(usize, Vec<(usize, usize)>)> = None;
+    let mut md = MetaData::new(0);
+    while !r.done() {
+        let tag = r.byte()?;
+        let len = r.length()?;
+        let mut s = Reader {
+            bytes: r.take(len)?,
+            pos: 0,
+        };
+        match tag {
+            TAG_ORDER => order = Some(read_order(&mut s)?),
+            TAG_META => read_meta(&mut s, &mut md)?,
+            TAG_META_EXTRA => read_meta_extra(&mut s, &mut md)?,
+            _ => {}
+        }
+    }
+    let (n, pairs) = order.ok_or(SnapshotError::Malformed("missing order section"))?;
+    md.n = n;
+    check_meta(&md)?;
+
+    let mut up: Vec<Vec<usize>> = vec![vec![]; n];
+    for &(x, y) in pairs.iter() {
+        up[x].push(y);
+    }
+    check_acyclic(&up)?;
+    let covers = || -> Hasse { (0..n).map(|x| up[x].iter().copied().collect()).collect() };
+    let up_sets = || map_range(n, |x| up_set(&up, x));
+    let mut p = match kind {
+        Kind::Graph => {
+            let g: BiPaGraph = up_sets()
+                .into_iter()
+                .enumerate()
+                .map(|(x, s)| (x, s.into_iter().collect()))
+                .collect();
+            DynPoset::Graph(PosetG::new(&g))
+        }
+        Kind::Matrix => {
+            let leq: Vec<Vec<bool>> = up_sets()
+                .into_iter()
+                .map(|s| {
+                    let mut row = vec![false; n];
+                    s.into_iter().for_each(|y| row[y] = true);
+                    row
+                })
+                .collect();
+            DynPoset::Matrix(PosetM::new(&leq))
+        }
+        Kind::Hasse => DynPoset::Hasse(PosetH::from_covers(covers())),
+        Kind::Ordered => {
+            let g: SortedGraph = up_sets()
+                .into_iter()
+                .enumerate()
+                .map(|(x, s)| (x, s.into_iter().collect()))
+                .collect();
+            DynPoset::Ordered(PosetO::new(&g))
+        }
+        Kind::Indexed => DynPoset::Indexed(PosetR::from_covers(covers())),
+    };
+    *p.md_mut() = md;
+    Ok(p)
+}
+
+fn read_order(r: &mut Reader) -> Result<(usize, Vec<(usize, usize)>), SnapshotError> {
+    let n = r.length()?;
+    if n > MAX_ELEMENTS {
+        return Err(SnapshotError::Malformed("too many elements"));
+    }
+    let count = r.length()?;
+    let mut pairs = Vec::with_capacity(count.min(r.remaining()));
+    let (mut x, mut y) = (0usize, 0usize);
+    for k in 0..count {
+        let dx = r.length()?;
+        let dy = r.length()?;
+        x = x
+            .checked_add(dx)
+            .ok_or(SnapshotError::Malformed("element out of range"))?;
+        y = if dx == 0 && k > 0 {
+            y.checked_add(dy)
+        } else {
+            Some(dy)
+        }
+        .ok_or(SnapshotError::Malformed("element out of range"))?;
+        if x >= n || y >= n {
+            return Err(SnapshotError::Malformed("element out of range"));
+        }
+        pairs.push((x, y));
+    }
+    Ok((n, pairs))
+}
+
+fn read_meta(r: &mut Reader, md: &mut MetaData) -> Result<(), SnapshotError> {
+    let flags = r.byte()?;
+    let elt = |r: &mut Reader| -> Result<Elt, SnapshotError> {
+        Ok(match r.length()? {
+            0 => Elt::NotPresent,
+            i => Elt::A(i - 1),
+        })
+    };
+    if flags & 1 != 0 {
+        md.top = Some(elt(r)?);
+    }
+    if flags & 2 != 0 {
+        md.bot = Some(elt(r)?);
+    }
+    if flags & 4 != 0 {
+        md.minimals = Some(read_set(r)?);
+    }
+    if flags & 8 != 0 {
+        md.maximals = Some(read_set(r)?);
+    }
+    Ok(())
+}
+
+fn read_meta_extra(r: &mut Reader, md: &mut MetaData) -> Result<(), SnapshotError> {
+    let flags = r.byte()?;
+    if flags & 1 != 0 {
+        md.height = Some(r.length()?);
+    }
+    if flags & 2 != 0 {
+        md.atoms = Some(read_set(r)?);
+    }
+    if flags & 4 != 0 {
+        md.coatoms = Some(read_set(r)?);
+    }
+    Ok(())
+}
+
+/// Checks that the stored metadata only mentions existing elements, and that the height is at most $n$.
+fn check_meta(md: &MetaData) -> Result<(), SnapshotError> {
+    let in_range = |e: &Option<Elt>| !matches!(e, Some(Elt::A(i)) if *i >= md.n);
+    let set_in_range = |s: &Option<HashSet<usize>>| s.iter().flatten().all(|&i| i < md.n);
+    if !(in_range(&md.top)
+        && in_range(&md.bot)
+        && set_in_range(&md.minimals)
+        && set_in_range(&md.maximals)
+        && set_in_range(&md.atoms)
+        && set_in_range(&md.coatoms))
+    {
+        return Err(SnapshotError::Malformed("metadata element out of range"));
+    }
+    if md.height.is_some_and(|h| h > md.n) {
+        return Err(SnapshotError::Malformed(
+            "height exceeds the number of elements",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the cover pairs contain no cycle, loops included, by Kahn's algorithm in $O(n + e)$ steps,
+/// where $e$ is the number of cover pairs. This runs before anything of size $n^2$ is built.
+fn check_acyclic(up: &[Vec<usize>]) -> Result<(), SnapshotError> {
+    let mut indegree = vec![0usize; up.len()];
+    for &y in up.iter().flatten() {
+        indegree[y] += 1;
+    }
+    let mut stack: Vec<usize> = (0..up.len()).filter(|&x| indegree[x] == 0).collect();
+    let mut ordered = 0;
+    while let Some(x) = stack.pop() {
+        ordered += 1;
+        for &y in up[x].iter() {
+            indegree[y] -= 1;
+            if indegree[y] == 0 {
+                stack.push(y);
+            }
+        }
+    }
+    match ordered == up.len() {
+        true => Ok(()),
+        false => Err(SnapshotError::Malformed("cover pairs contain a cycle")),
+    }
+}
+
+/// The elements reachable from x along the acyclic cover pairs, x included.
+fn up_set(up: &[Vec<usize>], x: usize) -> Vec<usize> {
+    let mut seen: HashSet<usize> = HashSet::new();
+    let mut stack = vec![x];
+    while let Some(z) = stack.pop() {
+        if seen.insert(z) {
+            stack.extend(up[z].iter().copied());
+        }
+    }
+    seen.into_iter().collect()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        let b = *self.bytes.get(self.pos).ok_or(SnapshotError::Truncated)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if len > self.remaining() {
+            return Err(SnapshotError::Truncated);
+        }
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SnapshotError::Malformed("varint too long"))
+    }
+
+    fn length(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.varint()?).map_err(|_| SnapshotError::Malformed("value too large"))
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        bytes.push((v as u8 & 0x7f) | 0x80);
+        v >>= 7;
+    }
+    bytes.push(v as u8);
+}
+
+fn write_set(bytes: &mut Vec<u8>, s: &HashSet<usize>) {
+    let mut v: Vec<usize> = s.iter().copied().collect();
+    v.sort_unstable();
+    write_varint(bytes, v.len() as u64);
+    let mut prev = 0;
+    for x in v {
+        write_varint(bytes, (x - prev) as u64);
+        prev = x;
+    }
+}
+
+fn read_set(r: &mut Reader) -> Result<HashSet<usize>, SnapshotError> {
+    let len = r.length()?;
+    let mut s = HashSet::with_capacity(len.min(r.remaining()));
+    let mut x = 0usize;
+    for _ in 0..len {
+        x = x
+            .checked_add(r.length()?)
+            .ok_or(SnapshotError::Malformed("element out of range"))?;
+        s.insert(x);
+    }
+    Ok(s)
+}
+
+/// The CRC-32 (IEEE) checksum.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::{boolean_lattice, corpus};
+
+    fn graph_of(p: &PosetM) -> PosetG {
+        let g: BiPaGraph = p
+            .elements()
+            .map(|i| (i, p.elements().filter(|&j| p.leq(i, j)).collect()))
+            .collect();
+        PosetG::new(&g)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for mut p in corpus() {
+            // The fields computed so far survive the round trip, and no others appear.
+            p.md.invalidate();
+            p.find_top();
+            p.find_minimals();
+            p.find_height();
+            p.find_coatoms();
+            match from_snapshot(&p.to_snapshot(), Kind::Matrix).unwrap() {
+                DynPoset::Matrix(q) => assert_eq!(p, q),
+                _ => panic!("wrong representation"),
+            }
+
+            let g = graph_of(&p);
+            match from_snapshot(&g.to_snapshot(), Kind::Graph).unwrap() {
+                DynPoset::Graph(q) => assert_eq!(g, q),
+                _ => panic!("wrong representation"),
+            }
+
+            match from_snapshot(&encode(&p), Kind::Hasse).unwrap() {
+                DynPoset::Hasse(q) => {
+                    assert!(q.same_order(&p));
+                    assert_eq!(q.md, p.md);
+                    assert_eq!(q.to_snapshot(), encode(&p));
+                }
+                _ => panic!("wrong representation"),
+            }
+
+            for kind in [Kind::Ordered, Kind::Indexed] {
+                let q = from_snapshot(&encode(&p), kind).unwrap();
+                assert_eq!(q.kind(), kind);
+                assert!(q.same_order(&p));
+                assert_eq!(q.md(), &p.md);
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_identity() {
+        // The same order with the same known fields, in five representations.
+        let mut m = boolean_lattice(3);
+        m.md.invalidate();
+        m.find_top();
+        let mut g = graph_of(&m);
+        let mut h: PosetH = FromPoset::from_poset(&m);
+        let mut o: PosetO = FromPoset::from_poset(&m);
+        let mut r: PosetR = FromPoset::from_poset(&m);
+        g.md = m.md.clone();
+        h.md = m.md.clone();
+        o.md = m.md.clone();
+        r.md = m.md.clone();
+        assert_eq!(g.to_snapshot(), m.to_snapshot());
+        assert_eq!(h.to_snapshot(), m.to_snapshot());
+        assert_eq!(o.to_snapshot(), m.to_snapshot());
+        assert_eq!(r.to_snapshot(), m.to_snapshot());
+    }
+
+    #[test]
+    fn test_unknown_sections_are_skipped() {
+        let p = PosetM::new_corolla(2);
+        let mut bytes = p.to_snapshot();
+        bytes.truncate(bytes.len() - 4);
+        bytes.extend([9, 3, 1, 2, 3]);
+        let crc = crc32(&bytes);
+        bytes.extend(crc.to_le_bytes());
+        assert_eq!(from_snapshot(&bytes, Kind::Matrix), Ok(DynPoset::Matrix(p)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let read = |b: &[u8]| from_snapshot(b, Kind::Matrix);
+        let sealed = |mut b: Vec<u8>| {
+            let crc = crc32(&b);
+            b.extend(crc.to_le_bytes());
+            b
+        };
+        let bytes = PosetM::new_chain(3).to_snapshot();
+        assert_eq!(read(b"nope"), Err(SnapshotError::BadMagic));
+        assert_eq!(
+            read(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::BadChecksum)
+        );
+
+        let mut b = bytes[..bytes.len() - 4].to_vec();
+        b[4] = FORMAT_VERSION + 1;
+        assert_eq!(
+            read(&sealed(b)),
+            Err(SnapshotError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+
+        // A cycle 0 < 1 < 0, with a valid checksum.
+        let mut b = MAGIC.to_vec();
+        b.extend([FORMAT_VERSION, TAG_ORDER, 6, 2, 2, 0, 1, 1, 0]);
+        assert_eq!(
+            read(&sealed(b)),
+            Err(SnapshotError::Malformed("cover pairs contain a cycle"))
+        );
+
+        // A height of 5 for a single element.
+        let mut b = MAGIC.to_vec();
+        b.extend([FORMAT_VERSION, TAG_ORDER, 2, 1, 0, TAG_META_EXTRA, 2, 1, 5]);
+        assert_eq!(
+            read(&sealed(b)),
+            Err(SnapshotError::Malformed(
+                "height exceeds the number of elements"
+            ))
+        );
+
+        // A loop 0 < 0.
+        let mut b = MAGIC.to_vec();
+        b.extend([FORMAT_VERSION, TAG_ORDER, 4, 1, 1, 0, 0]);
+        assert_eq!(
+            read(&sealed(b)),
+            Err(SnapshotError::Malformed("cover pairs contain a cycle"))
+        );
+
+        // The largest antichain allowed, restored along its covers without building the order.
+        let mut b = MAGIC.to_vec();
+        b.extend([FORMAT_VERSION, TAG_ORDER, 4, 0x80, 0x80, 0x04, 0]);
+        let p = from_snapshot(&sealed(b), Kind::Hasse).unwrap();
+        assert_eq!(p.elements().count(), MAX_ELEMENTS);
+
+        // A count of 2^40 elements without covers, with a valid checksum.
+        let mut b = MAGIC.to_vec();
+        b.extend([
+            FORMAT_VERSION,
+            TAG_ORDER,
+            7,
+            0x80,
+            0x80,
+            0x80,
+            0x80,
+            0x80,
+            0x20,
+            0,
+        ]);
+        assert_eq!(
+            read(&sealed(b)),
+            Err(SnapshotError::Malformed("too many elements"))
+        );
+    }
+
+    #[test]
+    fn test_corrupted_buffers() {
+        let mut p = boolean_lattice(3);
+        p.find_bot();
+        p.find_maximals();
+        let bytes = p.to_snapshot();
+        let read = |b: &[u8]| from_snapshot(b, Kind::Hasse);
+        for len in 0..bytes.len() {
+            assert!(read(&bytes[..len]).is_err());
+        }
+        for i in 0..bytes.len() {
+            for flip in [1u8, 0x80, 0xff] {
+                let mut b = bytes.clone();
+                b[i] ^= flip;
+                assert!(read(&b).is_err());
+            }
+        }
+        // Corruptions that keep the checksum valid must still not panic.
+        let body = &bytes[..bytes.len() - 4];
+        for i in 5..body.len() {
+            for v in [0u8, 1, 0x7f, 0x80, 0xff] {
+                let mut b = body.to_vec();
+                b[i] = v;
+                let crc = crc32(&b);
+                b.extend(crc.to_le_bytes());
+                let _ = read(&b);
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Sorting under partial information: recovering a hidden total order of the elements from an oracle that
+//! compares two of them, when the poset records the comparisons already known, so that the hidden order is
+//! one of its linear extensions.
+//!
+//! The oracle is a function `before(x, y)`, asked only about incomparable elements, answering whether x
+//! comes before y. Any sequence of answers leads to a linear extension of the poset, which is the hidden
+//! order if the answers are consistent with one. Two strategies are offered.
+//! - [sort_by_insertion] inserts the elements one at a time, along a linear extension, into the sorted list
+//!   of those inserted before, by a binary search among the positions left open by the known relations
+//!   ([insert_element_queries]). It is fast, and asks at most $\lceil \log_2(k+1) \rceil$ questions to
+//!   insert into a list of k elements, but it does not use the known relations to save questions beyond
+//!   narrowing the search.
+//! - [sort_by_balanced_queries] asks, at every step, about the pair that splits the linear extensions still
+//!   possible most evenly. By the theorem of Kahn and Saks, some pair leaves between $3/11$ and $8/11$ of
+//!   them either way, so at most $\log_{11/8} e(P)$ questions are asked, where $e(P)$ is the number of linear
+//!   extensions: the information theoretic lower bound $\log_2 e(P)$ up to a constant factor. It counts
+//!   linear extensions for every candidate pair, so it is meant for small posets, or for questions that are
+//!   expensive to answer.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // The hidden order is 3, 1, 0, 2, and 3 < 0 is known.
+//! let rank = [2, 1, 3, 0];
+//! let mut p = PosetM::new_antichain(4);
+//! p.add_relation(3, 0).unwrap();
+//! let sorted = p.sort_by_balanced_queries(|x, y| rank[x] < rank[y]);
+//! assert_eq!(sorted.order, vec![3, 1, 0, 2]);
+//! assert!(sorted.queries <= 4);
+//! ```
+
+use crate::posetm::PosetM;
+use crate::{AnElement, BoolMatrix, Poset};
+
+/// A total order recovered by asking an oracle, together with the number of questions asked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sorted {
+    /// The elements, from first to last.
+    pub order: Vec<AnElement>,
+    /// The number of times the oracle was asked.
+    pub queries: usize,
+}
+
+/// Inserts x into sorted by binary search, knowing that it belongs after the first lo elements and
+/// before the elements from position hi on, and returns the number of questions asked. The oracle is asked
+/// about x and an element of sorted at most $\lceil \log_2(hi - lo + 1) \rceil$ times. Panics if lo exceeds
+/// hi or hi exceeds the length of sorted.
+pub fn insert_element_queries<F>(
+    sorted: &mut Vec<AnElement>,
+    x: AnElement,
+    lo: usize,
+    hi: usize,
+    before: &mut F,
+) -> usize
+where
+    F: FnMut(AnElement, AnElement) -> bool,
+{
+    assert!(
+        lo <= hi && hi <= sorted.len(),
+        "the positions {}..={} are not within the sorted list",
+        lo,
+        hi
+    );
+    let (mut lo, mut hi, mut queries) = (lo, hi, 0);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        queries += 1;
+        if before(x, sorted[mid]) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    sorted.insert(lo, x);
+    queries
+}
+
+/// Sorts the elements of p by binary insertion along its [linear extension](Poset::linear_extension), as
+/// described in the [module level documentation](self). The elements known to be above an element are
+/// inserted after it, so the search for its position starts after the elements known to be below it.
+pub fn sort_by_insertion<P, F>(p: &P, mut before: F) -> Sorted
+where
+    P: Poset + ?Sized,
+    F: FnMut(AnElement, AnElement) -> bool,
+{
+    let mut order: Vec<AnElement> = Vec::with_capacity(p.md().n);
+    let mut queries = 0;
+    for x in p.linear_extension() {
+        let lo = order.iter().rposition(|&y| p.lt(y, x)).map_or(0, |i| i + 1);
+        let hi = order.len();
+        queries += insert_element_queries(&mut order, x, lo, hi, &mut before);
+    }
+    Sorted { order, queries }
+}
+
+/// Sorts the elements of p by asking about the most balanced pair at every step, as described in the
+/// [module level documentation](self). Each answer is added to the known relations, until they form a
+/// chain. A step counts the linear extensions once for every incomparable pair.
+pub fn sort_by_balanced_queries<P, F>(p: &P, mut before: F) -> Sorted
+where
+    P: Poset + ?Sized,
+    F: FnMut(AnElement, AnElement) -> bool,
+{
+    let n = p.md().n;
+    let m: BoolMatrix = (0..n)
+        .map(|x| (0..n).map(|y| p.leq(x, y)).collect())
+        .collect();
+    let mut known = PosetM::new(&m);
+    let mut queries = 0;
+    loop {
+        let total = known.count_linear_extensions();
+        let pairs = (0..n).flat_map(|x| (x + 1..n).map(move |y| (x, y)));
+        let balance = |&(x, y): &(AnElement, AnElement)| {
+            let mut q = PosetM::new(&known.m);
+            q.add_relation(x, y).expect("the pair is incomparable");
+            (2 * q.count_linear_extensions()).abs_diff(total)
+        };
+        let Some((x, y)) = pairs
+            .filter(|&(x, y)| known.incomparable(x, y))
+            .min_by_key(balance)
+        else {
+            break;
+        };
+        queries += 1;
+        let relation = if before(x, y) { (x, y) } else { (y, x) };
+        known
+            .add_relation(relation.0, relation.1)
+            .expect("the pair is incomparable");
+    }
+    Sorted {
+        order: known.linear_extension(),
+        queries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{corpus, random_poset};
+    use crate::rng::SplitMix64;
+
+    /// A linear extension of p chosen at random, as the rank of every element.
+    fn hidden_ranks(p: &PosetM, seed: u64) -> Vec<usize> {
+        let mut rng = SplitMix64::new(seed);
+        let priority: Vec<i64> = p.elements().map(|_| rng.below(1000) as i64).collect();
+        let order: Vec<AnElement> = p
+            .schedule_waves(1, |x| priority[x])
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut rank = vec![0; order.len()];
+        order.iter().enumerate().for_each(|(i, &x)| rank[x] = i);
+        rank
+    }
+
+    #[test]
+    fn test_recovers_the_hidden_order() {
+        for (i, p) in corpus().into_iter().enumerate() {
+            let n = p.md.n;
+            let rank = hidden_ranks(&p, i as u64);
+            let mut expected: Vec<AnElement> = p.elements().collect();
+            expected.sort_by_key(|&x| rank[x]);
+            let before = |x: AnElement, y: AnElement| {
+                assert!(p.incomparable(x, y), "asked about a known pair");
+                rank[x] < rank[y]
+            };
+            let by_insertion = sort_by_insertion(&p, before);
+            assert_eq!(by_insertion.order, expected);
+            let bound: usize = (1..=n)
+                .map(|k| k.next_power_of_two().trailing_zeros())
+                .sum::<u32>() as usize;
+            assert!(by_insertion.queries <= bound);
+            let balanced = sort_by_balanced_queries(&p, before);
+            assert_eq!(balanced.order, expected);
+            let e = p.count_linear_extensions() as f64;
+            assert!(balanced.queries as f64 <= e.ln() / (11.0f64 / 8.0).ln() + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_known_orders_need_no_questions() {
+        let p = PosetM::new_chain(5);
+        let never = |_: AnElement, _: AnElement| -> bool { panic!("a chain needs no questions") };
+        assert_eq!(sort_by_insertion(&p, never).queries, 0);
+        assert_eq!(
+            sort_by_balanced_queries(&p, never).order,
+            vec![0, 1, 2, 3, 4]
+        );
+        // Whatever the answers, the result extends the known relations.
+        let p = random_poset(8, 20, 4);
+        let mut flip = false;
+        let sorted = sort_by_insertion(&p, |_, _| {
+            flip = !flip;
+            flip
+        });
+        let position = |x| sorted.order.iter().position(|&y| y == x).unwrap();
+        assert!(p.elements().all(|x| p
+            .elements()
+            .all(|y| !p.lt(x, y) || position(x) < position(y))));
+    }
+
+    #[test]
+    fn test_insert_element_queries() {
+        let mut sorted = vec![4, 2, 0, 3];
+        let rank = [2, 9, 1, 3, 0];
+        let mut before = |x: AnElement, y: AnElement| rank[x] < rank[y];
+        assert_eq!(insert_element_queries(&mut sorted, 1, 0, 4, &mut before), 2);
+        assert_eq!(sorted, vec![4, 2, 0, 3, 1]);
+        let mut sorted = vec![4, 2, 3];
+        assert_eq!(insert_element_queries(&mut sorted, 0, 2, 2, &mut before), 0);
+        assert_eq!(sorted, vec![4, 2, 0, 3]);
+    }
+}
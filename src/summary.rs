@@ -0,0 +1,189 @@
+//! Coarse summaries of large posets, obtained by cutting a linear extension into a few intervals.
+//!
+//! The elements are listed by level (the number of elements of a longest chain strictly below), ties broken
+//! by index. This list is a linear extension, and the summary of a poset into $k$ parts cuts it into at
+//! most $k$ contiguous blocks. The cuts are chosen greedily from the bottom: with $r$ elements and $b$
+//! blocks left, the target length is $\lceil r/b \rceil$, and the next block ends at the position within
+//! half the target length of it that is best by the following preferences, in order:
+//! 1. the block is a genuine order interval $[x, y] = \{z \mid x \le z \le y\}$, where $x$ and $y$ are its
+//!    first and last elements,
+//! 2. the block ends at the end of a level, so that levels are not split,
+//! 3. the length is closest to the target, shorter blocks winning ties.
+//!
+//! The last block takes all the remaining elements. Every element thus lies in exactly one block, there
+//! are at most $k$ blocks, and the result depends only on the order relation. Since the blocks are
+//! intervals of a linear extension, an element of one block can only be below an element of a later one,
+//! and the summary poset orders the blocks by the transitive closure of the relation "some element of one
+//! is below some element of the other". When $k \ge n$ every block is a single element and the summary is
+//! isomorphic to the poset.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_chain(7);
+//! assert_eq!(p.interval_partition(3), vec![(0, 2), (3, 4), (5, 6)]);
+//! assert!(p.summarize(3).is_isomorphic(&PosetM::new_chain(3)));
+//! ```
+
+use crate::posetg::PosetG;
+use crate::relation::{levels, strict_matrix};
+use crate::{AnElement, Poset};
+
+use std::collections::HashSet;
+
+/// Cuts p into at most k blocks, each listed in the order of the linear extension (see the
+/// [module level documentation](self)). Panics if k is 0 and p is not empty.
+pub fn interval_blocks<P: Poset + ?Sized>(p: &P, k: usize) -> Vec<Vec<AnElement>> {
+    let lt = strict_matrix(p);
+    let n = lt.len();
+    assert!(k > 0 || n == 0, "cannot cut {} elements into 0 blocks", n);
+    let level = levels(&lt);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&x| (level[x], x));
+
+    let genuine = |s: usize, e: usize| {
+        let (x, y) = (order[s], order[e]);
+        order[s..=e]
+            .iter()
+            .all(|&z| (z == x || lt[x][z]) && (z == y || lt[z][y]))
+    };
+    let level_end = |e: usize| e + 1 == n || level[order[e]] != level[order[e + 1]];
+
+    let mut blocks = vec![];
+    let mut start = 0;
+    while start < n {
+        let (r, b) = (n - start, k - blocks.len());
+        let len = if b == 1 {
+            r
+        } else {
+            let t = r.div_ceil(b);
+            let lo = (t - t / 2).max(1);
+            let hi = (t + t / 2).min(r);
+            (lo..=hi)
+                .min_by_key(|&len| {
+                    let e = start + len - 1;
+                    (!genuine(start, e), !level_end(e), len.abs_diff(t), len)
+                })
+                .unwrap()
+        };
+        blocks.push(order[start..start + len].to_vec());
+        start += len;
+    }
+    blocks
+}
+
+/// Returns the first and last element of each block of [interval_blocks].
+pub fn interval_partition<P: Poset + ?Sized>(p: &P, k: usize) -> Vec<(AnElement, AnElement)> {
+    interval_blocks(p, k)
+        .into_iter()
+        .map(|b| (b[0], b[b.len() - 1]))
+        .collect()
+}
+
+/// Returns the poset of the blocks of [interval_blocks], block $i$ being element $i$.
+pub fn summarize<P: Poset + ?Sized>(p: &P, k: usize) -> PosetG {
+    let blocks = interval_blocks(p, k);
+    let m = blocks.len();
+    let mut below: Vec<Vec<bool>> = (0..m)
+        .map(|i| {
+            (0..m)
+                .map(|j| {
+                    i == j
+                        || blocks[i]
+                            .iter()
+                            .any(|&x| blocks[j].iter().any(|&y| p.leq(x, y)))
+                })
+                .collect()
+        })
+        .collect();
+    // Edges only go from earlier to later blocks, so one pass in reverse closes them transitively.
+    for i in (0..m).rev() {
+        for j in i + 1..m {
+            if below[i][j] {
+                let row_j = below[j].clone();
+                below[i].iter_mut().zip(row_j).for_each(|(a, b)| *a |= b);
+            }
+        }
+    }
+    let g = (0..m)
+        .map(|i| (i, (0..m).filter(|&j| below[i][j]).collect::<HashSet<_>>()))
+        .collect();
+    PosetG::new(&g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{boolean_lattice, corpus};
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_chain() {
+        let p = PosetM::new_chain(10);
+        for k in 1..=10 {
+            let blocks = interval_blocks(&p, k);
+            assert_eq!(blocks.len(), k);
+            assert_eq!(blocks.concat(), (0..10).collect::<Vec<_>>());
+            assert!(blocks
+                .iter()
+                .all(|b| b.len() == 10 / k || b.len() == 10 / k + 1));
+            assert!(p.summarize(k).is_isomorphic(&PosetM::new_chain(k)));
+        }
+    }
+
+    #[test]
+    fn test_boolean_lattice() {
+        let b4 = boolean_lattice(4);
+        let rank = |x: &usize| x.count_ones();
+        let blocks = interval_blocks(&b4, 2);
+        assert_eq!(blocks.len(), 2);
+        // The cut falls at the end of a rank next to the middle one.
+        let last_low = blocks[0].iter().map(rank).max().unwrap();
+        let first_high = blocks[1].iter().map(rank).min().unwrap();
+        assert_eq!(last_low + 1, first_high);
+        assert!([1, 2].contains(&last_low));
+        assert!(b4.summarize(2).is_isomorphic(&PosetM::new_chain(2)));
+        assert_eq!(b4.interval_partition(2)[0].0, 0);
+        assert_eq!(b4.interval_partition(2)[1].1, 15);
+    }
+
+    #[test]
+    fn test_contract() {
+        for p in corpus() {
+            let n = p.md.n;
+            for k in 1..n + 3 {
+                let blocks = interval_blocks(&p, k);
+                assert!(blocks.len() <= k);
+                let mut all = blocks.concat();
+                all.sort_unstable();
+                assert_eq!(all, (0..n).collect::<Vec<_>>());
+                assert_eq!(blocks, interval_blocks(&p, k));
+                let s = p.summarize(k);
+                for i in 0..blocks.len() {
+                    for j in 0..blocks.len() {
+                        let induced = blocks[i]
+                            .iter()
+                            .any(|&x| blocks[j].iter().any(|&y| p.leq(x, y)));
+                        assert!(!induced || s.leq(i, j));
+                    }
+                }
+            }
+            assert!(p.summarize(n.max(1)).is_isomorphic(&p));
+            assert!(p.summarize(n + 2).is_isomorphic(&p));
+        }
+    }
+
+    #[test]
+    fn test_weak_order_levels() {
+        // Three levels of three pairwise incomparable elements, each level below the next.
+        let m = (0..9)
+            .map(|x| (0..9).map(|y| x == y || x / 3 < y / 3).collect())
+            .collect();
+        let p = PosetM::new(&m);
+        let blocks = interval_blocks(&p, 3);
+        assert_eq!(blocks, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]]);
+        assert!(p.summarize(3).is_isomorphic(&PosetM::new_chain(3)));
+        assert!(interval_blocks(&PosetM::new_antichain(0), 0).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+//! Export of Hasse diagrams as [TikZ](https://tikz.dev) pictures, for inclusion in LaTeX documents.
+//!
+//! The output is a `tikzpicture` environment with a node for each element, placed at the coordinates of
+//! [hasse_layout](crate::layout::hasse_layout) with the default [LayoutOptions], and a line for each cover
+//! $x \lessdot y$. It needs only `\usepackage{tikz}`, and compiles on its own within
+//! `\documentclass{standalone}`. Elements are named by their indices, and optionally labeled with text of the
+//! caller's choosing, in which the characters special to LaTeX are escaped.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new_chain(2);
+//! assert_eq!(
+//!     p.to_tikz(),
+//!     "\\begin{tikzpicture}\n  \\node (0) at (0, 0) {0};\n  \\node (1) at (0, 1) {1};\n  \
+//!      \\draw (0) -- (1);\n\\end{tikzpicture}\n"
+//! );
+//! ```
+
+use crate::layout::{Layout, LayoutOptions};
+use crate::Poset;
+
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Writes the Hasse diagram of p to w, labeling element i with labels\[i\] if labels are given. Panics if
+/// there are fewer labels than elements.
+pub fn write_tikz<P, W, L>(p: &P, labels: Option<&[L]>, w: &mut W) -> io::Result<()>
+where
+    P: Poset + ?Sized,
+    W: Write,
+    L: Display,
+{
+    let Layout { coords, edges, .. } = p.hasse_layout(&LayoutOptions::default());
+
+    writeln!(w, "\\begin{{tikzpicture}}")?;
+    for (x, &(a, b)) in coords.iter().enumerate() {
+        let label = match labels {
+            Some(labels) => escape(&labels[x]),
+            None => x.to_string(),
+        };
+        writeln!(
+            w,
+            "  \\node ({}) at ({}, {}) {{{}}};",
+            x,
+            round(a),
+            round(b),
+            label
+        )?;
+    }
+    for (x, y) in edges {
+        writeln!(w, "  \\draw ({}) -- ({});", x, y)?;
+    }
+    writeln!(w, "\\end{{tikzpicture}}")
+}
+
+/// Returns the Hasse diagram of p as a string (see [write_tikz]).
+pub fn to_tikz<P, L>(p: &P, labels: Option<&[L]>) -> String
+where
+    P: Poset + ?Sized,
+    L: Display,
+{
+    let mut tikz = vec![];
+    write_tikz(p, labels, &mut tikz).expect("writing to a vector does not fail");
+    String::from_utf8(tikz).expect("the output is valid UTF-8")
+}
+
+/// Rounds a coordinate to two decimals, without a sign on zero.
+fn round(c: f64) -> f64 {
+    (c * 100.0).round() / 100.0 + 0.0
+}
+
+/// Escapes the characters special to LaTeX, for use as the text of a node.
+fn escape<L: Display>(label: &L) -> String {
+    let mut escaped = String::new();
+    for c in label.to_string().chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_diamond() {
+        let mut p = PosetG::new_antichain(2);
+        p.adjoin_bot();
+        p.adjoin_top();
+        let expected = "\\begin{tikzpicture}\n  \\node (0) at (-0.5, 1) {0};\n  \
+                        \\node (1) at (0.5, 1) {1};\n  \\node (2) at (0, 0) {2};\n  \
+                        \\node (3) at (0, 2) {3};\n  \\draw (0) -- (3);\n  \\draw (1) -- (3);\n  \
+                        \\draw (2) -- (0);\n  \\draw (2) -- (1);\n\\end{tikzpicture}\n";
+        assert_eq!(p.to_tikz(), expected);
+        assert_eq!(
+            PosetM::new_chain(0).to_tikz(),
+            "\\begin{tikzpicture}\n\\end{tikzpicture}\n"
+        );
+    }
+
+    #[test]
+    fn test_labels_and_edges() {
+        let p = PosetM::new_antichain(2);
+        assert_eq!(
+            to_tikz(&p, Some(&["a_1 & {b}", "50% \\ ~"])),
+            "\\begin{tikzpicture}\n  \\node (0) at (-0.5, 0) {a\\_1 \\& \\{b\\}};\n  \
+             \\node (1) at (0.5, 0) {50\\% \\textbackslash{} \\textasciitilde{}};\n\\end{tikzpicture}\n"
+        );
+        for p in corpus() {
+            let tikz = p.to_tikz();
+            assert_eq!(tikz.matches("\\node").count(), p.md.n);
+            assert_eq!(tikz.matches("\\draw").count(), p.layout_edges().len());
+        }
+    }
+}
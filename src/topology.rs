@@ -0,0 +1,164 @@
+//! Topological invariants of the order complex.
+//!
+//! The order complex $\Delta(P)$ of a poset is the simplicial complex whose faces are its chains (see
+//! [chains](crate::chains)). Its reduced Euler characteristic
+//! $$\tilde\chi(\Delta(P)) = \sum_{k \ge 0} (-1)^{k-1} c_k,$$
+//! where $c_k$ is the number of chains of $k$ elements (so that the empty chain counts $-1$), equals the
+//! Möbius function $\mu(\hat 0, \hat 1)$ of the poset with a new bottom $\hat 0$ and top $\hat 1$ adjoined
+//! (Hall's theorem). In particular it vanishes if the poset has a bottom or a top, as the complex is then a
+//! cone.
+//!
+//! With the `homology` feature, [homology_mod2] computes the ranks of the reduced simplicial homology
+//! groups $\tilde H_k(\Delta(P); \mathbb{F}_2)$, whose alternating sum is $\tilde\chi$. They are invariant
+//! under homotopy equivalence of the complexes, as in Quillen's fiber lemma.
+//!
+//! ```
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! // Two minimal elements below two maximal ones: the order complex is a circle.
+//! let p = PosetM::new(&vec![
+//!     vec![true, false, true, true],
+//!     vec![false, true, true, true],
+//!     vec![false, false, true, false],
+//!     vec![false, false, false, true],
+//! ]);
+//! assert_eq!(p.euler_characteristic(), -1);
+//! assert_eq!(PosetM::new_antichain(3).euler_characteristic(), 2);
+//! assert_eq!(PosetM::new_chain(3).euler_characteristic(), 0);
+//! ```
+
+use crate::Poset;
+
+#[cfg(feature = "homology")]
+use crate::bitset::BitRow;
+#[cfg(feature = "homology")]
+use crate::chains::Chains;
+#[cfg(feature = "homology")]
+use crate::AnElement;
+#[cfg(feature = "homology")]
+use std::collections::HashMap;
+
+/// Returns the reduced Euler characteristic of the order complex of p, from the numbers of chains.
+pub fn euler_characteristic<P: Poset + ?Sized>(p: &P) -> i64 {
+    p.count_chains_by_length()
+        .into_iter()
+        .enumerate()
+        .map(|(k, c)| if k % 2 == 1 { c as i64 } else { -(c as i64) })
+        .sum()
+}
+
+/// Returns the ranks of the reduced homology groups $\tilde H_k$ of the order complex of p with
+/// coefficients in $\mathbb{F}_2$, for $k = 0, ..., h-1$, where $h$ is the height. The empty poset, whose
+/// complex only has homology in degree $-1$, gives an empty vector.
+///
+/// The ranks of the boundary maps are found by Gaussian elimination over all the chains, so this is only
+/// practical for posets with few chains.
+#[cfg(feature = "homology")]
+pub fn homology_mod2<P: Poset + ?Sized>(p: &P) -> Vec<usize> {
+    // faces[k] lists the chains of k + 1 elements, i.e., the k-dimensional faces.
+    let mut faces: Vec<Vec<Vec<AnElement>>> = vec![];
+    for chain in Chains::new(p).filter(|c| !c.is_empty()) {
+        if faces.len() < chain.len() {
+            faces.resize(chain.len(), vec![]);
+        }
+        faces[chain.len() - 1].push(chain);
+    }
+    let h = faces.len();
+    // ranks[k] is the rank of the boundary map from k-faces to (k-1)-faces, the empty face being the
+    // only (-1)-face.
+    let mut ranks = vec![0; h + 1];
+    if h > 0 {
+        ranks[0] = 1;
+    }
+    for k in 1..h {
+        let index: HashMap<&[AnElement], usize> = faces[k - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.as_slice(), i))
+            .collect();
+        let rows = faces[k].iter().map(|c| {
+            let mut row = BitRow::new(index.len());
+            for skip in 0..c.len() {
+                let face: Vec<AnElement> = [&c[..skip], &c[skip + 1..]].concat();
+                row.set(index[face.as_slice()]);
+            }
+            row
+        });
+        ranks[k] = rank(index.len(), rows);
+    }
+    (0..h)
+        .map(|k| faces[k].len() - ranks[k] - ranks[k + 1])
+        .collect()
+}
+
+/// The rank over $\mathbb{F}_2$ of the rows, all subsets of $\{0, ..., len-1\}$.
+#[cfg(feature = "homology")]
+fn rank(len: usize, rows: impl Iterator<Item = BitRow>) -> usize {
+    // pivots[i] is a reduced row whose first element is i.
+    let mut pivots: Vec<Option<BitRow>> = vec![None; len];
+    let mut rank = 0;
+    for mut row in rows {
+        loop {
+            let Some(i) = row.iter_ones().next() else {
+                break;
+            };
+            match &pivots[i] {
+                Some(pivot) => row.xor_assign(pivot),
+                None => {
+                    pivots[i] = Some(row);
+                    rank += 1;
+                    break;
+                }
+            }
+        }
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    #[cfg(feature = "homology")]
+    use crate::corpus::boolean_lattice;
+    use crate::corpus::corpus;
+    use crate::incidence_algebra::IncidenceAlgebra;
+    use crate::posetm::PosetM;
+
+    #[test]
+    fn test_euler_characteristic_is_mobius() {
+        for p in corpus() {
+            let n = p.md.n;
+            let mut q = PosetM::from_poset(&p);
+            q.adjoin_bot();
+            q.adjoin_top();
+            let mu = IncidenceAlgebra::new(&q).mobius().value(n, n + 1);
+            assert_eq!(p.euler_characteristic(), mu);
+        }
+        assert_eq!(PosetM::new_chain(0).euler_characteristic(), -1);
+    }
+
+    #[cfg(feature = "homology")]
+    #[test]
+    fn test_homology() {
+        // The empty poset is left out, its homology being in degree -1.
+        for p in corpus().into_iter().filter(|p| p.md.n > 0) {
+            let betti = p.homology_mod2();
+            assert_eq!(betti.len() + 1, p.count_chains_by_length().len());
+            let alternating: i64 = betti
+                .iter()
+                .enumerate()
+                .map(|(k, &b)| if k % 2 == 0 { b as i64 } else { -(b as i64) })
+                .sum();
+            assert_eq!(alternating, p.euler_characteristic());
+        }
+        assert_eq!(PosetM::new_antichain(4).homology_mod2(), vec![3]);
+        assert_eq!(PosetM::new_chain(3).homology_mod2(), vec![0, 0, 0]);
+        // The proper part of the Boolean lattice of rank k is a (k-2)-sphere.
+        let (proper, _) = boolean_lattice(3).sub(&(1..7).collect());
+        assert_eq!(proper.homology_mod2(), vec![0, 1]);
+        let (proper, _) = boolean_lattice(4).sub(&(1..15).collect());
+        assert_eq!(proper.homology_mod2(), vec![0, 0, 1]);
+    }
+}
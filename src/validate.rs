@@ -0,0 +1,375 @@
+//! Checks that user-supplied data describes a poset.
+//!
+//! The constructors of the representations trust their input, and a matrix, graph or list of covers that
+//! does not encode a partial order gives silently wrong answers downstream. [Poset::is_valid_poset] checks
+//! the input once and reports the first offending element, pair, or triple as a [PosetError]:
+//! - the stored data must mention exactly the elements $0, ..., n-1$,
+//! - a relation must be reflexive, antisymmetric and transitive ([check_order]),
+//! - the covers of a Hasse diagram must not form a cycle ([check_acyclic]), loops included. Any acyclic
+//!   list of edges generates a partial order, so nothing else is checked for Hasse diagrams.
+//!
+//! The `try_new` constructors of the representations run these checks before returning the poset.
+//! [check_metadata] separately checks cached [MetaData](crate::MetaData) that was set by hand or read
+//! from storage.
+//!
+//! ```
+//! use fin_pos::error::PosetError;
+//! use fin_pos::posetm::PosetM;
+//! use fin_pos::Poset;
+//!
+//! let p = PosetM::new(&vec![vec![true, true], vec![true, true]]);
+//! assert_eq!(p.is_valid_poset(), Err(PosetError::NotAntisymmetric { x: 0, y: 1 }));
+//! ```
+
+use crate::error::PosetError;
+use crate::relation::strict_matrix;
+use crate::{AnElement, Elements, Elt, Hasse, Poset};
+
+/// Checks that [Poset::leq] is a partial order on the elements of p, assuming that the stored data of p
+/// is well formed. Reflexivity is checked first, then antisymmetry, then transitivity, each in increasing
+/// order of the elements involved.
+pub fn check_order<P: Poset + ?Sized>(p: &P) -> Result<(), PosetError> {
+    let n = p.md().n;
+    if let Some(x) = (0..n).find(|&x| !p.leq(x, x)) {
+        return Err(PosetError::NotReflexive { x });
+    }
+    for x in 0..n {
+        if let Some(y) = (x + 1..n).find(|&y| p.leq(x, y) && p.leq(y, x)) {
+            return Err(PosetError::NotAntisymmetric { x, y });
+        }
+    }
+    for x in 0..n {
+        for y in (0..n).filter(|&y| y != x && p.leq(x, y)) {
+            if let Some(z) = (0..n).find(|&z| p.leq(y, z) && !p.leq(x, z)) {
+                return Err(PosetError::NotTransitive { x, y, z });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the fields of the [MetaData](crate::MetaData) of p that are known agree with the order of
+/// p, which must be valid (see [Poset::is_valid_poset], which also covers the size), and that pinned
+/// handles resolve to elements of p. The fields are checked in the order in which they are declared, and
+/// the first disagreeing one is reported.
+pub fn check_metadata<P: Poset + ?Sized>(p: &P) -> Result<(), PosetError> {
+    let md = p.md();
+    let n = md.n;
+    let extreme = |e: Option<Elt>, is: &dyn Fn(AnElement) -> bool| match e {
+        Some(Elt::A(x)) => x < n && is(x),
+        Some(Elt::NotPresent) => !(0..n).any(is),
+        None => true,
+    };
+    if !extreme(md.top, &|x| (0..n).all(|y| p.leq(y, x))) {
+        return Err(PosetError::InconsistentMetaData { field: "top" });
+    }
+    if !extreme(md.bot, &|x| (0..n).all(|y| p.leq(x, y))) {
+        return Err(PosetError::InconsistentMetaData { field: "bot" });
+    }
+    let lt = strict_matrix(p);
+    let extremals = |s: &Option<Elements>, below: bool| match s {
+        Some(s) => {
+            let actual: Elements = (0..n)
+                .filter(|&x| (0..n).all(|y| !if below { lt[y][x] } else { lt[x][y] }))
+                .collect();
+            *s == actual
+        }
+        None => true,
+    };
+    if !extremals(&md.minimals, true) {
+        return Err(PosetError::InconsistentMetaData { field: "minimals" });
+    }
+    if !extremals(&md.maximals, false) {
+        return Err(PosetError::InconsistentMetaData { field: "maximals" });
+    }
+    if md
+        .height
+        .is_some_and(|h| h != crate::relation::longest_chain(&lt).len())
+    {
+        return Err(PosetError::InconsistentMetaData { field: "height" });
+    }
+    if md
+        .atoms
+        .as_ref()
+        .is_some_and(|a| *a != crate::lattice::atoms(p))
+    {
+        return Err(PosetError::InconsistentMetaData { field: "atoms" });
+    }
+    if md
+        .coatoms
+        .as_ref()
+        .is_some_and(|a| *a != crate::lattice::coatoms(p))
+    {
+        return Err(PosetError::InconsistentMetaData { field: "coatoms" });
+    }
+    if md
+        .handles
+        .sorted_slots()
+        .iter()
+        .any(|&(_, x)| x.is_some_and(|x| x >= n))
+    {
+        return Err(PosetError::InconsistentMetaData { field: "handles" });
+    }
+    Ok(())
+}
+
+/// Checks the up-sets stored by a representation such as [PosetG](crate::posetg::PosetG), given as pairs of
+/// an element and its up-set: every element of $0, ..., n-1$ must have an up-set, and no other element may
+/// appear. Then [check_order] checks the order.
+pub(crate) fn check_up_sets<P, I, S>(p: &P, up_sets: I) -> Result<(), PosetError>
+where
+    P: Poset + ?Sized,
+    I: IntoIterator<Item = (AnElement, S)>,
+    S: IntoIterator<Item = AnElement>,
+{
+    let n = p.md().n;
+    let mut has_up_set = vec![false; n];
+    let mut out_of_range: Option<AnElement> = None;
+    for (x, up) in up_sets {
+        if x < n {
+            has_up_set[x] = true;
+        }
+        for y in up.into_iter().chain([x]).filter(|&y| y >= n) {
+            out_of_range = Some(out_of_range.map_or(y, |z| z.min(y)));
+        }
+    }
+    if let Some(x) = has_up_set.iter().position(|&has| !has) {
+        return Err(PosetError::Missing { x });
+    }
+    if let Some(index) = out_of_range {
+        return Err(PosetError::IndexOutOfRange { index, n });
+    }
+    check_order(p)
+}
+
+/// Checks that the covers in h mention only the elements $0, ..., n-1$ and form no cycle.
+pub fn check_acyclic(h: &Hasse, n: usize) -> Result<(), PosetError> {
+    if h.len() < n {
+        return Err(PosetError::Missing { x: h.len() });
+    }
+    if let Some(index) = h.iter().flatten().copied().find(|&y| y >= n) {
+        return Err(PosetError::IndexOutOfRange { index, n });
+    }
+    if h.len() > n {
+        return Err(PosetError::IndexOutOfRange { index: n, n });
+    }
+    // Depth first search, with the elements on the current path marked as active.
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Active,
+        Done,
+    }
+    let mut state = vec![State::New; n];
+    for root in 0..n {
+        if state[root] != State::New {
+            continue;
+        }
+        state[root] = State::Active;
+        let mut stack: Vec<(AnElement, usize)> = vec![(root, 0)];
+        while let Some(&mut (x, ref mut next)) = stack.last_mut() {
+            match h[x].as_slice().get(*next) {
+                Some(&y) => {
+                    *next += 1;
+                    match state[y] {
+                        State::Active => return Err(PosetError::Cycle { x, y }),
+                        State::New => {
+                            state[y] = State::Active;
+                            stack.push((y, 0));
+                        }
+                        State::Done => {}
+                    }
+                }
+                None => {
+                    state[x] = State::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that $x \le y$ can be added to p: both must be elements, and y must not be strictly below x.
+/// Returns false if $x \le y$ already holds, so that there is nothing to add.
+pub(crate) fn check_new_relation<P: Poset + ?Sized>(
+    p: &P,
+    x: AnElement,
+    y: AnElement,
+) -> Result<bool, PosetError> {
+    let n = p.md().n;
+    if let Some(&index) = [x, y].iter().find(|&&z| z >= n) {
+        return Err(PosetError::IndexOutOfRange { index, n });
+    }
+    if p.leq(x, y) {
+        return Ok(false);
+    }
+    if p.leq(y, x) {
+        return Err(PosetError::NotAntisymmetric { x, y });
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertors::FromPoset;
+    use crate::corpus::corpus;
+    use crate::posetg::PosetG;
+    use crate::poseth::PosetH;
+    use crate::posetm::PosetM;
+    use crate::poseto::PosetO;
+    use crate::smallset::SmallSet;
+    use crate::BiPaGraph;
+
+    #[test]
+    fn test_corpus_is_valid() {
+        for p in corpus() {
+            assert_eq!(p.is_valid_poset(), Ok(()));
+            assert_eq!(PosetG::from_poset(&p).is_valid_poset(), Ok(()));
+            assert_eq!(PosetH::from_poset(&p).is_valid_poset(), Ok(()));
+            assert_eq!(PosetO::from_poset(&p).is_valid_poset(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_axioms() {
+        let mut m = PosetM::new_chain(3).m;
+        m[1][1] = false;
+        assert_eq!(
+            PosetM::new(&m).is_valid_poset(),
+            Err(PosetError::NotReflexive { x: 1 })
+        );
+        let mut m = PosetM::new_chain(3).m;
+        m[2][1] = true;
+        assert_eq!(
+            PosetM::new(&m).is_valid_poset(),
+            Err(PosetError::NotAntisymmetric { x: 1, y: 2 })
+        );
+        let mut m = PosetM::new_chain(3).m;
+        m[0][2] = false;
+        let err = PosetM::new(&m).is_valid_poset().unwrap_err();
+        assert_eq!(err, PosetError::NotTransitive { x: 0, y: 1, z: 2 });
+        assert_eq!(err.to_string(), "0 <= 1 <= 2 but not 0 <= 2");
+        let g: BiPaGraph = (0..3)
+            .map(|x| (x, (0..3).filter(|&y| m[x][y]).collect()))
+            .collect();
+        assert_eq!(PosetG::new(&g).is_valid_poset(), Err(err));
+    }
+
+    #[test]
+    fn test_malformed_data() {
+        let p = PosetM::new(&vec![vec![true, false], vec![true]]);
+        assert_eq!(p.is_valid_poset(), Err(PosetError::Missing { x: 1 }));
+        let mut g = PosetG::new_chain(3);
+        g.g.remove(&1);
+        g.g.insert(5, [5].into_iter().collect());
+        assert_eq!(g.is_valid_poset(), Err(PosetError::Missing { x: 1 }));
+        g.g.insert(1, [1, 2].into_iter().collect());
+        assert_eq!(
+            g.is_valid_poset(),
+            Err(PosetError::IndexOutOfRange { index: 5, n: 3 })
+        );
+        let h = PosetH::from_covers(vec![[1].into_iter().collect(), [4].into_iter().collect()]);
+        assert_eq!(
+            h.is_valid_poset(),
+            Err(PosetError::IndexOutOfRange { index: 4, n: 2 })
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let cycle: Vec<SmallSet> = (0..4)
+            .map(|x| [(x + 1) % 4].into_iter().collect())
+            .collect();
+        assert_eq!(
+            PosetH::from_covers(cycle).is_valid_poset(),
+            Err(PosetError::Cycle { x: 3, y: 0 })
+        );
+        let mut h = PosetH::new_chain(3).h;
+        h[2].insert(2);
+        assert_eq!(
+            PosetH::from_covers(h).is_valid_poset(),
+            Err(PosetError::Cycle { x: 2, y: 2 })
+        );
+        // Redundant edges generate the same order and are accepted.
+        let h = vec![
+            [1, 2].into_iter().collect(),
+            [2].into_iter().collect(),
+            SmallSet::new(),
+        ];
+        assert_eq!(PosetH::from_covers(h).is_valid_poset(), Ok(()));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let mut m = PosetM::new_chain(3).m;
+        assert!(PosetM::try_new(&m).is_ok());
+        m[2][0] = true;
+        assert_eq!(
+            PosetM::try_new(&m).unwrap_err(),
+            PosetError::NotAntisymmetric { x: 0, y: 2 }
+        );
+        let g: BiPaGraph = [(0, [0, 1].into()), (1, [1, 2].into())].into();
+        assert_eq!(
+            PosetG::try_new(&g).unwrap_err(),
+            PosetError::IndexOutOfRange { index: 2, n: 2 }
+        );
+        let o = PosetO::try_new(&[(0, [0].into()), (1, [0, 1].into())].into()).unwrap();
+        assert!(o.same_order(&PosetM::new(&vec![vec![true, false], vec![true, true]])));
+        assert_eq!(
+            PosetH::try_from_covers(vec![[1].into_iter().collect(), [0].into_iter().collect()])
+                .unwrap_err(),
+            PosetError::Cycle { x: 1, y: 0 }
+        );
+        assert!(PosetH::try_from_covers(PosetH::new_chain(4).h).is_ok());
+    }
+
+    #[test]
+    fn test_metadata() {
+        for p in corpus() {
+            let mut g = PosetG::from_poset(&p);
+            assert_eq!(check_metadata(&g), Ok(()));
+            g.find_top();
+            g.find_bot();
+            g.find_minimals();
+            g.find_maximals();
+            g.find_height();
+            g.find_atoms();
+            g.find_coatoms();
+            assert_eq!(check_metadata(&g), Ok(()));
+        }
+        let mut p = PosetM::new_antichain(2);
+        p.md.top = Some(Elt::A(0));
+        assert_eq!(
+            check_metadata(&p),
+            Err(PosetError::InconsistentMetaData { field: "top" })
+        );
+        p.md.top = Some(Elt::NotPresent);
+        p.md.minimals = Some([0].into());
+        let err = check_metadata(&p).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the cached minimals disagrees with the order"
+        );
+        p.md.minimals = None;
+        p.md.height = Some(2);
+        assert_eq!(
+            check_metadata(&p),
+            Err(PosetError::InconsistentMetaData { field: "height" })
+        );
+        p.md.height = None;
+        p.md.atoms = Some([1].into());
+        assert_eq!(
+            check_metadata(&p),
+            Err(PosetError::InconsistentMetaData { field: "atoms" })
+        );
+        p.md.atoms = Some([].into());
+        assert_eq!(check_metadata(&p), Ok(()));
+        p.md.handles.pin(2);
+        assert_eq!(
+            check_metadata(&p),
+            Err(PosetError::InconsistentMetaData { field: "handles" })
+        );
+    }
+}